@@ -0,0 +1,49 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use crate::error::Result;
+
+/// Manifest Apple TV uses to list its aerials, read from local sources by screensaver tools
+const MANIFEST: &str = "entries.json";
+
+/// Stable uppercase UUID for a file name, so adding the same video again replaces its entry.
+fn asset_id(file_name: &str) -> String {
+    let hex: String = Sha256::digest(file_name).iter().take(16).map(|byte| format!("{:02X}", byte)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+/// Copy `video` into the aerial folder `dir` and list it in the folder's `entries.json`,
+/// which is laid out like Apple TV's so tools that take a local aerial source can play it.
+pub fn add(video: &Path, title: &str, uploader: Option<&str>, dir: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let file_name = video.file_name().ok_or("Video has no file name")?.to_string_lossy().to_string();
+    let target = dir.join(&file_name);
+    fs::copy(video, &target)?;
+
+    let manifest_path = dir.join(MANIFEST);
+    let mut manifest: Value = fs::read_to_string(&manifest_path).ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .filter(|manifest: &Value| manifest["assets"].is_array())
+        .unwrap_or_else(|| json!({ "assets": [], "initialAssetCount": 0, "version": 1 }));
+
+    let id = asset_id(&file_name);
+    let label = match uploader {
+        Some(uploader) => format!("{} by {}", title, uploader),
+        None => title.to_string(),
+    };
+    let assets = manifest["assets"].as_array_mut().unwrap();
+    assets.retain(|asset| asset["id"] != id.as_str());
+    assets.push(json!({
+        "id": id,
+        "accessibilityLabel": title,
+        // Both keys point at the one rendition, so players that prefer 1080p still find it
+        "url-4K-SDR": file_name,
+        "url-1080-SDR": file_name,
+        "pointsOfInterest": { "0": label },
+    }));
+    manifest["initialAssetCount"] = json!(assets.len());
+
+    fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+    Ok(target)
+}
@@ -0,0 +1,146 @@
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use serde_json::Value;
+use crate::cancel::CancelHandle;
+use crate::config::Config;
+use crate::downloader::Downloader;
+use crate::error::Result;
+use crate::events::{ProgressEvent, ProgressObserver};
+use crate::history;
+use crate::logger::{self, Sink};
+use crate::utils;
+use crate::video_info::SelectedFormats;
+use crate::wallpaper;
+
+/// A video and the formats picked for it, from `analyze_url`.
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    /// Watch URL, after resolving video ids and `ytsearch:` queries
+    pub url: String,
+    pub selected: SelectedFormats,
+}
+
+/// What to `download`.
+#[derive(Clone)]
+pub struct DownloadOptions {
+    /// URL, video id or `ytsearch:` query
+    pub url: String,
+    pub config: Config,
+    /// From an earlier `analyze_url`, to skip analyzing again
+    pub analysis: Option<Analysis>,
+    /// Cancelling it stops the download's yt-dlp and ffmpeg processes
+    pub cancel: Option<CancelHandle>,
+    /// Gets this download's `ProgressEvent`s, typed rather than as the JSON of `on_event`
+    pub observer: Option<Arc<dyn ProgressObserver>>,
+}
+
+impl DownloadOptions {
+    pub fn new(url: &str, config: Config) -> Self {
+        Self { url: url.to_string(), config, analysis: None, cancel: None, observer: None }
+    }
+}
+
+/// A finished download.
+#[derive(Debug, Clone)]
+pub struct DownloadResult {
+    /// Job id that the events of this download carry
+    pub job: String,
+    pub title: String,
+    pub path: PathBuf,
+    /// Every file written when the download was split (chapters, scenes, extra resolutions)
+    pub outputs: Vec<PathBuf>,
+    pub size: Option<u64>,
+    pub conversion_seconds: Option<f64>,
+    /// `remux`, or `re-encode` and why
+    pub conversion_path: Option<String>,
+}
+
+/// Library output is silent; what the CLI would log arrives as events instead.
+fn quiet() {
+    logger::set_sink(Sink::Silent);
+}
+
+/// Call `handler` with every event (`analysis`, `progress`, `completed`, `failed`, and `log`
+/// for warnings and errors), as the JSON objects `--json` prints. Events of concurrent
+/// downloads are told apart by their `job`.
+pub fn on_event(handler: impl Fn(&Value) + Send + Sync + 'static) {
+    quiet();
+    logger::add_event_handler(Arc::new(handler));
+}
+
+/// Every event from now on, for a UI thread to drain.
+pub fn events() -> mpsc::Receiver<Value> {
+    let (sender, receiver) = mpsc::channel();
+    on_event(move |event| {
+        sender.send(event.clone()).ok();
+    });
+    receiver
+}
+
+/// Look a video up and pick its formats, without downloading anything.
+pub fn analyze_url(url: &str, config: &Config) -> Result<Analysis> {
+    quiet();
+    analyze_with(&mut Downloader::new(), url, config)
+}
+
+fn analyze_with(downloader: &mut Downloader, url: &str, config: &Config) -> Result<Analysis> {
+    let url = utils::resolve_video_input(url, config.video_preferences.license)?;
+    let selected = match downloader.analyze(&url, config) {
+        // The next cookie profile; the browser sign-in flow needs a person
+        Err(error) if downloader.rotate_profile_after(&error, config) => downloader.analyze(&url, config),
+        result => result,
+    }?;
+    Ok(Analysis { url, selected })
+}
+
+/// Download and convert a video as the CLI would, recording it in the history. Never asks
+/// anything or installs a wallpaper; see `install_wallpaper`.
+pub async fn download(options: DownloadOptions) -> Result<DownloadResult> {
+    quiet();
+    let config = &options.config;
+    let mut downloader = Downloader::new();
+    if let Some(cancel) = options.cancel {
+        downloader.set_cancel_handle(cancel);
+    }
+    if let Some(observer) = options.observer {
+        downloader.set_observer(move |event: &ProgressEvent| observer.on_event(event));
+    }
+
+    let Analysis { url, selected } = match options.analysis {
+        Some(analysis) => analysis,
+        None => analyze_with(&mut downloader, &options.url, config)?,
+    };
+    let started = std::time::Instant::now();
+    let result = match downloader.perform_download(&url, &selected, config).await {
+        Err(error) if downloader.rotate_profile_after(&error, config) => downloader.perform_download(&url, &selected, config).await,
+        result => result,
+    };
+
+    if let Ok(download_history) = history::History::load(config) {
+        let outputs = match &result {
+            Ok(_) if !downloader.batch_outputs().is_empty() => Ok(downloader.batch_outputs()),
+            Ok(path) => Ok(std::slice::from_ref(path)),
+            Err(error) => Err(error),
+        };
+        history::record_outcome(&download_history, &url, Some(&selected.info), &downloader.conversion(), outputs, started.elapsed());
+    }
+
+    let path = result?;
+    Ok(DownloadResult {
+        job: downloader.job_label().to_string(),
+        title: selected.info.title.clone(),
+        size: std::fs::metadata(&path).ok().map(|metadata| metadata.len()),
+        outputs: downloader.batch_outputs().to_vec(),
+        conversion_seconds: downloader.conversion_seconds(),
+        conversion_path: downloader.conversion_path().map(str::to_string),
+        path,
+    })
+}
+
+/// Install `video` as the wallpaper without asking anything: on macOS it replaces `target`
+/// (a file in the aerial folder) or the most recently installed aerial, which needs write
+/// access to that folder. Returns what was replaced.
+pub async fn install_wallpaper(video: &Path, config: &Config, target: Option<&str>) -> Result<String> {
+    quiet();
+    wallpaper::detect(config)?.swap(video, target).await
+}
@@ -0,0 +1,30 @@
+use std::env;
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Appearance {
+    Light,
+    Dark,
+}
+
+/// Current system appearance, read from the global `AppleInterfaceStyle` default.
+///
+/// The key only exists while Dark Mode is on. Under sudo the lookup runs as the
+/// invoking user, since root's preferences never change with the menu bar toggle.
+pub fn current_appearance() -> Appearance {
+    let mut command = match env::var("SUDO_USER") {
+        Ok(user) => {
+            let mut cmd = Command::new("sudo");
+            cmd.args(["-u", &user, "defaults"]);
+            cmd
+        }
+        Err(_) => Command::new("defaults"),
+    };
+
+    let output = command.args(["read", "-g", "AppleInterfaceStyle"]).output();
+
+    match output {
+        Ok(output) if output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "Dark" => Appearance::Dark,
+        _ => Appearance::Light,
+    }
+}
@@ -0,0 +1,388 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use crate::config::{Config, CookieSource};
+use crate::error::{DownloaderError, Result};
+use crate::logger;
+use crate::utils;
+use crate::ytdlp_error::YtDlpError;
+
+const AUTH_FILE: &str = "auth.json";
+
+/// Cookies given for this run, which replace the stored browser and profiles.
+static RUN_COOKIES: OnceLock<CookieSource> = OnceLock::new();
+
+/// Browsers yt-dlp can read cookies from.
+pub const SUPPORTED_BROWSERS: [&str; 7] = ["safari", "chrome", "firefox", "brave", "edge", "chromium", "vivaldi"];
+
+/// Credentials from `auth setup`, and when each `[auth.profiles]` entry was used.
+///
+/// Stored per user rather than in the output directory, since it applies to every download.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Auth {
+    /// Browser to read YouTube cookies from (`--cookies-from-browser`)
+    #[serde(default)]
+    pub cookies_from_browser: Option<String>,
+
+    /// Base URL of a bgutil PO token provider server, e.g. http://127.0.0.1:4416
+    #[serde(default)]
+    pub po_token_provider: Option<String>,
+
+    /// Usage of the profiles in `config.toml`, by name
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ProfileState>,
+}
+
+/// How a cookie profile has been used, so jobs take turns and a throttled one rests.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfileState {
+    #[serde(default)]
+    pub last_used: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub cooldown_until: Option<DateTime<Utc>>,
+    /// Where older versions kept the profile itself; kept so it can be moved to `config.toml`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub browser: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cookies_file: Option<PathBuf>,
+}
+
+impl ProfileState {
+    pub fn is_cooling_down(&self) -> bool {
+        self.cooldown_until.is_some_and(|until| until > Utc::now())
+    }
+}
+
+fn cookie_args(source: &CookieSource) -> Vec<String> {
+    match source {
+        CookieSource::Browser(browser) => vec!["--cookies-from-browser".to_string(), browser.clone()],
+        CookieSource::File(file) => vec!["--cookies".to_string(), file.to_string_lossy().to_string()],
+    }
+}
+
+impl Auth {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("rust-downloader").join(AUTH_FILE))
+    }
+
+    /// The stored state; one that can't be read counts as empty, with a warning.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        utils::read_json(&path).unwrap_or_else(|error| {
+            logger::warning(&format!("Ignoring the stored sign-in settings: {}", error));
+            Self::default()
+        })
+    }
+
+    /// Load, change and save the stored state while holding a lock, so concurrent jobs and
+    /// processes don't overwrite each other's profile use. Returns the saved state. Fails,
+    /// leaving the file alone, when it doesn't parse; `clear` starts over.
+    pub fn update(change: impl FnOnce(&mut Auth)) -> Result<Auth> {
+        let path = Self::path().ok_or("Could not determine config directory")?;
+        utils::update_json(&path, |auth: &mut Auth| {
+            change(auth);
+            auth.clone()
+        })
+    }
+
+    /// Forget the stored browser, token provider and profile cooldowns, also when the file
+    /// doesn't parse.
+    pub fn clear() -> Result<()> {
+        let path = Self::path().ok_or("Could not determine config directory")?;
+        match fs::remove_file(path) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_configured(&self, config: &Config) -> bool {
+        self.cookies_from_browser.is_some() || self.po_token_provider.is_some() || !config.auth.profiles.is_empty()
+    }
+
+    /// Least recently used profile that isn't cooling down.
+    fn next_profile(&self, config: &Config) -> Option<String> {
+        config.auth.profiles.keys()
+            .map(|name| (name, self.profiles.get(name).cloned().unwrap_or_default()))
+            .filter(|(_, state)| !state.is_cooling_down())
+            .min_by_key(|(_, state)| state.last_used)
+            .map(|(name, _)| name.clone())
+    }
+
+    fn po_token_args(&self) -> Vec<String> {
+        match &self.po_token_provider {
+            Some(provider) => vec![
+                "--extractor-args".to_string(),
+                format!("youtubepot-bgutilhttp:base_url={}", provider),
+            ],
+            None => Vec::new(),
+        }
+    }
+
+    /// Warn about profiles an older version kept in `auth.json`, which are no longer read.
+    fn warn_about_stored_profiles(&self, config: &Config) {
+        for (name, state) in &self.profiles {
+            if config.auth.profiles.contains_key(name) {
+                continue;
+            }
+            let source = match (&state.browser, &state.cookies_file) {
+                (Some(browser), _) => format!("browser = \"{}\"", browser),
+                (None, Some(file)) => format!("cookies = \"{}\"", file.display()),
+                (None, None) => continue,
+            };
+            logger::warning(&format!("Cookie profile '{}' is in auth.json, which no longer holds profiles; add [auth.profiles.{}] {} to config.toml", name, name, source));
+        }
+    }
+
+    pub fn display(&self, config: &Config) {
+        logger::header("YouTube Authentication");
+
+        if !self.is_configured(config) {
+            logger::info("No credentials configured; downloads run anonymously");
+            logger::info("Set up with: rust-downloader auth setup");
+            return;
+        }
+
+        if let Some(browser) = &self.cookies_from_browser {
+            logger::info(&format!("Cookies from browser: {}", browser));
+        }
+        if let Some(provider) = &self.po_token_provider {
+            logger::info(&format!("PO token provider: {}", provider));
+        }
+        if !config.auth.profiles.is_empty() {
+            logger::info(&format!("Cookie profiles: {} (see: rust-downloader auth list)", config.auth.profiles.len()));
+        }
+        if let Some(path) = Self::path() {
+            logger::file(&format!("Stored in: {}", path.display()));
+        }
+    }
+
+    pub fn display_profiles(&self, config: &Config) {
+        logger::header("Cookie Profiles");
+        self.warn_about_stored_profiles(config);
+
+        if config.auth.profiles.is_empty() {
+            logger::info("No cookie profiles configured");
+            logger::info("Add one to config.toml: [auth.profiles.work] browser = \"chrome:Profile 2\"");
+            return;
+        }
+
+        let next = self.next_profile(config);
+        for (name, source) in &config.auth.profiles {
+            let state = self.profiles.get(name).cloned().unwrap_or_default();
+            let source = match source {
+                CookieSource::Browser(browser) => format!("browser {}", browser),
+                CookieSource::File(file) => format!("file {}", file.display()),
+            };
+            let status = match state.cooldown_until {
+                Some(until) if until > Utc::now() => format!("cooling down until {}", until.with_timezone(&chrono::Local).format("%H:%M")),
+                _ if next.as_deref() == Some(name.as_str()) => "ready (next)".to_string(),
+                _ => "ready".to_string(),
+            };
+            let last_used = state.last_used
+                .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "never".to_string());
+
+            logger::info(&format!("{:<12} {:<32} {:<28} last used {}", name, source, status, last_used));
+        }
+        logger::info(&format!("Cooldown after throttling: {} minutes", config.auth.cooldown_minutes));
+    }
+}
+
+/// Use `source` for every yt-dlp call in this run instead of the stored credentials.
+/// The PO token provider still applies.
+pub fn use_cookies(source: &CookieSource) {
+    RUN_COOKIES.set(source.clone()).ok();
+}
+
+/// Whether this run was given its own cookies with `use_cookies`.
+pub fn has_run_cookies() -> bool {
+    RUN_COOKIES.get().is_some()
+}
+
+/// Whether yt-dlp calls sign in with some account.
+pub fn is_signed_in(config: &Config) -> bool {
+    let stored = Auth::load();
+    has_run_cookies() || stored.cookies_from_browser.is_some() || !config.auth.profiles.is_empty()
+}
+
+/// yt-dlp arguments for a one-off call outside a job, e.g. reading a playlist.
+pub fn yt_dlp_args(config: &Config) -> Vec<String> {
+    Session::new().yt_dlp_args(config)
+}
+
+/// The credentials of one job. With `[auth.profiles]`, the first yt-dlp call picks the least
+/// recently used profile that isn't cooling down, and later calls reuse it, so analysis and
+/// download sign in as the same account; when YouTube throttles it, only this job moves on
+/// to the next. Clones share the choice.
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    /// `None` until the first call chooses; `Some(None)` when no profile was available
+    profile: Arc<Mutex<Option<Option<String>>>>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The profile this job signs in with, once chosen.
+    pub fn profile(&self) -> Option<String> {
+        self.profile.lock().unwrap_or_else(|e| e.into_inner()).clone().flatten()
+    }
+
+    pub fn yt_dlp_args(&self, config: &Config) -> Vec<String> {
+        let stored = Auth::load();
+        let mut args = match RUN_COOKIES.get() {
+            Some(cookies) => cookie_args(cookies),
+            None => {
+                let mut profile = self.profile.lock().unwrap_or_else(|e| e.into_inner());
+                let profile = profile.get_or_insert_with(|| select_profile(config));
+                match profile.as_ref().and_then(|name| config.auth.profiles.get(name)) {
+                    Some(source) => cookie_args(source),
+                    None => stored.cookies_from_browser.iter()
+                        .flat_map(|browser| ["--cookies-from-browser".to_string(), browser.clone()])
+                        .collect(),
+                }
+            }
+        };
+        args.extend(stored.po_token_args());
+        args
+    }
+
+    /// Put this job's profile on cooldown when YouTube throttled or bot-checked it.
+    ///
+    /// Returns `true` when another profile is available and the caller should retry.
+    pub fn rotate_after(&self, error: &DownloaderError, config: &Config) -> bool {
+        if !matches!(error.ytdlp(), Some(YtDlpError::RateLimited | YtDlpError::BotCheck)) {
+            return false;
+        }
+
+        let mut profile = self.profile.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(name) = profile.clone().flatten() else {
+            return false;
+        };
+
+        let minutes = config.auth.cooldown_minutes;
+        let rested = Auth::update(|auth| {
+            auth.profiles.entry(name.clone()).or_default().cooldown_until = Some(Utc::now() + Duration::minutes(minutes));
+        });
+        let Ok(auth) = rested else {
+            return false;
+        };
+
+        logger::warning(&format!("Cookie profile '{}' was throttled; resting it for {} minutes", name, minutes));
+        *profile = None;
+
+        if auth.next_profile(config).is_some() {
+            logger::info("Retrying with the next cookie profile...");
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Choose the next profile and mark it used in one locked update, so jobs starting together
+/// spread over the profiles instead of all taking the same one.
+fn select_profile(config: &Config) -> Option<String> {
+    if config.auth.profiles.is_empty() {
+        return None;
+    }
+
+    let mut selected = None;
+    let result = Auth::update(|auth| {
+        auth.warn_about_stored_profiles(config);
+        selected = auth.next_profile(config);
+        if let Some(name) = &selected {
+            auth.profiles.entry(name.clone()).or_default().last_used = Some(Utc::now());
+        }
+    });
+    if let Err(error) = result {
+        logger::warning(&format!("Could not record cookie profile use: {}", error));
+        selected = Auth::load().next_profile(config);
+    }
+
+    match &selected {
+        Some(name) => logger::info(&format!("Using cookie profile: {}", name)),
+        None => logger::warning("All cookie profiles are cooling down; continuing without them"),
+    }
+    selected
+}
+
+/// Walk the user through choosing a cookie browser and an optional PO token provider.
+pub fn assisted_setup() -> Result<Auth> {
+    let stored = Auth::load();
+    let mut browser = stored.cookies_from_browser;
+    let mut provider = stored.po_token_provider;
+
+    logger::header("YouTube Sign-in Setup");
+    logger::info("yt-dlp can reuse the YouTube session of a browser you are signed in with.");
+    for (index, browser) in SUPPORTED_BROWSERS.iter().enumerate() {
+        logger::info(&format!("   {}. {}", index + 1, browser));
+    }
+
+    let choice = utils::prompt("Browser to read cookies from (number or name, Enter to skip): ")?;
+    if !choice.is_empty() {
+        let chosen = choice.parse::<usize>().ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| SUPPORTED_BROWSERS.get(i).copied())
+            .or_else(|| SUPPORTED_BROWSERS.iter().copied().find(|b| b.eq_ignore_ascii_case(&choice)))
+            .ok_or_else(|| format!("Unsupported browser: {}", choice))?;
+        browser = Some(chosen.to_string());
+        if chosen == "safari" {
+            logger::warning("Reading Safari cookies requires Full Disk Access for your terminal");
+        }
+    }
+
+    logger::info("");
+    logger::info("A PO token provider (bgutil-ytdlp-pot-provider) helps when cookies alone are not enough.");
+    let answer = utils::prompt("Provider URL (e.g. http://127.0.0.1:4416, Enter to skip): ")?;
+    if !answer.is_empty() {
+        provider = Some(answer);
+    }
+
+    if browser.is_none() && provider.is_none() {
+        return Err("No credentials configured".into());
+    }
+
+    let auth = Auth::update(|auth| {
+        auth.cookies_from_browser = browser;
+        auth.po_token_provider = provider;
+    })?;
+    logger::success("Authentication settings saved");
+    Ok(auth)
+}
+
+/// When `error` is a bot check, offer the assisted setup.
+///
+/// Returns `true` when credentials were configured and the caller should retry.
+pub fn recover_from_bot_check(error: &DownloaderError) -> bool {
+    // Credentials set up now wouldn't replace the cookies given for this run
+    if error.ytdlp() != Some(&YtDlpError::BotCheck) || has_run_cookies() || !std::io::stdin().is_terminal() {
+        return false;
+    }
+
+    logger::warning("YouTube wants to confirm you're not a bot");
+    match utils::prompt("Set up browser sign-in now and retry? (Y/n): ") {
+        Ok(answer) if answer.is_empty() || answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes") => {}
+        _ => return false,
+    }
+
+    match assisted_setup() {
+        Ok(_) => {
+            logger::info("Retrying with the new credentials...");
+            true
+        }
+        Err(error) => {
+            logger::warning(&format!("Sign-in setup skipped: {}", error));
+            false
+        }
+    }
+}
+
@@ -0,0 +1,236 @@
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use tokio::process::{Child, Command};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use crate::logger;
+use crate::orphans;
+
+/// Start `command` as the leader of a new process group, so `kill_group` also stops the
+/// processes it starts itself (yt-dlp's ffmpeg merge, ffmpeg's helpers). Out of the terminal's
+/// foreground group a read from it would stop the child, so stdin is closed as well.
+fn own_process_group(command: &mut std::process::Command) -> &mut std::process::Command {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+    }
+    command.stdin(Stdio::null())
+}
+
+/// The program's file name, which `orphans` looks for in the command line of a PID.
+fn program_name(command: &std::process::Command) -> String {
+    Path::new(command.get_program()).file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+/// How `kill_group` stops a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// Ask it to exit (SIGTERM), so ffmpeg can still close its output
+    Terminate,
+    /// Stop it right away (SIGKILL), for a process that doesn't react to `Terminate`
+    Kill,
+}
+
+/// Send `signal` to the group led by `pid`, or to `pid` alone when it leads no group (e.g. it
+/// was recorded by an older version). Returns whether anything was signalled.
+#[cfg(unix)]
+pub fn kill_group(pid: u32, signal: Signal) -> bool {
+    let signal = match signal {
+        Signal::Terminate => libc::SIGTERM,
+        Signal::Kill => libc::SIGKILL,
+    };
+    unsafe { libc::kill(-(pid as libc::pid_t), signal) == 0 || libc::kill(pid as libc::pid_t, signal) == 0 }
+}
+
+/// Windows has no signals; `taskkill /T` stops `pid` with the processes it started. Console
+/// tools like ffmpeg only exit when forced, so both signals force it.
+#[cfg(windows)]
+pub fn kill_group(pid: u32, _signal: Signal) -> bool {
+    std::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Whether `pid` exists, including processes owned by another user (e.g. started with sudo).
+#[cfg(unix)]
+pub fn is_running(pid: u32) -> bool {
+    let alive = unsafe { libc::kill(pid as libc::pid_t, 0) == 0 };
+    alive || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(windows)]
+pub fn is_running(pid: u32) -> bool {
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH", "/FO", "CSV"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&format!("\"{}\"", pid)))
+}
+
+/// `Command::output` through a job's `CancelHandle` (see `CancelHandle::output`), so the
+/// command reads the same as one that isn't tracked.
+pub trait TrackedCommand {
+    fn tracked_output<'a>(&'a mut self, cancel: &'a CancelHandle) -> impl Future<Output = io::Result<Output>> + Send + 'a;
+}
+
+impl TrackedCommand for Command {
+    fn tracked_output<'a>(&'a mut self, cancel: &'a CancelHandle) -> impl Future<Output = io::Result<Output>> + Send + 'a {
+        cancel.output(self)
+    }
+}
+
+/// Shared cancellation state for a run: clones observe the same cancellation, so a signal
+/// handler can stop the external tools a downloader started and clean up after them.
+#[derive(Debug, Clone, Default)]
+pub struct CancelHandle {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+    /// PIDs of running yt-dlp/ffmpeg children, each leading its own process group
+    processes: Mutex<Vec<u32>>,
+    /// Outputs that are incomplete until the step writing them finishes
+    partial_files: Mutex<Vec<PathBuf>>,
+    /// Handles made by `child`, cancelled along with this one
+    children: Mutex<Vec<CancelHandle>>,
+}
+
+impl CancelHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once `cancel` has been called.
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// A handle for one job of a run: cancelling it leaves the other jobs running, while
+    /// cancelling `self` cancels it too.
+    pub fn child(&self) -> CancelHandle {
+        let child = CancelHandle::new();
+        // Checked under the lock, so a concurrent `cancel` either sees the child or cancelled first
+        let mut children = self.inner.children.lock().unwrap_or_else(|e| e.into_inner());
+        child.inner.cancelled.store(self.is_cancelled(), Ordering::SeqCst);
+        children.push(child.clone());
+        child
+    }
+
+    /// Also recorded in the run state file, so a run after a crash can stop `program`.
+    /// A process started after cancelling is stopped right away.
+    pub fn track_process(&self, pid: u32, program: &str) {
+        self.inner.processes.lock().unwrap_or_else(|e| e.into_inner()).push(pid);
+        orphans::track_child(pid, program);
+        if self.is_cancelled() {
+            kill_group(pid, Signal::Terminate);
+        }
+    }
+
+    /// Start `command` in its own process group and track it; call `untrack_process` once it
+    /// has exited. It is killed when the child is dropped, e.g. with a future awaiting it.
+    /// Every external tool a job runs goes through here or `output`, so cancelling, the
+    /// stall watchdog and a run after a crash can stop it with everything it started.
+    pub fn spawn(&self, command: &mut Command) -> io::Result<Child> {
+        own_process_group(command.as_std_mut());
+        let child = command.kill_on_drop(true).spawn()?;
+        if let Some(pid) = child.id() {
+            self.track_process(pid, &program_name(command.as_std()));
+        }
+        Ok(child)
+    }
+
+    /// Run `command` to the end through `spawn`, capturing its output like `Command::output`.
+    pub async fn output(&self, command: &mut Command) -> io::Result<Output> {
+        let child = self.spawn(command.stdout(Stdio::piped()).stderr(Stdio::piped()))?;
+        let pid = child.id().unwrap_or_default();
+        let output = child.wait_with_output().await;
+        self.untrack_process(pid);
+        output
+    }
+
+    /// `output` for the blocking helpers a job runs with `downloader::blocking`.
+    pub fn output_blocking(&self, command: &mut std::process::Command) -> io::Result<Output> {
+        let child = own_process_group(command).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+        let pid = child.id();
+        self.track_process(pid, &program_name(command));
+        let output = child.wait_with_output();
+        self.untrack_process(pid);
+        output
+    }
+
+    pub fn untrack_process(&self, pid: u32) {
+        self.inner.processes.lock().unwrap_or_else(|e| e.into_inner()).retain(|p| *p != pid);
+        orphans::untrack_child(pid);
+    }
+
+    pub fn track_file(&self, path: &Path) {
+        self.inner.partial_files.lock().unwrap_or_else(|e| e.into_inner()).push(path.to_path_buf());
+        orphans::track_file(path);
+    }
+
+    pub fn untrack_file(&self, path: &Path) {
+        self.inner.partial_files.lock().unwrap_or_else(|e| e.into_inner()).retain(|p| p != path);
+        orphans::untrack_file(path);
+    }
+
+    /// Stop every tracked child (also those of `child` handles) with its process group, delete
+    /// incomplete outputs and wake anything awaiting `cancelled`, then print what was cleaned up.
+    pub fn cancel(&self) {
+        let (processes, files) = self.stop();
+        let removed: Vec<&PathBuf> = files.iter().filter(|path| fs::remove_file(path).is_ok()).collect();
+
+        logger::warning("Cancelled");
+        logger::info(&format!("   • Stopped {} running process(es)", processes.len()));
+        logger::info(&format!("   • Removed {} incomplete file(s)", removed.len()));
+        for path in removed {
+            logger::file(&format!("     {}", path.file_name().unwrap_or_default().to_string_lossy()));
+        }
+        logger::info("   • Partial downloads (.part) were kept; run the same command again to resume");
+    }
+
+    /// Mark this handle and its children cancelled, stop their processes and wake anything
+    /// awaiting `cancelled`. Returns the stopped PIDs and the incomplete outputs.
+    fn stop(&self) -> (Vec<u32>, Vec<PathBuf>) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+
+        let mut processes: Vec<u32> = self.inner.processes.lock().unwrap_or_else(|e| e.into_inner()).drain(..).collect();
+        for pid in &processes {
+            kill_group(*pid, Signal::Terminate);
+        }
+        let mut files: Vec<PathBuf> = self.inner.partial_files.lock().unwrap_or_else(|e| e.into_inner()).drain(..).collect();
+
+        let children = self.inner.children.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        for child in children {
+            let (child_processes, child_files) = child.stop();
+            processes.extend(child_processes);
+            files.extend(child_files);
+        }
+
+        self.inner.notify.notify_waiters();
+        (processes, files)
+    }
+}
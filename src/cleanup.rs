@@ -3,6 +3,7 @@ use std::path::PathBuf;
 
 use std::io::Write;
 use rust_downloader::{logger, utils, Config};
+#[cfg(unix)]
 use std::os::unix::fs::MetadataExt;
 
 pub struct CleanupUtility {
@@ -12,8 +13,7 @@ pub struct CleanupUtility {
 }
 
 impl CleanupUtility {
-    pub fn new() -> Self {
-        let config = Config::default();
+    pub fn new(config: &Config) -> Self {
         let output_dir = config.output_dir.clone();
         let backup_dir = output_dir.join(config.video_settings.backup_dir);
         
@@ -93,10 +93,13 @@ impl CleanupUtility {
                 Ok(stats) => {
                     let size = utils::format_file_size(Some(stats.len()));
                     let relative_path = std::path::Path::new(".")
-                        .join(file_path.strip_prefix(&std::env::current_dir().unwrap_or(PathBuf::from("."))).unwrap_or(file_path));
+                        .join(file_path.strip_prefix(std::env::current_dir().unwrap_or(PathBuf::from("."))).unwrap_or(file_path));
                     
                     println!("   {}. {}", index + 1, relative_path.display());
+                    #[cfg(unix)]
                     println!("      Size: {} | Owner: {}", size, stats.uid());
+                    #[cfg(not(unix))]
+                    println!("      Size: {}", size);
                 }
                 Err(_) => {
                     println!("   {}. {} (error reading stats)", index + 1, file_path.display());
@@ -124,7 +127,7 @@ impl CleanupUtility {
         Ok(match choice {
             "1" => "fix".to_string(),
             "2" => "delete".to_string(),
-            "3" | _ => "exit".to_string(),
+            _ => "exit".to_string(),
         })
     }
 
@@ -230,7 +233,8 @@ impl CleanupUtility {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     logger::init();
     
-    let mut cleanup = CleanupUtility::new();
+    let config = Config::load(None)?;
+    let mut cleanup = CleanupUtility::new(&config);
     
     match cleanup.run().await {
         Ok(_) => {
@@ -1,8 +1,14 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::env;
+use std::time::Duration;
+use serde::Deserialize;
+use crate::encoders::Encoder;
+use crate::error::Result;
+use crate::logger;
+use crate::config_migration::{self, Migration};
 
-#[path = "logger.rs"]
-mod logger;
+const CONFIG_FILE: &str = "config.toml";
 
 #[derive(Debug, Clone)]
 pub struct VideoPreferences {
@@ -11,6 +17,12 @@ pub struct VideoPreferences {
     pub max_resolution: u32,
     pub prefer_high_fps: bool,
     pub prefer_60fps: bool,
+    /// Print why each candidate format ranked where it did (`--explain-selection`)
+    pub explain_selection: bool,
+    /// Only royalty-free codecs (`--free-formats`); see `Config::use_free_formats`
+    pub free_formats: Option<FreeFormats>,
+    /// Only videos under this license (`--license`), for searches and analysis alike
+    pub license: Option<LicenseFilter>,
 }
 
 #[derive(Debug, Clone)]
@@ -33,6 +45,462 @@ pub struct DownloadSettings {
     pub use_hevc: bool,
     pub target_frame_rate: u32,
     pub target_resolution: &'static str,
+    /// Download only the first N seconds (`--sample`) to try the pipeline quickly
+    pub sample_seconds: Option<u32>,
+    /// Download only this part of the video (`--clip`, `--start`/`--end`)
+    pub clip: Option<ClipRange>,
+    /// What to do with SponsorBlock segments (`--sponsorblock`); `None` leaves the video as is
+    pub sponsorblock: Option<SponsorBlockMode>,
+    /// Cookies for this run (`--cookies`, `--cookies-from-browser`), used instead of `auth` settings
+    pub cookies: Option<CookieSource>,
+    /// Save YouTube's largest thumbnail and the first frame as a poster next to the video (`--thumbnail`)
+    pub save_thumbnail: bool,
+    /// Playlists being downloaded by a music profile, whose videos are named as their tracks
+    pub albums: Vec<crate::playlist::Album>,
+    /// Stop and retry a download that receives nothing for this long; `None` waits forever
+    pub stall_timeout: Option<Duration>,
+    /// The same video elsewhere (`--mirror`), tried in order when the URL fails for good
+    pub alternate_urls: Vec<String>,
+    /// Parts of a download fetched at once: yt-dlp's `--concurrent-fragments` for DASH/HLS, and
+    /// byte ranges of a directly fetched file
+    pub concurrent_fragments: u32,
+}
+
+/// Where yt-dlp reads the signed-in session from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CookieSource {
+    /// An exported cookies.txt file
+    File(PathBuf),
+    /// A browser, optionally with a browser profile (e.g. "chrome:Profile 2")
+    Browser(String),
+}
+
+/// Handling of SponsorBlock segments after downloading
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SponsorBlockMode {
+    /// Cut the segments out
+    Remove,
+    /// Keep the video whole and add a chapter for each segment
+    Mark,
+}
+
+/// What `--free-formats` does after downloading VP9/AV1 and Opus into WebM
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FreeFormats {
+    /// Keep the WebM as downloaded, skipping the HEVC conversion
+    Keep,
+    /// Re-encode to AV1 and Opus (libsvtav1), e.g. to shrink VP9 sources
+    Av1,
+}
+
+/// License a video must carry to be downloaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+pub enum LicenseFilter {
+    /// Creative Commons Attribution, YouTube's only reusable license
+    #[value(name = "creativecommons", alias = "cc")]
+    #[serde(rename = "creativecommons")]
+    CreativeCommons,
+}
+
+impl LicenseFilter {
+    /// YouTube's `sp` search parameter that returns only videos under this license
+    pub fn search_filter(self) -> &'static str {
+        match self {
+            LicenseFilter::CreativeCommons => "EgIwAQ%3D%3D",
+        }
+    }
+
+    /// Whether the `license` field yt-dlp reports satisfies the filter
+    pub fn allows(self, license: Option<&str>) -> bool {
+        match self {
+            LicenseFilter::CreativeCommons => license.is_some_and(|l| l.to_lowercase().contains("creative commons")),
+        }
+    }
+}
+
+/// Audio visualizer baked into the wallpaper from the source's audio track
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum AudioVisualizer {
+    /// Translucent waveform along the bottom edge
+    Waves,
+    /// Faded frequency spectrum along the bottom edge
+    Spectrum,
+}
+
+/// Image format for `--still` exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StillFormat {
+    Png,
+    /// Converted with `sips`, falls back to PNG when unavailable
+    Heic,
+}
+
+/// Export the best frame as a still image, optionally applying it as the static desktop picture
+#[derive(Debug, Clone)]
+pub struct StillExport {
+    pub format: StillFormat,
+    pub set_wallpaper: bool,
+}
+
+/// Additional downscaled rendition produced alongside the main 4K conversion
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputResolution {
+    pub label: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl OutputResolution {
+    /// Parse a 16:9 preset such as `1080p` or `1440p`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let label = value.trim().to_lowercase();
+        let height: u32 = label.strip_suffix('p')
+            .and_then(|h| h.parse().ok())
+            .ok_or_else(|| format!("Invalid resolution '{}', expected e.g. 1080p", value))?;
+
+        if !(360..2160).contains(&height) {
+            return Err(format!("Resolution '{}' must be between 360p and 1440p", value));
+        }
+
+        // Keep both dimensions even for the encoder
+        let width = (height * 16 / 9 + 1) & !1;
+        Ok(Self { label, width, height })
+    }
+}
+
+/// Part of the video to keep (`--clip`, `--start`/`--end`), in seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipRange {
+    pub start: f64,
+    /// `None` runs to the end of the video
+    pub end: Option<f64>,
+}
+
+impl ClipRange {
+    pub fn new(start: f64, end: Option<f64>) -> Result<Self, String> {
+        match end {
+            Some(end) if end <= start => Err(format!("The clip must end after it starts ({}s-{}s)", start, end)),
+            _ => Ok(Self { start, end }),
+        }
+    }
+
+    /// Parse `START-END`, e.g. `00:01:30-00:02:45` or `90-165`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (start, end) = value.split_once('-')
+            .ok_or_else(|| format!("Invalid clip '{}', expected START-END such as 1:30-2:45", value))?;
+        Self::new(parse_timestamp(start)?, Some(parse_timestamp(end)?))
+    }
+
+    /// Value for yt-dlp's `--download-sections`
+    pub fn sections(&self) -> String {
+        let end = self.end.map(|end| end.to_string()).unwrap_or_else(|| "inf".to_string());
+        format!("*{}-{}", self.start, end)
+    }
+
+    /// Suffix that keeps clips apart from the full video and from each other
+    pub fn label(&self) -> String {
+        match self.end {
+            Some(end) => format!("clip{}-{}s", self.start as u64, end.ceil() as u64),
+            None => format!("clip{}s-end", self.start as u64),
+        }
+    }
+}
+
+/// Parse a timestamp given as seconds (`90`, `90.5`) or `[HH:]MM:SS[.ms]`.
+pub fn parse_timestamp(value: &str) -> Result<f64, String> {
+    let parts: Vec<&str> = value.trim().split(':').collect();
+    if parts.len() > 3 {
+        return Err(format!("Invalid time '{}', expected seconds or HH:MM:SS", value));
+    }
+
+    parts.iter().try_fold(0.0, |total, part| {
+        match part.parse::<f64>() {
+            Ok(n) if n >= 0.0 => Ok(total * 60.0 + n),
+            _ => Err(format!("Invalid time '{}', expected seconds or HH:MM:SS", value)),
+        }
+    })
+}
+
+/// Corner (or center) where baked overlay text is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OverlayPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center,
+}
+
+/// Caption baked into the video with ffmpeg's drawtext filter.
+/// The template accepts {title}, {uploader} and {date} placeholders.
+#[derive(Debug, Clone)]
+pub struct TextOverlay {
+    pub template: String,
+    pub position: OverlayPosition,
+    pub font: Option<String>,
+    pub size: u32,
+    pub opacity: f32,
+}
+
+/// Short looping output made instead of the .mov (`--format`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LoopFormat {
+    /// Animated GIF with a palette generated from the clip
+    Gif,
+    /// Silent VP9 WebM, much smaller than a GIF at the same quality
+    WebmLoop,
+}
+
+/// File written by `--audio-only`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioOutputFormat {
+    /// AAC in .m4a
+    M4a,
+    /// Opus in .opus (Ogg)
+    Opus,
+    Mp3,
+    Flac,
+}
+
+impl AudioOutputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            AudioOutputFormat::M4a => "m4a",
+            AudioOutputFormat::Opus => "opus",
+            AudioOutputFormat::Mp3 => "mp3",
+            AudioOutputFormat::Flac => "flac",
+        }
+    }
+
+    /// ffmpeg encoder and quality options used when the source has to be transcoded.
+    pub fn encoder_args(self) -> &'static [&'static str] {
+        match self {
+            AudioOutputFormat::M4a => &["-c:a", "aac", "-b:a", "256k"],
+            AudioOutputFormat::Opus => &["-c:a", "libopus", "-b:a", "160k"],
+            AudioOutputFormat::Mp3 => &["-c:a", "libmp3lame", "-q:a", "0"],
+            AudioOutputFormat::Flac => &["-c:a", "flac"],
+        }
+    }
+
+    /// The format that holds `codec` as it is, from ffprobe (`aac`) or yt-dlp (`mp4a.40.2`).
+    pub fn for_codec(codec: &str) -> Option<Self> {
+        match codec.split('.').next()? {
+            "aac" | "mp4a" => Some(AudioOutputFormat::M4a),
+            "opus" => Some(AudioOutputFormat::Opus),
+            "mp3" => Some(AudioOutputFormat::Mp3),
+            "flac" => Some(AudioOutputFormat::Flac),
+            _ => None,
+        }
+    }
+}
+
+/// Audio changes made during conversion and `--audio-only` extraction
+#[derive(Debug, Clone, Default)]
+pub struct AudioProcessing {
+    /// Two-pass EBU R128 loudness normalization (`--normalize`)
+    pub normalize: bool,
+    /// Re-encode the audio at this many kbit/s (`--audio-bitrate`)
+    pub bitrate: Option<u32>,
+    /// Drop the audio track (`--strip-audio`)
+    pub strip: bool,
+}
+
+impl AudioProcessing {
+    /// Whether the audio can't simply be copied.
+    pub fn reencodes(&self) -> bool {
+        self.normalize || self.bitrate.is_some()
+    }
+}
+
+/// Tags written to `--audio-only` files (`[audio_tags]`)
+#[derive(Debug, Clone)]
+pub struct AudioTags {
+    /// ffmpeg tag name and template, e.g. `("artist", "{uploader}")`. Placeholders are
+    /// `{title}`, `{uploader}`, `{date}`, `{year}`, `{id}`, `{url}` and `{license}`.
+    pub templates: Vec<(String, String)>,
+    /// Embed the video thumbnail as cover art
+    pub cover_art: bool,
+}
+
+impl Default for AudioTags {
+    fn default() -> Self {
+        Self {
+            templates: [("title", "{title}"), ("artist", "{uploader}"), ("date", "{date}"), ("comment", "{url}"), ("copyright", "{license}")]
+                .into_iter()
+                .map(|(key, template)| (key.to_string(), template.to_string()))
+                .collect(),
+            cover_art: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoopSettings {
+    pub fps: u32,
+    /// Narrower sources are left at their own width
+    pub max_width: u32,
+    /// How many times the loop plays; 0 repeats forever (GIF) or leaves it to the player (WebM)
+    pub loop_count: u32,
+}
+
+/// Video codec of a conversion profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfileCodec {
+    /// H.265 through the detected hardware encoder or libx265
+    Hevc,
+    /// Apple ProRes 422 HQ (prores_ks), for editing
+    Prores,
+    /// Lossless FFV1, for archiving
+    Ffv1,
+}
+
+/// Named target format of the conversion (`--profile`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionProfile {
+    pub name: String,
+    pub codec: ProfileCodec,
+    /// `None` keeps the source resolution
+    pub resolution: Option<(u32, u32)>,
+    /// `None` keeps the source frame rate
+    pub frame_rate: Option<u32>,
+    /// Mbps; `None` for codecs that pick their own (ProRes, FFV1)
+    pub bitrate: Option<u32>,
+    pub pixel_format: String,
+    /// File extension of the output, e.g. `mov` or `mkv`
+    pub container: String,
+    /// Longer videos are cut to this many seconds
+    pub max_duration: Option<u32>,
+    /// Keep the audio track
+    pub audio: bool,
+    /// Keep only the audio (like `--audio-only`), filing playlists as albums
+    pub music: bool,
+}
+
+impl ConversionProfile {
+    /// The macOS live wallpaper format, used unless another profile is chosen
+    pub const DEFAULT: &'static str = "wallpaper-4k60";
+    /// Portrait movie half of a `--live-photo`
+    pub const LIVE_PHOTO: &'static str = "live-photo";
+    /// Apple TV aerial format; conversions are also added to the aerial folder
+    pub const AERIAL: &'static str = "aerial-4k";
+    /// Audio only; playlists become `{playlist}/{index} - {title}` folders with an .m3u
+    pub const MUSIC: &'static str = "music";
+
+    pub fn builtin() -> Vec<ConversionProfile> {
+        let profile = |name: &str, codec, resolution, frame_rate, bitrate, pixel_format: &str, container: &str| ConversionProfile {
+            name: name.to_string(),
+            codec,
+            resolution,
+            frame_rate,
+            bitrate,
+            pixel_format: pixel_format.to_string(),
+            container: container.to_string(),
+            max_duration: None,
+            audio: true,
+            music: false,
+        };
+        vec![
+            profile(Self::DEFAULT, ProfileCodec::Hevc, Some((3840, 2160)), Some(60), Some(50), "yuv420p10le", "mov"),
+            profile("archive-lossless", ProfileCodec::Ffv1, None, None, None, "yuv420p10le", "mkv"),
+            profile("mobile-1080p", ProfileCodec::Hevc, Some((1920, 1080)), Some(30), Some(8), "yuv420p", "mp4"),
+            profile("prores", ProfileCodec::Prores, None, None, None, "yuv422p10le", "mov"),
+            profile(Self::LIVE_PHOTO, ProfileCodec::Hevc, Some((1080, 1920)), Some(30), Some(12), "yuv420p", "mov"),
+            // Apple's own aerials are silent 8-bit SDR HEVC at 24fps and run a few minutes
+            ConversionProfile {
+                max_duration: Some(600),
+                audio: false,
+                ..profile(Self::AERIAL, ProfileCodec::Hevc, Some((3840, 2160)), Some(24), Some(25), "yuv420p", "mov")
+            },
+            // The video settings are never used, since nothing is converted to video
+            ConversionProfile { music: true, ..profile(Self::MUSIC, ProfileCodec::Hevc, None, None, None, "yuv420p", "mov") },
+        ]
+    }
+
+    /// HEVC in a .mov, which macOS can install as a wallpaper
+    pub fn is_wallpaper(&self) -> bool {
+        self.codec == ProfileCodec::Hevc && self.container == "mov"
+    }
+
+    pub fn ten_bit(&self) -> bool {
+        self.pixel_format.contains("10")
+    }
+
+    /// Where the conversion of `input` goes: `<name>.mov` for the default profile, otherwise
+    /// the profile name is added so outputs of different profiles don't overwrite each other.
+    pub fn output_path(&self, input: &Path) -> PathBuf {
+        if self.name == Self::DEFAULT {
+            return input.with_extension(&self.container);
+        }
+        let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+        input.with_file_name(format!("{}_{}.{}", stem, self.name, self.container))
+    }
+
+    /// `WIDTHxHEIGHT`, a 16:9 preset like `1080p`, or `source`.
+    fn parse_resolution(value: &str) -> Result<Option<(u32, u32)>, String> {
+        let value = value.trim().to_lowercase();
+        if value == "source" {
+            return Ok(None);
+        }
+        let size = match value.strip_suffix('p') {
+            Some(height) => height.parse::<u32>().ok().map(|h| ((h * 16 / 9 + 1) & !1, h)),
+            None => value.split_once('x').and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?))),
+        };
+        match size {
+            Some((width, height)) if width >= 16 && height >= 16 && width % 2 == 0 && height % 2 == 0 => Ok(Some((width, height))),
+            _ => Err(format!("Invalid profile resolution '{}', expected e.g. 1080p, 1920x1080 or source", value)),
+        }
+    }
+
+    /// Apply a `[profiles.NAME]` table on top of this profile.
+    fn apply(&mut self, file: &ProfileFile) -> Result<(), String> {
+        if let Some(codec) = file.codec {
+            self.codec = codec;
+        }
+        if let Some(resolution) = &file.resolution {
+            self.resolution = Self::parse_resolution(resolution)?;
+        }
+        if let Some(rate) = file.frame_rate {
+            self.frame_rate = Some(rate).filter(|r| *r > 0);
+        }
+        if let Some(bitrate) = file.bitrate {
+            self.bitrate = Some(bitrate).filter(|b| *b > 0);
+        }
+        if let Some(format) = &file.pixel_format {
+            self.pixel_format = format.clone();
+        }
+        if let Some(container) = &file.container {
+            self.container = container.trim_start_matches('.').to_string();
+        }
+        if let Some(seconds) = file.max_duration {
+            self.max_duration = Some(seconds).filter(|s| *s > 0);
+        }
+        if let Some(audio) = file.audio {
+            self.audio = audio;
+        }
+        if let Some(music) = file.music {
+            self.music = music;
+        }
+        Ok(())
+    }
+}
+
+/// When a source is "good enough" to be remuxed into .mov instead of re-encoded
+#[derive(Debug, Clone)]
+pub struct RemuxSettings {
+    /// Video codecs (ffprobe names) that can be copied as-is
+    pub allowed_codecs: Vec<String>,
+    /// Source containers (ffprobe format names) that can be remuxed
+    pub containers: Vec<String>,
+    /// Minimum video bitrate in bits per second
+    pub min_bitrate: u64,
+    pub min_frame_rate: f64,
+    /// Always re-encode (`--force-reencode`)
+    pub force_reencode: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -42,18 +510,107 @@ pub struct ConversionSettings {
     pub fallback_bitrates: Vec<&'static str>,
     pub fallback_frame_rates: Vec<u32>,
     pub conservative_mode: bool,
+    pub audio_visualizer: Option<AudioVisualizer>,
+    pub text_overlay: Option<TextOverlay>,
+    pub auto_trim: bool,
+    /// Scene-change score (0.0-1.0) to split at; `None` keeps the video whole
+    pub split_scenes: Option<f64>,
+    /// Cut the video into one file per chapter
+    pub split_chapters: bool,
+    pub still: Option<StillExport>,
+    pub extra_resolutions: Vec<OutputResolution>,
+    /// Run the encoder at reduced scheduling priority so the machine stays responsive
+    pub background_encode: bool,
+    /// ffmpeg `-threads`; `None` lets ffmpeg decide
+    pub threads: Option<u32>,
+    pub remux: RemuxSettings,
+    /// GIF or looping WebM instead of the .mov (`--format`)
+    pub loop_format: Option<LoopFormat>,
+    pub loop_settings: LoopSettings,
+    /// HEVC encoder (`--encoder`); `None` picks the best one that works on this machine
+    pub encoder: Option<Encoder>,
+    /// Built-in and config-file profiles
+    pub profiles: Vec<ConversionProfile>,
+    /// Name of the profile in `profiles` to convert with (`--profile`)
+    pub profile: String,
+    /// Make an iOS Live Photo (movie plus key photo) instead of a video (`--live-photo`)
+    pub live_photo: bool,
+    /// Download and keep only the audio (`--audio-only`)
+    pub audio_only: bool,
+    /// What `--audio-only` writes; `None` keeps AAC, Opus, MP3 or FLAC as it is and makes .m4a of the rest
+    pub audio_format: Option<AudioOutputFormat>,
+    pub audio: AudioProcessing,
+    pub audio_tags: AudioTags,
+    /// Stop an ffmpeg run whose output position doesn't move for this long, which then fails
+    /// the attempt (and falls back to software encoding); `None` waits forever
+    pub stall_timeout: Option<Duration>,
+}
+
+impl ConversionSettings {
+    pub fn find_profile(&self, name: &str) -> Option<&ConversionProfile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    /// The selected profile; names are checked when set, so it is always one of `profiles`.
+    pub fn profile(&self) -> &ConversionProfile {
+        self.find_profile(&self.profile).unwrap_or(&self.profiles[0])
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct VideoSettings {
-    pub customer_dir: &'static str,
-    pub target_sub_dir: &'static str,
+    /// macOS folder holding the downloaded system aerials (`customer_dir` under `[installation.macos]`)
+    pub customer_dir: PathBuf,
+    /// Folder inside `customer_dir` whose videos are replaced, which differs on some macOS betas
+    pub target_sub_dir: String,
     pub backup_dir: &'static str,
     pub required_format: &'static str,
     pub min_recommended_resolution: u32,
     pub min_recommended_duration: u32,
     pub max_retry_attempts: u32,
     pub retry_interval: u64,
+    /// Seconds to sample wallpaper CPU usage before and after installing; `None` skips the check
+    pub impact_window: Option<u64>,
+    /// Install on this display only (numbered from 1, main display first); `None` uses the usual flow
+    pub display: Option<usize>,
+    /// Scratch folder standing in for the system (`--sandbox`): installs write only there, and
+    /// skip sudo, daemon restarts and System Settings
+    pub sandbox: Option<PathBuf>,
+}
+
+impl VideoSettings {
+    /// The folder whose videos are replaced
+    pub fn target_dir(&self) -> PathBuf {
+        self.customer_dir.join(&self.target_sub_dir)
+    }
+
+    /// Point installation at `dir` itself (`--target-dir`), e.g. a sandbox copy for testing.
+    pub fn set_target_dir(&mut self, dir: &Path) -> Result<(), String> {
+        let name = dir.file_name().and_then(|name| name.to_str())
+            .ok_or_else(|| format!("Invalid target directory '{}'", dir.display()))?;
+        self.target_sub_dir = name.to_string();
+        self.customer_dir = dir.parent().map(Path::to_path_buf).unwrap_or_default();
+        self.validate()
+    }
+
+    /// Install into `dir` instead of the system: `<dir>/Customer/<target_sub_dir>` for the
+    /// system videos, with backups next to it.
+    pub fn use_sandbox(&mut self, dir: &Path) {
+        self.customer_dir = dir.join("Customer");
+        self.sandbox = Some(dir.to_path_buf());
+    }
+
+    /// `customer_dir` must be absolute and `target_sub_dir` a single folder name inside it.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.customer_dir.is_absolute() {
+            return Err(format!("customer_dir '{}' must be an absolute path", self.customer_dir.display()));
+        }
+        let mut components = Path::new(&self.target_sub_dir).components();
+        match (components.next(), components.next()) {
+            (Some(std::path::Component::Normal(_)), None) => Ok(()),
+            _ => Err(format!("target_sub_dir '{}' must be a single folder name, e.g. 4KSDR240FPS", self.target_sub_dir)),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -105,10 +662,344 @@ pub struct FileNamingConfig {
     pub template: &'static str,
 }
 
+/// Settings that can be set in `config.toml`; anything left out keeps its default.
+///
+/// ```toml
+/// version = 1
+/// output_dir = "~/Movies/Wallpapers"
+///
+/// [download]
+/// retry_attempts = 5
+///
+/// [conversion]
+/// extra_resolutions = ["1080p"]
+/// ```
+///
+/// Older layouts are upgraded by `config_migration` before deserializing.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub version: Option<i64>,
+    pub output_dir: Option<String>,
+    pub enable_video: Option<bool>,
+    pub check_for_updates: Option<bool>,
+    pub auto_install_deps: Option<bool>,
+    pub status_file: Option<String>,
+    pub video: VideoFile,
+    pub download: DownloadFile,
+    pub conversion: ConversionFile,
+    pub installation: InstallationFile,
+    pub logging: LoggingFile,
+    pub naming: NamingFile,
+    pub history: HistoryFile,
+    pub notifications: NotificationsFile,
+    pub sync: SyncFile,
+    pub mirrors: MirrorsFile,
+    pub auth: AuthFile,
+    pub audio_tags: AudioTagsFile,
+    pub profiles: BTreeMap<String, ProfileFile>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VideoFile {
+    pub max_resolution: Option<u32>,
+    pub prefer_high_fps: Option<bool>,
+    pub free_formats: Option<FreeFormats>,
+    pub license: Option<LicenseFilter>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DownloadFile {
+    pub retry_attempts: Option<u32>,
+    pub embed_subtitles: Option<bool>,
+    pub embed_thumbnail: Option<bool>,
+    pub skip_sponsors: Option<bool>,
+    pub sponsorblock: Option<SponsorBlockMode>,
+    pub thumbnail: Option<bool>,
+    /// Path to an exported cookies.txt file
+    pub cookies: Option<String>,
+    pub cookies_from_browser: Option<String>,
+    /// Minutes without data before a download is retried; 0 waits forever
+    pub stall_minutes: Option<u32>,
+    pub concurrent_fragments: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConversionFile {
+    pub max_attempts: Option<u32>,
+    pub auto_trim: Option<bool>,
+    pub background_encode: Option<bool>,
+    pub threads: Option<u32>,
+    pub force_reencode: Option<bool>,
+    /// ffprobe codec names a source may have to be remuxed, e.g. `["hevc", "h264"]`
+    pub remux_codecs: Option<Vec<String>>,
+    /// ffprobe container names a source may have to be remuxed
+    pub remux_containers: Option<Vec<String>>,
+    /// Mbps
+    pub remux_min_bitrate: Option<f64>,
+    pub remux_min_frame_rate: Option<f64>,
+    /// Extra renditions, e.g. `["1080p", "1440p"]`
+    pub extra_resolutions: Option<Vec<String>>,
+    pub format: Option<LoopFormat>,
+    pub loop_fps: Option<u32>,
+    pub loop_max_width: Option<u32>,
+    pub loop_count: Option<u32>,
+    pub encoder: Option<Encoder>,
+    pub profile: Option<String>,
+    pub audio_only: Option<bool>,
+    pub audio_format: Option<AudioOutputFormat>,
+    pub normalize: Option<bool>,
+    /// kbit/s
+    pub audio_bitrate: Option<u32>,
+    pub strip_audio: Option<bool>,
+    /// Minutes without progress before an encode is stopped; 0 waits forever
+    pub stall_minutes: Option<u32>,
+}
+
+/// A `[profiles.NAME]` table; a built-in name changes that profile, a new name starts from
+/// `wallpaper-4k60`. `resolution = "source"`, `frame_rate = 0` and `bitrate = 0` keep the
+/// source's value or leave it to the codec; `max_duration = 0` keeps the whole video.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileFile {
+    pub codec: Option<ProfileCodec>,
+    pub resolution: Option<String>,
+    pub frame_rate: Option<u32>,
+    pub bitrate: Option<u32>,
+    pub pixel_format: Option<String>,
+    pub container: Option<String>,
+    pub max_duration: Option<u32>,
+    pub audio: Option<bool>,
+    pub music: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyncFile {
+    /// Feeds, playlists and channels `sync` reads when none are given
+    pub sources: Option<Vec<String>>,
+}
+
+/// `[mirrors]`: API base URLs, e.g. `piped = ["https://pipedapi.kavin.rocks"]`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MirrorsFile {
+    pub piped: Vec<String>,
+    pub invidious: Vec<String>,
+}
+
+/// `[auth]`: signed-in accounts that downloads take turns with, as
+/// `[auth.profiles.work] browser = "chrome:Profile 2"` or `cookies = "~/work-cookies.txt"`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthFile {
+    /// How long a profile rests after YouTube throttles or bot-checks it
+    pub cooldown_minutes: Option<i64>,
+    pub profiles: BTreeMap<String, AuthProfileFile>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthProfileFile {
+    pub browser: Option<String>,
+    /// Path to an exported cookies.txt file
+    pub cookies: Option<String>,
+}
+
+/// Cookie profiles rotated across jobs; see `auth::Session`
+#[derive(Debug, Clone)]
+pub struct AuthSettings {
+    pub profiles: BTreeMap<String, CookieSource>,
+    pub cooldown_minutes: i64,
+}
+
+/// A Piped or Invidious API instance, by base URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mirror {
+    Piped(String),
+    Invidious(String),
+}
+
+/// `[audio_tags]`; `[audio_tags.template]` adds or replaces tags, and an empty template
+/// leaves that tag out.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AudioTagsFile {
+    pub cover_art: Option<bool>,
+    pub template: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InstallationFile {
+    pub min_recommended_resolution: Option<u32>,
+    pub min_recommended_duration: Option<u32>,
+    pub aerial_dir: Option<String>,
+    pub macos: MacosInstallFile,
+}
+
+/// `[installation.macos]`: where the macOS backend finds the system videos it replaces
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MacosInstallFile {
+    pub customer_dir: Option<String>,
+    pub target_sub_dir: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingFile {
+    pub level: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NamingFile {
+    pub max_title_length: Option<usize>,
+}
+
+/// Storage used for the download history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryBackend {
+    /// `history.db`, queryable with any SQLite client (requires the `sqlite` feature)
+    Sqlite,
+    /// `history.json`, no native dependency
+    Json,
+}
+
+impl Default for HistoryBackend {
+    fn default() -> Self {
+        if cfg!(feature = "sqlite") {
+            HistoryBackend::Sqlite
+        } else {
+            HistoryBackend::Json
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryFile {
+    pub backend: Option<HistoryBackend>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationsFile {
+    pub smtp: Option<SmtpSettings>,
+    pub mqtt: Option<MqttSettings>,
+}
+
+/// `[notifications.smtp]`: mail a report when a download queue finishes.
+/// Sent with curl; port 465 uses implicit TLS, any other port requires STARTTLS.
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SmtpSettings {
+    pub server: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Overridden by RUST_DOWNLOADER_SMTP_PASSWORD, which keeps it out of the file
+    #[serde(default)]
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    /// Skip the mail when every download succeeded
+    #[serde(default)]
+    pub only_on_failure: bool,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+// The config is dumped into diagnostic bundles, so never print the password
+impl std::fmt::Debug for SmtpSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SmtpSettings")
+            .field("server", &self.server)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .field("only_on_failure", &self.only_on_failure)
+            .finish()
+    }
+}
+
+/// `[notifications.mqtt]`: publish job state and daemon health for home automation.
+/// Plain TCP (no TLS), as used by a broker on the local network.
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MqttSettings {
+    pub host: String,
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+    /// Prefix for `<topic>/job`, `<topic>/queue` and `<topic>/daemon`
+    #[serde(default = "default_mqtt_topic")]
+    pub topic: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Overridden by RUST_DOWNLOADER_MQTT_PASSWORD
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_topic() -> String {
+    "rust-downloader".to_string()
+}
+
+/// `stall_minutes` from the config file; 0 turns the watchdog off.
+fn stall_timeout(minutes: u32) -> Option<Duration> {
+    (minutes > 0).then(|| Duration::from_secs(minutes as u64 * 60))
+}
+
+impl std::fmt::Debug for MqttSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MqttSettings")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("topic", &self.topic)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+/// SponsorBlock categories handled by `--sponsorblock`
+pub const SPONSORBLOCK_CATEGORIES: [&str; 5] = ["sponsor", "intro", "outro", "selfpromo", "interaction"];
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub enable_video: bool,
     pub output_dir: PathBuf,
+    /// Opt-in check for a newer release at startup (RUST_DOWNLOADER_UPDATE_CHECK=1)
+    pub check_for_updates: bool,
+    /// Install a missing ffmpeg without asking (`--auto-install-deps`; needs `managed-ffmpeg`)
+    pub auto_install_deps: bool,
+    pub history_backend: HistoryBackend,
+    /// JSON file with live job progress for menu bar plugins and widgets
+    pub status_file: Option<PathBuf>,
+    pub smtp: Option<SmtpSettings>,
+    pub mqtt: Option<MqttSettings>,
+    /// Feeds, playlists and channels `sync` downloads from when given none (`[sync] sources`)
+    pub sync_sources: Vec<String>,
+    /// Instances to extract through, in order, when YouTube blocks or throttles yt-dlp
+    /// (`[mirrors]`; needs the `mirrors` feature)
+    pub mirrors: Vec<Mirror>,
+    pub auth: AuthSettings,
+    /// Folder `aerial-4k` conversions are copied to, with an Apple TV style `entries.json`
+    pub aerial_dir: PathBuf,
     
     pub video_preferences: VideoPreferences,
     pub audio_preferences: AudioPreferences,
@@ -120,11 +1011,24 @@ pub struct Config {
     pub file_naming: FileNamingConfig,
 }
 
-impl Config {
-    pub fn default() -> Self {
+impl Default for Config {
+    fn default() -> Self {
         Self {
             enable_video: false,
             output_dir: Self::expand_tilde("~/Downloads"),
+            check_for_updates: false,
+            auto_install_deps: false,
+            history_backend: HistoryBackend::default(),
+            status_file: None,
+            smtp: None,
+            mqtt: None,
+            sync_sources: vec![crate::playlist::DEFAULT_SOURCE.to_string()],
+            mirrors: Vec::new(),
+            auth: AuthSettings {
+                profiles: BTreeMap::new(),
+                cooldown_minutes: 60,
+            },
+            aerial_dir: Self::expand_tilde("~/Movies/Aerials"),
             
             video_preferences: VideoPreferences {
                 preferred_formats: vec!["mp4", "mkv", "webm"],
@@ -132,6 +1036,9 @@ impl Config {
                 max_resolution: 2160, // 4K
                 prefer_high_fps: true,
                 prefer_60fps: true,
+                explain_selection: false,
+                free_formats: None,
+                license: None,
             },
             
             audio_preferences: AudioPreferences {
@@ -152,6 +1059,15 @@ impl Config {
                 use_hevc: true,
                 target_frame_rate: 60,
                 target_resolution: "3840x2160",
+                sample_seconds: None,
+                clip: None,
+                sponsorblock: None,
+                cookies: None,
+                save_thumbnail: false,
+                albums: Vec::new(),
+                stall_timeout: Some(Duration::from_secs(5 * 60)),
+                alternate_urls: Vec::new(),
+                concurrent_fragments: 1,
             },
 
             conversion_settings: ConversionSettings {
@@ -160,21 +1076,55 @@ impl Config {
                 fallback_bitrates: vec!["50M", "30M", "20M", "10M"],
                 fallback_frame_rates: vec![60, 30, 24],
                 conservative_mode: false,
+                audio_visualizer: None,
+                text_overlay: None,
+                auto_trim: false,
+                split_scenes: None,
+                split_chapters: false,
+                still: None,
+                extra_resolutions: Vec::new(),
+                background_encode: false,
+                threads: None,
+                remux: RemuxSettings {
+                    allowed_codecs: vec!["hevc".to_string()],
+                    containers: vec!["mp4".to_string(), "mov".to_string()],
+                    min_bitrate: 20_000_000,
+                    min_frame_rate: 30.0,
+                    force_reencode: false,
+                },
+                loop_format: None,
+                loop_settings: LoopSettings {
+                    fps: 15,
+                    max_width: 640,
+                    loop_count: 0,
+                },
+                encoder: None,
+                profiles: ConversionProfile::builtin(),
+                profile: ConversionProfile::DEFAULT.to_string(),
+                live_photo: false,
+                audio_only: false,
+                audio_format: None,
+                audio: AudioProcessing::default(),
+                audio_tags: AudioTags::default(),
+                stall_timeout: Some(Duration::from_secs(10 * 60)),
             },
 
             video_settings: VideoSettings {
-                customer_dir: "/Library/Application Support/com.apple.idleassetsd/Customer",
-                target_sub_dir: "4KSDR240FPS",
+                customer_dir: PathBuf::from("/Library/Application Support/com.apple.idleassetsd/Customer"),
+                target_sub_dir: "4KSDR240FPS".to_string(),
                 backup_dir: "video_backups",
                 required_format: ".mov",
                 min_recommended_resolution: 2160, // 4K
                 min_recommended_duration: 60, // 1 minute in seconds
                 max_retry_attempts: 30,
                 retry_interval: 1000,
+                impact_window: None,
+                display: None,
+                sandbox: None,
             },
             
             logging: LoggingConfig {
-                level: env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string()),
+                level: "info".to_string(),
                 colors: ColorConfig {
                     info: "\x1b[36m",    // Cyan
                     success: "\x1b[32m", // Green
@@ -204,12 +1154,16 @@ impl Config {
                 DependencyConfig {
                     command: "yt-dlp",
                     args: vec!["--version"],
-                    install_hint: "Install with: brew install yt-dlp (macOS) or pip install yt-dlp",
+                    install_hint: "Install with: rust-downloader update-deps, brew install yt-dlp (macOS) or pip install yt-dlp",
                 },
                 DependencyConfig {
                     command: "ffmpeg",
                     args: vec!["-version"],
-                    install_hint: "Install with: brew install ffmpeg (macOS) or apt install ffmpeg (Ubuntu)",
+                    install_hint: if cfg!(feature = "managed-ffmpeg") {
+                        "Install with: brew install ffmpeg (macOS) or apt install ffmpeg (Ubuntu), or rerun with --auto-install-deps"
+                    } else {
+                        "Install with: brew install ffmpeg (macOS) or apt install ffmpeg (Ubuntu)"
+                    },
                 }
             ],
             
@@ -221,6 +1175,331 @@ impl Config {
             }
         }
     }
+}
+
+impl Config {
+    /// Default location of the config file: `~/.config/rust-downloader/config.toml`.
+    pub fn file_path() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".config").join("rust-downloader").join(CONFIG_FILE))
+    }
+
+    /// Build the configuration from defaults, then the config file, then environment
+    /// variables. CLI flags are applied on top by the caller.
+    ///
+    /// A missing default config file is fine; a missing `path` given explicitly is an error.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let mut config = Self::default();
+
+        let file = match path {
+            Some(path) => Some(Self::read_file(path)?),
+            None => match Self::file_path() {
+                Some(path) if path.exists() => Some(Self::read_file(&path)?),
+                _ => None,
+            },
+        };
+        if let Some(file) = file {
+            config.apply_file(file)?;
+        }
+
+        config.apply_env();
+        Ok(config)
+    }
+
+    fn read_file(path: &Path) -> Result<ConfigFile> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read config file {}: {}", path.display(), e))?;
+        let mut table: toml::Table = toml::from_str(&content)
+            .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+
+        let migration = config_migration::migrate(&mut table)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        // A file that only lacks the version key is left alone
+        if !migration.changes.is_empty() {
+            Self::save_migrated(path, &content, &table, &migration);
+        }
+
+        let file = toml::Value::Table(table).try_into()
+            .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+        Ok(file)
+    }
+
+    /// Keep the old file as `config.toml.v<N>.bak` and write the upgraded one in its place.
+    /// A file that can't be rewritten is still used in its migrated form for this run.
+    fn save_migrated(path: &Path, original: &str, table: &toml::Table, migration: &Migration) {
+        logger::warning(&format!(
+            "Upgrading config file {} from version {} to {}",
+            path.display(), migration.from_version, config_migration::CURRENT_VERSION
+        ));
+        for change in &migration.changes {
+            logger::warning(&format!("   • {}", change));
+        }
+
+        let backup = path.with_extension(format!("toml.v{}.bak", migration.from_version));
+        let result = std::fs::write(&backup, original)
+            .map_err(|e| e.to_string())
+            .and_then(|_| toml::to_string_pretty(table).map_err(|e| e.to_string()))
+            .and_then(|content| std::fs::write(path, content).map_err(|e| e.to_string()));
+
+        match result {
+            Ok(()) => logger::info(&format!("   Previous file (with its comments) saved as {}", backup.display())),
+            Err(error) => logger::warning(&format!("   Could not rewrite the config file ({}); using the upgraded settings for this run", error)),
+        }
+    }
+
+    fn apply_file(&mut self, file: ConfigFile) -> Result<()> {
+        if let Some(dir) = file.output_dir {
+            self.output_dir = Self::expand_tilde(&dir);
+        }
+        if let Some(value) = file.enable_video {
+            self.enable_video = value;
+        }
+        if let Some(value) = file.check_for_updates {
+            self.check_for_updates = value;
+        }
+        if let Some(value) = file.auto_install_deps {
+            self.auto_install_deps = value;
+        }
+        if let Some(path) = file.status_file {
+            self.status_file = Some(Self::expand_tilde(&path));
+        }
+
+        let video = &mut self.video_preferences;
+        if let Some(value) = file.video.max_resolution {
+            video.max_resolution = value;
+        }
+        if let Some(value) = file.video.prefer_high_fps {
+            video.prefer_high_fps = value;
+        }
+        if let Some(mode) = file.video.free_formats {
+            self.use_free_formats(mode);
+        }
+        if let Some(license) = file.video.license {
+            self.video_preferences.license = Some(license);
+        }
+
+        let download = &mut self.download_settings;
+        if let Some(value) = file.download.retry_attempts {
+            download.retry_attempts = value.max(1);
+        }
+        if let Some(value) = file.download.embed_subtitles {
+            download.embed_subtitles = value;
+        }
+        if let Some(value) = file.download.embed_thumbnail {
+            download.embed_thumbnail = value;
+        }
+        if let Some(value) = file.download.thumbnail {
+            download.save_thumbnail = value;
+        }
+        if let Some(value) = file.download.skip_sponsors {
+            download.sponsorblock = value.then_some(SponsorBlockMode::Remove);
+        }
+        if let Some(mode) = file.download.sponsorblock {
+            download.sponsorblock = Some(mode);
+        }
+        if let Some(minutes) = file.download.stall_minutes {
+            download.stall_timeout = stall_timeout(minutes);
+        }
+        if let Some(value) = file.download.concurrent_fragments {
+            download.concurrent_fragments = value.clamp(1, 16);
+        }
+        match (file.download.cookies, file.download.cookies_from_browser) {
+            (Some(_), Some(_)) => return Err("[download] sets both cookies and cookies_from_browser; keep one".into()),
+            (Some(path), None) => download.cookies = Some(CookieSource::File(Self::expand_tilde(&path))),
+            (None, Some(browser)) => download.cookies = Some(CookieSource::Browser(browser)),
+            (None, None) => {}
+        }
+
+        let conversion = &mut self.conversion_settings;
+        if let Some(value) = file.conversion.max_attempts {
+            conversion.max_attempts = value.max(1);
+        }
+        if let Some(value) = file.conversion.auto_trim {
+            conversion.auto_trim = value;
+        }
+        if let Some(value) = file.conversion.background_encode {
+            conversion.background_encode = value;
+        }
+        if let Some(value) = file.conversion.threads {
+            conversion.threads = Some(value);
+        }
+        if let Some(value) = file.conversion.force_reencode {
+            conversion.remux.force_reencode = value;
+        }
+        if let Some(values) = file.conversion.remux_codecs {
+            conversion.remux.allowed_codecs = values;
+        }
+        if let Some(values) = file.conversion.remux_containers {
+            conversion.remux.containers = values;
+        }
+        if let Some(value) = file.conversion.remux_min_bitrate {
+            conversion.remux.min_bitrate = (value.max(0.0) * 1e6) as u64;
+        }
+        if let Some(value) = file.conversion.remux_min_frame_rate {
+            conversion.remux.min_frame_rate = value;
+        }
+        if let Some(values) = file.conversion.extra_resolutions {
+            conversion.extra_resolutions = values.iter()
+                .map(|value| OutputResolution::parse(value))
+                .collect::<Result<Vec<_>, _>>()?;
+        }
+        if let Some(format) = file.conversion.format {
+            conversion.loop_format = Some(format);
+        }
+        if let Some(value) = file.conversion.loop_fps {
+            conversion.loop_settings.fps = value.max(1);
+        }
+        if let Some(value) = file.conversion.loop_max_width {
+            conversion.loop_settings.max_width = value.max(16);
+        }
+        if let Some(value) = file.conversion.loop_count {
+            conversion.loop_settings.loop_count = value;
+        }
+        if let Some(encoder) = file.conversion.encoder {
+            conversion.encoder = Some(encoder);
+        }
+        if let Some(value) = file.conversion.audio_only {
+            conversion.audio_only = value;
+        }
+        if let Some(format) = file.conversion.audio_format {
+            conversion.audio_format = Some(format);
+        }
+        if let Some(value) = file.conversion.normalize {
+            conversion.audio.normalize = value;
+        }
+        if let Some(value) = file.conversion.audio_bitrate {
+            conversion.audio.bitrate = Some(value.max(8));
+        }
+        if let Some(value) = file.conversion.strip_audio {
+            conversion.audio.strip = value;
+        }
+        if let Some(minutes) = file.conversion.stall_minutes {
+            conversion.stall_timeout = stall_timeout(minutes);
+        }
+        if let Some(value) = file.audio_tags.cover_art {
+            conversion.audio_tags.cover_art = value;
+        }
+        for (key, template) in file.audio_tags.template {
+            let templates = &mut conversion.audio_tags.templates;
+            templates.retain(|(existing, _)| *existing != key);
+            if !template.is_empty() {
+                templates.push((key, template));
+            }
+        }
+        for (name, table) in &file.profiles {
+            let index = match conversion.profiles.iter().position(|profile| &profile.name == name) {
+                Some(index) => index,
+                None => {
+                    let mut profile = conversion.profiles[0].clone();
+                    profile.name = name.clone();
+                    conversion.profiles.push(profile);
+                    conversion.profiles.len() - 1
+                }
+            };
+            conversion.profiles[index].apply(table).map_err(|e| format!("[profiles.{}]: {}", name, e))?;
+        }
+        if let Some(name) = file.conversion.profile {
+            if conversion.find_profile(&name).is_none() {
+                return Err(format!("Unknown conversion profile '{}' in [conversion]", name).into());
+            }
+            conversion.profile = name;
+        }
+
+        if let Some(value) = file.installation.min_recommended_resolution {
+            self.video_settings.min_recommended_resolution = value;
+        }
+        if let Some(value) = file.installation.min_recommended_duration {
+            self.video_settings.min_recommended_duration = value;
+        }
+        if let Some(dir) = file.installation.aerial_dir {
+            self.aerial_dir = Self::expand_tilde(&dir);
+        }
+        if let Some(dir) = file.installation.macos.customer_dir {
+            self.video_settings.customer_dir = Self::expand_tilde(&dir);
+        }
+        if let Some(name) = file.installation.macos.target_sub_dir {
+            self.video_settings.target_sub_dir = name;
+        }
+        self.video_settings.validate().map_err(|e| format!("[installation.macos]: {}", e))?;
+        if let Some(value) = file.logging.level {
+            self.logging.level = value;
+        }
+        if let Some(value) = file.naming.max_title_length {
+            self.file_naming.max_title_length = value;
+        }
+        if let Some(value) = file.history.backend {
+            self.history_backend = value;
+        }
+        if let Some(smtp) = file.notifications.smtp {
+            self.smtp = Some(smtp);
+        }
+        if let Some(mqtt) = file.notifications.mqtt {
+            self.mqtt = Some(mqtt);
+        }
+        if let Some(sources) = file.sync.sources {
+            self.sync_sources = sources;
+        }
+        let base = |url: String| url.trim_end_matches('/').to_string();
+        self.mirrors = file.mirrors.piped.into_iter().map(|url| Mirror::Piped(base(url)))
+            .chain(file.mirrors.invidious.into_iter().map(|url| Mirror::Invidious(base(url))))
+            .collect();
+        if let Some(minutes) = file.auth.cooldown_minutes {
+            self.auth.cooldown_minutes = minutes.max(0);
+        }
+        for (name, profile) in file.auth.profiles {
+            let source = match (profile.browser, profile.cookies) {
+                (Some(_), Some(_)) => return Err(format!("[auth.profiles.{}] sets both browser and cookies; keep one", name).into()),
+                (Some(browser), None) => CookieSource::Browser(browser),
+                (None, Some(path)) => CookieSource::File(Self::expand_tilde(&path)),
+                (None, None) => return Err(format!("[auth.profiles.{}] needs a browser or a cookies file", name).into()),
+            };
+            self.auth.profiles.insert(name, source);
+        }
+        Ok(())
+    }
+
+    /// RUST_DOWNLOADER_OUTPUT_DIR, RUST_DOWNLOADER_UPDATE_CHECK, RUST_DOWNLOADER_STATUS_FILE,
+    /// RUST_DOWNLOADER_SMTP_PASSWORD, RUST_DOWNLOADER_MQTT_PASSWORD and LOG_LEVEL override the file.
+    fn apply_env(&mut self) {
+        if let Ok(dir) = env::var("RUST_DOWNLOADER_OUTPUT_DIR") {
+            if !dir.trim().is_empty() {
+                self.output_dir = Self::expand_tilde(dir.trim());
+            }
+        }
+        if let Ok(value) = env::var("RUST_DOWNLOADER_UPDATE_CHECK") {
+            self.check_for_updates = value == "1" || value.eq_ignore_ascii_case("true");
+        }
+        if let Ok(path) = env::var("RUST_DOWNLOADER_STATUS_FILE") {
+            if !path.trim().is_empty() {
+                self.status_file = Some(Self::expand_tilde(path.trim()));
+            }
+        }
+        if let (Ok(password), Some(smtp)) = (env::var("RUST_DOWNLOADER_SMTP_PASSWORD"), self.smtp.as_mut()) {
+            smtp.password = Some(password);
+        }
+        if let (Ok(password), Some(mqtt)) = (env::var("RUST_DOWNLOADER_MQTT_PASSWORD"), self.mqtt.as_mut()) {
+            mqtt.password = Some(password);
+        }
+        if let Ok(level) = env::var("LOG_LEVEL") {
+            self.logging.level = level;
+        }
+    }
+
+    /// Prefer VP9/AV1 video and Opus audio merged into WebM, for users avoiding
+    /// patent-encumbered codecs. Analysis then drops other codecs when free ones are offered,
+    /// and the HEVC conversion is skipped or replaced by an AV1 encode.
+    pub fn use_free_formats(&mut self, mode: FreeFormats) {
+        let video = &mut self.video_preferences;
+        video.free_formats = Some(mode);
+        video.preferred_formats = vec!["webm", "mkv", "mp4"];
+        video.preferred_codecs = vec!["av01", "vp09", "vp9"];
+
+        let audio = &mut self.audio_preferences;
+        audio.preferred_formats = vec!["webm", "ogg", "m4a"];
+        audio.preferred_codecs = vec!["opus", "vorbis"];
+
+        self.download_settings.merge_output_format = "webm";
+        self.download_settings.convert_to_mov = mode == FreeFormats::Av1;
+    }
 
     /// Expand tilde (~) to user's home directory
     pub fn expand_tilde(path: &str) -> PathBuf {
@@ -233,7 +1512,7 @@ impl Config {
     }
 
     /// Ensure output directory exists, create if needed
-    pub fn ensure_output_dir_exists(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn ensure_output_dir_exists(&mut self) -> Result<()> {
         // Expand tilde in the output path
         if self.output_dir.to_string_lossy().starts_with("~/") {
             self.output_dir = Self::expand_tilde(&self.output_dir.to_string_lossy());
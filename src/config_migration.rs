@@ -0,0 +1,83 @@
+use toml::{Table, Value};
+use crate::error::Result;
+
+/// Version written to `config.toml`. Bump it and add a step to `MIGRATIONS` whenever
+/// keys are renamed, moved or dropped.
+pub const CURRENT_VERSION: i64 = 1;
+
+/// A migration step upgrades a file from `version` to `version + 1` and returns what it changed.
+type Step = fn(&mut Table) -> Vec<String>;
+
+/// Indexed by the version the step upgrades from.
+const MIGRATIONS: [Step; CURRENT_VERSION as usize] = [v0_to_v1];
+
+/// What a migration did to a config file.
+#[derive(Debug, Default)]
+pub struct Migration {
+    pub from_version: i64,
+    /// One line per renamed, moved or dropped key
+    pub changes: Vec<String>,
+}
+
+/// Upgrade a parsed config file in place to `CURRENT_VERSION`.
+///
+/// Files without a `version` key predate versioning and are treated as version 0.
+pub fn migrate(table: &mut Table) -> Result<Migration> {
+    let from_version = match table.get("version") {
+        None => 0,
+        Some(Value::Integer(version)) => *version,
+        Some(other) => return Err(format!("Config version must be a number, found {}", other).into()),
+    };
+
+    if from_version > CURRENT_VERSION {
+        return Err(format!(
+            "Config file is version {}, but this release only understands up to version {}; please update rust-downloader",
+            from_version, CURRENT_VERSION
+        ).into());
+    }
+
+    let mut changes = Vec::new();
+    for step in &MIGRATIONS[from_version.max(0) as usize..] {
+        changes.extend(step(table));
+    }
+    table.insert("version".to_string(), Value::Integer(CURRENT_VERSION));
+
+    Ok(Migration { from_version, changes })
+}
+
+/// Move `section.from` to `section.to`, keeping an existing `to` value.
+fn rename_key(table: &mut Table, section: &str, from: &str, to: &str) -> Option<String> {
+    let section_table = table.get_mut(section)?.as_table_mut()?;
+    let value = section_table.remove(from)?;
+    if section_table.contains_key(to) {
+        return Some(format!("[{}] {} dropped; {} is already set", section, from, to));
+    }
+    section_table.insert(to.to_string(), value);
+    Some(format!("[{}] {} renamed to {}", section, from, to))
+}
+
+/// Drop a key that no longer has any meaning, and its section once empty.
+fn remove_key(table: &mut Table, section: &str, key: &str, reason: &str) -> Option<String> {
+    let section_table = table.get_mut(section)?.as_table_mut()?;
+    section_table.remove(key)?;
+    if section_table.is_empty() {
+        table.remove(section);
+    }
+    Some(format!("[{}] {} removed: {}", section, key, reason))
+}
+
+/// Version 1 names extra renditions after the setting rather than the CLI flag, and drops
+/// keys that were accepted but never affected a download.
+fn v0_to_v1(table: &mut Table) -> Vec<String> {
+    [
+        rename_key(table, "conversion", "also", "extra_resolutions"),
+        remove_key(table, "download", "timeout_seconds", "yt-dlp manages its own timeouts"),
+        remove_key(table, "conversion", "conservative_mode", "failed encodes already retry with safer settings up to max_attempts"),
+        remove_key(table, "video", "prefer_60fps", "use prefer_high_fps"),
+        remove_key(table, "audio", "min_bitrate", "audio formats are ranked by format and codec"),
+        remove_key(table, "audio", "preferred_bitrate", "audio formats are ranked by format and codec"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
@@ -0,0 +1,155 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Mutex;
+use crate::error::Result;
+use crate::logger;
+use crate::dependencies;
+use crate::config::Config;
+
+const LOG_LINES: usize = 200;
+
+/// Redacted `Debug` dump of the active config, captured at startup for the panic hook
+static CONFIG_SNAPSHOT: Mutex<Option<String>> = Mutex::new(None);
+
+/// stderr of the last external command that failed
+static LAST_STDERR: Mutex<Option<(String, String)>> = Mutex::new(None);
+
+pub fn set_config(config: &Config) {
+    *CONFIG_SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner()) = Some(redact(&format!("{:#?}", config)));
+}
+
+/// Remember a failing tool's stderr so it ends up in the next diagnostic bundle.
+pub fn record_stderr(command: &str, stderr: &str) {
+    *LAST_STDERR.lock().unwrap_or_else(|e| e.into_inner()) = Some((command.to_string(), stderr.to_string()));
+}
+
+/// Write a diagnostic bundle when the process panics, after the default panic message.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        report_failure(&format!("panic: {}", info));
+    }));
+}
+
+/// Save the failure details and build a bundle, printing where it was written.
+pub fn report_failure(error: &str) {
+    match save_failure(error).and_then(|_| build_bundle()) {
+        Ok(path) => {
+            logger::info(&format!("Diagnostic bundle written to: {}", path.display()));
+            logger::info("Attach it when opening an issue (paths and user names are redacted)");
+        }
+        Err(e) => logger::warning(&format!("Could not write diagnostic bundle: {}", e)),
+    }
+}
+
+/// Directory holding the details of the last failure, so `report` can rebuild the bundle later.
+fn crash_dir() -> Result<PathBuf> {
+    let base = dirs::data_local_dir().ok_or("Could not determine data directory")?;
+    let dir = base.join("rust-downloader").join("last_failure");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn save_failure(error: &str) -> Result<()> {
+    let dir = crash_dir()?;
+
+    fs::write(dir.join("error.txt"), redact(error))?;
+    fs::write(dir.join("log.txt"), redact(&logger::recent_lines(LOG_LINES).join("\n")))?;
+
+    if let Some(config) = CONFIG_SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        fs::write(dir.join("config.txt"), config)?;
+    }
+
+    match LAST_STDERR.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        Some((command, stderr)) => fs::write(dir.join("stderr.txt"), redact(&format!("$ {}\n{}", command, stderr)))?,
+        None => {
+            fs::remove_file(dir.join("stderr.txt")).ok();
+        }
+    }
+
+    Ok(())
+}
+
+/// Zip the last recorded failure together with fresh environment details.
+pub fn build_bundle() -> Result<PathBuf> {
+    let dir = crash_dir()?;
+
+    if !dir.join("error.txt").exists() {
+        fs::write(dir.join("error.txt"), "No failure recorded; bundle generated on request.\n")?;
+    }
+    if !dir.join("config.txt").exists() {
+        if let Some(config) = CONFIG_SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            fs::write(dir.join("config.txt"), config)?;
+        }
+    }
+    fs::write(dir.join("environment.txt"), redact(&environment_report()))?;
+
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let bundle = dir.parent().unwrap().join(format!("rust-downloader-report-{}.zip", timestamp));
+
+    let status = Command::new("zip")
+        .args(["-q", "-j", "-r"])
+        .arg(&bundle)
+        .arg(&dir)
+        .status()?;
+
+    if !status.success() {
+        return Err("zip failed to create the bundle".into());
+    }
+
+    Ok(bundle)
+}
+
+fn environment_report() -> String {
+    let mut report = format!("rust-downloader {}\n", env!("CARGO_PKG_VERSION"));
+
+    let probes: [(&str, &[&str]); 5] = [
+        ("os", &["sw_vers"]),
+        ("kernel", &["uname", "-a"]),
+        ("yt-dlp", &["yt-dlp", "--version"]),
+        ("ffmpeg", &["ffmpeg", "-version"]),
+        ("ffprobe", &["ffprobe", "-version"]),
+    ];
+
+    for (label, command) in probes {
+        let output = Command::new(dependencies::program(command[0])).args(&command[1..]).output();
+        let text = match output {
+            Ok(output) if output.status.success() => {
+                String::from_utf8_lossy(&output.stdout).lines().take(3).collect::<Vec<_>>().join("\n")
+            }
+            Ok(_) => "failed to run".to_string(),
+            Err(_) => "not found".to_string(),
+        };
+        report.push_str(&format!("\n[{}]\n{}\n", label, text.trim()));
+    }
+
+    report
+}
+
+/// Replace the home directory and user name so bundles can be shared publicly.
+fn redact(text: &str) -> String {
+    let mut redacted = text.to_string();
+
+    if let Some(home) = dirs::home_dir() {
+        let home = home.to_string_lossy().to_string();
+        if home.len() > 1 {
+            redacted = redacted.replace(&home, "~");
+        }
+    }
+
+    for variable in ["SUDO_USER", "USER"] {
+        if let Ok(user) = std::env::var(variable) {
+            if user.len() > 2 && user != "root" {
+                redacted = redacted.replace(&user, "<user>");
+            }
+        }
+    }
+
+    redacted
+}
+
+pub fn has_recorded_failure() -> bool {
+    crash_dir().is_ok_and(|dir| dir.join("log.txt").exists())
+}
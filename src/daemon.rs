@@ -0,0 +1,384 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Local, NaiveTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+use crate::error::Result;
+use crate::cancel::CancelHandle;
+use crate::logger;
+use crate::queue::{DownloadQueue, SavedQueue};
+use crate::config::Config;
+use crate::focus;
+use crate::appearance::{self, Appearance};
+use crate::library::Library;
+use crate::wallpaper;
+use crate::mqtt;
+use crate::sun;
+
+const AUTOMATION_FILE: &str = "automation.json";
+
+/// How often the daemon re-announces that it is alive over MQTT
+const HEALTH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Key used in the focus map for "no Focus mode active".
+pub const NO_FOCUS: &str = "none";
+
+/// Rules the daemon evaluates to decide which library entry should be installed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Automation {
+    /// Focus mode name (or `none`) -> library id
+    #[serde(default)]
+    pub focus: BTreeMap<String, String>,
+
+    /// Entries to follow the system Light/Dark appearance
+    #[serde(default)]
+    pub appearance: Option<AppearancePair>,
+
+    /// Entries for the morning, day, evening and night
+    #[serde(default)]
+    pub time_of_day: Option<TimeOfDaySet>,
+
+    /// System video to replace when switching; defaults to the most recently installed one
+    #[serde(default)]
+    pub target_file: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppearancePair {
+    pub light: String,
+    pub dark: String,
+}
+
+impl AppearancePair {
+    pub fn entry_for(&self, appearance: Appearance) -> &String {
+        match appearance {
+            Appearance::Light => &self.light,
+            Appearance::Dark => &self.dark,
+        }
+    }
+}
+
+/// Part of the day a `TimeOfDaySet` entry is shown in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Morning,
+    Day,
+    Evening,
+    Night,
+}
+
+impl Period {
+    pub fn name(self) -> &'static str {
+        match self {
+            Period::Morning => "morning",
+            Period::Day => "day",
+            Period::Evening => "evening",
+            Period::Night => "night",
+        }
+    }
+}
+
+/// Four entries shown in turn over the day, like Apple's dynamic wallpapers. The periods
+/// start at fixed local times, or follow sunrise and sunset when a location is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeOfDaySet {
+    pub morning: String,
+    pub day: String,
+    pub evening: String,
+    pub night: String,
+    /// Local times the morning, day, evening and night start, used without a location
+    #[serde(default = "TimeOfDaySet::default_starts")]
+    pub starts: [NaiveTime; 4],
+    /// Latitude and longitude in degrees (north and east positive)
+    #[serde(default)]
+    pub location: Option<(f64, f64)>,
+}
+
+impl TimeOfDaySet {
+    pub fn default_starts() -> [NaiveTime; 4] {
+        [(6, 0), (10, 0), (18, 0), (21, 0)].map(|(hour, minute)| NaiveTime::from_hms_opt(hour, minute, 0).unwrap())
+    }
+
+    /// When each period starts on the day of `now`. With a location the morning starts at
+    /// sunrise, the day two hours later, the evening an hour before sunset and the night an
+    /// hour after it; during polar day or night the fixed times apply.
+    pub fn starts_on(&self, now: DateTime<Local>) -> [NaiveTime; 4] {
+        let Some((sunrise, sunset)) = self.location.and_then(|(latitude, longitude)| sun::sunrise_sunset(now.date_naive(), latitude, longitude)) else {
+            return self.starts;
+        };
+        let (sunrise, sunset) = (sunrise.with_timezone(&Local).time(), sunset.with_timezone(&Local).time());
+        // Short winter days get a day period of at least the middle hour
+        let noon = sunrise + (sunset - sunrise) / 2;
+        let day = (sunrise + TimeDelta::hours(2)).min(noon - TimeDelta::minutes(30)).max(sunrise);
+        let evening = (sunset - TimeDelta::hours(1)).max(noon + TimeDelta::minutes(30)).min(sunset);
+        let night = (sunset + TimeDelta::hours(1)).max(sunset);
+        [sunrise, day, evening, night]
+    }
+
+    pub fn period_at(&self, now: DateTime<Local>) -> Period {
+        let [morning, day, evening, night] = self.starts_on(now);
+        let time = now.time();
+        if time >= night || time < morning {
+            Period::Night
+        } else if time >= evening {
+            Period::Evening
+        } else if time >= day {
+            Period::Day
+        } else {
+            Period::Morning
+        }
+    }
+
+    pub fn entry_for(&self, period: Period) -> &String {
+        match period {
+            Period::Morning => &self.morning,
+            Period::Day => &self.day,
+            Period::Evening => &self.evening,
+            Period::Night => &self.night,
+        }
+    }
+}
+
+impl Automation {
+    pub fn path(config: &Config) -> PathBuf {
+        config.output_dir.join(AUTOMATION_FILE)
+    }
+
+    pub fn load(config: &Config) -> Self {
+        fs::read_to_string(Self::path(config))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, config: &Config) -> Result<()> {
+        fs::write(Self::path(config), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn focus_entry(&self, mode: Option<&str>) -> Option<&String> {
+        let key = mode.unwrap_or(NO_FOCUS);
+        self.focus.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, id)| id)
+    }
+
+    /// Remove the mapping for a Focus mode, matching its name case-insensitively like `focus_entry`.
+    pub fn remove_focus(&mut self, mode: &str) -> Option<String> {
+        let key = self.focus.keys().find(|name| name.eq_ignore_ascii_case(mode))?.clone();
+        self.focus.remove(&key)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.focus.is_empty() && self.appearance.is_none() && self.time_of_day.is_none()
+    }
+
+    pub fn display(&self) {
+        logger::header("Wallpaper Automation");
+
+        if self.is_empty() {
+            logger::info("No automation rules configured");
+            logger::info("Map a Focus mode with: rust-downloader wallpaper focus Work <id>");
+            logger::info("Follow Light/Dark mode with: rust-downloader wallpaper pair-appearance <light-id> <dark-id>");
+            logger::info("Change with the time of day with: rust-downloader wallpaper time-of-day <morning-id> <day-id> <evening-id> <night-id>");
+        }
+        for (mode, id) in &self.focus {
+            logger::wallpaper(&format!("Focus {}: {}", mode, id));
+        }
+        if let Some(pair) = &self.appearance {
+            logger::wallpaper(&format!("Light appearance: {}", pair.light));
+            logger::wallpaper(&format!("Dark appearance: {}", pair.dark));
+        }
+        if let Some(set) = &self.time_of_day {
+            let now = Local::now();
+            let starts = set.starts_on(now);
+            for (index, period) in [Period::Morning, Period::Day, Period::Evening, Period::Night].into_iter().enumerate() {
+                logger::wallpaper(&format!("{} (from {}): {}", period.name(), starts[index].format("%H:%M"), set.entry_for(period)));
+            }
+            match set.location {
+                Some((latitude, longitude)) => logger::info(&format!("Following sunrise and sunset at {:.2}, {:.2}; times shown are today's", latitude, longitude)),
+                None => logger::info("Switching at fixed times"),
+            }
+            logger::info(&format!("Now: {}", set.period_at(now).name()));
+        }
+
+        if let Some(target) = &self.target_file {
+            logger::info(&format!("Replacing system video: {}", target));
+        }
+    }
+}
+
+/// Foreground loop that swaps the installed wallpaper when the conditions in `automation.json`
+/// change, and downloads the entries saved with `queue add`, resuming the ones a previous
+/// daemon was interrupted in.
+pub struct Daemon {
+    config: Config,
+    interval: Duration,
+    active_entry: Option<String>,
+    last_warning: Option<String>,
+    last_health: Option<Instant>,
+    /// How many of a saved run's jobs download and convert at once
+    jobs: (usize, usize),
+    /// Saved runs being downloaded
+    downloads: Vec<(CancelHandle, tokio::task::JoinHandle<()>)>,
+}
+
+impl Daemon {
+    pub fn new(config: &Config, interval: Duration) -> Self {
+        Self {
+            config: config.clone(),
+            interval,
+            active_entry: None,
+            last_warning: None,
+            last_health: None,
+            jobs: (2, 1),
+            downloads: Vec::new(),
+        }
+    }
+
+    /// Download and convert this many jobs of a saved run at once.
+    pub fn set_jobs(&mut self, downloads: usize, conversions: usize) {
+        self.jobs = (downloads, conversions);
+    }
+
+    pub async fn run(&mut self) -> Result<()> {
+        logger::header("Wallpaper Daemon");
+        logger::info(&format!("Rules: {}", Automation::path(&self.config).display()));
+        logger::info(&format!("Downloads: {}", SavedQueue::path(&self.config).display()));
+        logger::info(&format!("Checking every {}s, press Ctrl+C to stop", self.interval.as_secs()));
+
+        loop {
+            // Reload every tick so edits made from the CLI apply without a restart
+            let automation = Automation::load(&self.config);
+
+            if let Some(id) = self.desired_entry(&automation) {
+                if self.active_entry.as_deref() != Some(id.as_str()) {
+                    match self.switch_to(&id, &automation).await {
+                        Ok(()) => {
+                            self.active_entry = Some(id);
+                            self.report_health("running", None).await;
+                        }
+                        Err(error) => {
+                            let message = format!("Could not switch to {}: {}", id, error);
+                            self.report_health("error", Some(&message)).await;
+                            self.warn_once(&message);
+                        }
+                    }
+                }
+            }
+
+            if self.last_health.is_none_or(|at| at.elapsed() >= HEALTH_INTERVAL) {
+                self.report_health("running", None).await;
+            }
+            self.resume_saved_queues();
+
+            tokio::select! {
+                _ = tokio::time::sleep(self.interval) => {}
+                _ = tokio::signal::ctrl_c() => {
+                    self.stop_downloads().await;
+                    self.report_health("stopped", None).await;
+                    logger::info("Daemon stopped");
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Start downloading the saved runs nobody is working on: new ones from `queue add`, and
+    /// ones a daemon that was stopped or rebooted left unfinished.
+    fn resume_saved_queues(&mut self) {
+        self.downloads.retain(|(_, task)| !task.is_finished());
+
+        let claimed = match SavedQueue::update(&self.config, SavedQueue::claim_orphaned) {
+            Ok(claimed) => claimed,
+            Err(error) => {
+                self.warn_once(&format!("Could not read the saved queue: {}", error));
+                return;
+            }
+        };
+        for (id, run) in claimed {
+            let queue = match DownloadQueue::resume(&id, &run, self.jobs.0, self.jobs.1) {
+                Ok(queue) => queue,
+                Err(error) => {
+                    self.warn_once(&format!("Could not resume queue {}: {}", id, error));
+                    continue;
+                }
+            };
+            logger::info(&format!("Downloading saved queue {}", id));
+            let cancel = queue.cancel_handle();
+            let config = self.config.clone();
+            let task = tokio::spawn(async move {
+                queue.run(&config).await;
+            });
+            self.downloads.push((cancel, task));
+        }
+    }
+
+    /// Stop the downloads, leaving them saved for the next daemon to resume.
+    async fn stop_downloads(&mut self) {
+        for (cancel, _) in &self.downloads {
+            cancel.cancel();
+        }
+        for (_, task) in self.downloads.drain(..) {
+            task.await.ok();
+        }
+    }
+
+    /// An active, mapped Focus mode wins over the appearance pair, then the time-of-day set,
+    /// then the "none" Focus mapping.
+    fn desired_entry(&mut self, automation: &Automation) -> Option<String> {
+        let mut focus_mode = None;
+        if !automation.focus.is_empty() {
+            match focus::current_focus_mode() {
+                Ok(mode) => focus_mode = mode,
+                Err(error) => self.warn_once(&error.to_string()),
+            }
+        }
+
+        if let Some(id) = focus_mode.as_deref().and_then(|mode| automation.focus_entry(Some(mode))) {
+            return Some(id.clone());
+        }
+
+        if let Some(pair) = &automation.appearance {
+            return Some(pair.entry_for(appearance::current_appearance()).clone());
+        }
+
+        if let Some(set) = &automation.time_of_day {
+            return Some(set.entry_for(set.period_at(Local::now())).clone());
+        }
+
+        automation.focus_entry(None).cloned()
+    }
+
+    async fn switch_to(&self, id: &str, automation: &Automation) -> Result<()> {
+        let entry = Library::new(&self.config)
+            .find(id)
+            .ok_or_else(|| format!("No library entry with id '{}'", id))?;
+
+        logger::wallpaper(&format!("Switching wallpaper to {}", entry.id));
+        let replaced = wallpaper::detect(&self.config)?
+            .swap(&entry.path, automation.target_file.as_deref())
+            .await?;
+        logger::success(&format!("{} installed over {}", entry.id, replaced));
+        Ok(())
+    }
+
+    /// Retained on `<topic>/daemon`, so subscribers see the last state right away.
+    async fn report_health(&mut self, state: &str, error: Option<&str>) {
+        self.last_health = Some(Instant::now());
+        mqtt::publish(&self.config, "daemon", &serde_json::json!({
+            "state": state,
+            "entry": self.active_entry,
+            "error": error,
+            "at": chrono::Utc::now(),
+        }), true).await;
+    }
+
+    fn warn_once(&mut self, message: &str) {
+        if self.last_warning.as_deref() != Some(message) {
+            logger::warning(message);
+            self.last_warning = Some(message.to_string());
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Utc};
+use crate::error::Result;
+use crate::config::Config;
+use crate::history::{DownloadStatus, History, HistoryEntry};
+use crate::library::Library;
+use crate::logger;
+use crate::utils;
+
+const WEEKS: i64 = 12;
+const TOP_UPLOADERS: usize = 5;
+const BAR_WIDTH: f64 = 30.0;
+
+/// Files in the output directory that are downloads rather than library wallpapers
+const DOWNLOAD_EXTENSIONS: [&str; 7] = ["mp4", "mkv", "webm", "m4a", "png", "heic", "part"];
+
+/// Usage statistics from the download history and the output directory.
+///
+/// Everything is computed from local files; nothing is collected or sent anywhere.
+pub fn show(config: &Config) -> Result<()> {
+    let history = History::load(config)?;
+    let entries = history.since(DateTime::<Utc>::MIN_UTC)?;
+
+    logger::header("Usage Dashboard");
+    logger::info("Computed from local files only; nothing is sent anywhere");
+
+    downloads_per_week(&entries);
+    storage(config);
+    top_uploaders(&entries);
+    conversion_totals(&entries);
+    Ok(())
+}
+
+fn bar(value: f64, max: f64) -> String {
+    if value <= 0.0 || max <= 0.0 {
+        return String::new();
+    }
+    "█".repeat(((value / max * BAR_WIDTH).round() as usize).max(1))
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+fn downloads_per_week(entries: &[HistoryEntry]) {
+    println!();
+    logger::stats(&format!("Downloads per week (last {} weeks)", WEEKS));
+
+    let this_week = week_start(Local::now().date_naive());
+    let mut weeks: BTreeMap<NaiveDate, u32> = (0..WEEKS)
+        .map(|n| (this_week - Duration::weeks(n), 0))
+        .collect();
+
+    for entry in entries.iter().filter(|e| e.status == DownloadStatus::Completed) {
+        let week = week_start(Local.from_utc_datetime(&entry.finished_at.naive_utc()).date_naive());
+        if let Some(count) = weeks.get_mut(&week) {
+            *count += 1;
+        }
+    }
+
+    let max = weeks.values().copied().max().unwrap_or(0) as f64;
+    for (week, count) in &weeks {
+        logger::info(&format!("   {}  {:<30} {}", week.format("%b %d"), bar(*count as f64, max), count));
+    }
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(dir) = fs::read_dir(path) else {
+        return 0;
+    };
+    dir.flatten()
+        .map(|item| match item.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&item.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+fn storage(config: &Config) {
+    println!();
+    logger::stats("Storage used");
+
+    let library: u64 = Library::new(config).entries().iter().map(|entry| entry.size).sum();
+    let backups = dir_size(&config.output_dir.join(config.video_settings.backup_dir));
+    let downloads: u64 = fs::read_dir(&config.output_dir)
+        .map(|dir| dir.flatten()
+            .map(|item| item.path())
+            .filter(|path| path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| DOWNLOAD_EXTENSIONS.contains(&ext.to_lowercase().as_str())))
+            .filter_map(|path| fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum())
+        .unwrap_or(0);
+
+    let rows = [("Library", library), ("Other outputs", downloads), ("Backups", backups)];
+    let max = rows.iter().map(|(_, size)| *size).max().unwrap_or(0) as f64;
+    for (name, size) in rows {
+        logger::info(&format!("   {:<14} {:<30} {}", name, bar(size as f64, max), size_label(size)));
+    }
+    logger::info(&format!("   {:<14} {:<30} {}", "Total", "", size_label(library + downloads + backups)));
+}
+
+/// `format_file_size` treats zero as unknown; here it just means nothing is stored.
+fn size_label(size: u64) -> String {
+    if size == 0 { "0B".to_string() } else { utils::format_file_size(Some(size)) }
+}
+
+fn top_uploaders(entries: &[HistoryEntry]) {
+    println!();
+    logger::stats("Top uploaders");
+
+    let mut counts: BTreeMap<&str, u32> = BTreeMap::new();
+    for entry in entries.iter().filter(|e| e.status == DownloadStatus::Completed) {
+        if let Some(uploader) = &entry.uploader {
+            *counts.entry(uploader.as_str()).or_default() += 1;
+        }
+    }
+
+    if counts.is_empty() {
+        logger::info("   No uploaders recorded yet");
+        return;
+    }
+
+    let mut ranked: Vec<(&str, u32)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let max = ranked[0].1 as f64;
+    for (uploader, count) in ranked.into_iter().take(TOP_UPLOADERS) {
+        let name: String = uploader.chars().take(24).collect();
+        logger::info(&format!("   {:<24} {:<30} {}", name, bar(count as f64, max), count));
+    }
+}
+
+fn conversion_totals(entries: &[HistoryEntry]) {
+    println!();
+    logger::stats("Time spent");
+
+    let converted: Vec<f64> = entries.iter().filter_map(|e| e.conversion_seconds).collect();
+    let conversion: f64 = converted.iter().sum();
+    let total: f64 = entries.iter().map(|e| e.seconds).sum();
+
+    logger::info(&format!("   Downloading and converting: {}", utils::format_time(total)));
+    logger::info(&format!("   Converting: {} over {} video(s)", utils::format_time(conversion), converted.len()));
+    if !converted.is_empty() {
+        logger::info(&format!("   Average conversion: {}", utils::format_time(conversion / converted.len() as f64)));
+    }
+}
@@ -1,8 +1,22 @@
 use std::process::Command;
 use std::env;
+use std::fs;
+#[cfg(feature = "managed-ffmpeg")]
+use std::io::IsTerminal;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use crate::error::{DownloaderError, Result};
+use crate::encoders;
 use crate::logger;
+use crate::http;
+use crate::update;
 use crate::Config;
 
+const YT_DLP_RELEASE_URL: &str = "https://api.github.com/repos/yt-dlp/yt-dlp/releases/latest";
+/// yt-dlp older than this often fails on YouTube, which changes its player every few weeks
+const YT_DLP_STALE_DAYS: i64 = 60;
+
 #[derive(Clone)]
 pub struct DependencyResult {
     pub name: String,
@@ -14,18 +28,18 @@ pub struct DependencyResult {
 }
 
 pub struct DependencyChecker {
-    _config: Config,
+    config: Config,
 }
 
 impl DependencyChecker {
-    pub fn new() -> Self {
+    pub fn new(config: &Config) -> Self {
         Self {
-            _config: Config::default(),
+            config: config.clone(),
         }
     }
 
-    fn run_command(command: &str, args: &[&str]) -> Result<std::process::Output, Box<dyn std::error::Error>> {
-        let output = Command::new(command)
+    fn run_command(command: &str, args: &[&str]) -> Result<std::process::Output> {
+        let output = Command::new(program(command))
             .args(args)
             .output()?;
         Ok(output)
@@ -86,15 +100,19 @@ impl DependencyChecker {
         logger::header("Checking Dependencies");
         
         let mut results = Vec::new();
-        let config = Config::default();
         
-        for dependency in &config.dependencies {
+        for dependency in &self.config.dependencies {
             logger::info(&format!("Checking {}...", dependency.command));
-            let result = self.check_dependency(dependency.command, &config).await;
+            let result = self.check_dependency(dependency.command, &self.config).await;
             results.push(result.clone());
             
             if result.available {
                 logger::success(&format!("{} v{} - Available", result.name, result.version.as_deref().unwrap_or("unknown")));
+                if let Some(age) = result.version.as_deref().filter(|_| result.name == "yt-dlp").and_then(yt_dlp_age_days) {
+                    if age > YT_DLP_STALE_DAYS {
+                        logger::warning(&format!("yt-dlp is {} days old; if downloads fail, run: rust-downloader update-deps", age));
+                    }
+                }
             } else {
                 logger::error(&format!("{} - Not available", result.name));
                 if let Some(hint) = &result.install_hint {
@@ -106,9 +124,14 @@ impl DependencyChecker {
         results
     }
 
-    pub async fn validate_environment(&self) -> Result<Vec<DependencyResult>, Box<dyn std::error::Error>> {
+    pub async fn validate_environment(&self) -> Result<Vec<DependencyResult>> {
         let results = self.check_all_dependencies().await;
-        let missing: Vec<_> = results.iter().filter(|r| !r.available).collect();
+        // Builds with `native-extractor` get simple downloads from YouTube without yt-dlp
+        let optional = |name: &str| cfg!(feature = "native-extractor") && name == "yt-dlp";
+        let missing: Vec<_> = results.iter().filter(|r| !r.available && !optional(&r.name)).collect();
+        if results.iter().any(|r| !r.available && optional(&r.name)) {
+            logger::warning("yt-dlp is missing; only progressive formats (720p at most) can be downloaded without it");
+        }
         
         if !missing.is_empty() {
             logger::error("Missing required dependencies:");
@@ -118,7 +141,7 @@ impl DependencyChecker {
                 }
             }
             
-            return Err(format!("Missing {} required dependencies. Please install them and try again.", missing.len()).into());
+            return Err(DownloaderError::MissingDependency(format!("Missing {} required dependencies. Please install them and try again.", missing.len())));
         }
         
         logger::success("All dependencies are available!");
@@ -155,7 +178,7 @@ impl DependencyChecker {
         false
     }
 
-    pub fn prompt_for_sudo() -> Result<(), Box<dyn std::error::Error>> {
+    pub fn prompt_for_sudo() -> Result<()> {
         logger::warning("This application requires administrator privileges to access system wallpaper directories");
         logger::info("Please restart the application with sudo:");
         logger::info("");
@@ -168,15 +191,49 @@ impl DependencyChecker {
         logger::info("");
         logger::info(" Note: Your downloads will be saved to the outputs/ directory with proper ownership");
 
-        Err("Administrator privileges required. Please restart with sudo.".into())
+        Err(DownloaderError::SudoRequired("Administrator privileges required. Please restart with sudo.".to_string()))
+    }
+
+    /// Makes sure a yt-dlp runs, installing the official binary into `managed_dir()` when
+    /// none is found. Returns the one to run.
+    pub fn ensure_yt_dlp(&self) -> Result<PathBuf> {
+        let path = yt_dlp();
+        if yt_dlp_version(&path).is_some() {
+            return Ok(path);
+        }
+        logger::warning("yt-dlp not found; installing the official binary");
+        install_yt_dlp(false)
     }
 
-    pub async fn check_system_resources(&self) -> Result<(), Box<dyn std::error::Error>> {
+    /// When ffmpeg or ffprobe doesn't run, offers to download static builds into
+    /// `managed_dir()`, or does so without asking with `--auto-install-deps`. Returns the
+    /// installed ffmpeg, or `None` when nothing was installed.
+    #[cfg(feature = "managed-ffmpeg")]
+    pub fn ensure_ffmpeg(&self) -> Result<Option<PathBuf>> {
+        let runs = |tool: &str| Command::new(program(tool)).arg("-version").output().is_ok_and(|output| output.status.success());
+        if runs("ffmpeg") && runs("ffprobe") {
+            return Ok(None);
+        }
+
+        if !self.config.auto_install_deps {
+            // Scripts and --json runs can't answer; they get the install hint instead
+            if !std::io::stdin().is_terminal() || logger::sink() != logger::Sink::Text {
+                return Ok(None);
+            }
+            let dir = managed_dir().ok_or("Could not determine data directory")?;
+            let answer = crate::utils::prompt(&format!("ffmpeg was not found. Download a static build into {}? (Y/n): ", dir.display()))?;
+            if answer.eq_ignore_ascii_case("n") || answer.eq_ignore_ascii_case("no") {
+                return Ok(None);
+            }
+        }
+        crate::managed_ffmpeg::install().map(Some)
+    }
+
+    pub async fn check_system_resources(&self) -> Result<()> {
         logger::info("Checking system resources...");
         
         // Check available disk space (basic check)
-        let config = Config::default();
-        match std::fs::metadata(&config.output_dir) {
+        match std::fs::metadata(&self.config.output_dir) {
             Ok(_) => {
                 logger::success("Output directory is accessible");
             }
@@ -199,12 +256,13 @@ impl DependencyChecker {
         Ok(())
     }
 
-    pub async fn perform_full_check(&self) -> Result<bool, Box<dyn std::error::Error>> {
+    pub async fn perform_full_check(&self) -> Result<bool> {
         logger::header("Environment Check");
 
         // Check sudo privileges first only when wallpaper installation is enabled
-        let config = Config::default();
-        if config.enable_video {
+        if let (true, Some(sandbox)) = (self.config.enable_video, &self.config.video_settings.sandbox) {
+            logger::info(&format!("Installing into the sandbox {}; skipping sudo privileges check.", sandbox.display()));
+        } else if self.config.enable_video {
             if !Self::check_sudo_privileges() {
                 Self::prompt_for_sudo()?;
             }
@@ -218,14 +276,140 @@ impl DependencyChecker {
         // Check system resources
         self.check_system_resources().await?;
 
-        // Check dependencies
+        // Check dependencies, installing yt-dlp if it's missing
+        if let Err(error) = self.ensure_yt_dlp() {
+            logger::warning(&format!("Could not install yt-dlp: {}", error));
+        }
+        #[cfg(feature = "managed-ffmpeg")]
+        if let Err(error) = self.ensure_ffmpeg() {
+            logger::warning(&format!("Could not install ffmpeg: {}", error));
+        }
         self.validate_environment().await?;
 
+        encoders::report();
+
         logger::success("Environment check completed successfully!");
         Ok(true)
     }
 }
 
+/// Where `update-deps` keeps the tools it installs: `~/Library/Application Support/rust-downloader/bin`
+/// on macOS, `~/.local/share/rust-downloader/bin` on Linux.
+pub fn managed_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("rust-downloader").join("bin"))
+}
+
+/// The yt-dlp to run: the managed copy once installed, otherwise the one on `PATH`.
+pub fn yt_dlp() -> PathBuf {
+    program("yt-dlp")
+}
+
+/// The ffmpeg to run, like `yt_dlp()`; only builds with `managed-ffmpeg` install one.
+pub fn ffmpeg() -> PathBuf {
+    program("ffmpeg")
+}
+
+pub fn ffprobe() -> PathBuf {
+    program("ffprobe")
+}
+
+/// `--ffmpeg-location` for yt-dlp, which merges and cuts with ffmpeg, when it's managed here.
+pub fn yt_dlp_ffmpeg_args() -> Vec<String> {
+    let ffmpeg = ffmpeg();
+    match ffmpeg.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        Some(dir) => vec!["--ffmpeg-location".to_string(), dir.to_string_lossy().to_string()],
+        None => Vec::new(),
+    }
+}
+
+/// Where to find an external tool: the copy in `managed_dir()` if there is one, otherwise
+/// the one on `PATH`.
+pub fn program(command: &str) -> PathBuf {
+    managed_dir()
+        .map(|dir| dir.join(command))
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(command))
+}
+
+/// Whether the yt-dlp from `yt_dlp()` runs.
+pub fn yt_dlp_available() -> bool {
+    yt_dlp_version(&yt_dlp()).is_some()
+}
+
+fn yt_dlp_version(path: &Path) -> Option<String> {
+    let output = Command::new(path).arg("--version").output().ok()?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !version.is_empty()).then_some(version)
+}
+
+/// Days since a yt-dlp release, from its version, e.g. "2025.01.15".
+fn yt_dlp_age_days(version: &str) -> Option<i64> {
+    let released = chrono::NaiveDate::parse_from_str(version.get(..10)?, "%Y.%m.%d").ok()?;
+    Some((chrono::Local::now().date_naive() - released).num_days())
+}
+
+/// The standalone build yt-dlp publishes for this platform.
+fn yt_dlp_asset_name() -> Option<&'static str> {
+    match (env::consts::OS, env::consts::ARCH) {
+        ("macos", _) => Some("yt-dlp_macos"),
+        ("linux", "x86_64") => Some("yt-dlp_linux"),
+        ("linux", "aarch64") => Some("yt-dlp_linux_aarch64"),
+        _ => None,
+    }
+}
+
+/// Download the latest official yt-dlp into `managed_dir()`, verified against the release's
+/// `SHA2-256SUMS`. A managed copy that is already the latest is kept unless `force` is set.
+pub fn install_yt_dlp(force: bool) -> Result<PathBuf> {
+    let asset_name = yt_dlp_asset_name()
+        .ok_or_else(|| format!("yt-dlp publishes no binary for this platform ({} {})", env::consts::OS, env::consts::ARCH))?;
+    let dir = managed_dir().ok_or("Could not determine data directory")?;
+    let target = dir.join("yt-dlp");
+
+    logger::search("Checking for the latest yt-dlp...");
+    let release = update::fetch_release(YT_DLP_RELEASE_URL, 15)?;
+    if let Some(installed) = yt_dlp_version(&target).filter(|_| !force) {
+        if !update::is_newer(release.version(), &installed) {
+            logger::success(&format!("yt-dlp is up to date ({})", installed));
+            return Ok(target);
+        }
+    }
+
+    let binary = release.asset(asset_name)
+        .ok_or_else(|| format!("yt-dlp {} has no {} build", release.tag_name, asset_name))?;
+    let checksums = release.asset("SHA2-256SUMS")
+        .ok_or_else(|| format!("yt-dlp {} has no checksums; refusing to install", release.tag_name))?;
+
+    // Stage in the same directory so the final rename replaces the old copy in one step
+    fs::create_dir_all(&dir)?;
+    let staged = tempfile::Builder::new().prefix(".yt-dlp-update").tempfile_in(&dir)?;
+    logger::download(&format!("Downloading {} {}...", asset_name, release.tag_name));
+    http::download_to(&binary.browser_download_url, staged.path())?;
+
+    // "<sha256>  <asset name>" per line
+    let checksum_file = tempfile::NamedTempFile::new()?;
+    http::download_to(&checksums.browser_download_url, checksum_file.path())?;
+    let expected = fs::read_to_string(checksum_file.path())?
+        .lines()
+        .find_map(|line| {
+            let (hash, name) = line.split_once(char::is_whitespace)?;
+            (name.trim().trim_start_matches('*') == asset_name).then(|| hash.to_lowercase())
+        })
+        .ok_or_else(|| format!("SHA2-256SUMS has no entry for {}", asset_name))?;
+
+    let actual = update::sha256_file(staged.path())?;
+    if actual != expected {
+        return Err(format!("Checksum mismatch for {} (expected {}, got {})", asset_name, expected, actual).into());
+    }
+    logger::success("Checksum verified");
+
+    #[cfg(unix)]
+    fs::set_permissions(staged.path(), fs::Permissions::from_mode(0o755))?;
+    staged.persist(&target).map_err(|e| e.error)?;
+    logger::success(&format!("Installed yt-dlp {} in {}", release.version(), dir.display()));
+    Ok(target)
+}
+
 // Platform-specific imports for macOS
 #[cfg(target_os = "macos")]
 extern "C" {
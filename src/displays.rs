@@ -0,0 +1,181 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::config::Config;
+use crate::error::Result;
+use crate::logger;
+use crate::video_manager::VideoManager;
+
+const ASSIGNMENTS_FILE: &str = "display_assignments.json";
+
+/// A connected display, numbered from 1 with the main display first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Display {
+    pub index: usize,
+    pub name: String,
+    /// e.g. "3024 x 1964 @ 120.00Hz"
+    pub resolution: Option<String>,
+    pub main: bool,
+}
+
+/// Connected displays as reported by `system_profiler SPDisplaysDataType`.
+pub fn list() -> Result<Vec<Display>> {
+    if !cfg!(target_os = "macos") {
+        return Err("Listing displays is only supported on macOS".into());
+    }
+    let output = Command::new("system_profiler").args(["SPDisplaysDataType", "-json"]).output()?;
+    if !output.status.success() {
+        return Err(format!("system_profiler failed: {}", String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    Ok(parse_displays(&serde_json::from_slice(&output.stdout)?))
+}
+
+/// Displays hang off each graphics card's `spdisplays_ndrvs`.
+fn parse_displays(value: &Value) -> Vec<Display> {
+    let mut displays: Vec<Display> = value["SPDisplaysDataType"].as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|card| card["spdisplays_ndrvs"].as_array())
+        .flatten()
+        .map(|display| Display {
+            index: 0,
+            name: display["_name"].as_str().unwrap_or("Unknown display").to_string(),
+            resolution: display["_spdisplays_resolution"].as_str().map(str::to_string),
+            main: display["spdisplays_main"].as_str() == Some("spdisplays_yes"),
+        })
+        .collect();
+
+    displays.sort_by_key(|display| !display.main);
+    for (index, display) in displays.iter_mut().enumerate() {
+        display.index = index + 1;
+    }
+    displays
+}
+
+/// Maps a display to the system video behind the aerial it shows.
+///
+/// Like Spaces, each display picks its own aerial in System Settings, so giving every display
+/// a different aerial and replacing only that file leaves the other displays alone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayAssignment {
+    pub display: usize,
+    pub display_name: String,
+    /// Library id or file name of the video installed last
+    pub video: String,
+    pub target_file: String,
+    pub assigned_at: String,
+}
+
+pub struct DisplayAssignments {
+    path: PathBuf,
+    assignments: Vec<DisplayAssignment>,
+}
+
+impl DisplayAssignments {
+    pub fn load(config: &Config) -> Self {
+        let path = config.output_dir.join(ASSIGNMENTS_FILE);
+        let assignments = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self { path, assignments }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(&self.assignments)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, display: usize) -> Option<&DisplayAssignment> {
+        self.assignments.iter().find(|a| a.display == display)
+    }
+
+    pub fn set(&mut self, assignment: DisplayAssignment) {
+        // One aerial file shows the same video everywhere, so drop any display that shared it
+        self.assignments.retain(|a| a.display != assignment.display && a.target_file != assignment.target_file);
+        self.assignments.push(assignment);
+        self.assignments.sort_by_key(|a| a.display);
+    }
+
+    /// Connected displays with what each one shows; assignments for displays that are no
+    /// longer connected are listed too, as they apply again once it's plugged back in.
+    pub fn display(&self) {
+        logger::header("Displays");
+
+        let displays = list().unwrap_or_else(|error| {
+            logger::warning(&format!("Could not list displays: {}", error));
+            Vec::new()
+        });
+        for display in &displays {
+            let main = if display.main { " (main)" } else { "" };
+            let resolution = display.resolution.as_deref().map(|r| format!(", {}", r)).unwrap_or_default();
+            match self.get(display.index) {
+                Some(a) => logger::wallpaper(&format!("{}. {}{}{}: {} (replaces {})", display.index, display.name, main, resolution, a.video, a.target_file)),
+                None => logger::info(&format!("{}. {}{}{}: not assigned", display.index, display.name, main, resolution)),
+            }
+        }
+        for a in self.assignments.iter().filter(|a| a.display > displays.len()) {
+            logger::info(&format!("{}. {} (not connected): {} (replaces {})", a.display, a.display_name, a.video, a.target_file));
+        }
+        if self.assignments.is_empty() {
+            logger::info("No wallpapers assigned to displays yet");
+            logger::info("Assign one with: rust-downloader wallpaper assign --display 2 <id>");
+        }
+    }
+}
+
+/// Install `video` on one display only. The first time, the user picks the system video
+/// behind that display's aerial; later installs replace the same file without asking.
+pub async fn install_on_display(config: &Config, index: usize, video: &Path, label: &str) -> Result<Option<DisplayAssignment>> {
+    let displays = list()?;
+    let display = displays.iter().find(|d| d.index == index).ok_or_else(|| {
+        format!("Display {} is not connected ({} display{} found; see 'rust-downloader wallpaper displays')",
+            index, displays.len(), if displays.len() == 1 { "" } else { "s" })
+    })?;
+
+    let mut assignments = DisplayAssignments::load(config);
+    let video_mgr = VideoManager::new(config);
+    let videos = config.video_settings.target_dir();
+
+    let known_target = assignments.get(index)
+        .map(|a| a.target_file.clone())
+        .filter(|target| videos.join(target).exists());
+    let target_file = match known_target {
+        Some(target) => {
+            logger::wallpaper(&format!("Display {} ({}) uses {}", index, display.name, target));
+            video_mgr.swap_video(video, Some(&target)).await?
+        }
+        None => {
+            logger::header(&format!("Install on Display {}: {}", index, display.name));
+            logger::info(&format!("Check which aerial {} uses in System Settings > Wallpaper", display.name));
+            logger::info("Each display needs its own aerial; the one you pick next is replaced by this video");
+            for other in displays.iter().filter(|d| d.index != index) {
+                if let Some(a) = assignments.get(other.index) {
+                    logger::info(&format!("   Display {} ({}) already uses {}", other.index, other.name, a.target_file));
+                }
+            }
+            println!();
+
+            match video_mgr.setup_video_target(video).await? {
+                Some(target) => target,
+                None => return Ok(None),
+            }
+        }
+    };
+
+    let assignment = DisplayAssignment {
+        display: index,
+        display_name: display.name.clone(),
+        video: label.to_string(),
+        target_file,
+        assigned_at: chrono::Local::now().to_rfc3339(),
+    };
+    assignments.set(assignment.clone());
+    assignments.save()?;
+
+    logger::success(&format!("Display {} ({}) now shows {}", index, display.name, label));
+    Ok(Some(assignment))
+}
@@ -1,47 +1,229 @@
-use serde_json::Value;
-use std::process::{Command, Stdio};
-use std::io::{BufRead, BufReader};
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::io::Write;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
 use std::time::{Duration, SystemTime};
+use crate::error::{DownloaderError, Result};
 use crate::utils;
+use crate::dependencies;
 use crate::logger;
-use crate::config::Config;
-use crate::video_info::{SelectedFormats, VideoFormat, AudioFormat};
+use crate::still;
+use crate::crash;
+use crate::integrity;
+use crate::ytdlp_error::YtDlpError;
+use crate::auth;
+use crate::status::{self, Stage};
+use crate::phases::PhaseWeights;
+use crate::mqtt;
+use crate::http;
+use crate::sponsorblock::{self, Segment};
+use crate::speed::{self, SpeedTracker};
+use crate::cancel::{self, CancelHandle, TrackedCommand};
+use crate::watchdog::{self, Watchdog};
+use crate::telemetry::{EncodeReport, GpuSampler};
+use crate::ffmpeg_progress::{Progress, ProgressParser};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use crate::encoders::{self, Encoder};
+use crate::live_photo;
+use crate::loudness;
+use crate::history::Conversion;
+use crate::events::{ProgressEvent, ProgressObserver};
+use crate::aerial;
+use crate::playlist::Album;
+use crate::config::{parse_timestamp, AudioOutputFormat, AudioVisualizer, ClipRange, Config, ConversionProfile, ProfileCodec, FreeFormats, LoopFormat, LoopSettings, SponsorBlockMode, OutputResolution, OverlayPosition, StillExport, TextOverlay};
+use crate::video_info::{Chapter, SelectedFormats, VideoFormat, AudioFormat};
+
+/// How far apart the merged video and audio streams may end, in seconds; YouTube's streams
+/// differ by a few frames, a failed merge by minutes
+const MERGE_TOLERANCE_SECONDS: f64 = 2.0;
+
+/// Files smaller than this are fetched in one request; more would only add overhead
+const MIN_SEGMENT_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Inclusive byte ranges splitting `size` bytes into at most `count` segments of at least
+/// `MIN_SEGMENT_BYTES` each.
+fn segment_ranges(size: u64, count: u32) -> Vec<(u64, u64)> {
+    let count = (count as u64).min(size / MIN_SEGMENT_BYTES).max(1);
+    let length = size.div_ceil(count);
+    (0..count)
+        .map(|index| (index * length, ((index + 1) * length).min(size) - 1))
+        .collect()
+}
+
+/// The source properties that decide between remuxing and re-encoding
+#[derive(Debug, Clone, Copy)]
+struct SourceProbe<'a> {
+    /// ffprobe codec name, e.g. "hevc"
+    codec: &'a str,
+    /// ffprobe format names, comma-separated
+    container: &'a str,
+    width: u64,
+    height: u64,
+    fps: f64,
+    /// Bits per second
+    bitrate: u64,
+}
+
+impl<'a> SourceProbe<'a> {
+    /// What ffprobe should report once `format` is downloaded into `container`.
+    fn predict(format: &VideoFormat, container: &'a str) -> Self {
+        // yt-dlp codec strings carry a profile, e.g. "avc1.640028" or "vp09.00.51.08"
+        let codec = match format.vcodec.as_deref().unwrap_or("").split('.').next().unwrap_or("") {
+            "avc1" | "h264" => "h264",
+            "hvc1" | "hev1" | "hevc" => "hevc",
+            "vp09" | "vp9" => "vp9",
+            "av01" => "av1",
+            _ => "unknown",
+        };
+        Self {
+            codec,
+            container,
+            width: format.width.unwrap_or(0) as u64,
+            height: format.height.unwrap_or(0) as u64,
+            fps: format.fps.unwrap_or(30.0),
+            bitrate: (format.tbr.unwrap_or(0.0) * 1000.0) as u64,
+        }
+    }
+}
 
 pub struct Downloader {
-    is_downloading: bool,
-    current_process: Option<std::process::Child>,
+    current_process: Option<tokio::process::Child>,
+    cancel: CancelHandle,
+    /// The cookie profile this job signs in with
+    auth: auth::Session,
+    batch_outputs: Vec<PathBuf>,
+    /// Wall time of the last conversion, for the download history
+    conversion_seconds: Option<f64>,
+    /// Whether the last conversion remuxed or re-encoded, and why it re-encoded
+    conversion_path: Option<String>,
+    sample_seconds: Option<u32>,
+    job_label: String,
+    /// Set by `set_job_label`, so the label isn't derived from the URL again
+    label_assigned: bool,
+    /// Another download shares the progress rows, so the owner releases them
+    shared_progress: bool,
+    /// Metadata tags written into converted files
+    tags: Vec<(String, String)>,
+    /// Loudness of the last `--audio-only` output
+    loudness: Option<loudness::Measurement>,
+    observer: Option<Arc<dyn ProgressObserver>>,
+    /// The info of a video analyzed through a mirror, which yt-dlp loads instead of asking YouTube
+    mirror_info: Option<tempfile::NamedTempFile>,
+    /// The chosen format when it is fetched from its URL with curl instead of by yt-dlp
+    direct_format: Option<Value>,
+    /// Set by `expect_install`
+    install_follows: bool,
+    /// A queue's download slot, given back once the file is downloaded
+    download_slot: Option<OwnedSemaphorePermit>,
+    /// A queue's conversion slots, one of which a conversion waits for
+    conversion_slots: Option<Arc<Semaphore>>,
+}
+
+impl Default for Downloader {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Downloader {
     pub fn new() -> Self {
         Self {
-            is_downloading: false,
             current_process: None,
+            cancel: CancelHandle::new(),
+            auth: auth::Session::new(),
+            batch_outputs: Vec::new(),
+            conversion_seconds: None,
+            conversion_path: None,
+            sample_seconds: None,
+            job_label: "download".to_string(),
+            label_assigned: false,
+            shared_progress: false,
+            tags: Vec::new(),
+            loudness: None,
+            observer: None,
+            mirror_info: None,
+            direct_format: None,
+            install_follows: false,
+            download_slot: None,
+            conversion_slots: None,
+        }
+    }
+
+    /// Report progress to `observer` (e.g. a closure updating a UI), replacing any earlier one.
+    pub fn set_observer(&mut self, observer: impl ProgressObserver + 'static) {
+        self.observer = Some(Arc::new(observer));
+    }
+
+    /// Report progress as `ProgressEvent`s on a channel.
+    pub fn send_events_to(&mut self, sender: mpsc::Sender<ProgressEvent>) {
+        self.set_observer(sender);
+    }
+
+    fn notify(&self, event: ProgressEvent) {
+        if let Some(observer) = &self.observer {
+            observer.on_event(&event);
         }
     }
 
-    fn create_output_filename(&self, info: &crate::video_info::VideoInfo, video_format: &VideoFormat, config: &crate::config::Config) -> String {
-        let quality = format!("{}p_{}fps", video_format.height.unwrap_or(0), video_format.fps.unwrap_or(30.0) as u32);
+    /// `video_info::analyze`, reported to the observer as the start of the job.
+    pub fn analyze(&mut self, url: &str, config: &Config) -> Result<SelectedFormats> {
+        self.label_from(url);
+        self.notify(ProgressEvent::AnalysisStarted { job: self.job_label.clone(), url: url.to_string() });
+        crate::video_info::analyze(url, &self.auth, config)
+    }
+
+    /// The album and zero-based track number of a video that a music profile is downloading
+    /// as part of a playlist.
+    fn album_track<'a>(&self, info: &crate::video_info::VideoInfo, config: &'a Config) -> Option<(&'a Album, usize)> {
+        let id = info.id.as_deref()?;
+        config.download_settings.albums.iter().find_map(|album| Some((album, album.position(id)?)))
+    }
+
+    fn create_output_filename(&self, analysis: &SelectedFormats, config: &crate::config::Config) -> String {
+        let video_format = &analysis.video_format;
+        let (mut quality, extension) = match (&analysis.audio_format, config.conversion_settings.audio_only) {
+            (Some(audio), true) => ("audio".to_string(), audio.ext.as_str()),
+            (None, true) => ("audio".to_string(), video_format.ext.as_str()),
+            (_, false) => (
+                format!("{}p_{}fps", video_format.height.unwrap_or(0), video_format.fps.unwrap_or(30.0) as u32),
+                config.download_settings.merge_output_format,
+            ),
+        };
+        if let Some((album, position)) = self.album_track(&analysis.info, config).filter(|_| config.conversion_settings.audio_only) {
+            return album.track_path(position, extension).to_string_lossy().to_string();
+        }
+        // Keep samples apart from full downloads so neither is mistaken for the other
+        if let Some(seconds) = config.download_settings.sample_seconds {
+            quality.push_str(&format!("_sample{}s", seconds));
+        }
+        if let Some(clip) = &config.download_settings.clip {
+            quality.push('_');
+            quality.push_str(&clip.label());
+        }
         utils::create_safe_filename(
-            &info.title,
+            &analysis.info.title,
             &quality,
-            self.get_extension(),
-            config.file_naming.max_title_length,
+            extension,
+            &config.file_naming,
         )
     }
 
-    fn get_extension(&self) -> &'static str {
-        Config::default().download_settings.merge_output_format
-    }
-
-    fn check_existing_video(&self, output_path: &Path) -> (bool, Option<PathBuf>, bool) {
-        // First check for .mov version (final format)
-        let mov_path = output_path.with_extension("mov");
+    fn check_existing_video(&self, output_path: &Path, config: &Config) -> (bool, Option<PathBuf>, bool) {
+        // First check for the converted version (final format)
+        let settings = &config.conversion_settings;
+        let mov_path = match (settings.audio_only, settings.audio_format) {
+            (true, Some(format)) => self.audio_output_path(output_path, format),
+            // Without a format the download itself may be kept, so it can't tell
+            (true, None) => PathBuf::new(),
+            (false, _) => settings.profile().output_path(output_path),
+        };
         if mov_path.exists() {
             if let Ok(stats) = fs::metadata(&mov_path) {
-                logger::success(&format!("📁 Final .mov video already exists: {}", mov_path.file_name().unwrap().to_string_lossy()));
+                logger::success(&format!("📁 Final converted video already exists: {}", mov_path.file_name().unwrap().to_string_lossy()));
                 logger::stats(&format!("📊 Size: {}", utils::format_file_size(Some(stats.len()))));
                 return (true, Some(mov_path), false);
             }
@@ -57,6 +239,16 @@ impl Downloader {
             }
         }
 
+        // Finally look for an interrupted download that yt-dlp can resume
+        let partials = partial_downloads(output_path);
+        if !partials.is_empty() {
+            let resumable: u64 = partials.iter().map(|(_, size)| size).sum();
+            for (path, _) in &partials {
+                logger::info(&format!("Found partial download: {}", path.file_name().unwrap().to_string_lossy()));
+            }
+            logger::info(&format!("Resuming with {} already downloaded", utils::format_file_size(Some(resumable))));
+        }
+
         (false, None, false)
     }
 
@@ -64,7 +256,7 @@ impl Downloader {
         let resolution = video_format.height.unwrap_or(0);
         let min_recommended = config.video_settings.min_recommended_resolution;
 
-        if resolution < min_recommended as u32 {
+        if resolution < min_recommended {
             logger::warning(" Video quality warning!");
             logger::warning(&format!("Selected: {}p ({}x{})", resolution, video_format.width.unwrap_or(0), resolution));
             logger::warning(&format!("Recommended: {}p for best wallpaper quality", min_recommended));
@@ -74,18 +266,18 @@ impl Downloader {
         }
     }
 
-    async fn get_video_duration(&self, input_path: &Path) -> Result<f64, Box<dyn std::error::Error>> {
-        let output = Command::new("ffprobe")
+    async fn get_video_duration(&self, input_path: &Path) -> Result<f64> {
+        let output = Command::new(dependencies::ffprobe())
             .args([
                 "-v", "quiet",
                 "-print_format", "json",
                 "-show_format",
                 input_path.to_str().unwrap()
             ])
-            .output()?;
+            .tracked_output(&self.cancel).await?;
 
         if !output.status.success() {
-            return Err(format!("ffprobe failed: {}", String::from_utf8_lossy(&output.stderr)).into());
+            return Err(DownloaderError::Ffmpeg(format!("ffprobe failed: {}", String::from_utf8_lossy(&output.stderr))));
         }
 
         let info: Value = serde_json::from_str(&String::from_utf8_lossy(&output.stdout))?;
@@ -98,50 +290,33 @@ impl Downloader {
         Ok(duration)
     }
 
-    async fn extend_video(&self, input_path: &Path, min_duration: f64) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    async fn extend_video(&self, input_path: &Path, min_duration: f64, config: &Config) -> Result<PathBuf> {
         let original_duration = self.get_video_duration(input_path).await?;
         let output_path = input_path.with_extension("extended.mp4");
 
         // Calculate how many loops we need
         let loops_needed = (min_duration / original_duration).ceil() as i32;
 
-        logger::info(&format!("Creating extended version by looping the video..."));
+        logger::info("Creating extended version by looping the video...");
         logger::info(&format!("Original: {} → Target: {} ({} loops)", 
             utils::format_time(original_duration), 
             utils::format_time(min_duration), 
             loops_needed));
 
         // Use FFmpeg to loop the video
-        let args = [
-            "-stream_loop", "-1", // Loop indefinitely
-            "-i", input_path.to_str().unwrap(),
-            "-t", &min_duration.to_string(), // Stop at minimum duration
-            "-c", "copy", // Copy streams without re-encoding for speed
-            "-avoid_negative_ts", "make_zero",
-            "-fflags", "+genpts", // Generate presentation timestamps
-            "-y", // Overwrite output file
-            output_path.to_str().unwrap(),
-        ];
-
-        let mut child = Command::new("ffmpeg")
-            .args(&args)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?;
-
-        let stderr = child.stderr.take().unwrap();
-        let reader = BufReader::new(stderr);
-
-        // Monitor progress
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                if line.contains("time=") {
-                    // Progress monitoring could be added here
-                }
-            }
-        }
-
-        let status = child.wait()?;
+        let mut command = Command::new(dependencies::ffmpeg());
+        command
+            .args(["-y", "-v", "error", "-nostats", "-progress", "pipe:1"])
+            .args(["-stream_loop", "-1"]) // Loop indefinitely
+            .arg("-i")
+            .arg(input_path)
+            .args(["-t", &min_duration.to_string()]) // Stop at minimum duration
+            .args(["-c", "copy"]) // Copy streams without re-encoding for speed
+            .args(["-avoid_negative_ts", "make_zero", "-fflags", "+genpts"]) // Generate presentation timestamps
+            .arg(&output_path);
+
+        let (output, _) = self.run_with_progress(command, &output_path, min_duration, Stage::Extending, "Looping", config).await?;
+        let status = output.status;
 
         if status.success() {
             if output_path.exists() {
@@ -151,13 +326,325 @@ impl Downloader {
                     return Ok(output_path);
                 }
             }
-            return Err("Extended video file not found after processing".into());
+            Err("Extended video file not found after processing".into())
         } else {
-            return Err(format!("Video extension failed with code {:?}", status.code()).into());
+            Err(DownloaderError::Ffmpeg(format!("Video extension failed with code {:?}", status.code())))
+        }
+    }
+
+    /// Find black or silent stretches touching the start and end of the video and return the
+    /// (start, end) range worth keeping, or `None` when nothing should be cut.
+    async fn detect_trim_points(&self, input_path: &Path, duration: f64) -> Result<Option<(f64, f64)>> {
+        let output = Command::new(dependencies::ffmpeg())
+            .args(["-hide_banner", "-nostats", "-i"])
+            .arg(input_path)
+            .args([
+                "-vf", "blackdetect=d=0.5:pix_th=0.10",
+                "-af", "silencedetect=n=-50dB:d=1",
+                "-f", "null", "-",
+            ])
+            .tracked_output(&self.cancel).await?;
+
+        if !output.status.success() {
+            return Err(DownloaderError::Ffmpeg(format!("Trim detection failed: {}", String::from_utf8_lossy(&output.stderr))));
+        }
+
+        let intervals = parse_detected_intervals(&String::from_utf8_lossy(&output.stderr), duration);
+        Ok(choose_trim_range(&intervals, duration))
+    }
+
+    async fn auto_trim(&self, input_path: &Path) -> Result<Option<PathBuf>> {
+        logger::search("Detecting intro/outro title cards (black frames and silence)...");
+
+        let duration = self.get_video_duration(input_path).await?;
+        let (start, end) = match self.detect_trim_points(input_path, duration).await? {
+            Some(range) => range,
+            None => {
+                logger::info("No intro or outro detected, keeping full video");
+                return Ok(None);
+            }
+        };
+
+        logger::info(&format!("Trimming {:.1}s from the start and {:.1}s from the end", start, duration - end));
+
+        let output_path = input_path.with_extension("trimmed.mp4");
+        let status = Command::new(dependencies::ffmpeg())
+            .args(["-y", "-v", "error", "-ss", &format!("{:.3}", start), "-i"])
+            .arg(input_path)
+            .args(["-t", &format!("{:.3}", end - start), "-c", "copy", "-avoid_negative_ts", "make_zero"])
+            .arg(&output_path)
+            .tracked_output(&self.cancel).await?.status;
+
+        if !status.success() || !output_path.exists() {
+            logger::warning("Trimming failed, continuing with the untrimmed video");
+            return Ok(None);
+        }
+
+        logger::success(&format!("Trimmed video: {}", utils::format_time(end - start)));
+        Ok(Some(output_path))
+    }
+
+    /// Timestamps where the scene score crosses `threshold`, dropping cuts that would leave clips
+    /// shorter than `min_clip` seconds.
+    async fn detect_scene_cuts(&self, input_path: &Path, threshold: f64, min_clip: f64) -> Result<Vec<f64>> {
+        let output = Command::new(dependencies::ffmpeg())
+            .args(["-hide_banner", "-nostats", "-i"])
+            .arg(input_path)
+            .args([
+                "-an",
+                "-vf", &format!("select='gt(scene,{})',showinfo", threshold),
+                "-f", "null", "-",
+            ])
+            .tracked_output(&self.cancel).await?;
+
+        if !output.status.success() {
+            return Err(DownloaderError::Ffmpeg(format!("Scene detection failed: {}", String::from_utf8_lossy(&output.stderr))));
+        }
+
+        let duration = self.get_video_duration(input_path).await?;
+        let mut cuts: Vec<f64> = Vec::new();
+        for line in String::from_utf8_lossy(&output.stderr).lines().filter(|l| l.contains("showinfo")) {
+            let time = line.split("pts_time:").nth(1)
+                .and_then(|rest| rest.split_whitespace().next())
+                .and_then(|value| value.parse::<f64>().ok());
+
+            if let Some(time) = time {
+                let previous = cuts.last().copied().unwrap_or(0.0);
+                if time - previous >= min_clip && duration - time >= min_clip {
+                    cuts.push(time);
+                }
+            }
+        }
+
+        Ok(cuts)
+    }
+
+    /// Split a video at detected scene cuts into `<name>_sceneNN.mp4` clips next to it.
+    async fn split_scenes(&self, input_path: &Path, threshold: f64) -> Result<Vec<PathBuf>> {
+        logger::search(&format!("Detecting scene cuts (threshold {:.2})...", threshold));
+
+        let cuts = self.detect_scene_cuts(input_path, threshold, 3.0).await?;
+        if cuts.is_empty() {
+            logger::info("No scene cuts detected, keeping the video as a single clip");
+            return Ok(vec![input_path.to_path_buf()]);
+        }
+
+        logger::info(&format!("Found {} scene cuts, splitting into {} clips", cuts.len(), cuts.len() + 1));
+
+        let stem = input_path.file_stem().unwrap().to_string_lossy().to_string();
+        let pattern = input_path.with_file_name(format!("{}_scene%02d.mp4", stem));
+        let segment_times = cuts.iter().map(|t| format!("{:.3}", t)).collect::<Vec<_>>().join(",");
+
+        let status = Command::new(dependencies::ffmpeg())
+            .args(["-y", "-v", "error", "-i"])
+            .arg(input_path)
+            .args([
+                "-map", "0", "-c", "copy",
+                "-f", "segment",
+                "-segment_times", &segment_times,
+                "-reset_timestamps", "1",
+            ])
+            .arg(&pattern)
+            .tracked_output(&self.cancel).await?.status;
+
+        if !status.success() {
+            return Err(DownloaderError::Ffmpeg("Failed to split video into scenes".to_string()));
+        }
+
+        let clips: Vec<PathBuf> = (0..=cuts.len())
+            .map(|i| input_path.with_file_name(format!("{}_scene{:02}.mp4", stem, i)))
+            .filter(|clip| clip.exists())
+            .collect();
+
+        for clip in &clips {
+            logger::file(&format!("  {}", clip.file_name().unwrap().to_string_lossy()));
+        }
+
+        Ok(clips)
+    }
+
+    /// Cut out or mark the video's SponsorBlock segments, replacing `path`.
+    async fn apply_sponsorblock(&self, url: &str, path: &Path, mode: SponsorBlockMode, config: &Config) -> Result<()> {
+        let video_id = utils::extract_video_id(url).ok_or_else(|| DownloaderError::InvalidUrl(url.to_string()))?;
+        logger::search("Looking up SponsorBlock segments...");
+
+        // Clips and samples only cover part of the video the segments are timed against
+        let duration = self.get_video_duration(path).await?;
+        let offset = config.download_settings.clip.map(|clip| clip.start).unwrap_or(0.0);
+        let segments = blocking(move || sponsorblock::fetch_segments(&video_id)).await?;
+        let segments = sponsorblock::fit_to_file(&segments, offset, duration);
+        if segments.is_empty() {
+            logger::info("No SponsorBlock segments in this video");
+            return Ok(());
+        }
+
+        let total: f64 = segments.iter().map(|s| s.end - s.start).sum();
+        logger::info(&format!("Found {} segment(s), {} in total", segments.len(), utils::format_time(total)));
+
+        let extension = path.extension().unwrap_or_default().to_string_lossy();
+        let temporary = path.with_extension(format!("sponsorblock.{}", extension));
+        self.cancel.track_file(&temporary);
+        let result = match mode {
+            SponsorBlockMode::Remove => self.cut_segments(path, &temporary, &segments, duration).await,
+            SponsorBlockMode::Mark => self.mark_segments(path, &temporary, &segments, duration).await,
+        };
+        self.cancel.untrack_file(&temporary);
+
+        if let Err(error) = result {
+            fs::remove_file(&temporary).ok();
+            return Err(error);
+        }
+        fs::rename(&temporary, path)?;
+
+        match mode {
+            SponsorBlockMode::Remove => logger::success(&format!("Removed {} of sponsored segments", utils::format_time(total))),
+            SponsorBlockMode::Mark => logger::success(&format!("Marked {} segment(s) as chapters", segments.len())),
+        }
+        Ok(())
+    }
+
+    /// Join the parts between `segments` with ffmpeg's concat demuxer, without re-encoding.
+    async fn cut_segments(&self, input_path: &Path, output_path: &Path, segments: &[Segment], duration: f64) -> Result<()> {
+        let ranges = sponsorblock::keep_ranges(segments, duration);
+        if ranges.is_empty() {
+            return Err("the segments cover the whole video".into());
+        }
+
+        let source = fs::canonicalize(input_path)?.to_string_lossy().replace('\'', "'\\''");
+        let mut list = tempfile::Builder::new().suffix(".txt").tempfile()?;
+        for (start, end) in ranges {
+            writeln!(list, "file '{}'\ninpoint {:.3}\noutpoint {:.3}", source, start, end)?;
+        }
+
+        let output = Command::new(dependencies::ffmpeg())
+            .args(["-y", "-v", "error", "-f", "concat", "-safe", "0", "-i"])
+            .arg(list.path())
+            .args(["-map", "0", "-c", "copy"])
+            .arg(output_path)
+            .tracked_output(&self.cancel).await?;
+
+        if !output.status.success() {
+            return Err(DownloaderError::Ffmpeg(format!("Failed to cut segments: {}", String::from_utf8_lossy(&output.stderr).trim())));
+        }
+        Ok(())
+    }
+
+    /// Add chapters alternating between the video and each segment, named after its category.
+    async fn mark_segments(&self, input_path: &Path, output_path: &Path, segments: &[Segment], duration: f64) -> Result<()> {
+        let mut chapters = Vec::new();
+        let mut position = 0.0;
+        for segment in segments {
+            if segment.start > position {
+                chapters.push((position, segment.start, "Video".to_string()));
+            }
+            chapters.push((segment.start, segment.end, format!("SponsorBlock: {}", segment.category)));
+            position = segment.end;
+        }
+        if duration > position {
+            chapters.push((position, duration, "Video".to_string()));
+        }
+
+        let mut metadata = tempfile::Builder::new().suffix(".txt").tempfile()?;
+        writeln!(metadata, ";FFMETADATA1")?;
+        for (start, end, title) in chapters {
+            writeln!(metadata, "[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}", (start * 1000.0) as u64, (end * 1000.0) as u64, title)?;
+        }
+
+        let output = Command::new(dependencies::ffmpeg())
+            .args(["-y", "-v", "error", "-i"])
+            .arg(input_path)
+            .arg("-i")
+            .arg(metadata.path())
+            .args(["-map", "0", "-map_metadata", "0", "-map_chapters", "1", "-c", "copy"])
+            .arg(output_path)
+            .tracked_output(&self.cancel).await?;
+
+        if !output.status.success() {
+            return Err(DownloaderError::Ffmpeg(format!("Failed to add chapters: {}", String::from_utf8_lossy(&output.stderr).trim())));
+        }
+        Ok(())
+    }
+
+    /// The full download of the same video and format, when clipping and it is already on disk.
+    fn full_source_for_clip(&self, analysis: &SelectedFormats, config: &Config) -> Option<PathBuf> {
+        config.download_settings.clip?;
+
+        let mut full = config.clone();
+        full.download_settings.clip = None;
+        let source = config.output_dir.join(self.create_output_filename(analysis, &full));
+        source.exists().then_some(source)
+    }
+
+    fn cut_clip_command(&self, source: &Path, output_path: &Path, clip: &ClipRange) -> Command {
+        let mut command = Command::new(dependencies::ffmpeg());
+        command.args(["-y", "-v", "error", "-ss", &format!("{:.3}", clip.start)]);
+        if let Some(end) = clip.end {
+            command.args(["-to", &format!("{:.3}", end)]);
+        }
+        command
+            .arg("-i")
+            .arg(source)
+            .args(["-map", "0", "-c", "copy", "-avoid_negative_ts", "make_zero"])
+            .arg(output_path);
+        command
+    }
+
+    async fn cut_clip(&self, source: &Path, output_path: &Path, config: &Config) -> Result<PathBuf> {
+        let clip = config.download_settings.clip.ok_or("No clip range set")?;
+        logger::info(&format!("Cutting the clip from {}", source.file_name().unwrap().to_string_lossy()));
+
+        let output = self.cut_clip_command(source, output_path, &clip).tracked_output(&self.cancel).await?;
+
+        if !output.status.success() {
+            return Err(DownloaderError::Ffmpeg(format!("Failed to cut clip: {}", String::from_utf8_lossy(&output.stderr).trim())));
+        }
+        logger::success(&format!("Clip saved: {}", output_path.file_name().unwrap().to_string_lossy()));
+        Ok(output_path.to_path_buf())
+    }
+
+    /// Cut a video into `<name>_chNN_<chapter title>.<ext>` files next to it, one per chapter.
+    async fn split_chapters(&self, input_path: &Path, chapters: &[Chapter], naming: &crate::config::FileNamingConfig) -> Result<Vec<PathBuf>> {
+        if chapters.is_empty() {
+            logger::info("The video has no chapters, keeping it as a single file");
+            return Ok(vec![input_path.to_path_buf()]);
+        }
+
+        logger::info(&format!("Splitting into {} chapters", chapters.len()));
+
+        let stem = input_path.file_stem().unwrap().to_string_lossy().to_string();
+        let extension = input_path.extension().unwrap_or_default().to_string_lossy().to_string();
+        let mut clips = Vec::new();
+
+        for (index, chapter) in chapters.iter().enumerate() {
+            let title = utils::clean_title(&chapter.title, naming);
+            let clip = input_path.with_file_name(match title.is_empty() {
+                true => format!("{}_ch{:02}.{}", stem, index + 1, extension),
+                false => format!("{}_ch{:02}_{}.{}", stem, index + 1, title, extension),
+            });
+
+            let output = Command::new(dependencies::ffmpeg())
+                .args(["-y", "-v", "error", "-ss", &format!("{:.3}", chapter.start_time), "-to", &format!("{:.3}", chapter.end_time), "-i"])
+                .arg(input_path)
+                .args(["-map", "0", "-c", "copy", "-avoid_negative_ts", "make_zero"])
+                .arg(&clip)
+                .tracked_output(&self.cancel).await?;
+
+            // A sample or clipped download can end before the later chapters start
+            if !output.status.success() || !clip.exists() {
+                logger::warning(&format!("Skipping chapter {} \"{}\": {}", index + 1, chapter.title, String::from_utf8_lossy(&output.stderr).trim()));
+                continue;
+            }
+            logger::file(&format!("  {}", clip.file_name().unwrap().to_string_lossy()));
+            clips.push(clip);
+        }
+
+        if clips.is_empty() {
+            return Err(DownloaderError::Ffmpeg("Failed to split video into chapters".to_string()));
         }
+        Ok(clips)
     }
 
-    fn fix_file_permissions(&self, file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    fn fix_file_permissions(&self, file_path: &Path) -> Result<()> {
         logger::info(&format!("🔧 Fixing file permissions for: {}", file_path.file_name().unwrap().to_string_lossy()));
 
         let success = utils::fix_file_permissions(file_path)?;
@@ -172,20 +659,24 @@ impl Downloader {
         Ok(())
     }
 
-    async fn cleanup_source_file(&self, source_path: &Path, converted_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        // Verify the converted file exists and has reasonable size
+    /// Delete the downloaded source once the conversion is known to be good: it must have the
+    /// `expected_duration` and decode at both ends (`integrity::problem`).
+    async fn cleanup_source_file(&self, source_path: &Path, converted_path: &Path, expected_duration: f64) -> Result<()> {
         if !converted_path.exists() {
             logger::warning("Converted file not found, keeping source file");
             return Ok(());
         }
 
-        let source_stats = fs::metadata(source_path)?;
-        let converted_stats = fs::metadata(converted_path)?;
-
-        // Basic sanity check - converted file should be at least 10% of source size
-        if converted_stats.len() < source_stats.len() / 10 {
-            logger::warning(" Converted file seems too small, keeping source file for safety");
-            return Ok(());
+        match integrity::problem(converted_path, Some(expected_duration), &self.cancel).await {
+            Ok(None) => logger::success("Verified the converted file"),
+            Ok(Some(problem)) => {
+                logger::warning(&format!(" Converted file failed verification ({}), keeping source file for safety", problem));
+                return Ok(());
+            }
+            Err(error) => {
+                logger::warning(&format!(" Could not verify the converted file ({}), keeping source file for safety", error));
+                return Ok(());
+            }
         }
 
         // Only clean up MP4 files (not other formats)
@@ -228,250 +719,1257 @@ impl Downloader {
         Ok(())
     }
 
-    async fn convert_with_hevc(&self, input_path: &Path, output_path: &Path, mut use_fallback: bool, mut reencode_audio: bool) -> Result<PathBuf, Box<dyn std::error::Error>> {
-        let config = Config::default();
-        let max_attempts = config.conversion_settings.max_attempts;
+    async fn has_audio_stream(&self, input_path: &Path) -> bool {
+        Command::new(dependencies::ffprobe())
+            .args(["-v", "quiet", "-select_streams", "a", "-show_entries", "stream=index", "-of", "csv=p=0"])
+            .arg(input_path)
+            .tracked_output(&self.cancel).await
+            .map(|output| output.status.success() && !output.stdout.trim_ascii().is_empty())
+            .unwrap_or(false)
+    }
 
-        for attempt in 1..=max_attempts {
-            if attempt > 1 {
-                logger::info(&format!("Conversion attempt {}/{}", attempt, max_attempts));
+    /// Check that yt-dlp's merge of separate video and audio formats kept both streams at the
+    /// same length. A failed merge can still exit successfully, leaving a video-only file that
+    /// would otherwise only show up after the conversion; it is deleted so a retry starts over.
+    async fn verify_merge(&self, path: &Path) -> Result<()> {
+        let output = Command::new(dependencies::ffprobe())
+            .args(["-v", "error", "-show_entries", "stream=codec_type,duration:stream_tags=DURATION", "-of", "json"])
+            .arg(path)
+            .tracked_output(&self.cancel).await?;
+        let probe = match serde_json::from_slice::<Value>(&output.stdout) {
+            Ok(probe) if output.status.success() && probe["streams"].is_array() => probe,
+            _ => {
+                logger::warning("Could not check the merged streams with ffprobe; continuing without the check");
+                return Ok(());
             }
+        };
 
-            if use_fallback {
-                logger::convert("Converting to HEVC .mov format (software encoding)...");
-                logger::warning(" Hardware acceleration not available, using software encoding");
-            } else {
-                logger::convert("Converting to HEVC .mov format with hardware acceleration...");
-                logger::info("Using Apple VideoToolbox for optimal performance");
-            }
-
-            logger::info(" Conversion settings:");
-            logger::info("   • Codec: HEVC (H.265) 10-bit");
-            logger::info("   • Resolution: 4K (3840x2160)");
-            logger::info("   • Frame Rate: 60fps");
-            logger::info("   • Bitrate: 50 Mbps");
-
-            let video_codec = if use_fallback { "libx265" } else { "hevc_videotoolbox" };
-            let pixel_format = "yuv420p10le";
-
-            // Prepare arguments
-            let mut args = vec![
-                "-y",
-                "-i", input_path.to_str().unwrap(),
-                "-c:v", video_codec,
-                "-tag:v", "hvc1", // Ensure proper HEVC tag for QuickTime compatibility
-                "-movflags", "+faststart",
-                "-pix_fmt", pixel_format,
-                "-r", "60", // Force 60fps for smooth wallpaper
-                "-vf", "scale=3840:2160:flags=lanczos", // Ensure 4K resolution
-                "-b:v", "50M", // High bitrate for quality (50 Mbps)
-                "-maxrate", "60M",
-                "-bufsize", "100M"
-            ];
-
-            // Add audio codec
-            if reencode_audio {
-                args.extend_from_slice(&["-c:a", "aac"]);
-            } else {
-                args.extend_from_slice(&["-c:a", "copy"]);
+        match merge_problem(&probe) {
+            Some(problem) => {
+                fs::remove_file(path).ok();
+                Err(DownloaderError::Ffmpeg(format!("Merge of video and audio failed: {}", problem)))
             }
-
-            // Add profile settings for software encoding
-            if use_fallback {
-                args.extend_from_slice(&["-profile:v", "main10", "-level", "5.1", "-preset", "medium"]);
+            None => {
+                logger::success("Verified the merged video and audio streams");
+                Ok(())
             }
+        }
+    }
 
-            args.push("-y"); // Overwrite output file
-            args.push(output_path.to_str().unwrap());
+    /// Check that a finished download decodes at both ends. A damaged file is deleted so a
+    /// retry starts over; a check that can't run only warns.
+    async fn verify_download(&self, path: &Path) -> Result<()> {
+        match integrity::problem(path, None, &self.cancel).await {
+            Ok(None) => Ok(()),
+            Ok(Some(problem)) => {
+                fs::remove_file(path).ok();
+                Err(DownloaderError::Ffmpeg(format!("Downloaded file is damaged: {}", problem)))
+            }
+            Err(error) => {
+                logger::warning(&format!("Could not verify the download: {}", error));
+                Ok(())
+            }
+        }
+    }
 
-            // Run ffmpeg
-            let mut child = Command::new("ffmpeg")
-                .args(&args)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()?;
+    /// Whether the source is taller than it is wide, as Shorts are.
+    async fn is_vertical(&self, input_path: &Path) -> bool {
+        Command::new(dependencies::ffprobe())
+            .args(["-v", "quiet", "-select_streams", "v:0", "-show_entries", "stream=width,height", "-of", "csv=p=0"])
+            .arg(input_path)
+            .tracked_output(&self.cancel).await
+            .ok()
+            .and_then(|output| {
+                let text = String::from_utf8_lossy(&output.stdout).to_string();
+                let (width, height) = text.trim().split_once(',')?;
+                Some(width.parse::<u32>().ok()? < height.parse::<u32>().ok()?)
+            })
+            .unwrap_or(false)
+    }
 
-            let start_time = SystemTime::now();
-            let stderr = child.stderr.take().unwrap();
-            let reader = BufReader::new(stderr);
-
-            // Collect stderr for error reporting
-            let mut stderr_output = String::new();
-
-            // Parse progress
-            let mut video_duration = None;
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    stderr_output.push_str(&line);
-                    stderr_output.push('\n');
-
-                    // Extract video duration from initial output
-                    if video_duration.is_none() && line.contains("Duration:") {
-                        if let Some(duration_match) = line.split("Duration: ").nth(1) {
-                            let time_part = duration_match.split(',').next().unwrap_or("");
-                            let parts: Vec<&str> = time_part.split(':').collect();
-                            if parts.len() >= 3 {
-                                let hours = parts[0].parse::<f64>().unwrap_or(0.0);
-                                let minutes = parts[1].parse::<f64>().unwrap_or(0.0);
-                                let seconds = parts[2].parse::<f64>().unwrap_or(0.0);
-                                video_duration = Some(hours * 3600.0 + minutes * 60.0 + seconds);
-                            }
-                        }
-                    }
+    fn render_overlay_text(&self, template: &str, info: &crate::video_info::VideoInfo) -> String {
+        template
+            .replace("{title}", &info.title)
+            .replace("{uploader}", info.uploader.as_deref().unwrap_or("Unknown"))
+            .replace("{date}", &utils::format_date(info.upload_date.as_deref().unwrap_or("")))
+    }
 
-                    // Extract progress information
-                    if let Some(progress_data) = utils::parse_progress(&line) {
-                        let (percentage, _, _, eta) = progress_data;
-                        let progress_bar = utils::create_progress_bar(percentage, 20);
-
-                        if let Some(_duration) = video_duration {
-                            let elapsed = start_time.elapsed()?.as_secs_f64();
-                            let eta_text = if percentage > 5.0 {
-                                let estimated_total = elapsed / (percentage / 100.0);
-                                let eta_secs = (estimated_total - elapsed).max(0.0);
-                                format!(" | ETA: {}", utils::format_time(eta_secs))
-                            } else {
-                                String::new()
-                            };
-
-                            logger::progress(&format!("Converting {} | {} ETA: {}{}", progress_bar, eta, eta, eta_text));
-                        }
-                    }
-                }
-            }
+    fn drawtext_filter(&self, overlay: &TextOverlay, text_file: &Path) -> String {
+        let margin = 60;
+        let (x, y) = match overlay.position {
+            OverlayPosition::TopLeft => (format!("{}", margin), format!("{}", margin)),
+            OverlayPosition::TopRight => (format!("w-tw-{}", margin), format!("{}", margin)),
+            OverlayPosition::BottomLeft => (format!("{}", margin), format!("h-th-{}", margin)),
+            OverlayPosition::BottomRight => (format!("w-tw-{}", margin), format!("h-th-{}", margin)),
+            OverlayPosition::Center => ("(w-tw)/2".to_string(), "(h-th)/2".to_string()),
+        };
 
-            let status = child.wait()?;
+        let opacity = overlay.opacity.clamp(0.0, 1.0);
+        let font = match overlay.font.as_deref() {
+            Some(font) if Path::new(font).exists() => format!("fontfile='{}'", font),
+            Some(font) => format!("font='{}'", font),
+            None if Path::new("/System/Library/Fonts/Helvetica.ttc").exists() => "fontfile=/System/Library/Fonts/Helvetica.ttc".to_string(),
+            None => "font=Sans".to_string(),
+        };
 
-            if status.success() {
-                let conversion_time = start_time.elapsed()?.as_secs_f64();
-                logger::success(&format!("HEVC conversion completed in {:.1}s: {}",
-                    conversion_time,
-                    output_path.file_name().unwrap().to_string_lossy()));
+        // Text is read from a file so captions never need filtergraph escaping
+        format!("drawtext={}:textfile='{}':expansion=none:fontsize={}:fontcolor=white@{:.2}:shadowcolor=black@{:.2}:shadowx=2:shadowy=2:x={}:y={}",
+            font,
+            text_file.display(),
+            overlay.size,
+            opacity,
+            opacity * 0.6,
+            x,
+            y)
+    }
 
-                // Verify output file
-                if output_path.exists() {
-                    if let Ok(stats) = fs::metadata(output_path) {
-                        logger::stats(&format!("HEVC .mov size: {}", utils::format_file_size(Some(stats.len()))));
-                        logger::info("Video optimized for macOS live wallpaper with 4K 60fps HEVC");
+    /// Filter graph that scales the video to 4K and adds the optional visualizer and text overlay.
+    /// Returns `None` when a plain scale is enough; otherwise the result is the `[v]` output pad.
+    /// Extra resolutions are split off the finished 4K stream as `[out0]`, `[out1]`, ...
+    /// Filter bringing the source to 4K. Vertical sources are centered over a blurred,
+    /// screen-filling copy of themselves instead of being stretched to 16:9.
+    /// Scale to the profile's `resolution`; vertical sources are centered over a blurred copy.
+    fn base_scale_filter(&self, vertical: bool, resolution: Option<(u32, u32)>) -> String {
+        match (resolution, vertical) {
+            // Portrait targets are filled and cropped rather than stretched
+            (Some((width, height)), _) if height > width => format!(
+                "scale={w}:{h}:force_original_aspect_ratio=increase:flags=lanczos,crop={w}:{h}",
+                w = width, h = height
+            ),
+            (Some((width, height)), true) => format!(
+                "split[main][fill];[fill]scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h},boxblur=40:5[blurred];[main]scale=-2:{h}:flags=lanczos[fg];[blurred][fg]overlay=(W-w)/2:0",
+                w = width, h = height
+            ),
+            (Some((width, height)), false) => format!("scale={}:{}:flags=lanczos", width, height),
+            (None, _) => "null".to_string(),
+        }
+    }
 
-                        // Fix file permissions and ownership
-                        self.fix_file_permissions(output_path)?;
+    fn video_filter_graph(&self, visualizer: Option<AudioVisualizer>, drawtext: Option<&str>, extra: &[OutputResolution], vertical: bool, resolution: Option<(u32, u32)>) -> Option<String> {
+        if visualizer.is_none() && drawtext.is_none() && extra.is_empty() && !(vertical && resolution.is_some()) {
+            return None;
+        }
 
-                        return Ok(output_path.to_path_buf());
-                    }
-                }
-                return Err("Conversion completed but output file not found".into());
-            } else {
-                logger::warning(&format!(" Conversion attempt {} failed with exit code {:?}", attempt, status.code()));
+        let mut stages = vec![format!("[0:v]{}[bg]", self.base_scale_filter(vertical, resolution))];
+        let mut current = "bg";
+
+        if let Some(visualizer) = visualizer {
+            // The band spans the frame; sized for 4K when the source resolution is kept
+            let (width, height) = resolution.unwrap_or((3840, 2160));
+            let size = format!("{}x{}", width, (height * 320 / 2160) & !1);
+            let source = match visualizer {
+                AudioVisualizer::Waves => format!("[0:a]showwaves=s={}:mode=cline:rate=60:colors=white@0.45,format=rgba[viz]", size),
+                AudioVisualizer::Spectrum => format!("[0:a]showspectrum=s={}:mode=combined:slide=scroll:color=intensity,format=rgba,colorchannelmixer=aa=0.35[viz]", size),
+            };
+            stages.push(source);
+            stages.push(format!("[{}][viz]overlay=0:H-h-80:shortest=1[withviz]", current));
+            current = "withviz";
+        }
 
-                // Log FFmpeg stderr output for diagnostics
-                if !stderr_output.is_empty() {
-                    logger::error("FFmpeg error output:");
-                    for line in stderr_output.lines().take(10) { // Limit to first 10 lines
-                        logger::error(&format!("  {}", line));
-                    }
-                    if stderr_output.lines().count() > 10 {
-                        logger::error("  ... (truncated)");
-                    }
-                }
+        if let Some(drawtext) = drawtext {
+            stages.push(format!("[{}]{}[withtext]", current, drawtext));
+            current = "withtext";
+        }
 
-                // Determine next attempt settings
-                if !use_fallback && attempt < max_attempts {
-                    use_fallback = true;
-                    logger::info("Next attempt: using software encoding...");
-                } else if !reencode_audio && attempt < max_attempts {
-                    reencode_audio = true;
-                    logger::info("Next attempt: re-encoding audio...");
-                } else if attempt >= max_attempts {
-                    return Err(format!("FFmpeg HEVC conversion failed after {} attempts with code {:?}. Last error output:\n{}",
-                        attempt, status.code(), stderr_output).into());
-                }
+        if extra.is_empty() {
+            stages.push(format!("[{}]null[v]", current));
+        } else {
+            let branches: String = (0..extra.len()).map(|i| format!("[split{}]", i)).collect();
+            stages.push(format!("[{}]split={}[v]{}", current, extra.len() + 1, branches));
+            for (i, resolution) in extra.iter().enumerate() {
+                stages.push(format!("[split{}]scale={}:{}:flags=lanczos[out{}]", i, resolution.width, resolution.height, i));
             }
         }
-        
-        unreachable!("Should have returned from within the loop")
+
+        Some(stages.join(";"))
     }
 
-    async fn convert_to_mov(&self, input_path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
-        let output_path = input_path.with_extension("mov");
+    /// ffmpeg command for the main encode, demoted to background priority when requested:
+    /// `taskpolicy -b` on macOS (throttled CPU/IO, efficiency cores), `nice` elsewhere.
+    fn encoder_command(&self, config: &Config) -> Command {
+        if !config.conversion_settings.background_encode {
+            return Command::new(dependencies::ffmpeg());
+        }
 
-        if output_path.exists() {
-            logger::success(&format!("HEVC .mov version already exists: {}", output_path.file_name().unwrap().to_string_lossy()));
-            return Ok(output_path);
+        let mut command = if cfg!(target_os = "macos") {
+            let mut cmd = Command::new("taskpolicy");
+            cmd.arg("-b");
+            cmd
+        } else {
+            let mut cmd = Command::new("nice");
+            cmd.args(["-n", "15"]);
+            cmd
+        };
+        command.arg(dependencies::ffmpeg());
+        command
+    }
+
+    /// Why the source has to be re-encoded, or `None` when it already meets the remux
+    /// thresholds in `config.conversion_settings.remux` and no filters were requested.
+    async fn reencode_reason(&self, input_path: &Path, config: &Config) -> Option<String> {
+        if let Some(reason) = self.requested_reencode(config) {
+            return Some(reason);
         }
 
-        // Check video duration and extend if needed
-        let duration = self.get_video_duration(input_path).await?;
-        let min_duration = Config::default().video_settings.min_recommended_duration as f64;
+        let output = match Command::new(dependencies::ffprobe())
+            .args([
+                "-v", "error",
+                "-select_streams", "v:0",
+                "-show_entries", "stream=codec_name,width,height,avg_frame_rate,bit_rate:format=format_name,bit_rate",
+                "-of", "json",
+            ])
+            .arg(input_path)
+            .tracked_output(&self.cancel).await
+        {
+            Ok(output) if output.status.success() => output,
+            _ => return Some("could not probe source".to_string()),
+        };
 
-        let mut processed_input_path = input_path.to_path_buf();
+        let info: Value = match serde_json::from_slice(&output.stdout) {
+            Ok(info) => info,
+            Err(_) => return Some("could not probe source".to_string()),
+        };
+        let stream = &info["streams"][0];
+        let codec = stream["codec_name"].as_str().unwrap_or("unknown");
+        let width = stream["width"].as_u64().unwrap_or(0);
+        let height = stream["height"].as_u64().unwrap_or(0);
+        let fps = stream["avg_frame_rate"].as_str().and_then(parse_frame_rate).unwrap_or(0.0);
+        // Stream bitrate is missing for some containers; the overall bitrate is close enough
+        let bitrate = stream["bit_rate"].as_str()
+            .or_else(|| info["format"]["bit_rate"].as_str())
+            .and_then(|b| b.parse::<u64>().ok())
+            .unwrap_or(0);
+        let container = info["format"]["format_name"].as_str().unwrap_or("");
+
+        self.remux_blocker(&SourceProbe { codec, container, width, height, fps, bitrate }, config)
+    }
 
-        if duration < min_duration {
-            logger::info(&format!(" Video duration: {} ({:.1}s)", utils::format_time(duration), duration));
-            logger::info(" Extending video to minimum 3 minutes for better experience...");
-            processed_input_path = self.extend_video(input_path, min_duration).await?;
+    /// Options that always need a re-encode, whatever the source.
+    fn requested_reencode(&self, config: &Config) -> Option<String> {
+        let settings = &config.conversion_settings;
+        if settings.remux.force_reencode {
+            Some("--force-reencode".to_string())
+        } else if settings.profile != ConversionProfile::DEFAULT {
+            Some(format!("profile {}", settings.profile))
+        } else if settings.audio_visualizer.is_some() || settings.text_overlay.is_some() || !settings.extra_resolutions.is_empty() {
+            Some("filters requested".to_string())
         } else {
-            logger::info(&format!("  Video duration: {}", utils::format_time(duration)));
+            None
         }
+    }
 
-        // Try hardware-accelerated HEVC first, fallback to software if needed
-        let converted_path = self.convert_with_hevc(&processed_input_path, &output_path, false, false).await?;
-
-        // Clean up temporary extended file if created
-        if processed_input_path != *input_path {
-            if let Err(e) = fs::remove_file(&processed_input_path) {
-                logger::warning(&format!("  Could not clean up temporary file: {}", e));
-            } else {
-                logger::info("  Cleaned up temporary extended video file");
-            }
+    /// Why a source with these properties can't be remuxed, if it can't.
+    fn remux_blocker(&self, source: &SourceProbe, config: &Config) -> Option<String> {
+        let SourceProbe { codec, container, width, height, fps, bitrate } = *source;
+        let remux = &config.conversion_settings.remux;
+        if !remux.allowed_codecs.iter().any(|allowed| allowed == codec) {
+            Some(format!("codec {} not in {}", codec, remux.allowed_codecs.join("/")))
+        } else if !container.split(',').any(|name| remux.containers.iter().any(|allowed| allowed == name)) {
+            Some(format!("container {} not in {}", container, remux.containers.join("/")))
+        } else if width < height {
+            Some("vertical source needs 16:9 framing".to_string())
+        } else if height < config.video_settings.min_recommended_resolution as u64 {
+            Some(format!("{}p is below {}p", height, config.video_settings.min_recommended_resolution))
+        } else if fps < remux.min_frame_rate {
+            Some(format!("{:.0}fps is below {:.0}fps", fps, remux.min_frame_rate))
+        } else if bitrate < remux.min_bitrate {
+            Some(format!("{:.1} Mbps is below {:.1} Mbps", bitrate as f64 / 1e6, remux.min_bitrate as f64 / 1e6))
+        } else {
+            None
         }
+    }
 
-        // Clean up original MP4 file after successful conversion
-        self.cleanup_source_file(input_path, &converted_path).await?;
-
-        Ok(converted_path)
+    fn remux_command(&self, input_path: &Path, output_path: &Path, audio_args: &[String]) -> Command {
+        let mut command = Command::new(dependencies::ffmpeg());
+        command
+            .args(["-y", "-v", "error", "-i"])
+            .arg(input_path)
+            .args(["-map", "0:v:0", "-map", "0:a?", "-c:v", "copy"])
+            .args(audio_args)
+            .args(["-tag:v", "hvc1", "-movflags", "+faststart"])
+            .args(self.tag_args())
+            .arg(output_path);
+        command
     }
 
-    fn setup_cleanup_handlers(&mut self) {
-        let cleanup = || {
-            logger::warning("Cleaning up download process...");
-            std::process::exit(0);
-        };
+    /// Copy the streams into a .mov with the `hvc1` tag QuickTime needs. Returns `false`
+    /// when ffmpeg fails, so the caller can fall back to a full encode.
+    async fn remux_to_mov(&self, input_path: &Path, output_path: &Path, config: &Config) -> bool {
+        logger::convert("Source is already wallpaper-ready HEVC; remuxing without re-encoding...");
 
-        let _ = cleanup;
+        let loudnorm = self.loudnorm_filter(input_path, config).await;
+        let audio_args = self.audio_args(config, &["-c:a", "aac"], false, loudnorm.as_deref());
+        let result = self.remux_command(input_path, output_path, &audio_args).tracked_output(&self.cancel).await;
+
+        match result {
+            Ok(output) if output.status.success() && output_path.exists() => {
+                logger::success(&format!("Remuxed to: {}", output_path.file_name().unwrap().to_string_lossy()));
+                true
+            }
+            Ok(output) => {
+                logger::warning(&format!("Remux failed, re-encoding instead: {}", String::from_utf8_lossy(&output.stderr).trim()));
+                fs::remove_file(output_path).ok();
+                false
+            }
+            Err(_) => false,
+        }
     }
 
-    fn parse_download_progress(&self, line: &str) -> Option<(f64, String, String, String)> {
-        utils::parse_progress(line)
+    /// Audio options for one output: none with `--strip-audio`, a copy unless `reencode` or
+    /// the audio settings ask for more, otherwise `codec_args` with the `--audio-bitrate`
+    /// and `loudnorm` filter applied.
+    fn audio_args(&self, config: &Config, codec_args: &[&str], reencode: bool, loudnorm: Option<&str>) -> Vec<String> {
+        let audio = &config.conversion_settings.audio;
+        if audio.strip {
+            return vec!["-an".to_string()];
+        }
+        if !reencode && !audio.reencodes() {
+            return vec!["-c:a".to_string(), "copy".to_string()];
+        }
+        let mut args: Vec<String> = match audio.bitrate {
+            // The bitrate replaces the codec's own quality options
+            Some(kbps) => codec_args.iter().take(2).map(|arg| arg.to_string()).chain(["-b:a".to_string(), format!("{}k", kbps)]).collect(),
+            None => codec_args.iter().map(|arg| arg.to_string()).collect(),
+        };
+        if let Some(filter) = loudnorm {
+            args.extend(["-af".to_string(), filter.to_string()]);
+        }
+        args
     }
 
-    async fn download_video(&mut self, url: &str, video_format: &VideoFormat, audio_format: &Option<AudioFormat>, output_path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
-        logger::header("Starting Download");
-        logger::download(&format!("Output: {}", output_path.display()));
-        
-        // Ensure unique filename
-        let final_output_path = utils::get_unique_filename(output_path)?;
-        if final_output_path != *output_path {
-            logger::warning(&format!("File exists, using: {}", final_output_path.display()));
+    /// The `loudnorm` filter for `--normalize`. The loudness of `input_path` is measured
+    /// first, so the conversion applies a single gain change instead of adjusting as it goes.
+    async fn loudnorm_filter(&self, input_path: &Path, config: &Config) -> Option<String> {
+        let audio = &config.conversion_settings.audio;
+        if !audio.normalize || audio.strip || !self.has_audio_stream(input_path).await {
+            return None;
+        }
+        logger::info("Measuring loudness (EBU R128, pass 1 of 2)...");
+        let (input, cancel) = (input_path.to_path_buf(), self.cancel.clone());
+        match blocking(move || loudness::measure(&input, &cancel)).await {
+            Ok(measured) => {
+                logger::stats(&format!("Loudness: {:.1} LUFS, true peak {:.1} dBTP; normalizing to {} LUFS",
+                    measured.integrated, measured.true_peak, loudness::TARGET_LUFS));
+                Some(loudness::filter(Some(&measured)))
+            }
+            Err(error) => {
+                logger::warning(&format!("{}; normalizing in a single pass instead", error));
+                Some(loudness::filter(None))
+            }
+        }
+    }
+
+    /// ffmpeg arguments for one encode attempt with the selected profile, including the
+    /// `--also` renditions. `encoder` is only used by HEVC profiles.
+    fn encode_args(&self, input_path: &Path, output_path: &Path, config: &Config, filter_graph: Option<&str>, encoder: Encoder, audio_args: &[String]) -> Vec<String> {
+        let profile = config.conversion_settings.profile();
+        let extra_resolutions = &config.conversion_settings.extra_resolutions;
+        let upload = match profile.codec {
+            ProfileCodec::Hevc => encoder.upload_filter(profile.ten_bit()),
+            _ => None,
+        };
+
+        let mut args = ["-y", "-v", "error", "-nostats", "-progress", "pipe:1"].map(String::from).to_vec();
+        if profile.codec == ProfileCodec::Hevc {
+            args.extend(encoder.input_args().into_iter().map(String::from));
+        }
+        args.extend(["-i".to_string(), input_path.to_string_lossy().to_string()]);
+        args.extend(self.video_codec_args(profile, encoder, profile.bitrate));
+
+        let scale = profile.resolution.map(|resolution| self.base_scale_filter(false, Some(resolution)));
+        let filters: Vec<&str> = [scale.as_deref(), upload].into_iter().flatten().collect();
+        if let Some(graph) = filter_graph {
+            // Overlay output replaces the plain scale; keep the audio track alongside it
+            let graph = self.with_upload_filter(graph, upload, extra_resolutions.len());
+            args.extend(["-filter_complex", &graph, "-map", "[v]"].map(String::from));
+            if profile.audio {
+                args.extend(["-map", "0:a?"].map(String::from));
+            }
+        } else if !filters.is_empty() {
+            args.extend(["-vf".to_string(), filters.join(",")]);
+        }
+
+        // Add audio codec
+        let audio_args = match profile.audio {
+            true => audio_args.to_vec(),
+            false => vec!["-an".to_string()],
+        };
+        args.extend(audio_args.iter().cloned());
+
+        if let Some(threads) = config.conversion_settings.threads {
+            args.extend(["-threads".to_string(), threads.to_string()]);
+        }
+
+        let max_duration = profile.max_duration.map(|seconds| ["-t".to_string(), seconds.to_string()]);
+        args.extend(max_duration.iter().flatten().cloned());
+        args.extend(self.tag_args());
+        args.push("-y".to_string()); // Overwrite output file
+        args.push(output_path.to_string_lossy().to_string());
+
+        // Each extra output repeats the encoder options with a bitrate scaled to its pixel count
+        let (base_width, base_height) = profile.resolution.unwrap_or((3840, 2160));
+        for (i, (resolution, path)) in extra_resolutions.iter().zip(self.extra_output_paths(output_path, config)).enumerate() {
+            let pixels = (resolution.width * resolution.height) as f64 / (base_width * base_height) as f64;
+            let bitrate = profile.bitrate.map(|bitrate| ((bitrate as f64 * pixels).round() as u32).max(8));
+            args.extend(["-map".to_string(), format!("[out{}]", i)]);
+            if profile.audio {
+                args.extend(["-map", "0:a?"].map(String::from));
+            }
+            args.extend(self.video_codec_args(profile, encoder, bitrate));
+            args.extend(audio_args.iter().cloned());
+            args.extend(max_duration.iter().flatten().cloned());
+            args.extend(self.tag_args());
+            args.push(path.to_string_lossy().to_string());
+        }
+        args
+    }
+
+    /// Name of the ffmpeg encoder the profile converts with.
+    fn video_encoder_name(&self, profile: &ConversionProfile, encoder: Encoder) -> &'static str {
+        match profile.codec {
+            ProfileCodec::Hevc => encoder.ffmpeg_name(),
+            ProfileCodec::Prores => "prores_ks",
+            ProfileCodec::Ffv1 => "ffv1",
+        }
+    }
+
+    /// Video codec, rate and container options for one output of `profile`.
+    fn video_codec_args(&self, profile: &ConversionProfile, encoder: Encoder, bitrate: Option<u32>) -> Vec<String> {
+        let mut args = vec!["-c:v", self.video_encoder_name(profile, encoder)];
+        let pixel_format = match profile.codec {
+            // Hardware encoders only take their own 8- or 10-bit layouts
+            ProfileCodec::Hevc if encoder.is_hardware() => encoder.pixel_format(profile.ten_bit()),
+            _ => Some(profile.pixel_format.as_str()),
+        };
+        if let Some(pixel_format) = pixel_format {
+            args.extend(["-pix_fmt", pixel_format]);
+        }
+        match profile.codec {
+            ProfileCodec::Hevc => {
+                args.extend(["-tag:v", "hvc1"]); // Ensure proper HEVC tag for QuickTime compatibility
+                args.extend(encoder.quality_args(profile.ten_bit()));
+                if encoder == Encoder::Software {
+                    args.extend(["-level", "5.1"]);
+                }
+            }
+            // 422 HQ, tagged like Apple's own encoder so Final Cut and QuickTime accept it
+            ProfileCodec::Prores => args.extend(["-profile:v", "3", "-vendor", "apl0"]),
+            // Intra-only with per-slice checksums, the usual archival settings
+            ProfileCodec::Ffv1 => args.extend(["-level", "3", "-g", "1", "-slicecrc", "1"]),
+        }
+        if matches!(profile.container.as_str(), "mov" | "mp4") {
+            args.extend(["-movflags", "+faststart"]);
+        }
+
+        let mut args: Vec<String> = args.into_iter().map(String::from).collect();
+        if let Some(rate) = profile.frame_rate {
+            args.extend(["-r".to_string(), rate.to_string()]);
+        }
+        if let Some(bitrate) = bitrate {
+            args.extend([
+                "-b:v".to_string(), format!("{}M", bitrate),
+                "-maxrate".to_string(), format!("{}M", bitrate * 6 / 5),
+                "-bufsize".to_string(), format!("{}M", bitrate * 2),
+            ]);
+        }
+        args
+    }
+
+    /// `graph` with `upload` (e.g. VAAPI's `hwupload`) at the end of the `[v]` and `[outN]` outputs.
+    fn with_upload_filter(&self, graph: &str, upload: Option<&str>, extra_outputs: usize) -> String {
+        let Some(upload) = upload else {
+            return graph.to_string();
+        };
+        let mut graph = graph.replace("[v]", "[v_sw]").replace("[out", "[out_sw");
+        graph.push_str(&format!(";[v_sw]{}[v]", upload));
+        for i in 0..extra_outputs {
+            graph.push_str(&format!(";[out_sw{}]{}[out{}]", i, upload, i));
+        }
+        graph
+    }
+
+    /// Where the `--also` renditions of `output_path` go.
+    fn extra_output_paths(&self, output_path: &Path, config: &Config) -> Vec<PathBuf> {
+        let stem = output_path.file_stem().unwrap().to_string_lossy().to_string();
+        config.conversion_settings.extra_resolutions.iter()
+            .map(|r| output_path.with_file_name(format!("{}_{}.{}", stem, r.label, config.conversion_settings.profile().container)))
+            .collect()
+    }
+
+    /// Encode with the selected profile, retrying with libx265 and then with re-encoded audio.
+    async fn convert_with_profile(&self, input_path: &Path, output_path: &Path, config: &Config) -> Result<PathBuf> {
+        let max_attempts = config.conversion_settings.max_attempts;
+        let profile = config.conversion_settings.profile();
+        // Only HEVC has a choice of encoder; ProRes and FFV1 are always encoded in software
+        let mut encoder = match profile.codec {
+            ProfileCodec::Hevc => encoders::select(config.conversion_settings.encoder),
+            _ => Encoder::Software,
+        };
+        let mut reencode_audio = false;
+
+        let mut visualizer = config.conversion_settings.audio_visualizer;
+        if visualizer.is_some() && !self.has_audio_stream(input_path).await {
+            logger::warning("Source has no audio track; skipping audio visualizer");
+            visualizer = None;
+        }
+
+        let overlay = config.conversion_settings.text_overlay.as_ref();
+        let overlay_file = match overlay {
+            Some(overlay) => {
+                let file = tempfile::NamedTempFile::new()?;
+                fs::write(file.path(), &overlay.template)?;
+                Some(file)
+            }
+            None => None,
+        };
+        let drawtext = overlay.zip(overlay_file.as_ref()).map(|(o, f)| self.drawtext_filter(o, f.path()));
+        let extra_resolutions = &config.conversion_settings.extra_resolutions;
+        let vertical = self.is_vertical(input_path).await;
+        if vertical && profile.resolution.is_some_and(|(width, height)| width > height) {
+            logger::info("Vertical source (e.g. Shorts): centering it over a blurred 16:9 backdrop");
+        }
+        let filter_graph = self.video_filter_graph(visualizer, drawtext.as_deref(), extra_resolutions, vertical, profile.resolution);
+        let extra_outputs = self.extra_output_paths(output_path, config);
+        let source_duration = self.get_video_duration(input_path).await.unwrap_or(0.0);
+        let duration = profile.max_duration.map_or(source_duration, |max| source_duration.min(max as f64));
+        let loudnorm = match profile.audio {
+            true => self.loudnorm_filter(input_path, config).await,
+            false => None,
+        };
+
+        let mut report = EncodeReport::default();
+
+        for attempt in 1..=max_attempts {
+            if attempt > 1 {
+                logger::info(&format!("Conversion attempt {}/{}", attempt, max_attempts));
+            }
+
+            let codec = match profile.codec {
+                ProfileCodec::Hevc => "HEVC (H.265)",
+                ProfileCodec::Prores => "ProRes 422 HQ",
+                ProfileCodec::Ffv1 => "FFV1 (lossless)",
+            };
+            if encoder.is_hardware() {
+                logger::convert(&format!("Converting to {} .{} with hardware acceleration...", codec, profile.container));
+                logger::info(&format!("Using {} for optimal performance", encoder.description()));
+            } else if profile.codec == ProfileCodec::Hevc {
+                logger::convert(&format!("Converting to {} .{} (software encoding)...", codec, profile.container));
+                logger::warning(" Hardware acceleration not available, using software encoding");
+            } else {
+                logger::convert(&format!("Converting to {} .{}...", codec, profile.container));
+            }
+
+            logger::info(&format!(" Conversion settings ({}):", profile.name));
+            logger::info(&format!("   • Codec: {} {}-bit", codec, if profile.ten_bit() { 10 } else { 8 }));
+            match profile.resolution {
+                Some((width, height)) => logger::info(&format!("   • Resolution: {}x{}", width, height)),
+                None => logger::info("   • Resolution: same as source"),
+            }
+            match profile.frame_rate {
+                Some(rate) => logger::info(&format!("   • Frame Rate: {}fps", rate)),
+                None => logger::info("   • Frame Rate: same as source"),
+            }
+            if let Some(bitrate) = profile.bitrate {
+                logger::info(&format!("   • Bitrate: {} Mbps", bitrate));
+            }
+            let audio = &config.conversion_settings.audio;
+            if !profile.audio || audio.strip {
+                logger::info("   • Audio: none");
+            } else if audio.reencodes() {
+                let bitrate = audio.bitrate.map(|kbps| format!(" at {} kbps", kbps)).unwrap_or_default();
+                let normalized = if loudnorm.is_some() { format!(", normalized to {} LUFS", loudness::TARGET_LUFS) } else { String::new() };
+                logger::info(&format!("   • Audio: AAC{}{}", bitrate, normalized));
+            }
+            if let Some(v) = visualizer {
+                logger::info(&format!("   • Audio visualizer: {:?}", v));
+            }
+            if let Some(o) = overlay {
+                logger::info(&format!("   • Text overlay: \"{}\" ({:?})", o.template, o.position));
+            }
+            if config.conversion_settings.background_encode {
+                logger::info("   • Priority: background (low)");
+            }
+            if !extra_resolutions.is_empty() {
+                let labels: Vec<&str> = extra_resolutions.iter().map(|r| r.label.as_str()).collect();
+                logger::info(&format!("   • Also rendering: {}", labels.join(", ")));
+            }
+
+            let audio_args = self.audio_args(config, &["-c:a", "aac"], reencode_audio, loudnorm.as_deref());
+            let args = self.encode_args(input_path, output_path, config, filter_graph.as_deref(), encoder, &audio_args);
+
+            // Run ffmpeg
+            let sampler = GpuSampler::start();
+            for path in &extra_outputs {
+                self.cancel.track_file(path);
+            }
+            let start_time = SystemTime::now();
+            let mut command = self.encoder_command(config);
+            command.args(&args);
+            let (output, progress) = self.run_with_progress(command, output_path, duration, Stage::Converting, codec, config).await?;
+            let status = output.status;
+            let stderr_output = String::from_utf8_lossy(&output.stderr).to_string();
+            let gpu_utilization = sampler.stop();
+            let attempt_time = start_time.elapsed()?.as_secs_f64();
+            report.record_attempt(encoder.is_hardware(), attempt_time);
+
+            if status.success() {
+                for path in &extra_outputs {
+                    self.cancel.untrack_file(path);
+                }
+                let conversion_time = attempt_time;
+                report.frames = progress.frame;
+                report.encode_seconds = attempt_time;
+                report.gpu_utilization = gpu_utilization;
+                report.encoder = Some(self.video_encoder_name(profile, encoder).to_string());
+                logger::success(&format!("Conversion completed in {:.1}s: {}",
+                    conversion_time,
+                    output_path.file_name().unwrap().to_string_lossy()));
+
+                // Verify output file
+                if output_path.exists() {
+                    if let Ok(stats) = fs::metadata(output_path) {
+                        logger::stats(&format!("Converted .{} size: {}", profile.container, utils::format_file_size(Some(stats.len()))));
+                        if profile.name == ConversionProfile::DEFAULT {
+                            logger::info("Video optimized for macOS live wallpaper with 4K 60fps HEVC");
+                        }
+                        report.display();
+
+                        // Fix file permissions and ownership
+                        self.fix_file_permissions(output_path)?;
+
+                        for extra in extra_outputs.iter().filter(|p| p.exists()) {
+                            self.fix_file_permissions(extra)?;
+                            logger::file(&format!("Also saved: {}", extra.file_name().unwrap().to_string_lossy()));
+                        }
+
+                        return Ok(output_path.to_path_buf());
+                    }
+                }
+                return Err(DownloaderError::Ffmpeg("Conversion completed but output file not found".to_string()));
+            } else {
+                logger::warning(&format!(" Conversion attempt {} failed with exit code {:?}", attempt, status.code()));
+
+                // Log FFmpeg stderr output for diagnostics
+                if !stderr_output.is_empty() {
+                    logger::error("FFmpeg error output:");
+                    for line in stderr_output.lines().take(10) { // Limit to first 10 lines
+                        logger::error(&format!("  {}", line));
+                    }
+                    if stderr_output.lines().count() > 10 {
+                        logger::error("  ... (truncated)");
+                    }
+                }
+
+                // Determine next attempt settings
+                if encoder.is_hardware() && attempt < max_attempts {
+                    encoder = Encoder::Software;
+                    logger::info("Next attempt: using software encoding...");
+                } else if !reencode_audio && attempt < max_attempts {
+                    reencode_audio = true;
+                    logger::info("Next attempt: re-encoding audio...");
+                } else if attempt >= max_attempts {
+                    crash::record_stderr(&format!("ffmpeg {}", args.join(" ")), &stderr_output);
+                    return Err(DownloaderError::Ffmpeg(format!("FFmpeg conversion failed after {} attempts with code {:?}. Last error output:\n{}",
+                        attempt, status.code(), stderr_output)));
+                }
+            }
         }
         
-        // Build yt-dlp arguments
-        let format_arg = if let Some(audio) = audio_format {
-            format!("{}+{}", video_format.format_id, audio.format_id)
+        unreachable!("Should have returned from within the loop")
+    }
+
+    /// Convert with `--free-formats=av1` to AV1, otherwise to the HEVC .mov wallpapers need.
+    async fn convert(&mut self, input_path: &Path, config: &Config) -> Result<PathBuf> {
+        if config.conversion_settings.audio_only {
+            return self.convert_to_audio(input_path, config).await;
+        }
+        if let Some(format) = config.conversion_settings.loop_format {
+            return self.convert_to_loop(input_path, format, config).await;
+        }
+        if config.conversion_settings.live_photo {
+            return self.convert_to_live_photo(input_path, config).await;
+        }
+        match config.video_preferences.free_formats {
+            Some(FreeFormats::Av1) => self.convert_to_av1(input_path, config).await,
+            _ => self.convert_to_mov(input_path, config).await,
+        }
+    }
+
+    fn av1_output_path(&self, input_path: &Path) -> PathBuf {
+        let stem = input_path.file_stem().unwrap().to_string_lossy();
+        input_path.with_file_name(format!("{}_av1.webm", stem))
+    }
+
+    fn av1_command(&self, input_path: &Path, output_path: &Path, config: &Config, loudnorm: Option<&str>) -> Command {
+        let mut command = self.encoder_command(config);
+        command
+            .args(["-y", "-v", "error", "-nostats", "-progress", "pipe:1", "-i"])
+            .arg(input_path)
+            .args(["-map", "0:v:0", "-map", "0:a?", "-c:v", "libsvtav1", "-preset", "8", "-crf", "30", "-pix_fmt", "yuv420p10le"])
+            .args(self.audio_args(config, &["-c:a", "libopus", "-b:a", "160k"], true, loudnorm));
+        if let Some(threads) = config.conversion_settings.threads {
+            command.args(["-threads", &threads.to_string()]);
+        }
+        command.args(self.tag_args()).arg(output_path);
+        command
+    }
+
+    /// Re-encode to AV1 video and Opus audio in WebM, at the source's own resolution and
+    /// frame rate. A source that is already AV1 is kept as it is.
+    async fn convert_to_av1(&self, input_path: &Path, config: &Config) -> Result<PathBuf> {
+        let output_path = self.av1_output_path(input_path);
+        if output_path.exists() {
+            logger::success(&format!("AV1 version already exists: {}", output_path.file_name().unwrap().to_string_lossy()));
+            return Ok(output_path);
+        }
+
+        let codec = Command::new(dependencies::ffprobe())
+            .args(["-v", "error", "-select_streams", "v:0", "-show_entries", "stream=codec_name", "-of", "csv=p=0"])
+            .arg(input_path)
+            .tracked_output(&self.cancel).await?;
+        if String::from_utf8_lossy(&codec.stdout).trim() == "av1" {
+            logger::info("Source is already AV1; keeping it without re-encoding");
+            return Ok(input_path.to_path_buf());
+        }
+
+        let duration = self.get_video_duration(input_path).await?;
+        logger::convert("Converting to AV1/Opus WebM (libsvtav1)...");
+
+        let loudnorm = self.loudnorm_filter(input_path, config).await;
+        let (output, _) = self.run_with_progress(self.av1_command(input_path, &output_path, config, loudnorm.as_deref()), &output_path, duration, Stage::Converting, "AV1", config).await?;
+        if !output.status.success() {
+            fs::remove_file(&output_path).ok();
+            return Err(DownloaderError::Ffmpeg(format!("AV1 conversion failed: {}", String::from_utf8_lossy(&output.stderr).trim())));
+        }
+
+        self.fix_file_permissions(&output_path)?;
+        if let Ok(stats) = fs::metadata(&output_path) {
+            logger::stats(&format!("AV1 .webm size: {}", utils::format_file_size(Some(stats.len()))));
+        }
+        logger::success(&format!("AV1 conversion completed: {}", output_path.file_name().unwrap().to_string_lossy()));
+        self.cleanup_source_file(input_path, &output_path, duration).await?;
+        Ok(output_path)
+    }
+
+    /// Run an ffmpeg `command` that reports with `-progress pipe:1`, showing its position
+    /// against `duration` seconds on the job's row as `stage`, and return its output with the
+    /// last report. Cancelling removes `output_path`; a run whose position stops moving for the
+    /// conversion `stall_timeout` is killed, so it fails like any other.
+    async fn run_with_progress(&self, mut command: Command, output_path: &Path, duration: f64, stage: Stage, detail: &str, config: &Config) -> Result<(std::process::Output, Progress)> {
+        let mut child = self.cancel.spawn(command.stdout(Stdio::piped()).stderr(Stdio::piped()))?;
+        let pid = child.id().unwrap();
+        self.cancel.track_file(output_path);
+        let stall_timeout = config.conversion_settings.stall_timeout;
+        let watchdog = Watchdog::start(pid, stall_timeout);
+
+        let stdout = child.stdout.take().unwrap();
+        let mut stderr_pipe = child.stderr.take().unwrap();
+        let mut stderr = Vec::new();
+        let mut last = Progress::default();
+        let report = async {
+            let mut parser = ProgressParser::default();
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let Some(progress) = parser.feed(&line) else {
+                    continue;
+                };
+                // ffmpeg keeps reporting while stuck; only a moving position counts
+                if progress.out_seconds > last.out_seconds || progress.frame > last.frame {
+                    watchdog.feed();
+                }
+                let percentage = progress.percent(duration);
+                let summary = progress.summary(duration);
+                status::update(&self.job_label, stage, percentage, &format!("{} · {}", detail, summary));
+                self.notify(ProgressEvent::ConversionProgress {
+                    job: self.job_label.clone(),
+                    percent: percentage,
+                    speed: progress.speed,
+                    eta: progress.eta(duration),
+                });
+                logger::job_progress(&self.job_label, &format!("Converting {} {:5.1}% {}{}", utils::create_progress_bar(percentage, 20), percentage, summary, self.overall_suffix()));
+                last = progress;
+            }
+        };
+        // Read both pipes at once, so a full stderr pipe can't block ffmpeg
+        let _ = tokio::join!(report, stderr_pipe.read_to_end(&mut stderr));
+        if !self.shared_progress {
+            logger::finish_job_progress();
+        }
+
+        let status = child.wait().await?;
+        self.cancel.untrack_process(pid);
+        self.cancel.untrack_file(output_path);
+        if watchdog.stop() {
+            logger::warning(&format!("{} made no progress for {}; stopped it", detail, stall_timeout.map(watchdog::describe).unwrap_or_default()));
+        }
+        Ok((std::process::Output { status, stdout: Vec::new(), stderr }, last))
+    }
+
+    /// `<name>.<ext>` next to the download, or `<name>_<ext>.<ext>` when that is the download.
+    fn audio_output_path(&self, input_path: &Path, format: AudioOutputFormat) -> PathBuf {
+        let output_path = input_path.with_extension(format.extension());
+        if output_path != input_path {
+            return output_path;
+        }
+        let stem = input_path.file_stem().unwrap().to_string_lossy();
+        input_path.with_file_name(format!("{}_{}.{}", stem, format.extension(), format.extension()))
+    }
+
+    /// The format `--audio-only` writes for source audio in `codec`, and whether the audio
+    /// can be copied into it as it is.
+    fn audio_plan(&self, codec: &str, config: &Config) -> (AudioOutputFormat, bool) {
+        let source = AudioOutputFormat::for_codec(codec);
+        let format = config.conversion_settings.audio_format.or(source).unwrap_or(AudioOutputFormat::M4a);
+        (format, source == Some(format) && !config.conversion_settings.audio.reencodes())
+    }
+
+    /// The thumbnail yt-dlp wrote next to an `--audio-only` download, when it can be embedded
+    /// as cover art. Ogg has no attached-picture stream, so .opus files go without.
+    fn cover_art_path(&self, input_path: &Path, format: AudioOutputFormat, config: &Config) -> Option<PathBuf> {
+        let cover = input_path.with_extension("jpg");
+        (config.conversion_settings.audio_tags.cover_art && format != AudioOutputFormat::Opus).then_some(cover)
+    }
+
+    /// ffmpeg command that writes the audio of `input_path` as planned by `audio_plan`.
+    fn audio_command(&self, input_path: &Path, output_path: &Path, (format, copy): (AudioOutputFormat, bool), config: &Config, loudnorm: Option<&str>, cover: Option<&Path>) -> Command {
+        let mut command = Command::new(dependencies::ffmpeg());
+        command
+            .args(["-y", "-v", "error", "-nostats", "-progress", "pipe:1", "-i"])
+            .arg(input_path);
+        if let Some(cover) = cover {
+            command.arg("-i").arg(cover);
+        }
+        command.args(["-map", "0:a:0"]);
+        match cover {
+            // Copied as it is; the thumbnail is already a JPEG
+            Some(_) => command.args(["-map", "1:v:0", "-c:v", "copy", "-disposition:v:0", "attached_pic"]),
+            None => command.arg("-vn"),
+        };
+        command
+            .args(self.audio_args(config, format.encoder_args(), !copy, loudnorm))
+            // Only the [audio_tags], not what yt-dlp embedded in the download
+            .args(["-map_metadata", "-1"])
+            .args(self.tag_args());
+        if format == AudioOutputFormat::Mp3 {
+            // ID3v2.3 is what most players and car stereos read
+            command.args(["-id3v2_version", "3"]);
+        }
+        command.arg(output_path);
+        command
+    }
+
+    /// Extract the audio of an `--audio-only` download. AAC, Opus, MP3 and FLAC are copied
+    /// into their own container without re-encoding, unless `audio_format` asks for another.
+    async fn convert_to_audio(&self, input_path: &Path, config: &Config) -> Result<PathBuf> {
+        let probe = Command::new(dependencies::ffprobe())
+            .args(["-v", "error", "-select_streams", "a:0", "-show_entries", "stream=codec_name", "-of", "csv=p=0"])
+            .arg(input_path)
+            .tracked_output(&self.cancel).await?;
+        let codec = String::from_utf8_lossy(&probe.stdout).trim().to_string();
+        if codec.is_empty() {
+            return Err(DownloaderError::Ffmpeg(format!("No audio stream in {}", input_path.display())));
+        }
+
+        let (format, copy) = self.audio_plan(&codec, config);
+        let extension = format.extension();
+        if copy {
+            logger::info(&format!("Source audio is already {}; copying it into .{} without re-encoding", codec, extension));
+        } else if AudioOutputFormat::for_codec(&codec) == Some(format) {
+            logger::info(&format!("Source audio is already {}, but re-encoding it for the audio settings", codec));
+        } else {
+            logger::info(&format!("Source audio is {}; transcoding to .{} ({})", codec, extension, format.encoder_args()[1]));
+        }
+        // A download that is already in the right container is rewritten in place, to tag it
+        let in_place = copy && input_path.extension().is_some_and(|ext| ext == extension);
+        let output_path = if in_place { input_path.to_path_buf() } else { self.audio_output_path(input_path, format) };
+        if !in_place && output_path.exists() {
+            logger::success(&format!("Audio already exists: {}", output_path.file_name().unwrap().to_string_lossy()));
+            return Ok(output_path);
+        }
+        let write_path = match in_place {
+            true => input_path.with_file_name(format!("{}.tagged.{}", input_path.file_stem().unwrap().to_string_lossy(), extension)),
+            false => output_path.clone(),
+        };
+
+        let cover = self.cover_art_path(input_path, format, config).filter(|cover| cover.exists());
+        if config.conversion_settings.audio_tags.cover_art && cover.is_none() {
+            match format {
+                AudioOutputFormat::Opus => logger::info("Opus files can't hold cover art; saving without it"),
+                _ => logger::warning("No thumbnail to use as cover art; saving without it"),
+            }
+        }
+
+        let duration = self.get_video_duration(input_path).await.unwrap_or(0.0);
+        let detail = if copy { "Copying audio" } else { "Transcoding audio" };
+        let loudnorm = self.loudnorm_filter(input_path, config).await;
+        let command = self.audio_command(input_path, &write_path, (format, copy), config, loudnorm.as_deref(), cover.as_deref());
+        let (output, _) = self.run_with_progress(command, &write_path, duration, Stage::Converting, detail, config).await?;
+        if !output.status.success() {
+            fs::remove_file(&write_path).ok();
+            return Err(DownloaderError::Ffmpeg(format!("Audio extraction failed: {}", String::from_utf8_lossy(&output.stderr).trim())));
+        }
+
+        if in_place {
+            fs::rename(&write_path, &output_path)?;
+        } else if let Err(error) = fs::remove_file(input_path) {
+            // The download holds nothing else worth keeping
+            logger::warning(&format!("Could not remove the download: {}", error));
+        }
+        if let Some(cover) = cover.as_ref().filter(|_| !config.download_settings.save_thumbnail) {
+            fs::remove_file(cover).ok();
+        }
+
+        self.fix_file_permissions(&output_path)?;
+        if let Ok(stats) = fs::metadata(&output_path) {
+            logger::stats(&format!("Audio size: {}", utils::format_file_size(Some(stats.len()))));
+        }
+        let tags: Vec<&str> = self.tags.iter().map(|(key, _)| key.as_str()).collect();
+        logger::info(&format!("Tagged with {}{}", tags.join(", "), if cover.is_some() { " and cover art" } else { "" }));
+        logger::success(&format!("Audio saved: {}", output_path.file_name().unwrap().to_string_lossy()));
+        Ok(output_path)
+    }
+
+    fn loop_output_path(&self, input_path: &Path, format: LoopFormat) -> PathBuf {
+        let stem = input_path.file_stem().unwrap().to_string_lossy();
+        match format {
+            LoopFormat::Gif => input_path.with_file_name(format!("{}.gif", stem)),
+            LoopFormat::WebmLoop => input_path.with_file_name(format!("{}_loop.webm", stem)),
+        }
+    }
+
+    fn loop_command(&self, input_path: &Path, output_path: &Path, format: LoopFormat, config: &Config) -> Command {
+        let settings = &config.conversion_settings.loop_settings;
+        let scale = format!("fps={},scale='min({},iw)':-2:flags=lanczos", settings.fps, settings.max_width);
+
+        let mut command = self.encoder_command(config);
+        command.args(["-y", "-v", "error", "-nostats", "-progress", "pipe:1"]);
+        match format {
+            LoopFormat::Gif => {
+                // One palette for the whole clip, weighted to what moves, keeps the file small
+                // without banding; rectangle diff mode only redraws the changed area per frame
+                let filter = format!(
+                    "{},split[a][b];[a]palettegen=stats_mode=diff[p];[b][p]paletteuse=dither=bayer:bayer_scale=5:diff_mode=rectangle",
+                    scale
+                );
+                command
+                    .arg("-i")
+                    .arg(input_path)
+                    .args(["-an", "-filter_complex", &filter, "-loop", &Self::gif_loop_value(settings).to_string()]);
+            }
+            LoopFormat::WebmLoop => {
+                // WebM has no loop flag, so repeats are encoded the same way extend_video loops
+                if settings.loop_count > 1 {
+                    command.args(["-stream_loop", &(settings.loop_count - 1).to_string()]);
+                }
+                command
+                    .arg("-i")
+                    .arg(input_path)
+                    .args(["-an", "-vf", &scale, "-c:v", "libvpx-vp9", "-b:v", "0", "-crf", "35"])
+                    .args(["-row-mt", "1", "-deadline", "good", "-cpu-used", "4", "-pix_fmt", "yuv420p"]);
+                command.args(self.tag_args());
+            }
+        }
+        command.arg(output_path);
+        command
+    }
+
+    /// The GIF muxer counts extra plays: 0 repeats forever and -1 plays once.
+    fn gif_loop_value(settings: &LoopSettings) -> i64 {
+        match settings.loop_count {
+            0 => 0,
+            1 => -1,
+            count => count as i64 - 1,
+        }
+    }
+
+    /// Make a short silent loop (GIF or VP9 WebM) instead of the wallpaper .mov, for sharing
+    /// a clip. The download is kept, since it is usually wanted again at another size.
+    async fn convert_to_loop(&self, input_path: &Path, format: LoopFormat, config: &Config) -> Result<PathBuf> {
+        let output_path = self.loop_output_path(input_path, format);
+        if output_path.exists() {
+            logger::success(&format!("Loop already exists: {}", output_path.file_name().unwrap().to_string_lossy()));
+            return Ok(output_path);
+        }
+
+        let settings = &config.conversion_settings.loop_settings;
+        let plays = match format {
+            LoopFormat::WebmLoop => settings.loop_count.max(1),
+            LoopFormat::Gif => 1,
+        };
+        let duration = self.get_video_duration(input_path).await? * plays as f64;
+        let label = match format {
+            LoopFormat::Gif => "GIF",
+            LoopFormat::WebmLoop => "WebM loop",
+        };
+        logger::convert(&format!("Making a {} ({} fps, up to {}px wide)...", label, settings.fps, settings.max_width));
+
+        let (output, _) = self.run_with_progress(self.loop_command(input_path, &output_path, format, config), &output_path, duration, Stage::Converting, label, config).await?;
+        if !output.status.success() {
+            fs::remove_file(&output_path).ok();
+            return Err(DownloaderError::Ffmpeg(format!("{} export failed: {}", label, String::from_utf8_lossy(&output.stderr).trim())));
+        }
+
+        self.fix_file_permissions(&output_path)?;
+        if let Ok(stats) = fs::metadata(&output_path) {
+            logger::stats(&format!("{} size: {}", label, utils::format_file_size(Some(stats.len()))));
+        }
+        logger::success(&format!("{} saved: {}", label, output_path.file_name().unwrap().to_string_lossy()));
+        Ok(output_path)
+    }
+
+    /// Print the loudness and silences of an extracted audio file, and whether it would
+    /// benefit from `--normalize`.
+    async fn report_loudness(&self, audio_path: &Path) -> Option<loudness::Measurement> {
+        logger::info("Analyzing loudness...");
+        let (audio, cancel) = (audio_path.to_path_buf(), self.cancel.clone());
+        let report = match blocking(move || loudness::analyze(&audio, &cancel)).await {
+            Ok(report) => report,
+            Err(error) => {
+                logger::warning(&format!("Loudness analysis failed: {}", error));
+                return None;
+            }
+        };
+
+        match &report.measurement {
+            Some(m) => logger::stats(&format!("Loudness: {:.1} LUFS integrated, true peak {:+.1} dBTP, range {:.1} LU", m.integrated, m.true_peak, m.range)),
+            None => logger::warning("The audio is silent throughout"),
+        }
+        if let (true, Some(m)) = (report.needs_normalization(), &report.measurement) {
+            logger::warning(&format!("{:+.1} LU from the {} LUFS target{}; --normalize would even it out",
+                m.integrated - loudness::TARGET_LUFS,
+                loudness::TARGET_LUFS,
+                if m.true_peak > loudness::MAX_TRUE_PEAK { ", and peaks may clip" } else { "" }));
+        }
+        for (start, end) in &report.silences {
+            match end {
+                Some(end) if *start == 0.0 => logger::info(&format!("Starts with {:.1}s of silence", end)),
+                Some(end) => logger::info(&format!("Silent from {} to {}", utils::format_time(*start), utils::format_time(*end))),
+                None if *start == 0.0 => {}
+                None => logger::info(&format!("Silent from {} to the end", utils::format_time(*start))),
+            }
+        }
+        report.measurement
+    }
+
+    /// Cut `start..end` out of `input_path`; re-encoding makes the cut frame-accurate.
+    fn live_segment_command(&self, input_path: &Path, output_path: &Path, start: f64, end: f64) -> Command {
+        let mut command = Command::new(dependencies::ffmpeg());
+        command
+            .args(["-y", "-v", "error", "-ss", &format!("{:.3}", start), "-i"])
+            .arg(input_path)
+            .args(["-t", &format!("{:.3}", end - start), "-map", "0:v:0", "-map", "0:a?"])
+            .args(["-c:v", "libx264", "-crf", "12", "-preset", "veryfast", "-c:a", "aac"])
+            .arg(output_path);
+        command
+    }
+
+    /// Make an iOS Live Photo: a 3-second portrait movie around the video's most
+    /// representative moment, encoded with the `live-photo` profile, and its key photo,
+    /// paired by a shared content identifier. The source is kept.
+    async fn convert_to_live_photo(&self, input_path: &Path, config: &Config) -> Result<PathBuf> {
+        let movie_path = config.conversion_settings.profile().output_path(input_path);
+        if movie_path.exists() {
+            logger::success(&format!("Live Photo already exists: {}", movie_path.file_name().unwrap().to_string_lossy()));
+            return Ok(movie_path);
+        }
+
+        logger::search("Picking a 3-second segment for the Live Photo...");
+        let duration = self.get_video_duration(input_path).await?;
+        let (input, cancel) = (input_path.to_path_buf(), self.cancel.clone());
+        let center = blocking(move || Ok(live_photo::representative_time(&input, duration, &cancel))).await?.unwrap_or(duration / 2.0);
+        let cuts = self.detect_scene_cuts(input_path, 0.4, 0.0).await.unwrap_or_default();
+        let (start, end) = live_photo::segment_around(center, duration, &cuts);
+        logger::info(&format!("Segment: {} to {}", utils::format_time(start), utils::format_time(end)));
+
+        let segment_path = input_path.with_extension("live-segment.mp4");
+        self.cancel.track_file(&segment_path);
+        let output = self.live_segment_command(input_path, &segment_path, start, end).tracked_output(&self.cancel).await?;
+        if !output.status.success() {
+            fs::remove_file(&segment_path).ok();
+            self.cancel.untrack_file(&segment_path);
+            return Err(DownloaderError::Ffmpeg(format!("Failed to cut the Live Photo segment: {}", String::from_utf8_lossy(&output.stderr).trim())));
+        }
+
+        let encoded_path = input_path.with_extension("live-encode.mov");
+        let encoded = self.convert_with_profile(&segment_path, &encoded_path, config).await;
+        fs::remove_file(&segment_path).ok();
+        self.cancel.untrack_file(&segment_path);
+        encoded?;
+
+        let identifier = live_photo::content_identifier(&movie_path);
+        let output = Command::from(live_photo::tag_movie_command(&encoded_path, &movie_path, &identifier)).tracked_output(&self.cancel).await?;
+        fs::remove_file(&encoded_path).ok();
+        if !output.status.success() {
+            return Err(DownloaderError::Ffmpeg(format!("Failed to tag the Live Photo movie: {}", String::from_utf8_lossy(&output.stderr).trim())));
+        }
+        let (movie, cancel) = (movie_path.clone(), self.cancel.clone());
+        let photo_path = blocking(move || live_photo::write_photo(&movie, &identifier, &cancel)).await?;
+        self.fix_file_permissions(&movie_path)?;
+        self.fix_file_permissions(&photo_path)?;
+
+        logger::success(&format!("Live Photo saved: {} + {}",
+            movie_path.file_name().unwrap().to_string_lossy(),
+            photo_path.file_name().unwrap().to_string_lossy()));
+        logger::info("Import both files into Photos together (or AirDrop them to an iPhone), then pick the photo as a wallpaper with Live Photo on");
+        Ok(movie_path)
+    }
+
+    async fn convert_to_mov(&mut self, input_path: &Path, config: &Config) -> Result<PathBuf> {
+        let profile = config.conversion_settings.profile();
+        let output_path = profile.output_path(input_path);
+
+        if output_path.exists() {
+            logger::success(&format!("Converted version already exists: {}", output_path.file_name().unwrap().to_string_lossy()));
+            return Ok(output_path);
+        }
+
+        let mut processed_input_path = input_path.to_path_buf();
+        let mut temporary_files = Vec::new();
+
+        // Cut title cards and end screens before looping so they don't repeat
+        if config.conversion_settings.auto_trim {
+            if let Some(trimmed) = self.auto_trim(input_path).await? {
+                self.cancel.track_file(&trimmed);
+                temporary_files.push(trimmed.clone());
+                processed_input_path = trimmed;
+            }
+        }
+
+        // Check video duration and extend if needed
+        let duration = self.get_video_duration(&processed_input_path).await?;
+        let min_duration = config.video_settings.min_recommended_duration as f64;
+        let mut expected_duration = duration;
+
+        // Only wallpapers loop; other profiles keep the video as long as it is
+        if duration < min_duration && profile.is_wallpaper() {
+            logger::info(&format!(" Video duration: {} ({:.1}s)", utils::format_time(duration), duration));
+            logger::info(" Extending video to minimum 3 minutes for better experience...");
+            processed_input_path = self.extend_video(&processed_input_path, min_duration, config).await?;
+            self.cancel.track_file(&processed_input_path);
+            temporary_files.push(processed_input_path.clone());
+            expected_duration = min_duration;
         } else {
-            video_format.format_id.clone()
+            logger::info(&format!("  Video duration: {}", utils::format_time(duration)));
+        }
+
+        // Sources that are already wallpaper-ready HEVC only need a new container;
+        // otherwise encode with the profile, trying hardware-accelerated HEVC first
+        let converted_path = match self.reencode_reason(&processed_input_path, config).await {
+            None if self.remux_to_mov(&processed_input_path, &output_path, config).await => {
+                logger::stats("Conversion path: remux (source already meets the wallpaper format)");
+                self.conversion_path = Some("remux".to_string());
+                output_path.clone()
+            }
+            reason => {
+                let reason = reason.unwrap_or_else(|| "remux failed".to_string());
+                logger::stats(&format!("Conversion path: re-encode ({})", reason));
+                self.conversion_path = Some(format!("re-encode ({})", reason));
+                self.convert_with_profile(&processed_input_path, &output_path, config).await?
+            }
+        };
+
+        // Clean up temporary trimmed/extended files if created
+        for temporary in &temporary_files {
+            self.cancel.untrack_file(temporary);
+            if let Err(e) = fs::remove_file(temporary) {
+                logger::warning(&format!("  Could not clean up temporary file: {}", e));
+            } else {
+                logger::info(&format!("  Cleaned up temporary file: {}", temporary.file_name().unwrap().to_string_lossy()));
+            }
+        }
+
+        // Clean up original MP4 file after successful conversion
+        let expected_duration = profile.max_duration.map_or(expected_duration, |max| expected_duration.min(max as f64));
+        self.cleanup_source_file(input_path, &converted_path, expected_duration).await?;
+
+        Ok(converted_path)
+    }
+
+    fn parse_download_progress(&self, line: &str) -> Option<utils::DownloadProgress> {
+        utils::parse_progress(line)
+    }
+
+    /// Keep the title, channel, source URL and license with the converted files, so the
+    /// credit travels with the video (`library attribution`). `--audio-only` files get the
+    /// `[audio_tags]` templates instead, which players read as title, artist and year.
+    fn set_tags(&mut self, url: &str, info: &crate::video_info::VideoInfo, config: &Config) {
+        if config.conversion_settings.audio_only {
+            self.tags = config.conversion_settings.audio_tags.templates.iter()
+                .filter_map(|(key, template)| Some((key.clone(), self.render_tag(template, url, info)?)))
+                .collect();
+            if let Some((album, position)) = self.album_track(info, config) {
+                // Unless the templates set them, players group and order by these
+                for (key, value) in [("album", album.title.clone()), ("track", format!("{}/{}", position + 1, album.tracks.len()))] {
+                    if !self.tags.iter().any(|(existing, _)| existing == key) {
+                        self.tags.push((key.to_string(), value));
+                    }
+                }
+            }
+            return;
+        }
+        self.tags = [
+            ("title", Some(info.title.clone())),
+            ("artist", info.uploader.clone()),
+            ("comment", Some(url.to_string())),
+            ("copyright", info.license.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|value| (key.to_string(), value)))
+        .collect();
+    }
+
+    /// `template` with its placeholders filled in; `None` when it uses one the video has no
+    /// value for, so a missing uploader leaves the tag out rather than writing "Unknown".
+    fn render_tag(&self, template: &str, url: &str, info: &crate::video_info::VideoInfo) -> Option<String> {
+        let date = info.upload_date.as_deref().filter(|date| date.len() == 8);
+        let values = [
+            ("{title}", Some(info.title.clone())),
+            ("{uploader}", info.uploader.clone()),
+            ("{date}", date.map(utils::format_date)),
+            ("{year}", date.map(|date| date[..4].to_string())),
+            ("{id}", utils::extract_video_id(url)),
+            ("{url}", Some(url.to_string())),
+            ("{license}", info.license.clone()),
+        ];
+        let mut text = template.to_string();
+        for (placeholder, value) in values {
+            if text.contains(placeholder) {
+                text = text.replace(placeholder, &value?);
+            }
+        }
+        Some(text).filter(|text| !text.trim().is_empty())
+    }
+
+    fn tag_args(&self) -> Vec<String> {
+        self.tags.iter()
+            .flat_map(|(key, value)| ["-metadata".to_string(), format!("{}={}", key, value)])
+            .collect()
+    }
+
+    /// yt-dlp arguments that download the chosen formats to `output_path`.
+    fn yt_dlp_args(&self, url: &str, video_format: &VideoFormat, audio_format: &Option<AudioFormat>, output_path: &Path, config: &Config) -> Vec<String> {
+        let format_arg = match (audio_format, config.conversion_settings.audio_only) {
+            (Some(audio), true) => audio.format_id.clone(),
+            (Some(audio), false) => format!("{}+{}", video_format.format_id, audio.format_id),
+            // No separate audio: the combined format, whose video the extraction drops
+            (None, _) => video_format.format_id.clone(),
         };
 
+        let output = output_path.to_string_lossy();
         let mut args = vec![
             "-f", &format_arg,
-            "-o", final_output_path.to_str().unwrap(),
-            "--merge-output-format", self.get_extension(),
+            "-o", &output,
+            "--merge-output-format", config.download_settings.merge_output_format,
             "--progress",
-            "--newline"
+            "--newline",
+            "--continue", // Resume from .part files left by an interrupted run
+            // The same tags as the conversion, for downloads that are kept as they are
+            "--embed-metadata",
+            "--parse-metadata", "webpage_url:(?P<meta_comment>.+)",
+            "--parse-metadata", "license:(?P<meta_copyright>.+)",
         ];
         
         // Add optional settings
-        let config = Config::default();
         if config.download_settings.embed_subtitles {
             args.push("--embed-subs");
         }
@@ -479,51 +1977,179 @@ impl Downloader {
         if config.download_settings.embed_thumbnail {
             args.push("--embed-thumbnail");
         }
+
+        // Written as <name>.jpg, which the library shows for the converted .mov too, and
+        // which becomes the cover art of `--audio-only` files
+        let cover_art = config.conversion_settings.audio_only && config.conversion_settings.audio_tags.cover_art;
+        if config.download_settings.save_thumbnail || cover_art {
+            args.extend(["--write-thumbnail", "--convert-thumbnails", "jpg"]);
+        }
+        
+        let sections = match (&config.download_settings.clip, self.sample_seconds) {
+            (Some(clip), _) => Some(clip.sections()),
+            (None, seconds) => seconds.map(|seconds| format!("*0-{}", seconds)),
+        };
+        if let Some(sections) = &sections {
+            args.extend(["--download-sections", sections.as_str()]);
+        }
+
+        let fragments = config.download_settings.concurrent_fragments.to_string();
+        if config.download_settings.concurrent_fragments > 1 {
+            args.extend(["--concurrent-fragments", fragments.as_str()]);
+        }
+
+        let auth_args = self.auth.yt_dlp_args(config);
+        args.extend(auth_args.iter().map(String::as_str));
+        let ffmpeg_args = dependencies::yt_dlp_ffmpeg_args();
+        args.extend(ffmpeg_args.iter().map(String::as_str));
+
+        let mirror_info = self.mirror_info.as_ref().map(|file| file.path().to_string_lossy());
+        match &mirror_info {
+            Some(path) => args.extend(["--load-info-json", path]),
+            None => args.push(url),
+        }
+        args.into_iter().map(String::from).collect()
+    }
+
+    async fn download_video(&mut self, url: &str, video_format: &VideoFormat, audio_format: &Option<AudioFormat>, output_path: &Path, config: &Config) -> Result<PathBuf> {
+        logger::header("Starting Download");
+        logger::download(&format!("Output: {}", output_path.display()));
         
-        args.push(url);
+        // Ensure unique filename
+        let final_output_path = utils::get_unique_filename(output_path)?;
+        if final_output_path != *output_path {
+            logger::warning(&format!("File exists, using: {}", final_output_path.display()));
+        }
         
+        if let Some(format) = self.direct_format.clone() {
+            return self.download_direct(&format, video_format, &final_output_path, config).await;
+        }
+
+        let args = self.yt_dlp_args(url, video_format, audio_format, &final_output_path, config);
         logger::info(&format!("Command: yt-dlp {}", args.join(" ")));
         
         // Start download process
-        let child = Command::new("yt-dlp")
+        let mut child = self.cancel.spawn(Command::new(dependencies::yt_dlp())
             .args(&args)
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?;
+            .stderr(Stdio::piped()))?;
             
-        self.is_downloading = true;
+        let pid = child.id().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let mut stderr_pipe = child.stderr.take().unwrap();
+        self.cancel.track_file(&final_output_path);
         self.current_process = Some(child);
+        let stall_timeout = config.download_settings.stall_timeout;
+        let watchdog = Watchdog::start(pid, stall_timeout);
         
-        // Handle stdout (progress)
-        let stdout = self.current_process.as_mut().unwrap().stdout.take().unwrap();
-        let reader = BufReader::new(stdout);
-
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                if !line.trim().is_empty() {
-                    self.parse_download_progress(&line);
+
+        // yt-dlp reports video and audio streams one after the other, each from 0%
+        let started = std::time::Instant::now();
+        let mut finished_bytes = 0;
+        let mut stream_bytes = None;
+        let mut last_percentage = 0.0;
+        let mut tracker = SpeedTracker::default();
+        let mut received = 0;
+
+        // Handle stdout (progress), reading stderr at the same time so neither pipe fills up
+        let mut stderr = Vec::new();
+        let progress = async {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if let Some(utils::DownloadProgress { percentage, total_size, estimated, speed, eta, fragments }) = self.parse_download_progress(&line) {
+                    if percentage + 1.0 < last_percentage {
+                        finished_bytes += stream_bytes.unwrap_or(0);
+                    }
+                    last_percentage = percentage;
+                    stream_bytes = utils::parse_size(&total_size);
+
+                    let stream_done = stream_bytes.map(|total| (total as f64 * percentage / 100.0) as u64);
+                    if let Some(done) = stream_done {
+                        tracker.record(finished_bytes + done);
+                    }
+                    // Progress lines repeat at a speed of 0 while the connection is stuck
+                    if finished_bytes + stream_done.unwrap_or(0) > received {
+                        received = finished_bytes + stream_done.unwrap_or(0);
+                        watchdog.feed();
+                    }
+
+                    let remaining = stream_bytes.zip(stream_done).map(|(total, done)| total.saturating_sub(done));
+                    self.notify(ProgressEvent::DownloadProgress {
+                        job: self.job_label.clone(),
+                        percent: percentage,
+                        speed: tracker.speed(),
+                        eta: remaining.and_then(|bytes| tracker.eta(bytes)),
+                    });
+
+                    // Prefer the smoothed speed and ETA; yt-dlp's own jump with every burst
+                    let (speed, eta) = match (tracker.speed(), stream_bytes, stream_done) {
+                        (Some(rate), Some(total), Some(done)) => (
+                            speed::format_speed(rate),
+                            tracker.eta(total.saturating_sub(done))
+                                .map(|seconds| utils::format_duration(Some(seconds.round() as u64)))
+                                .unwrap_or_else(|| "Unknown".to_string()),
+                        ),
+                        _ => (speed, eta),
+                    };
+
+                    let downloaded = stream_done
+                        .map(|done| utils::format_file_size(Some(done)))
+                        .unwrap_or_else(|| "?".to_string());
+                    let total_size = if estimated { format!("~{}", total_size) } else { total_size };
+                    let fragments = fragments
+                        .map(|(done, total)| format!(" (frag {}/{})", done, total))
+                        .unwrap_or_default();
+                    status::update(&self.job_label, Stage::Downloading, percentage, &format!(
+                        "{} / {} at {} ETA {}{}", downloaded, total_size, speed, eta, fragments
+                    ));
+                    logger::job_progress(&self.job_label, &format!(
+                        "Downloading {} {:5.1}% {} / {} at {} {} ETA {}{}{}",
+                        utils::create_progress_bar(percentage, 20), percentage, downloaded, total_size, speed, tracker.sparkline(12), eta, fragments, self.overall_suffix()
+                    ));
+                } else {
+                    // Extracting, merging and the like
+                    watchdog.feed();
                 }
             }
+        };
+        let _ = tokio::join!(progress, stderr_pipe.read_to_end(&mut stderr));
+
+        if let Some(bytes) = stream_bytes {
+            let summary = format!(
+                "Downloaded {} in {}",
+                utils::format_file_size(Some(finished_bytes + bytes)),
+                utils::format_time(started.elapsed().as_secs_f64())
+            );
+            if self.shared_progress {
+                logger::job_progress(&self.job_label, &summary);
+            } else {
+                logger::progress_complete(&summary);
+            }
+        } else if !self.shared_progress {
+            logger::finish_job_progress();
         }
         
         // Handle stderr (errors and additional info)
-        let stderr = self.current_process.as_mut().unwrap().stderr.take().unwrap();
-        let stderr_reader = BufReader::new(stderr);
-
-        for line in stderr_reader.lines() {
-            if let Ok(line) = line {
-                if !line.trim().is_empty() && !line.contains("WARNING") {
-                    logger::warning(&line);
-                }
+        let mut stderr_output = String::new();
+        for line in String::from_utf8_lossy(&stderr).lines() {
+            stderr_output.push_str(line);
+            stderr_output.push('\n');
+            if !line.trim().is_empty() && !line.contains("WARNING") {
+                logger::warning(line);
             }
         }
         
         // Handle process completion
-        let status = self.current_process.as_mut().unwrap().wait()?;
-        self.is_downloading = false;
+        let status = self.current_process.as_mut().unwrap().wait().await?;
         self.current_process = None;
+        self.cancel.untrack_process(pid);
+        if watchdog.stop() {
+            let limit = stall_timeout.map(watchdog::describe).unwrap_or_default();
+            return Err(DownloaderError::Network(format!("Download stalled: no data received for {}", limit)));
+        }
         
         if status.success() {
+            self.cancel.untrack_file(&final_output_path);
             logger::success("Download completed successfully!");
             
             // Check if file exists and show stats
@@ -532,15 +2158,107 @@ impl Downloader {
                 logger::stats(&format!("File size: {}", utils::format_file_size(Some(stats.len()))));
                 // Note: birthtime not available in Rust std::fs::Metadata
             }
+
+            if audio_format.is_some() && !config.conversion_settings.audio_only {
+                self.verify_merge(&final_output_path).await?;
+            }
+            self.verify_download(&final_output_path).await?;
             
             Ok(final_output_path)
         } else {
-            Err(format!("Download failed with exit code {:?}", status.code()).into())
+            crash::record_stderr(&format!("yt-dlp {}", url), &stderr_output);
+            let error = YtDlpError::from_stderr(&stderr_output);
+            if let YtDlpError::Other(_) = error {
+                return Err(DownloaderError::Download(format!("Download failed with exit code {:?}: {}", status.code(), error)));
+            }
+            Err(error.into())
+        }
+    }
+
+    /// Fetch a format (a yt-dlp format dict with a plain `url`) over HTTP. Large files of known
+    /// size are fetched as `concurrent_fragments` ranges at once and joined; otherwise a partial
+    /// file from an earlier attempt is resumed. Progress is read from the size of the growing files.
+    async fn download_direct(&mut self, format: &Value, video_format: &VideoFormat, output_path: &Path, config: &Config) -> Result<PathBuf> {
+        if config.download_settings.clip.is_some() || self.sample_seconds.is_some() {
+            logger::warning("Without yt-dlp the whole video is downloaded; --clip and --sample don't apply");
+        }
+        let format_url = format["url"].as_str().ok_or("The format has no URL")?;
+
+        let ranges = video_format.filesize
+            .map(|size| segment_ranges(size, config.download_settings.concurrent_fragments))
+            .unwrap_or_default();
+        let parts: Vec<(PathBuf, Option<(u64, u64)>)> = match ranges.len() {
+            0 | 1 => vec![(output_path.to_path_buf(), None)],
+            count => {
+                logger::info(&format!("Downloading in {} parallel segments", count));
+                ranges.into_iter().enumerate()
+                    .map(|(index, range)| {
+                        let mut name = output_path.as_os_str().to_owned();
+                        name.push(format!(".part{}", index));
+                        (PathBuf::from(name), Some(range))
+                    })
+                    .collect()
+            }
+        };
+
+        let headers: Vec<(String, String)> = format["http_headers"].as_object().into_iter().flatten()
+            .map(|(name, value)| (name.clone(), value.as_str().unwrap_or_default().to_string()))
+            .collect();
+        let mut downloads = tokio::task::JoinSet::new();
+        for (path, range) in &parts {
+            // A segment finished by an earlier attempt is kept
+            if let (Some((start, end)), Ok(metadata)) = (range, fs::metadata(path)) {
+                if metadata.len() == end - start + 1 {
+                    continue;
+                }
+            }
+            self.cancel.track_file(path);
+            downloads.spawn(http::fetch_segment(format_url.to_string(), path.clone(), *range, headers.clone(), self.cancel.clone()));
+        }
+
+        let mut ticker = tokio::time::interval(Duration::from_secs(1));
+        let mut failure = None;
+        while !downloads.is_empty() {
+            tokio::select! {
+                Some(joined) = downloads.join_next() => {
+                    if let Err(error) = joined.map_err(|error| error.to_string())? {
+                        failure.get_or_insert(error);
+                    }
+                }
+                _ = ticker.tick() => {
+                    let Some(total) = video_format.filesize else {
+                        continue;
+                    };
+                    let received: u64 = parts.iter().filter_map(|(path, _)| fs::metadata(path).ok()).map(|m| m.len()).sum();
+                    let percentage = (received as f64 * 100.0 / total as f64).min(100.0);
+                    status::update(&self.job_label, Stage::Downloading, percentage, &utils::format_file_size(Some(received)));
+                    logger::job_progress(&self.job_label, &format!("Downloading {} {:5.1}%{}", utils::create_progress_bar(percentage, 20), percentage, self.overall_suffix()));
+                }
+            }
+        }
+        if !self.shared_progress {
+            logger::finish_job_progress();
+        }
+        if let Some(error) = failure {
+            return Err(error);
+        }
+
+        if parts.len() > 1 {
+            let mut output = fs::File::create(output_path)?;
+            for (path, _) in &parts {
+                std::io::copy(&mut fs::File::open(path)?, &mut output)?;
+            }
+            for (path, _) in &parts {
+                fs::remove_file(path).ok();
+                self.cancel.untrack_file(path);
+            }
         }
+        self.cancel.untrack_file(output_path);
+        logger::success("Download completed successfully!");
+        Ok(output_path.to_path_buf())
     }
 
-    async fn download_with_retry(&mut self, url: &str, video_format: &VideoFormat, audio_format: &Option<AudioFormat>, output_path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
-        let config = Config::default();
+    async fn download_with_retry(&mut self, url: &str, video_format: &VideoFormat, audio_format: &Option<AudioFormat>, output_path: &Path, config: &Config) -> Result<PathBuf> {
         let mut _last_error = None;
 
         for attempt in 1..=config.download_settings.retry_attempts {
@@ -550,16 +2268,28 @@ impl Downloader {
                 tokio::time::sleep(Duration::from_secs(2)).await;
             }
 
-            match self.download_video(url, video_format, audio_format, output_path).await {
+            match self.download_video(url, video_format, audio_format, output_path, config).await {
                 Ok(result) => return Ok(result),
                 Err(error) => {
                     let error_msg = error.to_string();
                     logger::error(&format!("Attempt {} failed: {}", attempt, error_msg));
 
+                    // Bot checks, removed videos and the like fail the same way every time
+                    if let Some(ytdlp_error) = error.ytdlp() {
+                        if !ytdlp_error.is_retryable() {
+                            return Err(error);
+                        }
+                    }
+
                     if attempt == config.download_settings.retry_attempts {
-                        return Err(format!("Download failed after {} attempts. Last error: {}",
+                        let message = format!("Download failed after {} attempts. Last error: {}",
                             config.download_settings.retry_attempts,
-                            error_msg).into());
+                            error_msg);
+                        // Keep a network failure's code; the retries don't change what went wrong
+                        return Err(match error {
+                            DownloaderError::Network(_) => DownloaderError::Network(message),
+                            _ => DownloaderError::Download(message),
+                        });
                     }
                     _last_error = Some(error);
                 }
@@ -569,34 +2299,501 @@ impl Downloader {
         unreachable!()
     }
 
+    /// How long the last `perform_download` spent converting, if it converted anything.
+    pub fn conversion_seconds(&self) -> Option<f64> {
+        self.conversion_seconds
+    }
+
+    /// Leave the progress rows in place after downloading, for when several downloads run at once.
+    pub fn share_progress_rows(&mut self) {
+        self.shared_progress = true;
+    }
+
+    /// Loudness of the `--audio-only` output of the last download, for the history.
+    pub fn loudness(&self) -> Option<&loudness::Measurement> {
+        self.loudness.as_ref()
+    }
+
+    /// `remux`, or `re-encode` with the reason, for the last download that made a .mov.
+    pub fn conversion_path(&self) -> Option<&str> {
+        self.conversion_path.as_deref()
+    }
+
+    /// What converting the last download measured, for its history entry.
+    pub fn conversion(&self) -> Conversion {
+        Conversion {
+            seconds: self.conversion_seconds,
+            path: self.conversion_path.clone(),
+            loudness: self.loudness.clone(),
+        }
+    }
+
+    /// The job's progress across all its stages, to end a progress row with.
+    fn overall_suffix(&self) -> String {
+        status::overall_summary(&self.job_label).map(|summary| format!(" · {}", summary)).unwrap_or_default()
+    }
+
+    /// Id of the current or last job, as shown on progress rows and in events.
+    pub fn job_label(&self) -> &str {
+        &self.job_label
+    }
+
+    /// Use `label` for every job of this downloader, e.g. the one a queue made unique per run.
+    pub fn set_job_label(&mut self, label: &str) {
+        self.job_label = label.to_string();
+        self.label_assigned = true;
+    }
+
+    /// Label the job after the video in `url`, unless a label was assigned.
+    fn label_from(&mut self, url: &str) {
+        if let (false, Some(video_id)) = (self.label_assigned, utils::extract_video_id(url)) {
+            self.job_label = utils::job_label(&video_id);
+        }
+    }
+
+    /// Every converted output of the last `perform_download` when it produced more than one
+    /// wallpaper (e.g. `--split-scenes`); empty otherwise.
+    pub fn batch_outputs(&self) -> &[PathBuf] {
+        &self.batch_outputs
+    }
+
+    /// The caller installs the result as a wallpaper, so a finished download leaves its job in
+    /// the status at the `Installing` stage for the caller to `status::finish`.
+    pub fn expect_install(&mut self) {
+        self.install_follows = true;
+    }
+
+    /// Hold a queue's download slot until the file is downloaded, so the next download starts
+    /// while this one waits to convert.
+    pub fn set_download_slot(&mut self, download_slot: OwnedSemaphorePermit) {
+        self.download_slot = Some(download_slot);
+    }
+
+    /// Convert only once one of a queue's `conversion_slots` is free.
+    pub fn set_conversion_slots(&mut self, conversion_slots: Arc<Semaphore>) {
+        self.conversion_slots = Some(conversion_slots);
+    }
+
+    /// Convert a file a download-only job left, as a job of its own.
+    pub async fn convert_downloaded(&mut self, path: &Path, config: &Config) -> Result<PathBuf> {
+        self.conversion_seconds = None;
+        self.conversion_path = None;
+        let title = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        status::begin(&self.job_label, &title, PhaseWeights::for_run(config, false), self.cancel.clone());
+
+        let result = async {
+            let _conversion_slot = self.conversion_slot().await?;
+            let started = std::time::Instant::now();
+            let converted = self.convert(path, config).await?;
+            self.conversion_seconds = Some(started.elapsed().as_secs_f64());
+            Ok(converted)
+        }.await;
+        status::finish(&self.job_label);
+        result
+    }
+
+    /// Wait for a conversion slot of the queue this job runs in, if any.
+    async fn conversion_slot(&self) -> Result<Option<OwnedSemaphorePermit>> {
+        let Some(slots) = &self.conversion_slots else {
+            return Ok(None);
+        };
+        status::update(&self.job_label, Stage::WaitingToConvert, 0.0, "");
+        tokio::select! {
+            permit = slots.clone().acquire_owned() => Ok(permit.ok()),
+            _ = self.cancel.cancelled() => Err("Cancelled while waiting to convert".into()),
+        }
+    }
+
+    /// Handle for stopping this downloader's external tools from elsewhere, e.g. a signal handler.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+
+    /// Share one cancellation handle between several downloaders.
+    pub fn set_cancel_handle(&mut self, handle: CancelHandle) {
+        self.cancel = handle;
+    }
+
+    /// The credentials of this job, for analyzing the video with the same account.
+    pub fn auth_session(&self) -> &auth::Session {
+        &self.auth
+    }
+
+    /// Rest this job's cookie profile after YouTube throttled it; `true` when the caller
+    /// should retry with the next one.
+    pub fn rotate_profile_after(&self, error: &DownloaderError, config: &Config) -> bool {
+        self.auth.rotate_after(error, config)
+    }
+
     pub fn is_download_in_progress(&self) -> bool {
-        self.is_downloading
+        self.current_process.is_some()
     }
 
     pub fn cancel_download(&mut self) -> bool {
         if let Some(mut process) = self.current_process.take() {
             logger::warning("Cancelling download...");
-            let _ = process.kill();
-            self.is_downloading = false;
+            if let Some(pid) = process.id() {
+                cancel::kill_group(pid, cancel::Signal::Kill);
+                self.cancel.untrack_process(pid);
+            }
+            // tokio reaps it once it has exited
+            let _ = process.start_kill();
             return true;
         }
         false
     }
 
-    pub async fn perform_download(&mut self, url: &str, analysis: &SelectedFormats, config: &crate::config::Config) -> Result<PathBuf, Box<dyn std::error::Error>> {
-        // Setup cleanup handlers
-        self.setup_cleanup_handlers();
+    /// Print the commands a download would run and the files it would produce, without
+    /// downloading or converting anything (`--dry-run`).
+    ///
+    /// Steps that depend on the downloaded file (auto-trim, SponsorBlock, scene cuts) are
+    /// described rather than shown, and the remux decision is predicted from the format
+    /// metadata instead of ffprobe.
+    pub fn dry_run(&mut self, url: &str, analysis: &SelectedFormats, config: &Config) -> Result<()> {
+        self.sample_seconds = config.download_settings.sample_seconds;
+        self.load_mirror_info(analysis)?;
+        self.set_tags(url, &analysis.info, config);
+        logger::header("Dry Run");
+
+        let output_path = config.output_dir.join(self.create_output_filename(analysis, config));
+        let (exists, existing_path, needs_conversion) = self.check_existing_video(&output_path, config);
+
+        // Seconds of video the download keeps, when known
+        let full_duration = analysis.info.duration.map(|d| d as f64);
+        let kept_duration = match (&config.download_settings.clip, self.sample_seconds) {
+            (Some(clip), _) => clip.end.or(full_duration).map(|end| end - clip.start),
+            (None, Some(seconds)) => Some(full_duration.map_or(seconds as f64, |d| d.min(seconds as f64))),
+            (None, None) => full_duration,
+        };
+
+        let source = if exists {
+            let existing = existing_path.unwrap_or_else(|| output_path.clone());
+            logger::info(&format!("Would reuse {} instead of downloading", existing.display()));
+            if !needs_conversion {
+                return Ok(());
+            }
+            existing
+        } else if let (Some(full), Some(clip)) = (self.full_source_for_clip(analysis, config), &config.download_settings.clip) {
+            logger::info(&format!("Would cut the clip from {} instead of downloading", full.display()));
+            logger::info(&format!("$ {}", utils::command_line(&self.cut_clip_command(&full, &output_path, clip))));
+            output_path
+        } else {
+            let target = utils::get_unique_filename(&output_path)?;
+            let mut command = Command::new(dependencies::yt_dlp());
+            command.args(self.yt_dlp_args(url, &analysis.video_format, &analysis.audio_format, &target, config));
+            logger::info(&format!("$ {}", utils::command_line(&command)));
+            logger::file(&format!("Download: {}", target.display()));
+
+            let sizes = match (&analysis.audio_format, config.conversion_settings.audio_only) {
+                (Some(audio), true) => [Some(0), audio.filesize],
+                (audio, _) => [analysis.video_format.filesize, audio.as_ref().map_or(Some(0), |a| a.filesize)],
+            };
+            let size = match (sizes[0].zip(sizes[1]), kept_duration, full_duration) {
+                (Some((video, audio)), Some(kept), Some(full)) if full > 0.0 => Some(((video + audio) as f64 * kept / full) as u64),
+                (size, _, _) => size.map(|(video, audio)| video + audio),
+            };
+            logger::stats(&format!("Predicted download size: {}", utils::format_file_size(size)));
+            target
+        };
+
+        if let (Some(mode), false) = (config.download_settings.sponsorblock, exists) {
+            let action = match mode {
+                SponsorBlockMode::Remove => "cut out",
+                SponsorBlockMode::Mark => "mark",
+            };
+            logger::info(&format!("Would then look up SponsorBlock segments and {} any it finds", action));
+        }
+        if let Some(settings) = &config.conversion_settings.still {
+            logger::info(&format!("Would export the best frame as {:?} next to the download", settings.format));
+        }
+        if !config.download_settings.convert_to_mov {
+            return Ok(());
+        }
+        let audio = &config.conversion_settings.audio;
+        let loudnorm = (audio.normalize && !audio.strip).then(|| loudness::filter(None));
+        if loudnorm.is_some() {
+            logger::info("Would measure the loudness first and add the measured values to the loudnorm filter below");
+        }
+
+        if config.conversion_settings.split_scenes.is_some() {
+            logger::info("Would split the download at scene cuts and convert each scene as below");
+        } else if config.conversion_settings.split_chapters && !analysis.info.chapters.is_empty() {
+            logger::info(&format!("Would split the download into {} chapters and convert each as below", analysis.info.chapters.len()));
+        }
+
+        if config.conversion_settings.audio_only {
+            let codec = analysis.audio_format.as_ref()
+                .and_then(|audio| audio.acodec.clone())
+                .or_else(|| analysis.video_format.acodec.clone())
+                .unwrap_or_default();
+            let (format, copy) = self.audio_plan(&codec, config);
+            let in_place = copy && source.extension().is_some_and(|ext| ext == format.extension());
+            let audio_path = if in_place { source.clone() } else { self.audio_output_path(&source, format) };
+            if !in_place && audio_path.exists() {
+                logger::info(&format!("Would reuse {} instead of extracting", audio_path.display()));
+                return Ok(());
+            }
+            if in_place {
+                logger::stats(&format!("Audio path: tag in place ({} already fits .{})", codec, format.extension()));
+            } else if copy {
+                logger::stats(&format!("Audio path: copy ({} fits .{} as it is)", codec, format.extension()));
+            } else if AudioOutputFormat::for_codec(&codec) == Some(format) {
+                logger::stats(&format!("Audio path: re-encode ({} fits .{}, but the audio settings change it)", codec, format.extension()));
+            } else {
+                logger::stats(&format!("Audio path: transcode ({} to .{})", if codec.is_empty() { "unknown" } else { &codec }, format.extension()));
+            }
+            for (key, value) in &self.tags {
+                logger::stats(&format!("Tag {}: {}", key, value));
+            }
+            let cover = self.cover_art_path(&source, format, config);
+            if config.conversion_settings.audio_tags.cover_art && cover.is_none() {
+                logger::info("Opus files can't hold cover art; would save without it");
+            }
+            logger::info(&format!("$ {}", utils::command_line(&self.audio_command(&source, &audio_path, (format, copy), config, loudnorm.as_deref(), cover.as_deref()))));
+            logger::file(&format!("Output: {}", audio_path.display()));
+            return Ok(());
+        }
+
+        if let Some(format) = config.conversion_settings.loop_format {
+            let loop_path = self.loop_output_path(&source, format);
+            if loop_path.exists() {
+                logger::info(&format!("Would reuse {} instead of converting", loop_path.display()));
+            } else {
+                logger::info(&format!("$ {}", utils::command_line(&self.loop_command(&source, &loop_path, format, config))));
+                logger::file(&format!("Output: {}", loop_path.display()));
+            }
+            return Ok(());
+        }
+
+        if config.conversion_settings.live_photo {
+            let movie_path = config.conversion_settings.profile().output_path(&source);
+            if movie_path.exists() {
+                logger::info(&format!("Would reuse {} instead of converting", movie_path.display()));
+                return Ok(());
+            }
+            logger::info("Would pick a 3-second segment around the most representative frame, within one scene, and encode it:");
+            let encoder = encoders::select(config.conversion_settings.encoder);
+            let mut command = self.encoder_command(config);
+            command.args(self.encode_args(&source.with_extension("live-segment.mp4"), &source.with_extension("live-encode.mov"), config, None, encoder, &self.audio_args(config, &["-c:a", "aac"], false, loudnorm.as_deref())));
+            logger::info(&format!("$ {}", utils::command_line(&command)));
+            logger::info("Would then add the content identifier and save the key photo from its middle, tagged with exiftool");
+            logger::file(&format!("Output: {}", movie_path.display()));
+            let photo_extension = if cfg!(target_os = "macos") { "heic" } else { "jpg" };
+            logger::file(&format!("Output: {}", movie_path.with_extension(photo_extension).display()));
+            return Ok(());
+        }
+
+        if config.video_preferences.free_formats == Some(FreeFormats::Av1) {
+            let av1_path = self.av1_output_path(&source);
+            if av1_path.exists() {
+                logger::info(&format!("Would reuse {} instead of converting", av1_path.display()));
+            } else if SourceProbe::predict(&analysis.video_format, "webm").codec == "av1" {
+                logger::info("Would keep the download as it is, since it is already AV1");
+            } else {
+                logger::info(&format!("$ {}", utils::command_line(&self.av1_command(&source, &av1_path, config, loudnorm.as_deref()))));
+                logger::file(&format!("Output: {}", av1_path.display()));
+            }
+            return Ok(());
+        }
+
+        let profile = config.conversion_settings.profile();
+        let mov_path = profile.output_path(&source);
+        if mov_path.exists() {
+            logger::info(&format!("Would reuse {} instead of converting", mov_path.display()));
+            return Ok(());
+        }
+        if config.conversion_settings.auto_trim {
+            logger::info("Would first cut title cards and end screens found by scanning the download");
+        }
+        let min_duration = config.video_settings.min_recommended_duration as f64;
+        if let Some(duration) = kept_duration.filter(|d| *d < min_duration && profile.is_wallpaper()) {
+            logger::info(&format!("Would loop the video from {} to {} first", utils::format_time(duration), utils::format_time(min_duration)));
+        }
+
+        let container = config.download_settings.merge_output_format;
+        let reason = self.requested_reencode(config)
+            .or_else(|| self.remux_blocker(&SourceProbe::predict(&analysis.video_format, container), config));
+        match reason {
+            None => {
+                logger::stats("Conversion path: remux (source should already meet the wallpaper format)");
+                logger::info(&format!("$ {}", utils::command_line(&self.remux_command(&source, &mov_path, &self.audio_args(config, &["-c:a", "aac"], false, loudnorm.as_deref())))));
+            }
+            Some(reason) => {
+                logger::stats(&format!("Conversion path: re-encode ({})", reason));
+
+                // The overlay text goes through a temporary file when run for real
+                let settings = &config.conversion_settings;
+                let drawtext = settings.text_overlay.as_ref().map(|overlay| self.drawtext_filter(overlay, Path::new("<overlay text>")));
+                let vertical = analysis.video_format.width.zip(analysis.video_format.height).is_some_and(|(w, h)| w < h);
+                let filter_graph = self.video_filter_graph(settings.audio_visualizer, drawtext.as_deref(), &settings.extra_resolutions, vertical, profile.resolution);
+
+                let encoder = match profile.codec {
+                    ProfileCodec::Hevc => encoders::select(settings.encoder),
+                    _ => Encoder::Software,
+                };
+                let mut command = self.encoder_command(config);
+                command.args(self.encode_args(&source, &mov_path, config, filter_graph.as_deref(), encoder, &self.audio_args(config, &["-c:a", "aac"], false, loudnorm.as_deref())));
+                logger::info(&format!("$ {}", utils::command_line(&command)));
+                if settings.max_attempts > 1 && encoder.is_hardware() {
+                    logger::info("If that fails: the same with -c:v libx265, then with -c:a aac");
+                } else if settings.max_attempts > 1 {
+                    logger::info("If that fails: the same with -c:a aac");
+                }
+                let duration = kept_duration
+                    .map(|d| if profile.is_wallpaper() { d.max(min_duration) } else { d })
+                    .map(|d| profile.max_duration.map_or(d, |max| d.min(max as f64)));
+                if let Some((duration, bitrate)) = duration.zip(profile.bitrate) {
+                    let size = (duration * bitrate as f64 * 1e6 / 8.0) as u64;
+                    logger::stats(&format!("Predicted .{} size: ~{} ({} Mbps)", profile.container, utils::format_file_size(Some(size)), bitrate));
+                }
+            }
+        }
+        logger::file(&format!("Output: {}", mov_path.display()));
+        for extra in self.extra_output_paths(&mov_path, config) {
+            logger::file(&format!("Also: {}", extra.display()));
+        }
+        if profile.name == ConversionProfile::AERIAL {
+            logger::info(&format!("Would copy it to {} and list it in entries.json", config.aerial_dir.display()));
+        }
+        if config.download_settings.save_thumbnail {
+            logger::info(&format!("$ {}", utils::command_line(&self.poster_command(&mov_path, &self.poster_path(&mov_path)))));
+        }
+        Ok(())
+    }
+
+    pub async fn perform_download(&mut self, url: &str, analysis: &SelectedFormats, config: &crate::config::Config) -> Result<PathBuf> {
+        self.batch_outputs.clear();
+        self.conversion_seconds = None;
+        self.conversion_path = None;
+        self.loudness = None;
+        self.sample_seconds = config.download_settings.sample_seconds;
+        self.label_from(url);
+        self.load_mirror_info(analysis)?;
+        logger::info(&format!("Job: {} ({})", self.job_label, analysis.info.title));
+
+        status::begin(&self.job_label, &analysis.info.title, PhaseWeights::for_run(config, self.install_follows), self.cancel.clone());
+        mqtt::publish_job(config, &self.job_label, &analysis.info.title, "started", None).await;
+        logger::event("analysis", self.analysis_event(url, analysis));
+        let result = self.download_and_convert(url, analysis, config).await;
+        match (&result, self.install_follows) {
+            (Ok(_), true) => status::update(&self.job_label, Stage::Installing, 0.0, "Waiting to install"),
+            _ => status::finish(&self.job_label),
+        }
+
+        let (state, error) = match &result {
+            Ok(_) => ("completed", None),
+            Err(error) => ("failed", Some(error.to_string())),
+        };
+        mqtt::publish_job(config, &self.job_label, &analysis.info.title, state, error.clone()).await;
+        self.notify(match &result {
+            Ok(path) => ProgressEvent::Completed { job: self.job_label.clone(), path: path.clone(), outputs: self.batch_outputs.clone() },
+            Err(error) => ProgressEvent::Failed { job: self.job_label.clone(), error: error.to_string() },
+        });
+        if logger::wants_events() {
+            let record = match &result {
+                Ok(path) => self.completion_event(url, path, analysis).await,
+                Err(_) => json!({ "job": self.job_label, "url": url, "error": error }),
+            };
+            logger::event(state, record);
+        }
+        result
+    }
+
+    /// Writes the info a mirror gave for yt-dlp's `--load-info-json`, or keeps the chosen format
+    /// of a direct one for curl, clearing the last one.
+    fn load_mirror_info(&mut self, analysis: &SelectedFormats) -> Result<()> {
+        self.direct_format = None;
+        self.mirror_info = match &analysis.info.mirror {
+            Some(mirror) if mirror.direct => {
+                self.direct_format = mirror.info["formats"].as_array().into_iter().flatten()
+                    .find(|format| format["format_id"].as_str() == Some(&analysis.video_format.format_id))
+                    .cloned();
+                None
+            }
+            Some(mirror) => {
+                let mut file = tempfile::Builder::new().suffix(".info.json").tempfile()?;
+                serde_json::to_writer(&mut file, &mirror.info)?;
+                file.flush()?;
+                Some(file)
+            }
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// The video and the formats chosen for it, for `--json`.
+    fn analysis_event(&self, url: &str, analysis: &SelectedFormats) -> Value {
+        let info = &analysis.info;
+        let video = &analysis.video_format;
+        json!({
+            "job": self.job_label,
+            "url": url,
+            "id": info.id,
+            "title": info.title,
+            "uploader": info.uploader,
+            "duration": info.duration,
+            "upload_date": info.upload_date,
+            "license": info.license,
+            "source": info.mirror.as_ref().map(|mirror| mirror.name.as_str()),
+            "video_format": {
+                "id": video.format_id,
+                "ext": video.ext,
+                "width": video.width,
+                "height": video.height,
+                "fps": video.fps,
+                "codec": video.vcodec,
+                "filesize": video.filesize,
+            },
+            "audio_format": analysis.audio_format.as_ref().map(|audio| json!({
+                "id": audio.format_id,
+                "ext": audio.ext,
+                "codec": audio.acodec,
+                "bitrate": audio.abr,
+                "filesize": audio.filesize,
+            })),
+        })
+    }
+
+    /// Where the finished file is, with its size and length, for `--json`. Batches (chapters,
+    /// scenes, extra resolutions) also list every output.
+    async fn completion_event(&self, url: &str, path: &Path, analysis: &SelectedFormats) -> Value {
+        let outputs: Vec<Value> = self.batch_outputs.iter()
+            .map(|output| json!({ "path": output, "size": fs::metadata(output).ok().map(|m| m.len()) }))
+            .collect();
+        json!({
+            "job": self.job_label,
+            "url": url,
+            "title": analysis.info.title,
+            "path": path,
+            "size": fs::metadata(path).ok().map(|m| m.len()),
+            "duration": self.get_video_duration(path).await.ok().or(analysis.info.duration.map(|d| d as f64)),
+            "conversion_seconds": self.conversion_seconds,
+            "conversion_path": self.conversion_path,
+            "outputs": outputs,
+        })
+    }
+
+    async fn download_and_convert(&mut self, url: &str, analysis: &SelectedFormats, config: &Config) -> Result<PathBuf> {
+        self.set_tags(url, &analysis.info, config);
+        if let Some(seconds) = self.sample_seconds {
+            logger::info(&format!("Sample mode: downloading only the first {}s", seconds));
+        }
+        if let Some(clip) = &config.download_settings.clip {
+            let end = clip.end.map(utils::format_time).unwrap_or_else(|| "the end".to_string());
+            logger::info(&format!("Clip mode: keeping {} to {}", utils::format_time(clip.start), end));
+        }
 
         // Check video quality and warn if needed
         self.check_video_quality(&analysis.video_format, config);
 
         // Create output filename
-        let output_filename = self.create_output_filename(&analysis.info, &analysis.video_format, config);
+        let output_filename = self.create_output_filename(analysis, config);
         let output_path = config.output_dir.join(&output_filename);
-        utils::ensure_directory_exists(&config.output_dir).ok();
+        // Album tracks go into a folder of their own
+        utils::ensure_directory_exists(output_path.parent().unwrap_or(&config.output_dir)).ok();
 
         // Check if video already exists
-        let (exists, existing_path, needs_conversion) = self.check_existing_video(&output_path);
+        let (exists, existing_path, needs_conversion) = self.check_existing_video(&output_path, config);
         let final_path;
 
         if exists && !needs_conversion {
@@ -608,24 +2805,318 @@ impl Downloader {
             // Source file exists but needs conversion
             final_path = existing_path.unwrap();
             logger::info(" Skipping download, using existing video for conversion");
+        } else if let Some(source) = self.full_source_for_clip(analysis, config) {
+            // The whole video is already here, so cut the clip locally instead of downloading it
+            final_path = self.cut_clip(&source, &output_path, config).await?;
         } else {
             // Need to download
+            let mut formats = vec![analysis.video_format.format_id.clone()];
+            formats.extend(analysis.audio_format.as_ref().map(|audio| audio.format_id.clone()));
+            self.notify(ProgressEvent::DownloadStarted { job: self.job_label.clone(), path: output_path.clone(), formats });
             final_path = self.download_with_retry(
                 url,
                 &analysis.video_format,
                 &analysis.audio_format,
-                &output_path
+                &output_path,
+                config,
             ).await?;
             logger::success(&format!("Video downloaded successfully: {}", final_path.file_name().unwrap().to_string_lossy()));
         }
 
+        // Only fresh files: an existing source may have been processed by an earlier run
+        if let (Some(mode), false) = (config.download_settings.sponsorblock, exists) {
+            if let Err(error) = self.apply_sponsorblock(url, &final_path, mode, config).await {
+                logger::warning(&format!("SponsorBlock segments left in: {}", error));
+            }
+        }
+
+        // Grab the still before conversion, which removes the source file
+        if let Some(settings) = &config.conversion_settings.still {
+            self.export_still(&final_path, settings).await;
+        }
+
+        self.download_slot = None;
+        let converts = config.download_settings.convert_to_mov || config.conversion_settings.split_scenes.is_some() || config.conversion_settings.split_chapters;
+        let _conversion_slot = match converts {
+            true => self.conversion_slot().await?,
+            false => None,
+        };
+
         // Convert to .mov format for wallpaper compatibility
-        let config = Config::default();
         if config.download_settings.convert_to_mov {
-            let mov_path = self.convert_to_mov(&final_path).await?;
-            return Ok(mov_path);
+            // Fill in the overlay template now that the video metadata is known
+            let mut config = config.clone();
+            if let Some(overlay) = config.conversion_settings.text_overlay.as_mut() {
+                overlay.template = self.render_overlay_text(&overlay.template, &analysis.info);
+            }
+
+            let conversion_started = std::time::Instant::now();
+            if let Some(threshold) = config.conversion_settings.split_scenes {
+                let clips = self.split_scenes(&final_path, threshold).await?;
+                self.batch_outputs = self.convert_clips(&clips, "scene", &config).await?;
+                self.conversion_seconds = Some(conversion_started.elapsed().as_secs_f64());
+                if config.download_settings.save_thumbnail {
+                    for path in &self.batch_outputs {
+                        self.save_poster(path).await;
+                    }
+                }
+                self.add_to_aerials(&self.batch_outputs, &analysis.info, &config);
+                return Ok(self.batch_outputs[0].clone());
+            }
+            if config.conversion_settings.split_chapters {
+                let clips = self.split_chapters(&final_path, &analysis.info.chapters, &config.file_naming).await?;
+                self.batch_outputs = self.convert_clips(&clips, "chapter", &config).await?;
+                self.conversion_seconds = Some(conversion_started.elapsed().as_secs_f64());
+                if config.download_settings.save_thumbnail {
+                    for path in &self.batch_outputs {
+                        self.save_poster(path).await;
+                    }
+                }
+                self.add_to_aerials(&self.batch_outputs, &analysis.info, &config);
+                return Ok(self.batch_outputs[0].clone());
+            }
+
+            let converted_path = self.convert(&final_path, &config).await?;
+            self.conversion_seconds = Some(conversion_started.elapsed().as_secs_f64());
+            if config.conversion_settings.audio_only {
+                self.loudness = self.report_loudness(&converted_path).await;
+            }
+            if config.download_settings.save_thumbnail && !config.conversion_settings.audio_only {
+                self.save_poster(&converted_path).await;
+            }
+            self.add_to_aerials(std::slice::from_ref(&converted_path), &analysis.info, &config);
+            return Ok(converted_path);
+        }
+
+        if let Some(threshold) = config.conversion_settings.split_scenes {
+            let clips = self.split_scenes(&final_path, threshold).await?;
+            return Ok(clips.into_iter().next().unwrap_or(final_path));
+        }
+        if config.conversion_settings.split_chapters {
+            let clips = self.split_chapters(&final_path, &analysis.info.chapters, &config.file_naming).await?;
+            return Ok(clips.into_iter().next().unwrap_or(final_path));
         }
 
         Ok(final_path)
     }
+
+    /// `<name>.poster.jpg` next to `video_path`.
+    fn poster_path(&self, video_path: &Path) -> PathBuf {
+        let stem = video_path.file_stem().unwrap().to_string_lossy();
+        video_path.with_file_name(format!("{}.poster.jpg", stem))
+    }
+
+    fn poster_command(&self, video_path: &Path, poster_path: &Path) -> Command {
+        let mut command = Command::new(dependencies::ffmpeg());
+        command
+            .args(["-y", "-v", "error", "-i"])
+            .arg(video_path)
+            .args(["-frames:v", "1", "-q:v", "2", "-update", "1"])
+            .arg(poster_path);
+        command
+    }
+
+    /// Save the first frame of `video_path` at full resolution as `<name>.poster.jpg`.
+    ///
+    /// macOS shows a static image until the live wallpaper starts playing; the first frame
+    /// makes that handover seamless, unlike the YouTube thumbnail which is usually a
+    /// different shot.
+    pub async fn extract_poster_frame(&self, video_path: &Path) -> Result<PathBuf> {
+        let poster_path = self.poster_path(video_path);
+        let output = self.poster_command(video_path, &poster_path).tracked_output(&self.cancel).await?;
+
+        if !output.status.success() || !poster_path.exists() {
+            return Err(DownloaderError::Ffmpeg(format!("Failed to extract poster frame: {}", String::from_utf8_lossy(&output.stderr).trim())));
+        }
+        Ok(poster_path)
+    }
+
+    /// A failed poster is reported but never fails the download itself.
+    async fn save_poster(&self, video_path: &Path) {
+        match self.extract_poster_frame(video_path).await {
+            Ok(path) => logger::success(&format!("Poster saved: {}", path.file_name().unwrap().to_string_lossy())),
+            Err(error) => logger::warning(&format!("Poster export failed: {}", error)),
+        }
+    }
+
+    /// Copy `aerial-4k` conversions into the aerial folder. Failures are reported but never
+    /// fail the download itself.
+    fn add_to_aerials(&self, paths: &[PathBuf], info: &crate::video_info::VideoInfo, config: &Config) {
+        if config.conversion_settings.profile != ConversionProfile::AERIAL {
+            return;
+        }
+        for path in paths {
+            match aerial::add(path, &info.title, info.uploader.as_deref(), &config.aerial_dir) {
+                Ok(target) => logger::success(&format!("Added to the aerial folder: {}", target.display())),
+                Err(error) => logger::warning(&format!("Could not add to the aerial folder: {}", error)),
+            }
+        }
+    }
+
+    /// A failed still export is reported but never fails the download itself.
+    async fn export_still(&self, video_path: &Path, settings: &StillExport) {
+        let result = match self.get_video_duration(video_path).await {
+            Ok(duration) => {
+                let (video, format, cancel) = (video_path.to_path_buf(), settings.format, self.cancel.clone());
+                blocking(move || still::extract_still(&video, duration, format, &cancel)).await
+            }
+            Err(error) => Err(error),
+        };
+
+        let image_path = match result {
+            Ok(path) => path,
+            Err(error) => {
+                logger::warning(&format!("Still export failed: {}", error));
+                return;
+            }
+        };
+
+        logger::success(&format!("Still saved: {}", image_path.file_name().unwrap().to_string_lossy()));
+
+        if settings.set_wallpaper {
+            match still::set_desktop_picture(&image_path) {
+                Ok(()) => logger::wallpaper("Still set as the desktop picture"),
+                Err(error) => logger::warning(&error.to_string()),
+            }
+        }
+    }
+
+    /// Convert every scene or chapter clip into its own library entry.
+    async fn convert_clips(&mut self, clips: &[PathBuf], kind: &str, config: &Config) -> Result<Vec<PathBuf>> {
+        let total = clips.len();
+        let mut converted = Vec::new();
+
+        for (index, clip) in clips.iter().enumerate() {
+            logger::convert(&format!("Converting {} {}/{}", kind, index + 1, total));
+            match self.convert(clip, config).await {
+                Ok(converted_path) => converted.push(converted_path),
+                Err(error) => logger::warning(&format!("Skipping {}: {}", clip.file_name().unwrap().to_string_lossy(), error)),
+            }
+        }
+
+        if converted.is_empty() {
+            return Err(format!("None of the {} clips could be converted", kind).into());
+        }
+
+        logger::success(&format!("Added {} of {} {}s to the library", converted.len(), total, kind));
+        Ok(converted)
+    }
+}
+
+/// yt-dlp `.part` files for `output_path`, including per-format parts (`name.f299.mp4.part`)
+/// written before merging, with their sizes.
+pub fn partial_downloads(output_path: &Path) -> Vec<(PathBuf, u64)> {
+    let (Some(dir), Some(stem)) = (output_path.parent(), output_path.file_stem()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", stem.to_string_lossy());
+
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries.flatten()
+                .filter(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    name.starts_with(&prefix) && name.ends_with(".part")
+                })
+                .filter_map(|entry| Some((entry.path(), entry.metadata().ok()?.len())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Run `work` that blocks for long (a whole-file ffmpeg pass, an HTTP request) on tokio's
+/// blocking threads, so it doesn't hold up other downloads.
+pub(crate) async fn blocking<T: Send + 'static>(work: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(work).await.map_err(|error| error.to_string())?
+}
+
+/// Parse `black_start/black_end` and `silence_start/silence_end` pairs from ffmpeg detection output.
+/// A silence that never ends runs to the end of the video.
+fn parse_detected_intervals(stderr: &str, duration: f64) -> Vec<(f64, f64)> {
+    let value_after = |line: &str, key: &str| -> Option<f64> {
+        line.split(key).nth(1)?
+            .split(|c: char| c.is_whitespace() || c == '|')
+            .find(|s| !s.is_empty())?
+            .parse::<f64>().ok()
+    };
+
+    let mut intervals = Vec::new();
+    let mut silence_start = None;
+
+    for line in stderr.lines() {
+        if line.contains("black_start:") {
+            if let (Some(start), Some(end)) = (value_after(line, "black_start:"), value_after(line, "black_end:")) {
+                intervals.push((start, end));
+            }
+        } else if line.contains("silence_start:") {
+            silence_start = value_after(line, "silence_start:");
+        } else if line.contains("silence_end:") {
+            if let (Some(start), Some(end)) = (silence_start.take(), value_after(line, "silence_end:")) {
+                intervals.push((start.max(0.0), end));
+            }
+        }
+    }
+
+    if let Some(start) = silence_start {
+        intervals.push((start, duration));
+    }
+
+    intervals
+}
+
+/// What's wrong with a merged file, given `ffprobe -show_entries stream=codec_type,duration`
+/// as JSON: a missing video or audio stream, or streams whose lengths differ by more than
+/// `MERGE_TOLERANCE_SECONDS`. Matroska and WebM keep stream lengths in a `DURATION` tag.
+fn merge_problem(probe: &Value) -> Option<String> {
+    let streams = probe["streams"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let duration = |kind: &str| -> Option<Option<f64>> {
+        let stream = streams.iter().find(|stream| stream["codec_type"] == kind)?;
+        let seconds = stream["duration"].as_str()
+            .and_then(|value| value.parse().ok())
+            .or_else(|| stream["tags"]["DURATION"].as_str().and_then(|value| parse_timestamp(value).ok()));
+        Some(seconds)
+    };
+
+    match (duration("video"), duration("audio")) {
+        (None, _) => Some("no video stream".to_string()),
+        (_, None) => Some("no audio stream".to_string()),
+        (Some(Some(video)), Some(Some(audio))) if (video - audio).abs() > MERGE_TOLERANCE_SECONDS => Some(format!(
+            "video is {} long but audio is {}",
+            utils::format_time(video), utils::format_time(audio)
+        )),
+        _ => None,
+    }
+}
+
+/// Parse an ffprobe rational frame rate such as `60000/1001`.
+fn parse_frame_rate(rate: &str) -> Option<f64> {
+    let (numerator, denominator) = rate.split_once('/').unwrap_or((rate, "1"));
+    let denominator: f64 = denominator.parse().ok()?;
+    if denominator == 0.0 {
+        return None;
+    }
+    Some(numerator.parse::<f64>().ok()? / denominator)
+}
+
+fn choose_trim_range(intervals: &[(f64, f64)], duration: f64) -> Option<(f64, f64)> {
+    const EDGE_TOLERANCE: f64 = 0.5;
+    const MAX_INTRO: f64 = 20.0;
+    const MAX_OUTRO: f64 = 30.0;
+
+    let start = intervals.iter()
+        .filter(|(s, e)| *s <= EDGE_TOLERANCE && *e <= MAX_INTRO)
+        .map(|(_, e)| *e)
+        .fold(0.0, f64::max);
+
+    let end = intervals.iter()
+        .filter(|(s, e)| *e >= duration - EDGE_TOLERANCE && duration - *s <= MAX_OUTRO)
+        .map(|(s, _)| *s)
+        .fold(duration, f64::min);
+
+    // Never cut away more than a quarter of the video on a heuristic
+    if start <= 0.0 && end >= duration || end - start < duration * 0.75 {
+        return None;
+    }
+
+    Some((start, end))
 }
@@ -0,0 +1,182 @@
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::Instant;
+use serde::Deserialize;
+use crate::dependencies;
+use crate::logger;
+
+/// VAAPI render node; the first GPU on almost every Linux machine
+const VAAPI_DEVICE: &str = "/dev/dri/renderD128";
+
+static DETECTED: OnceLock<Encoder> = OnceLock::new();
+
+/// HEVC encoder used for the .mov conversion (`--encoder`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Encoder {
+    /// Apple VideoToolbox (macOS)
+    #[value(name = "videotoolbox")]
+    #[serde(rename = "videotoolbox")]
+    VideoToolbox,
+    /// NVIDIA NVENC
+    Nvenc,
+    /// Intel Quick Sync Video
+    Qsv,
+    /// VAAPI on Linux (Intel and AMD)
+    Vaapi,
+    /// AMD AMF (Windows)
+    Amf,
+    /// libx265 on the CPU; slow but works everywhere
+    #[value(name = "libx265")]
+    #[serde(rename = "libx265")]
+    Software,
+}
+
+impl Encoder {
+    /// Hardware encoders in order of preference
+    const HARDWARE: [Encoder; 5] = [Encoder::VideoToolbox, Encoder::Nvenc, Encoder::Qsv, Encoder::Amf, Encoder::Vaapi];
+
+    /// Name of the encoder in `ffmpeg -encoders`
+    pub fn ffmpeg_name(self) -> &'static str {
+        match self {
+            Encoder::VideoToolbox => "hevc_videotoolbox",
+            Encoder::Nvenc => "hevc_nvenc",
+            Encoder::Qsv => "hevc_qsv",
+            Encoder::Vaapi => "hevc_vaapi",
+            Encoder::Amf => "hevc_amf",
+            Encoder::Software => "libx265",
+        }
+    }
+
+    pub fn is_hardware(self) -> bool {
+        self != Encoder::Software
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Encoder::VideoToolbox => "Apple VideoToolbox",
+            Encoder::Nvenc => "NVIDIA NVENC",
+            Encoder::Qsv => "Intel Quick Sync",
+            Encoder::Vaapi => "VAAPI",
+            Encoder::Amf => "AMD AMF",
+            Encoder::Software => "Software (CPU)",
+        }
+    }
+
+    /// Options that go before `-i`, e.g. the device frames are uploaded to.
+    pub fn input_args(self) -> Vec<&'static str> {
+        match self {
+            Encoder::Vaapi => vec!["-vaapi_device", VAAPI_DEVICE],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Filter appended to each output's video chain to hand frames to the encoder.
+    pub fn upload_filter(self, ten_bit: bool) -> Option<&'static str> {
+        match (self, ten_bit) {
+            (Encoder::Vaapi, true) => Some("format=p010,hwupload"),
+            (Encoder::Vaapi, false) => Some("format=nv12,hwupload"),
+            _ => None,
+        }
+    }
+
+    /// Pixel format to request with `-pix_fmt`; `None` when the upload filter sets it.
+    pub fn pixel_format(self, ten_bit: bool) -> Option<&'static str> {
+        match (self, ten_bit) {
+            (Encoder::VideoToolbox | Encoder::Software, true) => Some("yuv420p10le"),
+            (Encoder::VideoToolbox | Encoder::Software, false) => Some("yuv420p"),
+            (Encoder::Nvenc | Encoder::Qsv | Encoder::Amf, true) => Some("p010le"),
+            (Encoder::Nvenc | Encoder::Qsv | Encoder::Amf, false) => Some("nv12"),
+            (Encoder::Vaapi, _) => None,
+        }
+    }
+
+    /// Profile and speed options for one output.
+    pub fn quality_args(self, ten_bit: bool) -> Vec<&'static str> {
+        let profile = if ten_bit { "main10" } else { "main" };
+        match self {
+            Encoder::VideoToolbox => Vec::new(),
+            Encoder::Nvenc => vec!["-profile:v", profile, "-preset", "p5"],
+            Encoder::Qsv => vec!["-profile:v", profile, "-preset", "medium"],
+            Encoder::Vaapi => vec!["-profile:v", profile],
+            Encoder::Amf => vec!["-profile:v", profile, "-quality", "quality"],
+            Encoder::Software => vec!["-profile:v", profile, "-preset", "medium"],
+        }
+    }
+}
+
+/// The encoder to convert with: `preferred` when set, otherwise the best one that works here.
+pub fn select(preferred: Option<Encoder>) -> Encoder {
+    preferred.unwrap_or_else(|| *DETECTED.get_or_init(|| {
+        let encoder = detect();
+        logger::info(&format!("Using {} ({}) for HEVC encoding", encoder.description(), encoder.ffmpeg_name()));
+        encoder
+    }))
+}
+
+/// The first hardware encoder ffmpeg was built with that passes a test encode, or libx265.
+///
+/// Being listed in `ffmpeg -encoders` only means support was compiled in; the GPU, driver
+/// or device node may still be missing, so each candidate encodes a few frames first.
+pub fn detect() -> Encoder {
+    let listed = listed_encoders();
+    Encoder::HARDWARE.into_iter()
+        .filter(|encoder| listed.iter().any(|name| name == encoder.ffmpeg_name()))
+        .find(|encoder| test_encode(*encoder).is_some())
+        .unwrap_or(Encoder::Software)
+}
+
+/// Names of the HEVC encoders in `ffmpeg -encoders`.
+fn listed_encoders() -> Vec<String> {
+    let Ok(output) = Command::new(dependencies::ffmpeg()).args(["-hide_banner", "-encoders"]).output() else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .filter(|name| name.starts_with("hevc_") || *name == "libx265")
+        .map(String::from)
+        .collect()
+}
+
+/// Encode a second of test pattern with `encoder`, returning how long it took.
+fn test_encode(encoder: Encoder) -> Option<f64> {
+    let filter = match encoder.upload_filter(true) {
+        Some(upload) => format!("scale=1280:720,{}", upload),
+        None => "scale=1280:720".to_string(),
+    };
+    let mut command = Command::new(dependencies::ffmpeg());
+    command.args(["-hide_banner", "-v", "error"])
+        .args(encoder.input_args())
+        .args(["-f", "lavfi", "-i", "testsrc2=duration=1:size=1280x720:rate=30", "-vf", &filter])
+        .args(["-c:v", encoder.ffmpeg_name()]);
+    if let Some(pixel_format) = encoder.pixel_format(true) {
+        command.args(["-pix_fmt", pixel_format]);
+    }
+    command.args(encoder.quality_args(true)).args(["-f", "null", "-"]);
+
+    let start = Instant::now();
+    let output = command.output().ok()?;
+    output.status.success().then(|| start.elapsed().as_secs_f64())
+}
+
+/// List every HEVC encoder with whether it works here, for `check`.
+pub fn report() {
+    logger::header("HEVC Encoders");
+    let listed = listed_encoders();
+    let mut detected = None;
+    for encoder in Encoder::HARDWARE.into_iter().chain([Encoder::Software]) {
+        if !listed.iter().any(|name| name == encoder.ffmpeg_name()) {
+            logger::info(&format!("{} ({}) - not in this ffmpeg build", encoder.description(), encoder.ffmpeg_name()));
+            continue;
+        }
+        match test_encode(encoder) {
+            Some(seconds) => {
+                logger::success(&format!("{} ({}) - test encode at {:.0} fps", encoder.description(), encoder.ffmpeg_name(), 30.0 / seconds));
+                detected = detected.or(Some(encoder));
+            }
+            None => logger::warning(&format!("{} ({}) - listed but the test encode failed (no device or driver?)", encoder.description(), encoder.ffmpeg_name())),
+        }
+    }
+    logger::info(&format!("Auto-detected: {}; override with --encoder or [conversion] encoder", detected.unwrap_or(Encoder::Software).description()));
+}
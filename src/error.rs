@@ -0,0 +1,110 @@
+use crate::ytdlp_error::YtDlpError;
+
+/// Crate-wide error, so callers can match on what went wrong instead of parsing messages.
+#[derive(Debug, thiserror::Error)]
+pub enum DownloaderError {
+    /// yt-dlp failed; the inner error says why
+    #[error(transparent)]
+    YtDlp(#[from] YtDlpError),
+
+    /// ffmpeg or ffprobe failed or produced unusable output
+    #[error("{0}")]
+    Ffmpeg(String),
+
+    /// A file or directory could not be accessed
+    #[error("{0}")]
+    Permission(String),
+
+    /// The operation writes system directories and needs sudo
+    #[error("{0}")]
+    SudoRequired(String),
+
+    /// macOS privacy controls (TCC) blocked access; the terminal needs Full Disk Access
+    #[error("{0}")]
+    PrivacyDenied(String),
+
+    /// There is no installed system wallpaper to replace
+    #[error("{0}")]
+    NoWallpaper(String),
+
+    /// A required external tool is not installed or doesn't run
+    #[error("{0}")]
+    MissingDependency(String),
+
+    /// The video offers no format matching the configured preferences
+    #[error("{0}")]
+    NoFormat(String),
+
+    /// The download itself failed for a reason yt-dlp didn't classify
+    #[error("{0}")]
+    Download(String),
+
+    /// A network request failed
+    #[error("{0}")]
+    Network(String),
+
+    #[error("Invalid YouTube URL: {0}")]
+    InvalidUrl(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    /// The history database could not be read or written
+    #[cfg(feature = "sqlite")]
+    #[error("History database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    /// Anything without a more specific variant
+    #[error("{0}")]
+    Other(String),
+}
+
+pub type Result<T, E = DownloaderError> = std::result::Result<T, E>;
+
+impl DownloaderError {
+    /// The yt-dlp failure behind this error, if any.
+    pub fn ytdlp(&self) -> Option<&YtDlpError> {
+        match self {
+            DownloaderError::YtDlp(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// The video is private, deleted or region-blocked rather than failing to download.
+    pub fn is_unavailable(&self) -> bool {
+        matches!(self.ytdlp(), Some(YtDlpError::Private | YtDlpError::Unavailable | YtDlpError::Geoblocked))
+    }
+
+    /// Whether trying the same URL again won't help: the video is unavailable, or needs an
+    /// account this run doesn't have.
+    pub fn is_permanent(&self) -> bool {
+        self.is_unavailable() || matches!(self.ytdlp(), Some(YtDlpError::AgeRestricted))
+    }
+}
+
+impl From<String> for DownloaderError {
+    fn from(message: String) -> Self {
+        DownloaderError::Other(message)
+    }
+}
+
+impl From<&str> for DownloaderError {
+    fn from(message: &str) -> Self {
+        DownloaderError::Other(message.to_string())
+    }
+}
+
+impl From<std::time::SystemTimeError> for DownloaderError {
+    fn from(error: std::time::SystemTimeError) -> Self {
+        DownloaderError::Other(error.to_string())
+    }
+}
+
+impl From<tokio::task::JoinError> for DownloaderError {
+    fn from(error: tokio::task::JoinError) -> Self {
+        DownloaderError::Other(error.to_string())
+    }
+}
@@ -0,0 +1,200 @@
+use crate::error::DownloaderError;
+
+/// Stable identifiers for the failures users hit most, printed next to error messages
+/// and expanded by `rust-downloader explain <code>`.
+///
+/// Codes are grouped by phase: E01xx input and download, E02xx system and installation,
+/// E03xx external tools and conversion, E04xx network. Never renumber an existing code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    FormatNotFound,
+    VideoUnavailable,
+    InvalidUrl,
+    DownloadFailed,
+    BotCheck,
+    AgeRestricted,
+    SudoRequired,
+    PermissionDenied,
+    TccDenied,
+    WallpaperNotFound,
+    DependencyMissing,
+    ConversionFailed,
+    NetworkError,
+    RateLimited,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 14] = [
+        ErrorCode::FormatNotFound,
+        ErrorCode::VideoUnavailable,
+        ErrorCode::InvalidUrl,
+        ErrorCode::DownloadFailed,
+        ErrorCode::BotCheck,
+        ErrorCode::AgeRestricted,
+        ErrorCode::SudoRequired,
+        ErrorCode::PermissionDenied,
+        ErrorCode::TccDenied,
+        ErrorCode::WallpaperNotFound,
+        ErrorCode::DependencyMissing,
+        ErrorCode::ConversionFailed,
+        ErrorCode::NetworkError,
+        ErrorCode::RateLimited,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorCode::FormatNotFound => "E0101",
+            ErrorCode::VideoUnavailable => "E0102",
+            ErrorCode::InvalidUrl => "E0103",
+            ErrorCode::DownloadFailed => "E0104",
+            ErrorCode::BotCheck => "E0105",
+            ErrorCode::AgeRestricted => "E0106",
+            ErrorCode::SudoRequired => "E0201",
+            ErrorCode::PermissionDenied => "E0202",
+            ErrorCode::TccDenied => "E0203",
+            ErrorCode::WallpaperNotFound => "E0204",
+            ErrorCode::DependencyMissing => "E0301",
+            ErrorCode::ConversionFailed => "E0302",
+            ErrorCode::NetworkError => "E0401",
+            ErrorCode::RateLimited => "E0402",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorCode::FormatNotFound => "FormatNotFound",
+            ErrorCode::VideoUnavailable => "VideoUnavailable",
+            ErrorCode::InvalidUrl => "InvalidUrl",
+            ErrorCode::DownloadFailed => "DownloadFailed",
+            ErrorCode::BotCheck => "BotCheck",
+            ErrorCode::AgeRestricted => "AgeRestricted",
+            ErrorCode::SudoRequired => "SudoRequired",
+            ErrorCode::PermissionDenied => "PermissionDenied",
+            ErrorCode::TccDenied => "TccDenied",
+            ErrorCode::WallpaperNotFound => "WallpaperNotFound",
+            ErrorCode::DependencyMissing => "DependencyMissing",
+            ErrorCode::ConversionFailed => "ConversionFailed",
+            ErrorCode::NetworkError => "NetworkError",
+            ErrorCode::RateLimited => "RateLimited",
+        }
+    }
+
+    /// One-line hint printed under the error message.
+    pub fn hint(&self) -> &'static str {
+        match self {
+            ErrorCode::FormatNotFound => "No downloadable format matched the quality requirements",
+            ErrorCode::VideoUnavailable => "The video might be private, deleted, or region-locked",
+            ErrorCode::InvalidUrl => "The input is not a recognized YouTube URL",
+            ErrorCode::DownloadFailed => "Make sure yt-dlp is installed, up to date and accessible",
+            ErrorCode::BotCheck => "YouTube asked to confirm you're not a bot; a signed-in session is needed",
+            ErrorCode::AgeRestricted => "The video is age-restricted and needs a signed-in session",
+            ErrorCode::SudoRequired => "Wallpaper installation must be run with sudo",
+            ErrorCode::PermissionDenied => "A file or directory could not be read or written",
+            ErrorCode::TccDenied => "macOS privacy protection blocked access; grant Full Disk Access to your terminal",
+            ErrorCode::WallpaperNotFound => "No aerial wallpaper has been downloaded by macOS yet",
+            ErrorCode::DependencyMissing => "A required tool (yt-dlp, ffmpeg or ffprobe) is missing",
+            ErrorCode::ConversionFailed => "Make sure ffmpeg is installed and accessible",
+            ErrorCode::NetworkError => "Check your internet connection and try again",
+            ErrorCode::RateLimited => "YouTube is rate-limiting this IP; wait before retrying",
+        }
+    }
+
+    /// Extended remediation text for `explain`.
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            ErrorCode::FormatNotFound => "\
+yt-dlp returned no video (or audio) format that passed the selection filters.
+ • Check the video plays in a browser at the resolution you expect
+ • Update yt-dlp (rust-downloader update-deps); YouTube format changes often break old versions
+ • Very old or low-resolution uploads may not offer any usable format",
+            ErrorCode::VideoUnavailable => "\
+YouTube refused to serve the video.
+ • Private, deleted, members-only or age-restricted videos cannot be fetched anonymously
+ • Region-locked videos only work from an allowed country
+ • Open the URL in a browser to see YouTube's own message",
+            ErrorCode::InvalidUrl => "\
+The URL could not be parsed as a YouTube video link.
+ • Supported forms: youtube.com/watch?v=ID, youtu.be/ID, youtube.com/embed/ID, youtube.com/v/ID
+ • Quote the URL in your shell so '&' and '?' are not interpreted",
+            ErrorCode::DownloadFailed => "\
+yt-dlp exited with an error while downloading.
+ • Run 'rust-downloader check' to verify yt-dlp is installed
+ • Update yt-dlp: rust-downloader update-deps (or brew upgrade yt-dlp)
+ • Run 'rust-downloader report' and attach the bundle to an issue if it persists",
+            ErrorCode::BotCheck => "\
+YouTube served a \"Sign in to confirm you're not a bot\" page instead of the video.
+ • Sign in to YouTube in a browser on this Mac
+ • Run 'rust-downloader auth setup' to pick that browser (and optionally a PO token provider)
+ • Heavy use from one IP triggers this more often; slow down between downloads",
+            ErrorCode::AgeRestricted => "\
+The video is age-restricted and YouTube requires a signed-in, age-verified account.
+ • Sign in to YouTube in a browser with an account that can watch the video
+ • Pass --cookies-from-browser BROWSER (or --cookies FILE) for one run, or set cookies_from_browser under [download]
+ • Run 'rust-downloader auth setup' to use that browser's cookies for every run
+ • If cookies were already given, that account hasn't confirmed its age; verify it on youtube.com",
+            ErrorCode::SudoRequired => "\
+Replacing the system aerial video writes to /Library/Application Support/com.apple.idleassetsd.
+ • Re-run the same command with sudo
+ • Use --download-only (or the download subcommand) to skip installation",
+            ErrorCode::PermissionDenied => "\
+A file could not be read or written, usually after a previous run as root left files owned by root.
+ • Run 'cargo run --bin cleanup' to fix ownership and permissions in the output directory
+ • Check the output directory is writable, or choose another one with --output",
+            ErrorCode::TccDenied => "\
+macOS Transparency, Consent and Control (TCC) blocked access even for root.
+ • Open System Settings > Privacy & Security > Full Disk Access
+ • Enable your terminal app (Terminal, iTerm, ...), then restart it
+ • Focus-mode automation also needs this to read the DoNotDisturb database",
+            ErrorCode::WallpaperNotFound => "\
+The aerial video directory is empty or missing, so there is nothing to replace.
+ • Open System Settings > Wallpaper and pick any landscape aerial
+ • Wait for it to finish downloading, then run the installer again",
+            ErrorCode::DependencyMissing => "\
+One of the external tools this program drives is not on PATH.
+ • Install with: brew install yt-dlp ffmpeg
+ • Run 'rust-downloader check' to see which one is missing",
+            ErrorCode::ConversionFailed => "\
+ffmpeg could not convert the download to HEVC, even after falling back to software encoding.
+ • Run 'rust-downloader check' to verify ffmpeg is installed
+ • Make sure there is enough free disk space for a 4K intermediate file
+ • An encode that stops making progress is stopped after [conversion] stall_minutes
+ • The full ffmpeg output is included in the bundle from 'rust-downloader report'",
+            ErrorCode::NetworkError => "\
+The connection to YouTube failed or timed out.
+ • Check your internet connection and any VPN or proxy
+ • Retry later if YouTube is rate-limiting you (HTTP 429)
+ • Downloads that receive nothing for [download] stall_minutes are stopped and retried",
+            ErrorCode::RateLimited => "\
+YouTube answered HTTP 429 Too Many Requests for this IP address.
+ • Wait 30-60 minutes before retrying; immediate retries extend the block
+ • Signed-in sessions (browser cookies) are throttled less aggressively",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        let code = code.trim().to_uppercase();
+        Self::ALL.into_iter().find(|c| c.code() == code || c.name().eq_ignore_ascii_case(&code))
+    }
+
+    /// Code for an error, from its variant alone.
+    pub fn for_error(error: &DownloaderError) -> Option<Self> {
+        let code = match error {
+            DownloaderError::YtDlp(ytdlp_error) => ytdlp_error.error_code(),
+            DownloaderError::InvalidUrl(_) => ErrorCode::InvalidUrl,
+            DownloaderError::Network(_) => ErrorCode::NetworkError,
+            DownloaderError::Permission(_) => ErrorCode::PermissionDenied,
+            DownloaderError::SudoRequired(_) => ErrorCode::SudoRequired,
+            DownloaderError::PrivacyDenied(_) => ErrorCode::TccDenied,
+            DownloaderError::NoWallpaper(_) => ErrorCode::WallpaperNotFound,
+            DownloaderError::MissingDependency(_) => ErrorCode::DependencyMissing,
+            DownloaderError::NoFormat(_) => ErrorCode::FormatNotFound,
+            DownloaderError::Download(_) => ErrorCode::DownloadFailed,
+            DownloaderError::Ffmpeg(_) => ErrorCode::ConversionFailed,
+            // TCC refuses even root with EPERM, where plain permission problems are EACCES
+            DownloaderError::Io(io_error) if cfg!(target_os = "macos") && io_error.raw_os_error() == Some(1) => ErrorCode::TccDenied,
+            DownloaderError::Io(io_error) if io_error.kind() == std::io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+            _ => return None,
+        };
+        Some(code)
+    }
+}
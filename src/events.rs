@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+
+/// What a `Downloader` is doing, for a UI to show without parsing log lines. Every event
+/// carries the `job` id, which tells concurrent downloads apart.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    AnalysisStarted { job: String, url: String },
+    /// The format ids being fetched into `path`; until they are merged, what has arrived is
+    /// kept in `.part` files next to it (see `downloader::partial_downloads`)
+    DownloadStarted { job: String, path: PathBuf, formats: Vec<String> },
+    DownloadProgress {
+        job: String,
+        percent: f64,
+        /// Bytes per second, smoothed; `None` until enough has arrived to tell
+        speed: Option<f64>,
+        /// Seconds left for the current stream
+        eta: Option<f64>,
+    },
+    ConversionProgress {
+        job: String,
+        percent: f64,
+        /// Relative to real time, e.g. 0.8 for 0.8x
+        speed: Option<f64>,
+        eta: Option<f64>,
+    },
+    Completed {
+        job: String,
+        path: PathBuf,
+        /// Every file written when the download was split (chapters, scenes, extra resolutions)
+        outputs: Vec<PathBuf>,
+    },
+    Failed { job: String, error: String },
+}
+
+impl ProgressEvent {
+    pub fn job(&self) -> &str {
+        match self {
+            ProgressEvent::AnalysisStarted { job, .. }
+            | ProgressEvent::DownloadStarted { job, .. }
+            | ProgressEvent::DownloadProgress { job, .. }
+            | ProgressEvent::ConversionProgress { job, .. }
+            | ProgressEvent::Completed { job, .. }
+            | ProgressEvent::Failed { job, .. } => job,
+        }
+    }
+}
+
+/// Receives a `Downloader`'s events as they happen, on the thread doing the work, so it
+/// should return quickly; a channel `Sender` hands them to another thread.
+pub trait ProgressObserver: Send + Sync {
+    fn on_event(&self, event: &ProgressEvent);
+}
+
+impl<F: Fn(&ProgressEvent) + Send + Sync> ProgressObserver for F {
+    fn on_event(&self, event: &ProgressEvent) {
+        self(event)
+    }
+}
+
+impl ProgressObserver for Sender<ProgressEvent> {
+    fn on_event(&self, event: &ProgressEvent) {
+        // A receiver that went away just stops listening
+        self.send(event.clone()).ok();
+    }
+}
@@ -0,0 +1,109 @@
+use std::process::Command;
+use serde_json::Value;
+use crate::auth;
+use crate::dependencies;
+use crate::config::Config;
+use crate::error::{DownloaderError, Result};
+use crate::logger;
+use crate::ytdlp_error::YtDlpError;
+
+/// Gets the info of a video in the layout of `yt-dlp --dump-json`, which the rest of the
+/// pipeline reads, whatever the info came from.
+pub trait Extractor {
+    /// e.g. "Piped (pipedapi.kavin.rocks)"
+    fn name(&self) -> String;
+    fn extract(&self, url: &str) -> Result<Value>;
+
+    /// Whether the formats are plain HTTP URLs that are fetched with curl rather than by yt-dlp
+    fn direct(&self) -> bool {
+        false
+    }
+}
+
+/// yt-dlp asking YouTube, signed in with `auth_args`.
+pub struct YtDlp {
+    pub auth_args: Vec<String>,
+}
+
+impl Extractor for YtDlp {
+    fn name(&self) -> String {
+        "yt-dlp".to_string()
+    }
+
+    fn extract(&self, url: &str) -> Result<Value> {
+        let output = Command::new(dependencies::yt_dlp())
+            .args(["--dump-json", "--no-warnings"])
+            .args(&self.auth_args)
+            .arg(url)
+            .output()?;
+
+        if !output.status.success() {
+            return Err(YtDlpError::from_stderr(&String::from_utf8_lossy(&output.stderr)).into());
+        }
+        Ok(serde_json::from_slice(&output.stdout)?)
+    }
+}
+
+/// Info from a mirror instead of YouTube. yt-dlp downloads it with `--load-info-json`, from
+/// the mirror's stream URLs, so YouTube isn't asked again.
+#[derive(Debug, Clone)]
+pub struct Mirrored {
+    pub name: String,
+    pub info: Value,
+    /// The chosen format is fetched from its URL with curl instead, e.g. from YouTube's player
+    /// API when yt-dlp isn't installed
+    pub direct: bool,
+}
+
+/// Whether YouTube turned yt-dlp away, rather than the video being unavailable.
+fn is_blocked(error: &DownloaderError) -> bool {
+    matches!(error.ytdlp(), Some(YtDlpError::BotCheck | YtDlpError::RateLimited))
+}
+
+#[cfg(feature = "mirrors")]
+fn mirrors(config: &Config) -> Vec<Box<dyn Extractor>> {
+    config.mirrors.iter().map(crate::mirrors::extractor).collect()
+}
+
+#[cfg(not(feature = "mirrors"))]
+fn mirrors(_config: &Config) -> Vec<Box<dyn Extractor>> {
+    Vec::new()
+}
+
+/// The info of `url` from yt-dlp or, when YouTube blocks or throttles it, from the first of
+/// the configured mirrors that has it. If they all fail, yt-dlp's error is returned.
+///
+/// Builds with `native-extractor` ask YouTube's player API directly when yt-dlp isn't installed.
+pub fn extract(url: &str, session: &auth::Session, config: &Config) -> Result<(Value, Option<Mirrored>)> {
+    #[cfg(feature = "native-extractor")]
+    if !dependencies::yt_dlp_available() {
+        let native = crate::native::Native;
+        logger::warning(&format!("yt-dlp is not installed; asking the {} for progressive formats (720p at most)", native.name()));
+        let info = native.extract(url)?;
+        return Ok((info.clone(), Some(Mirrored { name: native.name(), info, direct: native.direct() })));
+    }
+
+    let error = match (YtDlp { auth_args: session.yt_dlp_args(config) }).extract(url) {
+        Ok(info) => return Ok((info, None)),
+        Err(error) if is_blocked(&error) && !config.mirrors.is_empty() => error,
+        Err(error) => return Err(error),
+    };
+
+    let mirrors = mirrors(config);
+    if mirrors.is_empty() {
+        logger::warning("[mirrors] is set, but this build can't use them; rebuild with --features mirrors");
+        return Err(error);
+    }
+
+    logger::warning(&format!("YouTube turned yt-dlp away ({}); trying {} mirror(s)", error, mirrors.len()));
+    for mirror in mirrors {
+        match mirror.extract(url) {
+            Ok(info) => {
+                logger::success(&format!("Got the video info from {}", mirror.name()));
+                return Ok((info.clone(), Some(Mirrored { name: mirror.name(), info, direct: mirror.direct() })));
+            }
+            Err(mirror_error) => logger::warning(&format!("{}: {}", mirror.name(), mirror_error)),
+        }
+    }
+    Err(error)
+}
@@ -0,0 +1,86 @@
+use crate::utils;
+
+/// One report from `ffmpeg -progress pipe:1`, which writes a block of `key=value` lines about
+/// twice a second and ends each with `progress=continue` (or `progress=end`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Progress {
+    pub frame: u64,
+    pub fps: f64,
+    /// Output bitrate so far in kbit/s; `None` until ffmpeg knows it
+    pub bitrate: Option<f64>,
+    /// Bytes written so far
+    pub total_size: u64,
+    /// Seconds of output written so far
+    pub out_seconds: f64,
+    /// Encoding speed relative to real time, e.g. 0.8 for 0.8x
+    pub speed: Option<f64>,
+    /// Set on the last report, once ffmpeg is done
+    pub end: bool,
+}
+
+impl Progress {
+    /// Share of `duration` seconds written so far, in percent.
+    pub fn percent(&self, duration: f64) -> f64 {
+        if duration <= 0.0 {
+            return 0.0;
+        }
+        (self.out_seconds / duration * 100.0).clamp(0.0, 100.0)
+    }
+
+    /// Seconds until `duration` is reached at the current speed.
+    pub fn eta(&self, duration: f64) -> Option<f64> {
+        self.speed.filter(|speed| *speed > 0.0).map(|speed| ((duration - self.out_seconds) / speed).max(0.0))
+    }
+
+    /// `48 fps · 45.2 Mbps · 0.80x · ETA 1m 05s`, leaving out what ffmpeg hasn't reported.
+    pub fn summary(&self, duration: f64) -> String {
+        let mut parts = Vec::new();
+        if self.fps > 0.0 {
+            parts.push(format!("{:.0} fps", self.fps));
+        }
+        if let Some(bitrate) = self.bitrate {
+            parts.push(format!("{:.1} Mbps", bitrate / 1000.0));
+        }
+        if let Some(speed) = self.speed {
+            parts.push(format!("{:.2}x", speed));
+        }
+        if let Some(eta) = self.eta(duration) {
+            parts.push(format!("ETA {}", utils::format_time(eta)));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// Collects `-progress` lines into reports.
+#[derive(Debug, Default)]
+pub struct ProgressParser {
+    current: Progress,
+}
+
+impl ProgressParser {
+    /// Read one line, returning the report it completes, if any.
+    pub fn feed(&mut self, line: &str) -> Option<Progress> {
+        let (key, value) = line.trim().split_once('=')?;
+        let value = value.trim();
+        let current = &mut self.current;
+        match key {
+            "frame" => current.frame = value.parse().unwrap_or(current.frame),
+            "fps" => current.fps = value.parse().unwrap_or(current.fps),
+            "bitrate" => current.bitrate = value.trim_end_matches("kbits/s").parse().ok(),
+            "total_size" => current.total_size = value.parse().unwrap_or(current.total_size),
+            // out_time_ms is also in microseconds, despite the name
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(micros) = value.parse::<f64>() {
+                    current.out_seconds = (micros / 1e6).max(0.0);
+                }
+            }
+            "speed" => current.speed = value.trim_end_matches('x').parse().ok(),
+            "progress" => {
+                current.end = value == "end";
+                return Some(current.clone());
+            }
+            _ => {}
+        }
+        None
+    }
+}
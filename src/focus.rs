@@ -0,0 +1,45 @@
+use std::fs;
+use std::path::PathBuf;
+use serde_json::Value;
+use crate::error::{DownloaderError, Result};
+
+/// Reads the active macOS Focus mode from the DoNotDisturb database.
+///
+/// Returns `Ok(None)` when no Focus is active. Reading the database requires
+/// Full Disk Access for the terminal running this tool.
+pub fn current_focus_mode() -> Result<Option<String>> {
+    let db_dir = focus_db_dir().ok_or("Could not determine home directory")?;
+
+    let assertions: Value = serde_json::from_str(&fs::read_to_string(db_dir.join("Assertions.json"))
+        .map_err(|e| DownloaderError::PrivacyDenied(format!("Cannot read Focus state ({}). Grant Full Disk Access to your terminal.", e)))?)?;
+
+    let mode_id = assertions.pointer("/data/0/storeAssertionRecords")
+        .and_then(|records| records.as_array())
+        .and_then(|records| records.last())
+        .and_then(|record| record.pointer("/assertionDetails/assertionDetailsModeIdentifier"))
+        .and_then(|id| id.as_str());
+
+    let mode_id = match mode_id {
+        Some(id) => id.to_string(),
+        None => return Ok(None),
+    };
+
+    // Map the mode identifier to the user-visible name ("Work", "Sleep", ...)
+    let name = fs::read_to_string(db_dir.join("ModeConfigurations.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|configs| {
+            configs.pointer("/data/0/modeConfigurations")
+                .and_then(|modes| modes.get(&mode_id))
+                .and_then(|mode| mode.pointer("/mode/name"))
+                .and_then(|name| name.as_str())
+                .map(|name| name.to_string())
+        });
+
+    Ok(Some(name.unwrap_or(mode_id)))
+}
+
+fn focus_db_dir() -> Option<PathBuf> {
+    let home = std::env::var("HOME").ok()?;
+    Some(PathBuf::from(home).join("Library/DoNotDisturb/DB"))
+}
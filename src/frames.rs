@@ -0,0 +1,81 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::config::Config;
+use crate::error::{DownloaderError, Result};
+use crate::library;
+use crate::dependencies;
+use crate::logger;
+use crate::utils;
+
+/// More stills than this is almost always a mistake in `--every`
+const MAX_FRAMES: usize = 2000;
+
+/// Where stills are taken.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Spacing {
+    /// One still every this many seconds, starting at the first frame
+    Every(f64),
+    /// This many stills spread evenly, each in the middle of its share of the video
+    Count(u32),
+}
+
+/// Seconds into a `duration`-second video to take stills at.
+pub fn timestamps(duration: f64, spacing: Spacing) -> Vec<f64> {
+    match spacing {
+        Spacing::Every(seconds) => (0..)
+            .map(|i| i as f64 * seconds)
+            .take_while(|t| *t < duration)
+            .take(MAX_FRAMES)
+            .collect(),
+        Spacing::Count(count) => (0..count)
+            .map(|i| (i as f64 + 0.5) * duration / count as f64)
+            .collect(),
+    }
+}
+
+/// Save stills of `video` as full-resolution JPEGs into `<title>_frames` in the output
+/// directory, named like downloads with the position in place of the quality,
+/// e.g. `Ocean_Waves_frame003_1m05s.jpg`.
+pub fn extract(video: &Path, title: &str, spacing: Spacing, config: &Config) -> Result<PathBuf> {
+    logger::header("Frame Extraction");
+
+    let duration = library::probe_duration(video)
+        .ok_or_else(|| DownloaderError::Ffmpeg(format!("Could not read the duration of {}", video.display())))?;
+    let times = timestamps(duration, spacing);
+    if times.is_empty() {
+        return Err("No frames to extract".into());
+    }
+    if matches!(spacing, Spacing::Every(_)) && times.len() == MAX_FRAMES {
+        logger::warning(&format!("Stopping at {} frames; use a longer --every", MAX_FRAMES));
+    }
+
+    let dir = config.output_dir.join(format!("{}_frames", utils::clean_title(title, &config.file_naming)));
+    fs::create_dir_all(&dir)?;
+    logger::info(&format!("Extracting {} frames from {} ({})", times.len(), video.display(), utils::format_time(duration)));
+
+    for (index, time) in times.iter().enumerate() {
+        let position = format!("frame{:03}_{}", index + 1, utils::format_time(*time).replace(' ', ""));
+        let path = dir.join(utils::create_safe_filename(title, &position, "jpg", &config.file_naming));
+
+        // Seeking before the input jumps straight to the nearest keyframe and decodes from
+        // there, so each still costs the same however far into the video it is
+        let output = Command::new(dependencies::ffmpeg())
+            .args(["-y", "-v", "error", "-ss", &format!("{:.3}", time), "-i"])
+            .arg(video)
+            .args(["-frames:v", "1", "-q:v", "2", "-update", "1"])
+            .arg(&path)
+            .output()?;
+        if !output.status.success() || !path.exists() {
+            logger::finish_job_progress();
+            return Err(DownloaderError::Ffmpeg(format!("Failed to extract the frame at {}: {}", utils::format_time(*time), String::from_utf8_lossy(&output.stderr).trim())));
+        }
+
+        let percentage = (index + 1) as f64 / times.len() as f64 * 100.0;
+        logger::job_progress("frames", &format!("{} {}/{}", utils::create_progress_bar(percentage, 20), index + 1, times.len()));
+    }
+    logger::finish_job_progress();
+
+    logger::success(&format!("{} frames saved to {}", times.len(), dir.display()));
+    Ok(dir)
+}
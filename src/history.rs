@@ -0,0 +1,630 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::error::{DownloaderError, Result};
+use crate::config::{Config, HistoryBackend};
+use crate::logger;
+use crate::loudness::Measurement;
+use crate::utils;
+use crate::video_info::VideoInfo;
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+#[cfg(feature = "sqlite")]
+const SQLITE_FILE: &str = "history.db";
+const JSON_FILE: &str = "history.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadStatus {
+    Completed,
+    Failed,
+    /// Private, deleted or region-blocked
+    Unavailable,
+}
+
+/// One finished (or failed) download.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub video_id: Option<String>,
+    pub url: String,
+    pub title: Option<String>,
+    #[serde(default)]
+    pub uploader: Option<String>,
+    #[serde(default)]
+    pub license: Option<String>,
+    pub path: Option<PathBuf>,
+    pub status: DownloadStatus,
+    pub error: Option<String>,
+    pub bytes: Option<u64>,
+    /// Wall time from analysis to the finished file
+    pub seconds: f64,
+    /// Part of `seconds` spent converting
+    #[serde(default)]
+    pub conversion_seconds: Option<f64>,
+    /// `remux`, or `re-encode` and why
+    #[serde(default)]
+    pub conversion_path: Option<String>,
+    /// Integrated loudness in LUFS, measured for `--audio-only` downloads
+    #[serde(default)]
+    pub loudness: Option<f64>,
+    /// True peak in dBTP, alongside `loudness`
+    #[serde(default)]
+    pub true_peak: Option<f64>,
+    /// Where the video came from when not `url`: a `--mirror` URL or a Piped/Invidious instance
+    #[serde(default)]
+    pub source: Option<String>,
+    /// SHA-256 of `path` when it was finished, checked again by `verify`
+    #[serde(default)]
+    pub sha256: Option<String>,
+    pub finished_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HistoryStats {
+    pub completed: u64,
+    pub failed: u64,
+    pub unavailable: u64,
+    pub total_bytes: u64,
+    pub total_seconds: f64,
+}
+
+impl HistoryStats {
+    fn from_entries(entries: &[HistoryEntry]) -> Self {
+        let mut stats = Self::default();
+        for entry in entries {
+            match entry.status {
+                DownloadStatus::Completed => {
+                    stats.completed += 1;
+                    stats.total_bytes += entry.bytes.unwrap_or(0);
+                }
+                DownloadStatus::Failed => stats.failed += 1,
+                DownloadStatus::Unavailable => stats.unavailable += 1,
+            }
+            stats.total_seconds += entry.seconds;
+        }
+        stats
+    }
+}
+
+/// Where download history is kept. Implementations must be safe to use from several
+/// processes at once (CLI, queued jobs, daemon).
+pub trait Store: Send + Sync {
+    fn record(&self, entry: &HistoryEntry) -> Result<()>;
+
+    /// Most recent entries first.
+    fn recent(&self, limit: usize) -> Result<Vec<HistoryEntry>>;
+
+    /// Entries finished at or after `since`, oldest first.
+    fn since(&self, since: DateTime<Utc>) -> Result<Vec<HistoryEntry>>;
+
+    /// The latest completed download that produced `path`.
+    fn for_path(&self, path: &Path) -> Result<Option<HistoryEntry>>;
+
+    /// Ids of every video downloaded successfully at least once.
+    fn downloaded_ids(&self) -> Result<HashSet<String>>;
+
+    /// Ids of videos whose latest attempt found them private, deleted or region-blocked.
+    fn unavailable_ids(&self) -> Result<HashSet<String>>;
+
+    fn stats(&self) -> Result<HistoryStats>;
+}
+
+/// Download history in the output directory, backed by the store chosen in the config.
+/// Clones share the same store.
+#[derive(Clone)]
+pub struct History {
+    store: Arc<dyn Store>,
+}
+
+impl History {
+    pub fn new(store: impl Store + 'static) -> Self {
+        Self { store: Arc::new(store) }
+    }
+
+    pub fn load(config: &Config) -> Result<Self> {
+        match config.history_backend {
+            #[cfg(feature = "sqlite")]
+            HistoryBackend::Sqlite => Ok(Self::new(SqliteStore::open(&config.output_dir.join(SQLITE_FILE))?)),
+            #[cfg(not(feature = "sqlite"))]
+            HistoryBackend::Sqlite => {
+                logger::warning("Built without SQLite support; keeping history in history.json");
+                Ok(Self::new(JsonStore::new(&config.output_dir.join(JSON_FILE))))
+            }
+            HistoryBackend::Json => Ok(Self::new(JsonStore::new(&config.output_dir.join(JSON_FILE)))),
+        }
+    }
+
+    pub fn record(&self, entry: &HistoryEntry) -> Result<()> {
+        self.store.record(entry)
+    }
+
+    pub fn recent(&self, limit: usize) -> Result<Vec<HistoryEntry>> {
+        self.store.recent(limit)
+    }
+
+    pub fn since(&self, since: DateTime<Utc>) -> Result<Vec<HistoryEntry>> {
+        self.store.since(since)
+    }
+
+    pub fn for_path(&self, path: &Path) -> Result<Option<HistoryEntry>> {
+        self.store.for_path(path)
+    }
+
+    pub fn downloaded_ids(&self) -> Result<HashSet<String>> {
+        self.store.downloaded_ids()
+    }
+
+    pub fn unavailable_ids(&self) -> Result<HashSet<String>> {
+        self.store.unavailable_ids()
+    }
+
+    pub fn stats(&self) -> Result<HistoryStats> {
+        self.store.stats()
+    }
+
+    pub fn display(&self, limit: usize) -> Result<()> {
+        logger::header("Download History");
+
+        let entries = self.recent(limit)?;
+        if entries.is_empty() {
+            logger::info("No downloads recorded yet");
+            return Ok(());
+        }
+
+        for entry in &entries {
+            let when = entry.finished_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+            let name = entry.title.as_deref().unwrap_or(&entry.url);
+            match entry.status {
+                DownloadStatus::Completed => {
+                    let loudness = entry.loudness.map(|lufs| format!(", {:.1} LUFS", lufs)).unwrap_or_default();
+                    let source = entry.source.as_ref().map(|source| format!(", from {}", source)).unwrap_or_default();
+                    logger::success(&format!("{}  {}  ({}{}{})", when, name, crate::utils::format_file_size(entry.bytes), loudness, source))
+                }
+                DownloadStatus::Unavailable => logger::warning(&format!("{}  {}  (unavailable)", when, name)),
+                DownloadStatus::Failed => logger::error(&format!("{}  {}  ({})", when, name, entry.error.as_deref().unwrap_or("failed"))),
+            }
+        }
+
+        let stats = self.stats()?;
+        logger::stats(&format!(
+            "{} completed, {} failed, {} unavailable | {} downloaded in {}",
+            stats.completed,
+            stats.failed,
+            stats.unavailable,
+            crate::utils::format_file_size(Some(stats.total_bytes)),
+            crate::utils::format_time(stats.total_seconds),
+        ));
+        Ok(())
+    }
+}
+
+/// History as a plain JSON array, for minimal builds without the SQLite dependency.
+///
+/// Writers take an exclusive lock on `<file>.lock` and replace the file atomically,
+/// so concurrent writers don't lose entries and readers never see a partial file.
+pub struct JsonStore {
+    path: PathBuf,
+}
+
+impl JsonStore {
+    pub fn new(path: &Path) -> Self {
+        Self { path: path.to_path_buf() }
+    }
+
+    fn read(&self) -> Result<Vec<HistoryEntry>> {
+        utils::read_json(&self.path)
+    }
+}
+
+impl Store for JsonStore {
+    fn record(&self, entry: &HistoryEntry) -> Result<()> {
+        utils::update_json(&self.path, |entries: &mut Vec<HistoryEntry>| entries.push(entry.clone()))
+    }
+
+    fn recent(&self, limit: usize) -> Result<Vec<HistoryEntry>> {
+        // Entries are appended, so the newest are at the end
+        let mut entries = self.read()?;
+        entries.reverse();
+        entries.truncate(limit);
+        Ok(entries)
+    }
+
+    fn since(&self, since: DateTime<Utc>) -> Result<Vec<HistoryEntry>> {
+        let mut entries = self.read()?;
+        entries.retain(|entry| entry.finished_at >= since);
+        Ok(entries)
+    }
+
+    fn for_path(&self, path: &Path) -> Result<Option<HistoryEntry>> {
+        Ok(self.read()?.into_iter().rev().find(|entry| {
+            entry.status == DownloadStatus::Completed && entry.path.as_deref() == Some(path)
+        }))
+    }
+
+    fn downloaded_ids(&self) -> Result<HashSet<String>> {
+        Ok(self.read()?.into_iter()
+            .filter(|entry| entry.status == DownloadStatus::Completed)
+            .filter_map(|entry| entry.video_id)
+            .collect())
+    }
+
+    fn unavailable_ids(&self) -> Result<HashSet<String>> {
+        // Entries are appended in order, so the last one per video wins
+        let latest: HashMap<String, DownloadStatus> = self.read()?.into_iter()
+            .filter_map(|entry| Some((entry.video_id?, entry.status)))
+            .collect();
+        Ok(latest.into_iter()
+            .filter(|(_, status)| *status == DownloadStatus::Unavailable)
+            .map(|(id, _)| id)
+            .collect())
+    }
+
+    fn stats(&self) -> Result<HistoryStats> {
+        Ok(HistoryStats::from_entries(&self.read()?))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::collections::HashSet;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use chrono::{DateTime, Utc};
+    use rusqlite::{params, Connection, OpenFlags, OptionalExtension, TransactionBehavior};
+    use crate::error::Result;
+    use super::{DownloadStatus, HistoryEntry, HistoryStats, Store};
+
+    const POOL_SIZE: usize = 4;
+    /// How long a connection waits for another writer before failing with "database is locked"
+    const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+    const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS downloads (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    video_id    TEXT,
+    url         TEXT NOT NULL,
+    title       TEXT,
+    path        TEXT,
+    status      TEXT NOT NULL,
+    error       TEXT,
+    bytes       INTEGER,
+    seconds     REAL NOT NULL,
+    finished_at TEXT NOT NULL,
+    uploader    TEXT,
+    conversion_seconds REAL,
+    license     TEXT,
+    loudness    REAL,
+    true_peak   REAL,
+    source      TEXT,
+    sha256      TEXT,
+    conversion_path TEXT
+);
+CREATE INDEX IF NOT EXISTS downloads_finished_at ON downloads (finished_at);
+CREATE INDEX IF NOT EXISTS downloads_video_id ON downloads (video_id);
+";
+
+    /// Columns added after the first release, created on databases that predate them
+    const ADDED_COLUMNS: [(&str, &str); 8] = [
+        ("uploader", "TEXT"), ("conversion_seconds", "REAL"), ("license", "TEXT"), ("loudness", "REAL"), ("true_peak", "REAL"),
+        ("source", "TEXT"), ("sha256", "TEXT"), ("conversion_path", "TEXT"),
+    ];
+
+    const COLUMNS: &str = "video_id, url, title, path, status, error, bytes, seconds, finished_at, uploader, conversion_seconds, license, loudness, true_peak, source, sha256, conversion_path";
+
+    impl DownloadStatus {
+        fn as_str(self) -> &'static str {
+            match self {
+                DownloadStatus::Completed => "completed",
+                DownloadStatus::Failed => "failed",
+                DownloadStatus::Unavailable => "unavailable",
+            }
+        }
+
+        fn parse(value: &str) -> Self {
+            match value {
+                "completed" => DownloadStatus::Completed,
+                "unavailable" => DownloadStatus::Unavailable,
+                _ => DownloadStatus::Failed,
+            }
+        }
+    }
+
+    /// History in SQLite, queryable with any SQLite client.
+    ///
+    /// The database runs in WAL mode so the CLI, queued jobs and the daemon can read while
+    /// another process writes. Writes take the lock up front (`BEGIN IMMEDIATE`) and wait up
+    /// to `BUSY_TIMEOUT` for it, instead of failing when two writers meet.
+    /// Clones share the same connection pool.
+    #[derive(Debug, Clone)]
+    pub struct SqliteStore {
+        pool: Arc<Pool>,
+    }
+
+    #[derive(Debug)]
+    struct Pool {
+        path: PathBuf,
+        idle: Mutex<Vec<Connection>>,
+    }
+
+    /// A connection borrowed from the pool; returned to it on drop.
+    struct PooledConnection<'a> {
+        pool: &'a Pool,
+        connection: Option<Connection>,
+    }
+
+    impl std::ops::Deref for PooledConnection<'_> {
+        type Target = Connection;
+
+        fn deref(&self) -> &Connection {
+            self.connection.as_ref().expect("connection taken")
+        }
+    }
+
+    impl std::ops::DerefMut for PooledConnection<'_> {
+        fn deref_mut(&mut self) -> &mut Connection {
+            self.connection.as_mut().expect("connection taken")
+        }
+    }
+
+    impl Drop for PooledConnection<'_> {
+        fn drop(&mut self) {
+            if let Some(connection) = self.connection.take() {
+                let mut idle = self.pool.idle.lock().unwrap_or_else(|e| e.into_inner());
+                if idle.len() < POOL_SIZE {
+                    idle.push(connection);
+                }
+            }
+        }
+    }
+
+    impl Pool {
+        fn get(&self) -> Result<PooledConnection<'_>> {
+            let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+            let connection = match idle {
+                Some(connection) => connection,
+                None => open_connection(&self.path)?,
+            };
+            Ok(PooledConnection { pool: self, connection: Some(connection) })
+        }
+    }
+
+    fn open_connection(path: &Path) -> Result<Connection> {
+        let connection = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.pragma_update(None, "synchronous", "NORMAL")?;
+        Ok(connection)
+    }
+
+    /// Read a row selected with `COLUMNS`.
+    fn entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<HistoryEntry> {
+        let finished_at: String = row.get(8)?;
+        Ok(HistoryEntry {
+            video_id: row.get(0)?,
+            url: row.get(1)?,
+            title: row.get(2)?,
+            uploader: row.get(9)?,
+            license: row.get(11)?,
+            path: row.get::<_, Option<String>>(3)?.map(PathBuf::from),
+            status: DownloadStatus::parse(&row.get::<_, String>(4)?),
+            error: row.get(5)?,
+            bytes: row.get::<_, Option<i64>>(6)?.map(|b| b as u64),
+            seconds: row.get(7)?,
+            conversion_seconds: row.get(10)?,
+            loudness: row.get(12)?,
+            true_peak: row.get(13)?,
+            source: row.get(14)?,
+            sha256: row.get(15)?,
+            conversion_path: row.get(16)?,
+            finished_at: DateTime::parse_from_rfc3339(&finished_at)
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_default(),
+        })
+    }
+
+    impl SqliteStore {
+        /// Open (creating if needed) the database at `path`.
+        pub fn open(path: &Path) -> Result<Self> {
+            let pool = Pool { path: path.to_path_buf(), idle: Mutex::new(Vec::new()) };
+            {
+                let mut connection = pool.get()?;
+                let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+                transaction.execute_batch(SCHEMA)?;
+                for (column, kind) in ADDED_COLUMNS {
+                    let exists: bool = transaction.query_row(
+                        "SELECT COUNT(*) > 0 FROM pragma_table_info('downloads') WHERE name = ?1",
+                        params![column],
+                        |row| row.get(0),
+                    )?;
+                    if !exists {
+                        transaction.execute_batch(&format!("ALTER TABLE downloads ADD COLUMN {} {}", column, kind))?;
+                    }
+                }
+                transaction.commit()?;
+            }
+            Ok(Self { pool: Arc::new(pool) })
+        }
+    }
+
+    impl Store for SqliteStore {
+        fn record(&self, entry: &HistoryEntry) -> Result<()> {
+            let mut connection = self.pool.get()?;
+            let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            transaction.execute(
+                &format!("INSERT INTO downloads ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)", COLUMNS),
+                params![
+                    entry.video_id,
+                    entry.url,
+                    entry.title,
+                    entry.path.as_ref().map(|p| p.to_string_lossy().to_string()),
+                    entry.status.as_str(),
+                    entry.error,
+                    entry.bytes.map(|b| b as i64),
+                    entry.seconds,
+                    entry.finished_at.to_rfc3339(),
+                    entry.uploader,
+                    entry.conversion_seconds,
+                    entry.license,
+                    entry.loudness,
+                    entry.true_peak,
+                    entry.source,
+                    entry.sha256,
+                    entry.conversion_path,
+                ],
+            )?;
+            transaction.commit()?;
+            Ok(())
+        }
+
+        fn recent(&self, limit: usize) -> Result<Vec<HistoryEntry>> {
+            let connection = self.pool.get()?;
+            let mut statement = connection.prepare(&format!(
+                "SELECT {} FROM downloads ORDER BY finished_at DESC, id DESC LIMIT ?1", COLUMNS
+            ))?;
+            let entries = statement.query_map(params![limit as i64], entry_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(entries)
+        }
+
+        fn since(&self, since: DateTime<Utc>) -> Result<Vec<HistoryEntry>> {
+            let connection = self.pool.get()?;
+            let mut statement = connection.prepare(&format!(
+                "SELECT {} FROM downloads WHERE finished_at >= ?1 ORDER BY finished_at, id", COLUMNS
+            ))?;
+            let entries = statement.query_map(params![since.to_rfc3339()], entry_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(entries)
+        }
+
+        fn for_path(&self, path: &Path) -> Result<Option<HistoryEntry>> {
+            let connection = self.pool.get()?;
+            let entry = connection.query_row(
+                &format!(
+                    "SELECT {} FROM downloads WHERE path = ?1 AND status = 'completed' ORDER BY finished_at DESC, id DESC LIMIT 1",
+                    COLUMNS
+                ),
+                params![path.to_string_lossy()],
+                entry_from_row,
+            ).optional()?;
+            Ok(entry)
+        }
+
+        fn downloaded_ids(&self) -> Result<HashSet<String>> {
+            let connection = self.pool.get()?;
+            let mut statement = connection.prepare(
+                "SELECT DISTINCT video_id FROM downloads WHERE status = 'completed' AND video_id IS NOT NULL"
+            )?;
+            let ids = statement.query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<HashSet<String>>>()?;
+            Ok(ids)
+        }
+
+        fn unavailable_ids(&self) -> Result<HashSet<String>> {
+            let connection = self.pool.get()?;
+            let mut statement = connection.prepare(
+                "SELECT video_id FROM downloads AS d WHERE status = 'unavailable' AND video_id IS NOT NULL
+                    AND id = (SELECT MAX(id) FROM downloads WHERE video_id = d.video_id)"
+            )?;
+            let ids = statement.query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<HashSet<String>>>()?;
+            Ok(ids)
+        }
+
+        fn stats(&self) -> Result<HistoryStats> {
+            let connection = self.pool.get()?;
+            let stats = connection.query_row(
+                "SELECT
+                    COALESCE(SUM(status = 'completed'), 0),
+                    COALESCE(SUM(status = 'failed'), 0),
+                    COALESCE(SUM(status = 'unavailable'), 0),
+                    COALESCE(SUM(CASE WHEN status = 'completed' THEN bytes END), 0),
+                    COALESCE(SUM(seconds), 0.0)
+                 FROM downloads",
+                [],
+                |row| Ok(HistoryStats {
+                    completed: row.get::<_, i64>(0)? as u64,
+                    failed: row.get::<_, i64>(1)? as u64,
+                    unavailable: row.get::<_, i64>(2)? as u64,
+                    total_bytes: row.get::<_, i64>(3)? as u64,
+                    total_seconds: row.get(4)?,
+                }),
+            )?;
+            Ok(stats)
+        }
+    }
+}
+
+/// What converting a download measured, for its history entry.
+#[derive(Debug, Clone, Default)]
+pub struct Conversion {
+    pub seconds: Option<f64>,
+    /// `remux`, or `re-encode` and why
+    pub path: Option<String>,
+    pub loudness: Option<Measurement>,
+}
+
+/// Record how a download ended, with a checksum of its first file. History that can't be
+/// written only produces a warning.
+pub fn record_outcome(
+    history: &History,
+    url: &str,
+    info: Option<&VideoInfo>,
+    conversion: &Conversion,
+    outcome: std::result::Result<&[PathBuf], &DownloaderError>,
+    elapsed: Duration,
+) {
+    let (status, path, error) = match outcome {
+        Ok(paths) => (DownloadStatus::Completed, paths.first().cloned(), None),
+        Err(error) if error.is_unavailable() => (DownloadStatus::Unavailable, None, Some(error.to_string())),
+        Err(error) => (DownloadStatus::Failed, None, Some(error.to_string())),
+    };
+    let bytes = match outcome {
+        Ok(paths) => Some(paths.iter().filter_map(|p| std::fs::metadata(p).ok()).map(|m| m.len()).sum()),
+        Err(_) => None,
+    };
+    let sha256 = path.as_deref().and_then(|path| crate::update::sha256_file(path).ok());
+
+    let entry = HistoryEntry {
+        video_id: crate::utils::extract_video_id(url),
+        url: url.to_string(),
+        title: info.map(|info| info.title.clone()),
+        uploader: info.and_then(|info| info.uploader.clone()),
+        license: info.and_then(|info| info.license.clone()),
+        path,
+        status,
+        error,
+        bytes,
+        seconds: elapsed.as_secs_f64(),
+        conversion_seconds: conversion.seconds,
+        conversion_path: conversion.path.clone(),
+        loudness: conversion.loudness.as_ref().map(|m| m.integrated),
+        true_peak: conversion.loudness.as_ref().map(|m| m.true_peak),
+        source: info.and_then(|info| source(url, info)),
+        sha256,
+        finished_at: Utc::now(),
+    };
+
+    if let Err(error) = history.record(&entry) {
+        logger::warning(&format!("Could not update download history: {}", error));
+    }
+}
+
+/// The instance or `--mirror` URL the video came from, when not `url` itself. Another link
+/// to the same YouTube video, or its bare id, doesn't count.
+fn source(url: &str, info: &VideoInfo) -> Option<String> {
+    if let Some(mirror) = &info.mirror {
+        return Some(mirror.name.clone());
+    }
+    let url = url.trim();
+    let same_video = info.id.as_deref().is_some_and(|id| id == url || Some(id) == crate::utils::extract_video_id(url).as_deref());
+    (info.url != url && !same_video).then(|| info.url.clone())
+}
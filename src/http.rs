@@ -0,0 +1,318 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+#[cfg(feature = "native-extractor")]
+use std::time::Duration;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use crate::cancel::CancelHandle;
+use crate::error::{DownloaderError, Result};
+
+const DEFAULT_TIMEOUT_SECS: u32 = 20;
+
+/// `rust-downloader/<version>`, sent with every request unless one sets its own.
+pub fn user_agent() -> String {
+    format!("rust-downloader/{}", env!("CARGO_PKG_VERSION"))
+}
+
+/// A request to an API, sent in-process with `native-extractor` and with curl otherwise. Only
+/// failing to get an answer is an error; what the status means is up to the caller
+/// (`Response::error_for_status` for the common case).
+#[derive(Debug, Clone)]
+pub struct Request {
+    url: String,
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+    timeout_secs: u32,
+}
+
+/// What a server answered.
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub body: Vec<u8>,
+    host: String,
+}
+
+impl Request {
+    pub fn get(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            query: Vec::new(),
+            headers: vec![("User-Agent".to_string(), user_agent())],
+            body: None,
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+        }
+    }
+
+    pub fn post_json(url: &str, body: &Value) -> Self {
+        let mut request = Self::get(url).header("Content-Type", "application/json");
+        request.body = Some(body.to_string());
+        request
+    }
+
+    /// Add `name=value` to the query string, percent-encoded.
+    pub fn query(mut self, name: &str, value: &str) -> Self {
+        self.query.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Set a header, replacing one of the same name.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn timeout(mut self, seconds: u32) -> Self {
+        self.timeout_secs = seconds;
+        self
+    }
+
+    /// The URL with the query appended.
+    pub fn full_url(&self) -> String {
+        if self.query.is_empty() {
+            return self.url.clone();
+        }
+        let query: Vec<String> = self.query.iter()
+            .map(|(name, value)| format!("{}={}", encode(name), encode(value)))
+            .collect();
+        let separator = if self.url.contains('?') { '&' } else { '?' };
+        format!("{}{}{}", self.url, separator, query.join("&"))
+    }
+
+    pub fn send(&self) -> Result<Response> {
+        let url = self.full_url();
+        #[cfg(feature = "native-extractor")]
+        let (status, body) = self.send_in_process(&url)?;
+        #[cfg(not(feature = "native-extractor"))]
+        let (status, body) = self.send_with_curl(&url)?;
+        Ok(Response { status, body, host: host(&url).to_string() })
+    }
+
+    /// reqwest's blocking client can't run on a runtime thread, and requests are made from
+    /// both kinds, so each one gets a thread of its own.
+    #[cfg(feature = "native-extractor")]
+    fn send_in_process(&self, url: &str) -> Result<(u16, Vec<u8>)> {
+        let result = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let client = reqwest::blocking::Client::builder()
+                    .timeout(Duration::from_secs(self.timeout_secs.into()))
+                    .build()?;
+                let mut request = match &self.body {
+                    Some(body) => client.post(url).body(body.clone()),
+                    None => client.get(url),
+                };
+                for (name, value) in &self.headers {
+                    request = request.header(name, value);
+                }
+                let response = request.send()?;
+                Ok::<_, reqwest::Error>((response.status().as_u16(), response.bytes()?.to_vec()))
+            }).join()
+        });
+        match result {
+            Ok(Ok(answer)) => Ok(answer),
+            Ok(Err(error)) => Err(DownloaderError::Network(format!("Could not reach {}: {}", host(url), error))),
+            Err(_) => Err(DownloaderError::Network(format!("The request to {} panicked", host(url)))),
+        }
+    }
+
+    #[cfg(not(feature = "native-extractor"))]
+    fn send_with_curl(&self, url: &str) -> Result<(u16, Vec<u8>)> {
+        let mut command = curl();
+        command.args(["-L", "--max-time", &self.timeout_secs.to_string(), "-w", "\n%{http_code}"]);
+        for (name, value) in &self.headers {
+            command.arg("-H").arg(format!("{}: {}", name, value));
+        }
+        if let Some(body) = &self.body {
+            command.args(["-X", "POST", "--data-binary", body]);
+        }
+        let output = command.arg(url).output()?;
+        if !output.status.success() {
+            return Err(DownloaderError::Network(format!("Could not reach {}: {}", host(url), String::from_utf8_lossy(&output.stderr).trim())));
+        }
+
+        // The status follows the body on a line of its own
+        let mut body = output.stdout;
+        let split = body.iter().rposition(|&byte| byte == b'\n').unwrap_or(0);
+        let status = String::from_utf8_lossy(&body[split..]).trim().parse().unwrap_or(0);
+        body.truncate(split);
+        Ok((status, body))
+    }
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// The response itself when the status is 2xx, a network error naming the status otherwise.
+    pub fn error_for_status(self) -> Result<Self> {
+        if self.is_success() {
+            Ok(self)
+        } else {
+            Err(DownloaderError::Network(format!("{} answered HTTP {}", self.host, self.status)))
+        }
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+}
+
+/// GET `url` and parse the answer, which has to come with a 2xx status.
+pub fn get_json<T: DeserializeOwned>(url: &str, timeout_secs: u32) -> Result<T> {
+    Request::get(url).timeout(timeout_secs).send()?.error_for_status()?.json()
+}
+
+/// Save `url` to `destination`, following redirects and retrying twice.
+#[cfg(feature = "native-extractor")]
+pub fn download_to(url: &str, destination: &Path) -> Result<()> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let result = std::thread::scope(|scope| {
+            scope.spawn(|| -> std::result::Result<u16, String> {
+                let mut response = reqwest::blocking::Client::builder()
+                    .user_agent(user_agent())
+                    .timeout(None)
+                    .build()
+                    .and_then(|client| client.get(url).send())
+                    .map_err(|error| error.to_string())?;
+                if response.status().is_success() {
+                    let mut file = std::fs::File::create(destination).map_err(|error| error.to_string())?;
+                    response.copy_to(&mut file).map_err(|error| error.to_string())?;
+                }
+                Ok(response.status().as_u16())
+            }).join().unwrap_or_else(|_| Err("the download panicked".to_string()))
+        });
+        match result {
+            Ok(status) if (200..300).contains(&status) => return Ok(()),
+            // Like curl's --retry: server errors and lost connections are worth another try
+            Ok(status) if status >= 500 && attempt < 3 => {}
+            Err(_) if attempt < 3 => {}
+            Ok(status) => return Err(DownloaderError::Network(format!("Download failed: {} answered HTTP {}", host(url), status))),
+            Err(error) => return Err(DownloaderError::Network(format!("Download failed: {}: {}", url, error))),
+        }
+        std::thread::sleep(Duration::from_secs(attempt));
+    }
+}
+
+/// Save `url` to `destination`, following redirects and retrying twice.
+#[cfg(not(feature = "native-extractor"))]
+pub fn download_to(url: &str, destination: &Path) -> Result<()> {
+    let output = curl()
+        .args(["-L", "--retry", "2", "-A", &user_agent(), "-w", "%{http_code}", "-o"])
+        .arg(destination)
+        .arg(url)
+        .output()?;
+    if !output.status.success() {
+        return Err(DownloaderError::Network(format!("Download failed: {}: {}", url, String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        status if status.starts_with('2') => Ok(()),
+        status => Err(DownloaderError::Network(format!("Download failed: {} answered HTTP {}", host(url), status))),
+    }
+}
+
+/// curl printing nothing but errors, for the requests above, the parallel segment downloads
+/// and mail.
+pub(crate) fn curl() -> Command {
+    let mut command = Command::new("curl");
+    command.args(["--silent", "--show-error"]);
+    command
+}
+
+/// Fetch `url` (or `range` of it, inclusive) into `path`, resuming a partial file when there's
+/// no range, until done or `cancel` stops it.
+#[cfg(feature = "native-extractor")]
+pub(crate) async fn fetch_segment(url: String, path: PathBuf, range: Option<(u64, u64)>, headers: Vec<(String, String)>, cancel: CancelHandle) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let failed = |error: reqwest::Error| DownloaderError::Network(format!("Download failed: {}", error));
+    let existing = match range {
+        Some(_) => 0,
+        None => tokio::fs::metadata(&path).await.map(|metadata| metadata.len()).unwrap_or(0),
+    };
+    let client = reqwest::Client::builder().user_agent(user_agent()).build().map_err(failed)?;
+    let mut request = client.get(&url);
+    for (name, value) in &headers {
+        request = request.header(name, value);
+    }
+    request = match range {
+        Some((start, end)) => request.header("Range", format!("bytes={}-{}", start, end)),
+        None if existing > 0 => request.header("Range", format!("bytes={}-", existing)),
+        None => request,
+    };
+
+    let mut response = tokio::select! {
+        response = request.send() => response.map_err(failed)?,
+        _ = cancel.cancelled() => return Err(DownloaderError::Network("Download cancelled".to_string())),
+    };
+    let status = response.status().as_u16();
+    // A partial file that is already whole has nothing left to send
+    if status == 416 && existing > 0 {
+        return Ok(());
+    }
+    if !response.status().is_success() {
+        return Err(DownloaderError::Network(format!("Download failed: {} answered HTTP {}", host(&url), status)));
+    }
+
+    // A server that ignores the range sends the whole file again
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(status == 206 && existing > 0)
+        .truncate(status != 206 || existing == 0)
+        .open(&path)
+        .await?;
+    loop {
+        let chunk = tokio::select! {
+            chunk = response.chunk() => chunk.map_err(failed)?,
+            _ = cancel.cancelled() => return Err(DownloaderError::Network("Download cancelled".to_string())),
+        };
+        match chunk {
+            Some(chunk) => file.write_all(&chunk).await?,
+            None => break,
+        }
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+/// Fetch `url` (or `range` of it, inclusive) into `path` with curl, resuming a partial file
+/// when there's no range. The process is tracked by `cancel`, which stops it.
+#[cfg(not(feature = "native-extractor"))]
+pub(crate) async fn fetch_segment(url: String, path: PathBuf, range: Option<(u64, u64)>, headers: Vec<(String, String)>, cancel: CancelHandle) -> Result<()> {
+    let mut command = tokio::process::Command::from(curl());
+    command.args(["-L", "--fail", "-o"]).arg(&path);
+    match range {
+        Some((start, end)) => command.arg("-r").arg(format!("{}-{}", start, end)),
+        None => command.args(["-C", "-"]),
+    };
+    for (name, value) in &headers {
+        command.arg("-H").arg(format!("{}: {}", name, value));
+    }
+    command.arg(&url);
+
+    let output = cancel.output(&mut command).await?;
+    if !output.status.success() {
+        return Err(DownloaderError::Network(format!("Download failed: {}", String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(())
+}
+
+fn host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', '?']).next().unwrap_or(rest)
+}
+
+/// Percent-encode everything but RFC 3986's unreserved characters.
+fn encode(value: &str) -> String {
+    value.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
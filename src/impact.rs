@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+use crate::logger;
+
+/// Processes that decode and composite the aerial wallpaper.
+const WALLPAPER_PROCESSES: [&str; 4] = ["WindowServer", "idleassetsd", "WallpaperAgent", "WallpaperVideoExtension"];
+
+/// Increase in combined CPU (percentage points) considered a measurable cost.
+const WARN_THRESHOLD: f64 = 5.0;
+
+/// Average combined CPU of the wallpaper processes over `window`, sampled once per second.
+///
+/// Returns `None` when `ps` isn't usable.
+pub async fn sample_wallpaper_cpu(window: Duration) -> Option<f64> {
+    let samples = window.as_secs().max(1);
+    let mut total = 0.0;
+
+    for _ in 0..samples {
+        total += wallpaper_cpu_now()?;
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    Some(total / samples as f64)
+}
+
+fn wallpaper_cpu_now() -> Option<f64> {
+    let output = Command::new("ps").args(["-A", "-o", "%cpu=,comm="]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let mut by_process: HashMap<&str, f64> = HashMap::new();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in stdout.lines() {
+        let mut parts = line.trim().splitn(2, char::is_whitespace);
+        let cpu = parts.next().and_then(|c| c.parse::<f64>().ok());
+        let command = parts.next().map(str::trim).unwrap_or("");
+
+        if let (Some(cpu), Some(name)) = (cpu, WALLPAPER_PROCESSES.iter().find(|p| command.ends_with(*p))) {
+            *by_process.entry(name).or_default() += cpu;
+        }
+    }
+
+    Some(by_process.values().sum())
+}
+
+/// Idle CPU of the wallpaper processes before and after installing a video.
+#[derive(Debug, Clone, Copy)]
+pub struct ImpactReport {
+    pub before: f64,
+    pub after: f64,
+}
+
+impl ImpactReport {
+    pub fn increase(&self) -> f64 {
+        self.after - self.before
+    }
+
+    pub fn display(&self, video_path: &Path) {
+        logger::header("Wallpaper Power Impact");
+        logger::stats(&format!("Wallpaper process CPU before install: {:.1}%", self.before));
+        logger::stats(&format!("Wallpaper process CPU after install:  {:.1}%", self.after));
+
+        if self.increase() >= WARN_THRESHOLD {
+            logger::warning(&format!(
+                "{} adds about {:.1}% CPU while idle, which will shorten battery life",
+                video_path.file_name().unwrap_or_default().to_string_lossy(),
+                self.increase()
+            ));
+            logger::info("Consider a lighter rendition, e.g. re-run with --also 1440p and install that file instead");
+        } else {
+            logger::success("No measurable increase in idle CPU usage");
+        }
+    }
+}
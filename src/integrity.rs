@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use chrono::DateTime;
+use serde_json::Value;
+use tokio::process::Command;
+use crate::cancel::{CancelHandle, TrackedCommand};
+use crate::config::Config;
+use crate::dependencies;
+use crate::error::Result;
+use crate::history::{DownloadStatus, History};
+use crate::logger;
+use crate::update;
+use crate::utils;
+
+/// How far a file's length may be from what was expected, in seconds; encoders round to
+/// whole frames, a cut-off encode is off by minutes
+pub const DURATION_TOLERANCE_SECONDS: f64 = 2.0;
+
+/// How much of each end of the file is decoded
+const DECODE_SECONDS: &str = "1";
+
+/// What's wrong with the finished file at `path`, if anything: ffprobe can't read it, it has
+/// no video or audio stream, its length is more than `DURATION_TOLERANCE_SECONDS` from
+/// `expected_duration`, or its first or last second doesn't decode. Fails only when ffprobe
+/// or ffmpeg can't be run at all. Cancelling `cancel` stops the checks.
+pub async fn problem(path: &Path, expected_duration: Option<f64>, cancel: &CancelHandle) -> Result<Option<String>> {
+    let output = Command::new(dependencies::ffprobe())
+        .args(["-v", "error", "-show_entries", "format=duration:stream=codec_type", "-of", "json"])
+        .arg(path)
+        .tracked_output(cancel).await?;
+    let probe = match serde_json::from_slice::<Value>(&output.stdout) {
+        Ok(probe) if output.status.success() => probe,
+        _ => return Ok(Some(format!("ffprobe can't read it: {}", String::from_utf8_lossy(&output.stderr).trim()))),
+    };
+    if let Some(problem) = probe_problem(&probe, expected_duration) {
+        return Ok(Some(problem));
+    }
+
+    let streams = probe["streams"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let stream = match streams.iter().any(|stream| stream["codec_type"] == "video") {
+        true => "0:v:0",
+        false => "0:a:0",
+    };
+    if let Some(error) = decode_error(path, stream, false, cancel).await? {
+        return Ok(Some(format!("the start doesn't decode: {}", error)));
+    }
+    // Stills have no length, so there is no end to seek to
+    if probe["format"]["duration"].as_str().is_some() {
+        if let Some(error) = decode_error(path, stream, true, cancel).await? {
+            return Ok(Some(format!("the end doesn't decode: {}", error)));
+        }
+    }
+    Ok(None)
+}
+
+/// What's wrong with a file, given `ffprobe -show_entries format=duration:stream=codec_type`
+/// as JSON: no video or audio stream, or a length off from `expected_duration`.
+fn probe_problem(probe: &Value, expected_duration: Option<f64>) -> Option<String> {
+    let streams = probe["streams"].as_array().map(Vec::as_slice).unwrap_or_default();
+    if !streams.iter().any(|stream| stream["codec_type"] == "video" || stream["codec_type"] == "audio") {
+        return Some("no video or audio stream".to_string());
+    }
+
+    let duration = probe["format"]["duration"].as_str().and_then(|value| value.parse::<f64>().ok());
+    match (duration, expected_duration) {
+        (None, Some(_)) => Some("no length reported".to_string()),
+        (Some(actual), Some(expected)) if (actual - expected).abs() > DURATION_TOLERANCE_SECONDS => Some(format!(
+            "it is {} long but should be {}",
+            utils::format_time(actual), utils::format_time(expected)
+        )),
+        _ => None,
+    }
+}
+
+/// The first error ffmpeg reports decoding the first (or, with `from_end`, the last)
+/// `DECODE_SECONDS` of `stream`.
+async fn decode_error(path: &Path, stream: &str, from_end: bool, cancel: &CancelHandle) -> Result<Option<String>> {
+    let mut command = Command::new(dependencies::ffmpeg());
+    command.args(["-v", "error", "-xerror", "-nostdin"]);
+    if from_end {
+        command.args(["-sseof", &format!("-{}", DECODE_SECONDS)]);
+    }
+    let output = command
+        .arg("-i")
+        .arg(path)
+        .args(["-map", stream, "-t", DECODE_SECONDS, "-f", "null", "-"])
+        .tracked_output(cancel).await?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match (output.status.success(), stderr.lines().find(|line| !line.trim().is_empty())) {
+        (true, None) => Ok(None),
+        (_, Some(line)) => Ok(Some(line.trim().to_string())),
+        (false, None) => Ok(Some(format!("ffmpeg exited with {}", output.status))),
+    }
+}
+
+/// Re-check finished files: `target` is a file, or `all` for every completed download in the
+/// history. Each file gets the checks of `problem`, and is compared against the SHA-256
+/// recorded when it was downloaded. Files deleted since are only reported.
+pub async fn verify(target: &str, config: &Config) -> Result<()> {
+    let history = History::load(config)?;
+
+    let files: Vec<(PathBuf, Option<String>)> = if target == "all" {
+        // Oldest first, so a later download of the same path wins
+        let mut latest = BTreeMap::new();
+        for entry in history.since(DateTime::UNIX_EPOCH)? {
+            if let (DownloadStatus::Completed, Some(path)) = (entry.status, entry.path) {
+                latest.insert(path, entry.sha256);
+            }
+        }
+        latest.into_iter().collect()
+    } else {
+        let path = Path::new(target);
+        let path = path.canonicalize().map_err(|e| format!("{}: {}", target, e))?;
+        let checksum = history.for_path(&path)?.and_then(|entry| entry.sha256);
+        vec![(path, checksum)]
+    };
+
+    if files.is_empty() {
+        logger::info("No downloads recorded yet");
+        return Ok(());
+    }
+
+    logger::header("Verifying Files");
+    let (mut failed, mut missing) = (0, 0);
+    for (path, checksum) in &files {
+        let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+        if !path.exists() {
+            missing += 1;
+            logger::warning(&format!("{}: no longer on disk", name));
+            continue;
+        }
+        match verify_file(path, checksum.as_deref()).await? {
+            Some(problem) => {
+                failed += 1;
+                logger::error(&format!("{}: {}", name, problem));
+            }
+            None if checksum.is_none() => logger::success(&format!("{}  (no checksum recorded)", name)),
+            None => logger::success(&name),
+        }
+    }
+
+    let checked = files.len() - missing;
+    if failed > 0 {
+        return Err(format!("{} of {} files failed verification", failed, checked).into());
+    }
+    logger::stats(&format!("{} files verified, {} missing", checked, missing));
+    Ok(())
+}
+
+async fn verify_file(path: &Path, checksum: Option<&str>) -> Result<Option<String>> {
+    if let Some(expected) = checksum {
+        if update::sha256_file(path)? != expected {
+            return Ok(Some("changed since it was downloaded (checksum mismatch)".to_string()));
+        }
+    }
+    problem(path, None, &CancelHandle::new()).await
+}
@@ -1,11 +1,64 @@
 // Re-export all modules for easier importing
+pub mod error;
 pub mod config;
+pub mod config_migration;
 pub mod logger;
 pub mod utils;
 pub mod video_info;
 pub mod downloader;
 pub mod video_manager;
+pub mod wallpaper;
 pub mod dependencies;
+#[cfg(feature = "managed-ffmpeg")]
+pub mod managed_ffmpeg;
+pub mod library;
+pub mod spaces;
+pub mod displays;
+pub mod focus;
+pub mod appearance;
+pub mod daemon;
+pub mod still;
+pub mod telemetry;
+pub mod impact;
+pub mod update;
+pub mod crash;
+pub mod error_codes;
+pub mod ytdlp_error;
+pub mod auth;
+pub mod review;
+pub mod compare;
+pub mod queue;
+pub mod cancel;
+pub mod watchdog;
+pub mod events;
+pub mod extractor;
+pub mod http;
+#[cfg(feature = "mirrors")]
+pub mod mirrors;
+#[cfg(feature = "native-extractor")]
+pub mod native;
+pub mod orphans;
+pub mod api;
+pub mod history;
+pub mod integrity;
+pub mod dashboard;
+pub mod status;
+pub mod phases;
+pub mod notify;
+pub mod mqtt;
+pub mod sponsorblock;
+pub mod speed;
+pub mod playlist;
+pub mod transcript;
+pub mod frames;
+pub mod encoders;
+pub mod live_photo;
+pub mod aerial;
+pub mod ffmpeg_progress;
+pub mod loudness;
+pub mod sync;
+pub mod rotation;
+pub mod sun;
 
 // Re-export commonly used types
 pub use config::Config;
@@ -13,5 +66,7 @@ pub use logger::*;
 pub use utils::*;
 pub use video_info::{analyze, VideoInfo, SelectedFormats, VideoFormat, AudioFormat};
 pub use downloader::Downloader;
+pub use events::{ProgressEvent, ProgressObserver};
 pub use video_manager::VideoManager;
 pub use dependencies::DependencyChecker;
+pub use library::Library;
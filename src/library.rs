@@ -0,0 +1,340 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use crate::error::Result;
+use crate::dependencies;
+use crate::logger;
+use crate::config::Config;
+use crate::history::History;
+use crate::utils;
+
+const MANIFEST_NAME: &str = "manifest.json";
+const PACK_FORMAT_VERSION: u32 = 1;
+
+/// A converted wallpaper video living in the output directory.
+/// The id is the file stem, which is what users pass on the command line.
+#[derive(Debug, Clone)]
+pub struct LibraryEntry {
+    pub id: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+    pub thumbnail: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackManifest {
+    pub version: u32,
+    pub created: String,
+    pub entries: Vec<PackEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackEntry {
+    pub id: String,
+    pub file: String,
+    pub size: u64,
+    pub duration: Option<f64>,
+    pub thumbnail: Option<String>,
+}
+
+pub struct Library {
+    root: PathBuf,
+}
+
+impl Library {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            root: config.output_dir.clone(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn entries(&self) -> Vec<LibraryEntry> {
+        let mut entries = Vec::new();
+
+        if let Ok(dir) = fs::read_dir(&self.root) {
+            for item in dir.flatten() {
+                let path = item.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("mov") {
+                    continue;
+                }
+
+                if let Ok(metadata) = fs::metadata(&path) {
+                    let thumbnail = path.with_extension("jpg");
+                    entries.push(LibraryEntry {
+                        id: path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+                        size: metadata.len(),
+                        modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                        thumbnail: thumbnail.exists().then_some(thumbnail),
+                        path,
+                    });
+                }
+            }
+        }
+
+        entries.sort_by(|a, b| a.id.cmp(&b.id));
+        entries
+    }
+
+    pub fn find(&self, id: &str) -> Option<LibraryEntry> {
+        self.entries().into_iter().find(|e| e.id == id)
+    }
+
+    pub fn display_entries(&self) {
+        logger::header("Wallpaper Library");
+        logger::file(&format!("Library: {}", self.root.display()));
+
+        let entries = self.entries();
+        if entries.is_empty() {
+            logger::warning("No .mov wallpapers found in the library");
+            return;
+        }
+
+        for entry in &entries {
+            let modified = chrono::DateTime::<chrono::Local>::from(entry.modified).format("%Y-%m-%d %H:%M");
+            logger::video(&format!("{} ({} | Modified: {})", entry.id, utils::format_file_size(Some(entry.size)), modified));
+        }
+        logger::stats(&format!("{} wallpaper(s) in library", entries.len()));
+    }
+
+    /// Print a credit line for the entry, from the tags written at conversion time, with the
+    /// download history filling in what older files lack.
+    pub fn attribution(&self, id: &str, history: Option<&History>) -> Result<()> {
+        let entry = self.find(id).ok_or_else(|| format!("No library entry with id '{}'", id))?;
+        logger::header("Attribution");
+
+        let tags = probe_tags(&entry.path);
+        let recorded = history.and_then(|history| history.for_path(&entry.path).ok().flatten());
+        let tag = |key: &str| tags.get(key).and_then(|v| v.as_str()).filter(|v| !v.is_empty()).map(str::to_string);
+
+        let title = tag("title").or_else(|| recorded.as_ref().and_then(|r| r.title.clone()));
+        let channel = tag("artist").or_else(|| recorded.as_ref().and_then(|r| r.uploader.clone()));
+        let source = tag("comment").filter(|c| c.starts_with("http")).or_else(|| recorded.as_ref().map(|r| r.url.clone()));
+        let license = tag("copyright").or_else(|| recorded.as_ref().and_then(|r| r.license.clone()));
+
+        let Some(source) = source else {
+            return Err(format!("'{}' has no source information; it was converted before attribution was recorded", id).into());
+        };
+
+        logger::video(&format!("Title:   {}", title.as_deref().unwrap_or("Unknown")));
+        logger::info(&format!("Channel: {}", channel.as_deref().unwrap_or("Unknown")));
+        logger::info(&format!("Source:  {}", source));
+        logger::info(&format!("License: {}", license.as_deref().unwrap_or("Standard YouTube License")));
+
+        // YouTube's only alternative license is CC BY 3.0
+        let licensed = match license.as_deref() {
+            Some(license) if license.contains("Creative Commons") => "licensed under CC BY 3.0 (https://creativecommons.org/licenses/by/3.0/)".to_string(),
+            Some(license) => format!("license: {}", license),
+            None => "used with permission".to_string(),
+        };
+        println!();
+        println!("\"{}\" by {} ({}), {}", title.as_deref().unwrap_or(id), channel.as_deref().unwrap_or("unknown creator"), source, licensed);
+        println!();
+
+        if license.is_none() {
+            logger::warning("No Creative Commons license: ask the creator before publishing screenshots or recordings of this video");
+        }
+        Ok(())
+    }
+
+    /// Bundle the given entries, a manifest and a poster thumbnail per entry into a tar archive.
+    pub fn export_pack(&self, ids: &[String], output: &Path) -> Result<PathBuf> {
+        logger::header("Export Wallpaper Pack");
+
+        let entries: Vec<LibraryEntry> = if ids.is_empty() {
+            self.entries()
+        } else {
+            ids.iter()
+                .map(|id| self.find(id).ok_or_else(|| format!("No library entry with id '{}'", id)))
+                .collect::<Result<_, _>>()?
+        };
+
+        if entries.is_empty() {
+            return Err("Library is empty, nothing to export".into());
+        }
+
+        let staging = tempfile::tempdir()?;
+        let videos_dir = staging.path().join("videos");
+        let thumbs_dir = staging.path().join("thumbnails");
+        fs::create_dir_all(&videos_dir)?;
+        fs::create_dir_all(&thumbs_dir)?;
+
+        let mut manifest = PackManifest {
+            version: PACK_FORMAT_VERSION,
+            created: chrono::Utc::now().to_rfc3339(),
+            entries: Vec::new(),
+        };
+
+        for entry in &entries {
+            logger::info(&format!("Adding: {}", entry.id));
+            let file_name = entry.path.file_name().unwrap().to_string_lossy().to_string();
+            fs::copy(&entry.path, videos_dir.join(&file_name))?;
+
+            let thumb_name = format!("{}.jpg", entry.id);
+            let thumbnail = match &entry.thumbnail {
+                Some(existing) => fs::copy(existing, thumbs_dir.join(&thumb_name)).ok().map(|_| thumb_name),
+                None => extract_thumbnail(&entry.path, &thumbs_dir.join(&thumb_name)).then_some(thumb_name),
+            };
+
+            manifest.entries.push(PackEntry {
+                id: entry.id.clone(),
+                file: file_name,
+                size: entry.size,
+                duration: probe_duration(&entry.path),
+                thumbnail: thumbnail.map(|t| format!("thumbnails/{}", t)),
+            });
+        }
+
+        fs::write(staging.path().join(MANIFEST_NAME), serde_json::to_string_pretty(&manifest)?)?;
+
+        if let Some(parent) = output.parent() {
+            utils::ensure_directory_exists(parent)?;
+        }
+
+        let mut args = vec!["-c".to_string()];
+        if let Some(flag) = compression_flag(output) {
+            args.push(flag.to_string());
+        }
+        args.extend(["-f".to_string(), output.to_string_lossy().to_string(),
+            "-C".to_string(), staging.path().to_string_lossy().to_string(), ".".to_string()]);
+
+        let result = Command::new("tar").args(&args).output()?;
+        if !result.status.success() {
+            return Err(format!("tar failed: {}", String::from_utf8_lossy(&result.stderr)).into());
+        }
+
+        logger::success(&format!("Exported {} wallpaper(s) to {}", manifest.entries.len(), output.display()));
+        if let Some(stats) = utils::get_file_stats(output) {
+            logger::stats(&format!("Pack size: {}", utils::format_file_size(Some(stats.len()))));
+        }
+
+        Ok(output.to_path_buf())
+    }
+
+    /// Unpack a pack created by `export_pack` into the library, never overwriting existing files.
+    pub fn import_pack(&self, pack: &Path) -> Result<Vec<PathBuf>> {
+        logger::header("Import Wallpaper Pack");
+
+        if !pack.is_file() {
+            return Err(format!("Pack not found: {}", pack.display()).into());
+        }
+
+        let staging = tempfile::tempdir()?;
+        let mut args = vec!["-x".to_string()];
+        if let Some(flag) = compression_flag(pack) {
+            args.push(flag.to_string());
+        }
+        args.extend(["-f".to_string(), pack.to_string_lossy().to_string(),
+            "-C".to_string(), staging.path().to_string_lossy().to_string()]);
+
+        let result = Command::new("tar").args(&args).output()?;
+        if !result.status.success() {
+            return Err(format!("tar failed: {}", String::from_utf8_lossy(&result.stderr)).into());
+        }
+
+        let manifest_path = staging.path().join(MANIFEST_NAME);
+        let manifest: PackManifest = serde_json::from_str(&fs::read_to_string(&manifest_path)
+            .map_err(|_| "Pack does not contain a manifest.json")?)?;
+
+        if manifest.version > PACK_FORMAT_VERSION {
+            return Err(format!("Pack format v{} is newer than supported v{}", manifest.version, PACK_FORMAT_VERSION).into());
+        }
+
+        utils::ensure_directory_exists(&self.root)?;
+        let mut imported = Vec::new();
+
+        for entry in &manifest.entries {
+            // Only trust the bare file name from the manifest
+            let file_name = match Path::new(&entry.file).file_name() {
+                Some(name) => name.to_owned(),
+                None => {
+                    logger::warning(&format!("Skipping entry with invalid file name: {}", entry.file));
+                    continue;
+                }
+            };
+
+            let source = staging.path().join("videos").join(&file_name);
+            if !source.is_file() {
+                logger::warning(&format!("Skipping {}: video missing from pack", entry.id));
+                continue;
+            }
+
+            let target = utils::get_unique_filename(&self.root.join(&file_name))?;
+            fs::copy(&source, &target)?;
+
+            if let Some(thumb) = entry.thumbnail.as_ref().and_then(|t| Path::new(t).file_name()) {
+                let thumb_source = staging.path().join("thumbnails").join(thumb);
+                if thumb_source.is_file() {
+                    let _ = fs::copy(&thumb_source, target.with_extension("jpg"));
+                }
+            }
+
+            logger::success(&format!("Imported: {}", target.file_name().unwrap().to_string_lossy()));
+            imported.push(target);
+        }
+
+        logger::stats(&format!("Imported {} of {} wallpaper(s) into {}", imported.len(), manifest.entries.len(), self.root.display()));
+        Ok(imported)
+    }
+}
+
+fn compression_flag(archive: &Path) -> Option<&'static str> {
+    let name = archive.to_string_lossy();
+    if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+        Some("--zstd")
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some("-z")
+    } else {
+        None
+    }
+}
+
+pub(crate) fn extract_thumbnail(video: &Path, output: &Path) -> bool {
+    Command::new(dependencies::ffmpeg())
+        .args(["-y", "-v", "error", "-ss", "1", "-i"])
+        .arg(video)
+        .args(["-frames:v", "1", "-vf", "scale=640:-2"])
+        .arg(output)
+        .output()
+        .map(|o| o.status.success() && output.exists())
+        .unwrap_or(false)
+}
+
+/// The container's global tags, e.g. `title` and `copyright`; empty when unreadable.
+fn probe_tags(video: &Path) -> serde_json::Map<String, serde_json::Value> {
+    Command::new(dependencies::ffprobe())
+        .args(["-v", "quiet", "-show_entries", "format_tags", "-of", "json"])
+        .arg(video)
+        .output()
+        .ok()
+        .and_then(|output| serde_json::from_slice::<serde_json::Value>(&output.stdout).ok())
+        .and_then(|info| info["format"]["tags"].as_object().cloned())
+        .unwrap_or_default()
+}
+
+/// Width and height of the first video stream.
+pub(crate) fn probe_resolution(video: &Path) -> Option<(u64, u64)> {
+    let output = Command::new(dependencies::ffprobe())
+        .args(["-v", "quiet", "-select_streams", "v:0", "-show_entries", "stream=width,height", "-of", "csv=p=0"])
+        .arg(video)
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let (width, height) = text.trim().split_once(',')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+pub(crate) fn probe_duration(video: &Path) -> Option<f64> {
+    let output = Command::new(dependencies::ffprobe())
+        .args(["-v", "quiet", "-show_entries", "format=duration", "-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(video)
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse::<f64>().ok()
+}
@@ -0,0 +1,101 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use sha2::{Digest, Sha256};
+use crate::cancel::CancelHandle;
+use crate::error::{DownloaderError, Result};
+use crate::dependencies;
+use crate::logger;
+
+/// Length of the movie half; iOS plays about this much around the key photo
+pub const SEGMENT_SECONDS: f64 = 3.0;
+
+/// QuickTime metadata key Photos uses to pair the movie with its photo
+const CONTENT_IDENTIFIER_KEY: &str = "com.apple.quicktime.content.identifier";
+
+/// A 3-second window around `center` that stays inside the scene containing it, so the
+/// movie doesn't cut away mid-playback. Shorter when the scene or video is shorter.
+pub fn segment_around(center: f64, duration: f64, cuts: &[f64]) -> (f64, f64) {
+    let scene_start = cuts.iter().copied().filter(|cut| *cut <= center).fold(0.0, f64::max);
+    let scene_end = cuts.iter().copied().filter(|cut| *cut > center).fold(duration, f64::min);
+    let start = (center - SEGMENT_SECONDS / 2.0).clamp(scene_start, (scene_end - SEGMENT_SECONDS).max(scene_start));
+    (start, (start + SEGMENT_SECONDS).min(scene_end))
+}
+
+/// Second of the video's most representative frame, picked by ffmpeg's `thumbnail` filter
+/// from one frame every two seconds, like the `--still` export.
+pub fn representative_time(video: &Path, duration: f64, cancel: &CancelHandle) -> Option<f64> {
+    let samples = ((duration / 2.0).ceil() as u32).clamp(1, 300);
+    let output = cancel.output_blocking(Command::new(dependencies::ffmpeg())
+        .args(["-hide_banner", "-nostats", "-i"])
+        .arg(video)
+        .args(["-an", "-vf", &format!("fps=1/2,thumbnail={},showinfo", samples), "-frames:v", "1", "-f", "null", "-"]))
+        .ok()?;
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .filter(|line| line.contains("showinfo"))
+        .find_map(|line| line.split("pts_time:").nth(1)?.split_whitespace().next()?.parse().ok())
+}
+
+/// Random-enough UUID shared by the photo and the movie.
+pub fn content_identifier(seed: &Path) -> String {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+    let hash = Sha256::digest(format!("{}:{}:{}", seed.display(), nanos, std::process::id()));
+    let hex: String = hash.iter().take(16).map(|byte| format!("{:02X}", byte)).collect();
+    // Version 4, RFC 4122 variant
+    format!("{}-{}-4{}-{}{}-{}", &hex[0..8], &hex[8..12], &hex[13..16], ["8", "9", "A", "B"][hash[8] as usize % 4], &hex[17..20], &hex[20..32])
+}
+
+/// ffmpeg command that copies `movie` to `output` with the content identifier in its
+/// QuickTime metadata.
+pub fn tag_movie_command(movie: &Path, output: &Path, identifier: &str) -> Command {
+    let mut command = Command::new(dependencies::ffmpeg());
+    command
+        .args(["-y", "-v", "error", "-i"])
+        .arg(movie)
+        .args(["-map", "0", "-c", "copy", "-movflags", "use_metadata_tags+faststart"])
+        .args(["-metadata", &format!("{}={}", CONTENT_IDENTIFIER_KEY, identifier)])
+        .arg(output);
+    command
+}
+
+/// The key photo, `<movie name>.heic` on macOS (via `sips`) or `.jpg` elsewhere, taken
+/// from the middle of the movie and tagged with `identifier` by exiftool.
+pub fn write_photo(movie: &Path, identifier: &str, cancel: &CancelHandle) -> Result<PathBuf> {
+    let jpeg = movie.with_extension("jpg");
+    let output = cancel.output_blocking(Command::new(dependencies::ffmpeg())
+        .args(["-y", "-v", "error", "-ss", &format!("{:.3}", SEGMENT_SECONDS / 2.0), "-i"])
+        .arg(movie)
+        .args(["-frames:v", "1", "-q:v", "2", "-update", "1"])
+        .arg(&jpeg))?;
+    if !output.status.success() || !jpeg.exists() {
+        return Err(DownloaderError::Ffmpeg(format!("Failed to extract the key photo: {}", String::from_utf8_lossy(&output.stderr).trim())));
+    }
+
+    let mut photo = jpeg.clone();
+    if cfg!(target_os = "macos") {
+        let heic = movie.with_extension("heic");
+        let converted = cancel.output_blocking(Command::new("sips")
+            .args(["-s", "format", "heic"])
+            .arg(&jpeg)
+            .arg("--out")
+            .arg(&heic))
+            .is_ok_and(|output| output.status.success());
+        if converted {
+            std::fs::remove_file(&jpeg).ok();
+            photo = heic;
+        } else {
+            logger::warning("Could not convert the key photo to HEIC, keeping the JPEG");
+        }
+    }
+
+    // The identifier lives in Apple's maker note, which only exiftool writes
+    let tagged = cancel.output_blocking(Command::new("exiftool")
+        .args(["-q", "-overwrite_original", &format!("-ContentIdentifier={}", identifier)])
+        .arg(&photo))
+        .is_ok_and(|output| output.status.success());
+    if !tagged {
+        logger::warning("Could not tag the key photo (is exiftool installed?); Photos won't pair it with the movie");
+    }
+    Ok(photo)
+}
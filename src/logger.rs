@@ -1,8 +1,39 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::SystemTime;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
+use serde_json::Value;
 
 static mut START_TIME: Option<SystemTime> = None;
 
+/// Plain-text copy of recent output, kept for diagnostic bundles
+static HISTORY: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+const HISTORY_LIMIT: usize = 500;
+
+/// Jobs with a progress row, in row order; the last job owns the bottom line
+static PROGRESS_ROWS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Where output goes; text unless `set_sink` chose otherwise
+static SINK: OnceLock<Sink> = OnceLock::new();
+
+/// Where log output is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sink {
+    /// Colored, decorated lines and live progress rows for people at a terminal
+    Text,
+    /// One JSON object per line on stdout for scripts and GUIs: the events passed to `event`,
+    /// plus warnings and errors as `log` events. Everything else is left out.
+    Json,
+    /// Nothing is written; the events of `Json` only reach the handlers, for programs using
+    /// the library
+    Silent,
+}
+
+/// Receives each event as it would be printed in JSON mode.
+pub type EventHandler = Arc<dyn Fn(&Value) + Send + Sync>;
+
+static HANDLERS: Mutex<Vec<EventHandler>> = Mutex::new(Vec::new());
+
 // ANSI color codes
 const COLOR_RESET: &str = "\x1b[0m";
 const COLOR_INFO: &str = "\x1b[36m";     // Cyan
@@ -41,25 +72,23 @@ const SYMBOL_CONVERT: &str = "CONVERT:";
 #[allow(dead_code)]
 fn init_start_time() {
     unsafe {
-        let ptr = &raw const START_TIME as *const Option<SystemTime>;
+        let ptr = &raw const START_TIME;
         if (*ptr).is_none() {
             START_TIME = Some(SystemTime::now());
         }
     }
 }
 
-fn elapsed_time() -> String {
+fn elapsed_seconds() -> f64 {
     unsafe {
-        if let Some(start) = START_TIME {
-            if let Ok(elapsed) = start.elapsed() {
-                let seconds = elapsed.as_secs_f64();
-                return format!("[{:.1}s]", seconds);
-            }
-        }
-        "[0.0s]".to_string()
+        START_TIME.and_then(|start| start.elapsed().ok()).map_or(0.0, |elapsed| elapsed.as_secs_f64())
     }
 }
 
+fn elapsed_time() -> String {
+    format!("[{:.1}s]", elapsed_seconds())
+}
+
 fn colorize(text: &str, color: &str) -> String {
     format!("{}{}{}", color, text, COLOR_RESET)
 }
@@ -69,8 +98,82 @@ pub fn init() {
     init_start_time();
 }
 
+/// Choose where output goes, once, before anything is logged.
+pub fn set_sink(sink: Sink) {
+    SINK.set(sink).ok();
+}
+
+pub fn sink() -> Sink {
+    SINK.get().copied().unwrap_or(Sink::Text)
+}
+
+fn is_text() -> bool {
+    sink() == Sink::Text
+}
+
+/// Pass every event to `handler` from now on, whatever the sink.
+pub fn add_event_handler(handler: EventHandler) {
+    HANDLERS.lock().unwrap_or_else(|e| e.into_inner()).push(handler);
+}
+
+/// Whether `event` goes anywhere, so callers can skip building costly events.
+pub fn wants_events() -> bool {
+    !is_text() || !HANDLERS.lock().unwrap_or_else(|e| e.into_inner()).is_empty()
+}
+
+/// Report a structured event, e.g. `event("progress", json!({"percent": 42.0}))`, as
+/// `{"event":"progress","time":1.5,"percent":42.0}`: to the handlers, and on stdout when the
+/// sink is JSON.
+pub fn event(name: &str, fields: Value) {
+    if !wants_events() {
+        return;
+    }
+    let handlers = HANDLERS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let mut record = serde_json::Map::new();
+    record.insert("event".to_string(), name.into());
+    record.insert("time".to_string(), elapsed_seconds().into());
+    if let Value::Object(fields) = fields {
+        record.extend(fields);
+    }
+    let record = Value::Object(record);
+
+    for handler in &handlers {
+        handler(&record);
+    }
+    if sink() == Sink::Json {
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{}", record).ok();
+        stdout.flush().ok();
+    }
+}
+
+/// An empty line between sections of text output.
+pub fn blank() {
+    if is_text() {
+        println!();
+    }
+}
+
+/// The last `count` logged lines without color codes, oldest first.
+pub fn recent_lines(count: usize) -> Vec<String> {
+    let history = HISTORY.lock().unwrap_or_else(|e| e.into_inner());
+    history.iter().skip(history.len().saturating_sub(count)).cloned().collect()
+}
+
+fn remember(line: String) {
+    let mut history = HISTORY.lock().unwrap_or_else(|e| e.into_inner());
+    if history.len() == HISTORY_LIMIT {
+        history.pop_front();
+    }
+    history.push_back(line);
+}
+
 #[allow(dead_code)]
 pub fn header(s: &str) {
+    remember(format!("== {} ==", s));
+    if !is_text() {
+        return;
+    }
     println!();
     separator();
     println!("  {}", s);
@@ -80,84 +183,137 @@ pub fn header(s: &str) {
 
 #[allow(dead_code)]
 pub fn info(s: &str) {
-    println!("{}", format_message("info", SYMBOL_INFO, s));
+    emit("info", SYMBOL_INFO, s);
 }
 
 #[allow(dead_code)]
 pub fn success(s: &str) {
-    println!("{}", format_message("success", SYMBOL_SUCCESS, s));
+    emit("success", SYMBOL_SUCCESS, s);
 }
 
 #[allow(dead_code)]
 pub fn warning(s: &str) {
-    println!("{}", format_message("warning", SYMBOL_WARNING, s));
+    emit("warning", SYMBOL_WARNING, s);
 }
 
 #[allow(dead_code)]
 pub fn error(s: &str) {
-    eprintln!("{}", format_message("error", SYMBOL_ERROR, s));
+    emit("error", SYMBOL_ERROR, s);
 }
 
 #[allow(dead_code)]
 pub fn video(s: &str) {
-    println!("{}", format_message("info", SYMBOL_VIDEO, s));
+    emit("info", SYMBOL_VIDEO, s);
 }
 
 #[allow(dead_code)]
 pub fn audio(s: &str) {
-    println!("{}", format_message("info", SYMBOL_AUDIO, s));
+    emit("info", SYMBOL_AUDIO, s);
 }
 
 #[allow(dead_code)]
 pub fn file(s: &str) {
-    println!("{}", format_message("info", SYMBOL_FILE, s));
+    emit("info", SYMBOL_FILE, s);
 }
 
 #[allow(dead_code)]
 pub fn stats(s: &str) {
-    println!("{}", format_message("info", SYMBOL_STATS, s));
+    emit("info", SYMBOL_STATS, s);
 }
 
 #[allow(dead_code)]
 pub fn download(s: &str) {
-    println!("{}", format_message("info", SYMBOL_DOWNLOAD, s));
+    emit("info", SYMBOL_DOWNLOAD, s);
 }
 
 #[allow(dead_code)]
 pub fn search(s: &str) {
-    println!("{}", format_message("info", SYMBOL_SEARCH, s));
+    emit("info", SYMBOL_SEARCH, s);
 }
 
 #[allow(dead_code)]
 pub fn wallpaper(s: &str) {
-    println!("{}", format_message("info", SYMBOL_WALLPAPER, s));
+    emit("info", SYMBOL_WALLPAPER, s);
 }
 
 #[allow(dead_code)]
 pub fn backup(s: &str) {
-    println!("{}", format_message("info", SYMBOL_BACKUP, s));
+    emit("info", SYMBOL_BACKUP, s);
 }
 
 #[allow(dead_code)]
 pub fn install(s: &str) {
-    println!("{}", format_message("info", SYMBOL_INSTALL, s));
+    emit("info", SYMBOL_INSTALL, s);
 }
 
 #[allow(dead_code)]
 pub fn convert(s: &str) {
-    println!("{}", format_message("info", SYMBOL_CONVERT, s));
+    emit("info", SYMBOL_CONVERT, s);
 }
 
 #[allow(dead_code)]
 pub fn progress(s: &str) {
+    // Redirected output gets no carriage returns to collapse updates, so skip them there
+    if !std::io::stdout().is_terminal() || !is_text() {
+        return;
+    }
     clear_line();
     print!("{} {}", elapsed_time(), s);
     std::io::stdout().flush().ok();
 }
 
-#[allow(dead_code)]
+/// Update the progress row of `job`, so concurrent jobs don't overwrite each other.
+///
+/// On a terminal each job gets a stable row below the last regular output, claimed on its
+/// first update; otherwise every update is a plain line prefixed with the job id.
+pub fn job_progress(job: &str, s: &str) {
+    if !is_text() {
+        return;
+    }
+    let line = format!("{} [{}] {}", elapsed_time(), job, s);
+    if !std::io::stdout().is_terminal() {
+        println!("{}", line);
+        return;
+    }
+
+    let mut rows = PROGRESS_ROWS.lock().unwrap_or_else(|e| e.into_inner());
+    let row = match rows.iter().position(|j| j == job) {
+        Some(row) => row,
+        None => {
+            // Claim a new bottom row
+            if !rows.is_empty() {
+                println!();
+            }
+            rows.push(job.to_string());
+            rows.len() - 1
+        }
+    };
+
+    let up = rows.len() - 1 - row;
+    let mut stdout = std::io::stdout().lock();
+    if up > 0 {
+        write!(stdout, "\x1b[{}A\r\x1b[K{}\x1b[{}B\r", up, line, up).ok();
+    } else {
+        write!(stdout, "\r\x1b[K{}", line).ok();
+    }
+    stdout.flush().ok();
+}
+
+/// Release the progress rows once all jobs are done, so regular output continues below them.
+pub fn finish_job_progress() {
+    let mut rows = PROGRESS_ROWS.lock().unwrap_or_else(|e| e.into_inner());
+    if !rows.is_empty() && std::io::stdout().is_terminal() && is_text() {
+        println!();
+    }
+    rows.clear();
+}
+
+/// Replace the live progress line with a final summary and release the progress rows.
 pub fn progress_complete(s: &str) {
-    clear_line();
+    PROGRESS_ROWS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    if std::io::stdout().is_terminal() && is_text() {
+        clear_line();
+    }
     success(s);
 }
 
@@ -171,6 +327,20 @@ fn separator() {
     println!("{}", colorize("─".repeat(60).as_str(), COLOR_INFO));
 }
 
+/// Write one message to the sink: errors go to stderr as text, and only warnings and
+/// errors become events otherwise.
+fn emit(level: &str, symbol: &str, message: &str) {
+    let line = format_message(level, symbol, message);
+    match sink() {
+        Sink::Text if level == "error" => eprintln!("{}", line),
+        Sink::Text => println!("{}", line),
+        Sink::Json | Sink::Silent if matches!(level, "warning" | "error") => {
+            event("log", serde_json::json!({ "level": level, "message": message }));
+        }
+        Sink::Json | Sink::Silent => {}
+    }
+}
+
 fn format_message(level: &str, symbol: &str, message: &str) -> String {
     let timestamp = elapsed_time();
     let color = match level {
@@ -181,6 +351,8 @@ fn format_message(level: &str, symbol: &str, message: &str) -> String {
         _ => COLOR_RESET,
     };
     
+    remember(format!("{} {} {}", timestamp, symbol, message));
+
     let colored_symbol = colorize(symbol, color);
     let colored_message = colorize(message, color);
     
@@ -0,0 +1,127 @@
+use std::path::Path;
+use std::process::Command;
+use serde_json::Value;
+use crate::cancel::CancelHandle;
+use crate::dependencies;
+use crate::error::{DownloaderError, Result};
+
+/// Integrated loudness to normalize to, in LUFS; what streaming services play music at
+pub const TARGET_LUFS: f64 = -16.0;
+/// Highest true peak allowed after normalizing, in dBTP
+const TRUE_PEAK: f64 = -1.5;
+/// Loudness range to aim for, in LU
+const LOUDNESS_RANGE: f64 = 11.0;
+/// How far from `TARGET_LUFS` a file may be before the report suggests `--normalize`, in LU
+const TOLERANCE: f64 = 2.0;
+/// Highest true peak the report accepts; above it lossy encoders may clip, in dBTP
+pub const MAX_TRUE_PEAK: f64 = -1.0;
+/// Quieter than -50 dB for 2 seconds or more counts as silence
+const SILENCE_FILTER: &str = "silencedetect=noise=-50dB:duration=2";
+
+/// Loudness of a file as measured by the first `loudnorm` pass.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub integrated: f64,
+    pub true_peak: f64,
+    pub range: f64,
+    pub threshold: f64,
+    pub offset: f64,
+}
+
+/// Loudness and silences of a finished audio file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    /// `None` when the audio is silent throughout
+    pub measurement: Option<Measurement>,
+    /// Start and end of each silence; the end is `None` when it lasts to the end of the file
+    pub silences: Vec<(f64, Option<f64>)>,
+}
+
+impl Report {
+    /// Whether the file is far enough from the target loudness, or peaks high enough, that
+    /// `--normalize` would change it noticeably.
+    pub fn needs_normalization(&self) -> bool {
+        self.measurement.as_ref().is_some_and(|m| (m.integrated - TARGET_LUFS).abs() > TOLERANCE || m.true_peak > MAX_TRUE_PEAK)
+    }
+}
+
+/// Measure the loudness of the first audio track of `input` (EBU R128, pass one of two).
+pub fn measure(input: &Path, cancel: &CancelHandle) -> Result<Measurement> {
+    parse_measurement(&loudnorm_pass(input, None, cancel)?)
+}
+
+/// Measure the loudness of `input` and find its silences, in one pass.
+pub fn analyze(input: &Path, cancel: &CancelHandle) -> Result<Report> {
+    let stderr = loudnorm_pass(input, Some(SILENCE_FILTER), cancel)?;
+    Ok(Report {
+        measurement: parse_measurement(&stderr).ok(),
+        silences: parse_silences(&stderr),
+    })
+}
+
+/// Run loudnorm's measurement over `input`, after `before` if given, returning ffmpeg's log.
+fn loudnorm_pass(input: &Path, before: Option<&str>, cancel: &CancelHandle) -> Result<String> {
+    let filter = match before {
+        Some(before) => format!("{},{}:print_format=json", before, target()),
+        None => format!("{}:print_format=json", target()),
+    };
+    let output = cancel.output_blocking(Command::new(dependencies::ffmpeg())
+        .args(["-hide_banner", "-nostats", "-i"])
+        .arg(input)
+        .args(["-map", "0:a:0", "-af", &filter, "-f", "null", "-"]))?;
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    if !output.status.success() {
+        return Err(DownloaderError::Ffmpeg(format!("Loudness measurement failed: {}", stderr.trim())));
+    }
+    Ok(stderr)
+}
+
+fn parse_measurement(stderr: &str) -> Result<Measurement> {
+    // The JSON block is the last thing loudnorm prints, and its values are strings
+    let json = stderr.rfind('{').map(|start| &stderr[start..]).and_then(|tail| tail.rfind('}').map(|end| &tail[..=end]));
+    let info: Value = serde_json::from_str(json.ok_or("loudnorm printed no measurement")?)?;
+    let field = |key: &str| -> Result<f64> {
+        info[key].as_str()
+            .and_then(|value| value.parse().ok())
+            .filter(|value: &f64| value.is_finite())
+            .ok_or_else(|| DownloaderError::Ffmpeg(format!("loudnorm measurement has no usable {} (silent audio?)", key)))
+    };
+    Ok(Measurement {
+        integrated: field("input_i")?,
+        true_peak: field("input_tp")?,
+        range: field("input_lra")?,
+        threshold: field("input_thresh")?,
+        offset: field("target_offset")?,
+    })
+}
+
+/// `silencedetect` lines: `silence_start: 1.5`, then `silence_end: 4.2 | silence_duration: 2.7`.
+fn parse_silences(stderr: &str) -> Vec<(f64, Option<f64>)> {
+    let value = |line: &str, key: &str| line.split(key).nth(1)?.split_whitespace().next()?.parse::<f64>().ok();
+    let mut silences = Vec::new();
+    for line in stderr.lines().filter(|line| line.contains("silencedetect")) {
+        if let Some(start) = value(line, "silence_start: ") {
+            silences.push((start.max(0.0), None));
+        } else if let (Some(end), Some(last)) = (value(line, "silence_end: "), silences.last_mut()) {
+            last.1 = Some(end);
+        }
+    }
+    silences
+}
+
+fn target() -> String {
+    format!("loudnorm=I={}:TP={}:LRA={}", TARGET_LUFS, TRUE_PEAK, LOUDNESS_RANGE)
+}
+
+/// Audio filter for the second pass, which applies `measured` in one linear gain change.
+/// Without a measurement loudnorm adjusts as it goes, which can pump on dynamic music.
+pub fn filter(measured: Option<&Measurement>) -> String {
+    // loudnorm works at 192 kHz internally; bring it back to a rate every player takes
+    match measured {
+        Some(m) => format!(
+            "{}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}:linear=true,aresample=48000",
+            target(), m.integrated, m.true_peak, m.range, m.threshold, m.offset
+        ),
+        None => format!("{},aresample=48000", target()),
+    }
+}
@@ -1,14 +1,54 @@
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
-use std::io::Write;
-use rust_downloader::{logger, Config, video_info, downloader, video_manager, dependencies, utils};
+use std::path::{Path, PathBuf};
+use std::io::{IsTerminal, Write};
+use std::time::Duration;
+use rust_downloader::error_codes::ErrorCode;
+use rust_downloader::ytdlp_error::YtDlpError;
+use rust_downloader::error::{DownloaderError, Result};
+use rust_downloader::cancel::CancelHandle;
+use rust_downloader::config::{parse_timestamp, AudioOutputFormat, ClipRange, ConversionProfile, CookieSource, FreeFormats, LicenseFilter, LoopFormat, AudioVisualizer, OutputResolution, OverlayPosition, StillExport, StillFormat, TextOverlay, SponsorBlockMode};
+use rust_downloader::encoders::Encoder;
+use rust_downloader::{logger, Config, video_info, downloader, wallpaper, dependencies, utils, library, spaces, displays, daemon, orphans, impact, update, crash, auth, review, compare, queue, history, integrity, dashboard, status, notify, mqtt, sync, playlist, transcript, frames, rotation};
 
 #[derive(Parser, Debug)]
 #[command(name = "rust-downloader")]
 #[command(about = "Race into the future with stunning live video! Transform any YouTube video into a dynamic video with precision and speed.", long_about = None)]
 struct Args {
-    /// YouTube URL to download (optional). If omitted, you'll be prompted to paste one.
-    url: Option<String>,
+    #[command(subcommand)]
+    command: Option<Commands>,
+
+    /// YouTube URL(s) to download (optional). If omitted, you'll be prompted to paste one.
+    urls: Vec<String>,
+
+    /// Config file to use instead of ~/.config/rust-downloader/config.toml
+    #[arg(long, value_name = "FILE", global = true)]
+    config: Option<PathBuf>,
+
+    /// Write live job progress as JSON to this file every second, for menu bar plugins
+    #[arg(long, value_name = "FILE", global = true)]
+    status_file: Option<PathBuf>,
+
+    /// File with one URL per line to download as a batch
+    #[arg(long, value_name = "FILE")]
+    batch_file: Option<PathBuf>,
+
+    /// Number of downloads to run at once when several URLs are given
+    #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u32).range(1..=8))]
+    jobs: u32,
+
+    /// Number of downloaded videos to convert at once when several URLs are given
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=8))]
+    convert_jobs: u32,
+
+    /// Fetch each download in this many parts at once (DASH/HLS fragments, or ranges of a
+    /// directly fetched file); overrides concurrent_fragments under [download]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..=16))]
+    concurrent_fragments: Option<u32>,
+
+    /// The same video at another URL (e.g. on Vimeo), downloaded when the URL is removed,
+    /// private or blocked; repeat to try several in order
+    #[arg(long = "mirror", value_name = "URL", global = true)]
+    mirrors: Vec<String>,
     
     /// Disable video installation (download only mode)
     #[arg(long)]
@@ -21,9 +61,214 @@ struct Args {
     /// Custom output directory
     #[arg(short, long)]
     output: Option<PathBuf>,
+
+    /// Bake an audio visualizer from the soundtrack into the wallpaper
+    #[arg(long, value_enum)]
+    visualizer: Option<AudioVisualizer>,
+
+    /// Bake a caption into the video; supports {title}, {uploader} and {date}
+    #[arg(long)]
+    overlay_text: Option<String>,
+
+    /// Where to draw the overlay text
+    #[arg(long, value_enum, default_value = "bottom-right")]
+    overlay_position: OverlayPosition,
+
+    /// Font name or font file for the overlay text
+    #[arg(long)]
+    overlay_font: Option<String>,
+
+    /// Overlay font size in pixels (at 4K)
+    #[arg(long, default_value_t = 48)]
+    overlay_size: u32,
+
+    /// Overlay text opacity from 0.0 to 1.0
+    #[arg(long, default_value_t = 0.8)]
+    overlay_opacity: f32,
+
+    /// Cut leading title cards and trailing end screens (black frames/silence) before looping
+    #[arg(long)]
+    auto_trim: bool,
+
+    /// Split the video at scene cuts and convert each scene separately (optional threshold, default 0.4)
+    #[arg(long, value_name = "THRESHOLD", num_args = 0..=1, require_equals = true, default_missing_value = "0.4")]
+    split_scenes: Option<f64>,
+
+    /// Cut the video into one file per YouTube chapter, named after the chapter titles
+    #[arg(long, conflicts_with = "split_scenes")]
+    split_chapters: bool,
+
+    /// Also export the best frame as a still image sized to the display (png or heic)
+    #[arg(long, value_enum, value_name = "FORMAT", num_args = 0..=1, require_equals = true, default_missing_value = "png")]
+    still: Option<StillFormat>,
+
+    /// Set the exported still as the static desktop picture
+    #[arg(long, requires = "still")]
+    set_still: bool,
+
+    /// Extra downscaled outputs rendered in the same pass, e.g. 1080p,1440p
+    #[arg(long, value_delimiter = ',', value_parser = OutputResolution::parse)]
+    also: Vec<OutputResolution>,
+
+    /// Encode at low priority (taskpolicy -b on macOS, nice elsewhere) to keep the machine usable
+    #[arg(long)]
+    background_encode: bool,
+
+    /// Limit the number of ffmpeg encoding threads
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    encode_threads: Option<u32>,
+
+    /// Install on this display only (1 = main display; see 'wallpaper displays')
+    #[arg(long, value_name = "INDEX", value_parser = clap::value_parser!(u32).range(1..))]
+    display: Option<u32>,
+
+    /// Replace the macOS system videos in this folder instead of the configured one
+    /// (e.g. a sandbox copy, or a beta's renamed 4KSDR240FPS)
+    #[arg(long, value_name = "DIR", global = true)]
+    target_dir: Option<PathBuf>,
+
+    /// Try the installation without sudo or touching the system: system videos, backups and
+    /// the install itself all go into this folder, and daemons are not restarted
+    #[arg(long, value_name = "DIR", global = true, conflicts_with = "target_dir")]
+    sandbox: Option<PathBuf>,
+
+    /// After installing, compare idle wallpaper CPU usage before/after over a window (seconds, default 20)
+    #[arg(long, value_name = "SECONDS", num_args = 0..=1, require_equals = true, default_missing_value = "20")]
+    measure_impact: Option<u64>,
+
+    /// Download only the first part of the video (e.g. 30s, 2m) to try the whole pipeline quickly
+    #[arg(long, value_name = "LENGTH", value_parser = parse_sample_length)]
+    sample: Option<u32>,
+
+    /// Keep only this part of the video, e.g. 00:01:30-00:02:45 or 90-165
+    #[arg(long, value_name = "START-END", value_parser = ClipRange::parse, conflicts_with_all = ["start", "end", "sample"])]
+    clip: Option<ClipRange>,
+
+    /// Start the clip here (seconds or [HH:]MM:SS)
+    #[arg(long, value_name = "TIME", value_parser = parse_timestamp, conflicts_with = "sample")]
+    start: Option<f64>,
+
+    /// End the clip here (seconds or [HH:]MM:SS)
+    #[arg(long, value_name = "TIME", value_parser = parse_timestamp, conflicts_with = "sample")]
+    end: Option<f64>,
+
+    /// Start the clip where a `t=` link points (e.g. youtu.be/ID?t=90) instead of at 0:00
+    #[arg(long, conflicts_with_all = ["clip", "start", "sample"])]
+    honor_timestamp: bool,
+
+    /// Cut SponsorBlock sponsor, intro, outro and reminder segments out (or `=mark` them as chapters)
+    #[arg(long, value_enum, value_name = "MODE", num_args = 0..=1, require_equals = true, default_missing_value = "remove")]
+    sponsorblock: Option<SponsorBlockMode>,
+
+    /// Same as --sponsorblock=remove
+    #[arg(long, conflicts_with = "sponsorblock")]
+    skip_sponsors: bool,
+
+    /// Re-encode even when the source is already HEVC that could be remuxed
+    #[arg(long)]
+    force_reencode: bool,
+
+    /// Print why each candidate format was ranked where it was
+    #[arg(long)]
+    explain_selection: bool,
+
+    /// Prefer royalty-free VP9/AV1 and Opus in WebM and skip the HEVC conversion (`=av1` re-encodes to AV1 instead)
+    #[arg(long, value_enum, value_name = "MODE", num_args = 0..=1, require_equals = true, default_missing_value = "keep")]
+    free_formats: Option<FreeFormats>,
+
+    /// Make a looping GIF or silent VP9 WebM instead of the wallpaper .mov; best with --clip
+    #[arg(long, value_enum, value_name = "FORMAT", conflicts_with = "free_formats")]
+    format: Option<LoopFormat>,
+
+    /// Frame rate of the --format loop [default: 15]
+    #[arg(long, value_name = "FPS", value_parser = clap::value_parser!(u32).range(1..=60))]
+    loop_fps: Option<u32>,
+
+    /// Maximum width of the --format loop in pixels [default: 640]
+    #[arg(long, value_name = "PIXELS", value_parser = clap::value_parser!(u32).range(16..))]
+    loop_width: Option<u32>,
+
+    /// How many times the --format loop plays; 0 repeats forever [default: 0]
+    #[arg(long, value_name = "COUNT")]
+    loop_count: Option<u32>,
+
+    /// Conversion profile: wallpaper-4k60 (default), archive-lossless, mobile-1080p, prores, music (audio, playlists as albums), or one from [profiles]
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["free_formats", "format"])]
+    profile: Option<String>,
+
+    /// Experimental: make an iOS Live Photo (3-second portrait .mov plus key photo) for iPhone wallpapers
+    #[arg(long, conflicts_with_all = ["free_formats", "format", "profile"])]
+    live_photo: bool,
+
+    /// Download only the audio track; AAC and Opus are copied without re-encoding
+    #[arg(long, conflicts_with_all = ["free_formats", "format", "profile", "live_photo", "visualizer", "still", "split_scenes"])]
+    audio_only: bool,
+
+    /// File format for --audio-only and --profile music: m4a, opus, mp3 or flac [default: keep the source codec]
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    audio_format: Option<AudioOutputFormat>,
+
+    /// Normalize loudness to -16 LUFS (two-pass EBU R128 loudnorm) while converting
+    #[arg(long, conflicts_with = "strip_audio")]
+    normalize: bool,
+
+    /// Re-encode the audio at this bitrate in kbit/s while converting
+    #[arg(long, value_name = "KBPS", value_parser = clap::value_parser!(u32).range(8..=1024), conflicts_with = "strip_audio")]
+    audio_bitrate: Option<u32>,
+
+    /// Leave the audio out of the converted video; wallpapers play muted anyway
+    #[arg(long, conflicts_with = "audio_only")]
+    strip_audio: bool,
+
+    /// HEVC encoder for the .mov conversion; auto-detected when omitted (see the check command)
+    #[arg(long, value_enum, value_name = "ENCODER")]
+    encoder: Option<Encoder>,
+
+    /// Analyze and print the yt-dlp and ffmpeg commands that would run, without running them
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Download a static ffmpeg when none is found, without asking (builds with managed-ffmpeg)
+    #[arg(long, global = true)]
+    auto_install_deps: bool,
+
+    /// Print JSON events (analysis, progress, completion) on stdout instead of log lines, for scripts
+    #[arg(long, conflicts_with_all = ["video", "dry_run"])]
+    json: bool,
+
+    /// Read the YouTube session from an exported cookies.txt file, for age-restricted, private and members-only videos
+    #[arg(long, value_name = "FILE", conflicts_with = "cookies_from_browser")]
+    cookies: Option<PathBuf>,
+
+    /// Read the YouTube session from a browser, e.g. firefox or "chrome:Profile 2"
+    #[arg(long, value_name = "BROWSER")]
+    cookies_from_browser: Option<String>,
+
+    /// Save the largest YouTube thumbnail (<name>.jpg) and the first frame as a poster (<name>.poster.jpg)
+    #[arg(long)]
+    thumbnail: bool,
+
+    /// Only download videos under this license; searches use YouTube's license filter
+    #[arg(long, value_enum, value_name = "LICENSE")]
+    license: Option<LicenseFilter>,
+}
+
+/// Parse a sample length such as `30`, `30s` or `2m` into seconds.
+fn parse_sample_length(value: &str) -> Result<u32, String> {
+    let value = value.trim().to_lowercase();
+    let (number, multiplier) = match value.strip_suffix('m') {
+        Some(minutes) => (minutes, 60),
+        None => (value.strip_suffix('s').unwrap_or(&value), 1),
+    };
+
+    match number.trim().parse::<u32>() {
+        Ok(n) if n > 0 => Ok(n * multiplier),
+        _ => Err(format!("invalid sample length '{}', expected e.g. 30s or 2m", value)),
+    }
 }
 
 #[derive(Subcommand, Debug)]
+#[command(disable_help_subcommand = true)]
 enum Commands {
     /// Interactive mode - prompts for URL and walks through setup
     Interactive,
@@ -50,12 +295,347 @@ enum Commands {
     
     /// Check dependencies and environment
     Check,
+
+    /// Download short samples of several formats and compare them side by side
+    Compare {
+        /// YouTube URL to sample
+        url: String,
+
+        /// Format ids to compare, e.g. 299,303 (see yt-dlp -F)
+        #[arg(long, value_delimiter = ',', required = true)]
+        formats: Vec<String>,
+
+        /// Length of each sample in seconds
+        #[arg(long, default_value_t = 10)]
+        seconds: u32,
+
+        /// Export one still per format instead of a side-by-side video
+        #[arg(long)]
+        stills: bool,
+    },
     
+    /// Save a video's subtitles or automatic captions as a timestamped transcript
+    Transcript {
+        /// YouTube URL
+        url: String,
+
+        /// Subtitle language code
+        #[arg(long, default_value = "en")]
+        lang: String,
+
+        /// Write plain text instead of Markdown
+        #[arg(long)]
+        txt: bool,
+    },
+
+    /// Save stills from a video file or YouTube URL into a folder
+    Frames {
+        /// Video file, or YouTube URL to download first (without converting)
+        input: String,
+
+        /// One still every DURATION, e.g. 5s or 1m
+        #[arg(long, value_name = "DURATION", value_parser = parse_sample_length, required_unless_present = "count", conflicts_with = "count")]
+        every: Option<u32>,
+
+        /// This many stills spread evenly over the video
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..=2000))]
+        count: Option<u32>,
+    },
+
     /// Display usage information
     Help,
+
+    /// Manage downloaded wallpapers
+    Library {
+        #[command(subcommand)]
+        action: LibraryCommands,
+    },
+
+    /// Manage installed wallpapers
+    Wallpaper {
+        #[command(subcommand)]
+        action: WallpaperCommands,
+    },
+
+    /// Configure YouTube sign-in (browser cookies, PO token provider)
+    Auth {
+        #[command(subcommand)]
+        action: AuthCommands,
+    },
+
+    /// Explain an error code (e.g. E0203) and how to fix it
+    Explain {
+        /// Error code or name; lists all codes when omitted
+        code: Option<String>,
+    },
+
+    /// Build a diagnostic bundle (redacted config, recent log, tool versions) for bug reports
+    Report,
+
+    /// Download and install the latest release of this tool
+    SelfUpdate {
+        /// Reinstall even if already on the latest version
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Install or update the yt-dlp this tool manages (the official binary, checksum-verified)
+    UpdateDeps {
+        /// Reinstall even if already on the latest version
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Show recent downloads and totals
+    History {
+        /// Number of entries to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Re-check finished files: streams, both ends decode, and the checksum recorded at download
+    Verify {
+        /// File to check, or `all` for every completed download in the history
+        target: String,
+    },
+
+    /// Local usage statistics (nothing is sent anywhere)
+    Stats {
+        #[command(subcommand)]
+        action: StatsCommands,
+    },
+
+    /// Show the progress of a running download (needs --status-file or status_file in the config)
+    Status {
+        /// Print in the SwiftBar/xbar menu bar plugin format
+        #[arg(long)]
+        xbar: bool,
+
+        /// Stop the running downloads
+        #[arg(long, conflicts_with = "xbar")]
+        cancel: bool,
+    },
+
+    /// Control a running download through its status file
+    Ctl {
+        #[command(subcommand)]
+        action: CtlCommands,
+    },
+
+    /// Inspect the queue of a running batch through its status file
+    Queue {
+        #[command(subcommand)]
+        action: QueueCommands,
+    },
+
+    /// Download what's new in Watch Later, Liked videos, playlists or channels (needs cookies for your own feeds)
+    Sync {
+        /// :ytwatchlater, :ytfav, playlist or channel URLs; defaults to [sync] sources (Watch Later)
+        sources: Vec<String>,
+
+        /// Try videos that were private, deleted or region-blocked on an earlier sync again
+        #[arg(long)]
+        retry_unavailable: bool,
+    },
+
+    /// Cycle through a set of library wallpapers on a schedule
+    Rotate {
+        #[command(subcommand)]
+        action: RotateCommands,
+    },
+
+    /// Run in the foreground, switch wallpapers automatically (requires sudo) and download what `queue add` saved
+    Daemon {
+        /// Seconds between checks
+        #[arg(long, default_value_t = 30)]
+        interval: u64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum LibraryCommands {
+    /// List wallpapers in the library
+    List,
+
+    /// Bundle wallpapers with metadata and thumbnails into a shareable pack
+    ExportPack {
+        /// Library ids to export (all wallpapers when omitted)
+        ids: Vec<String>,
+
+        /// Pack file to write (.tar.zst, .tar.gz or .tar)
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+
+    /// Import a pack created with export-pack into the library
+    ImportPack {
+        /// Pack file to import
+        pack: PathBuf,
+    },
+
+    /// Print the title, channel, source and license of a wallpaper, with a credit line
+    Attribution {
+        /// Library id
+        id: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum RotateCommands {
+    /// Choose the wallpapers to rotate through, in order, and when to switch
+    Set {
+        /// Library ids
+        #[arg(required = true)]
+        ids: Vec<String>,
+
+        /// Hours between wallpapers
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..=168), required_unless_present = "at", conflicts_with = "at")]
+        every: Option<u32>,
+
+        /// Times of day to switch at, e.g. 08:00,20:00
+        #[arg(long, value_delimiter = ',', value_parser = parse_clock_time)]
+        at: Vec<chrono::NaiveTime>,
+
+        /// System video to replace; defaults to the most recently installed one
+        #[arg(long)]
+        target: Option<String>,
+    },
+
+    /// Install the next wallpaper now (what the launchd agent runs)
+    Next,
+
+    /// Show the rotation and its schedule
+    Show,
+
+    /// Write and load a launchd agent that rotates on the schedule without the CLI open (macOS)
+    Install,
+
+    /// Unload and remove the launchd agent
+    Uninstall,
+}
+
+fn parse_clock_time(value: &str) -> Result<chrono::NaiveTime, String> {
+    chrono::NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| format!("invalid time '{}', expected HH:MM", value))
+}
+
+#[derive(Subcommand, Debug)]
+enum StatsCommands {
+    /// Downloads per week, storage used, top uploaders and conversion time
+    Dashboard,
+}
+
+#[derive(Subcommand, Debug)]
+enum CtlCommands {
+    /// Cancel one job of the running download or queue, by its label (e.g. brave-otter)
+    Cancel {
+        job: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum QueueCommands {
+    /// Jobs downloading, converting and waiting for either, with each queue's limit, and the
+    /// entries saved for the daemon
+    List,
+
+    /// Save entries (and --batch-file lines) for the daemon to download; it resumes them
+    /// after a restart or reboot
+    Add {
+        /// URLs or batch-file entries, e.g. "https://youtu.be/ID as ocean"
+        entries: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AuthCommands {
+    /// Choose a browser to read cookies from and an optional PO token provider
+    Setup,
+
+    /// Show the configured credentials
+    Show,
+
+    /// Remove all stored credentials
+    Clear,
+
+    /// List the cookie profiles of [auth.profiles] with their rotation and cooldown status
+    List,
 }
 
-fn prompt_for_url() -> Result<String, Box<dyn std::error::Error>> {
+#[derive(Subcommand, Debug)]
+enum WallpaperCommands {
+    /// Install a library entry as the wallpaper of a specific Space or display (requires sudo)
+    #[command(group = clap::ArgGroup::new("where").required(true).args(["space", "display"]))]
+    Assign {
+        /// Mission Control Space number, starting at 1
+        #[arg(long)]
+        space: Option<u32>,
+
+        /// Display number, starting at 1 for the main display
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        display: Option<u32>,
+
+        /// Library id to install
+        id: String,
+    },
+
+    /// Show which library entries are assigned to which Spaces
+    Spaces,
+
+    /// List connected displays and the library entries assigned to them
+    Displays,
+
+    /// Map a Focus mode to a library entry for the daemon ("none" = no Focus active)
+    Focus {
+        /// Focus mode name as shown in Control Center (e.g. Work, Sleep)
+        mode: Option<String>,
+
+        /// Library id to show while the Focus mode is active
+        id: Option<String>,
+
+        /// Remove the mapping for this Focus mode
+        #[arg(long)]
+        remove: bool,
+    },
+
+    /// Follow the system Light/Dark appearance with two library entries
+    PairAppearance {
+        /// Library id to show in Light mode
+        light: String,
+
+        /// Library id to show in Dark mode
+        dark: String,
+    },
+
+    /// Change between four library entries over the day, by the clock or by sunrise and sunset
+    TimeOfDay {
+        /// Library id to show in the morning
+        morning: String,
+
+        /// Library id to show during the day
+        day: String,
+
+        /// Library id to show in the evening
+        evening: String,
+
+        /// Library id to show at night
+        night: String,
+
+        /// When the morning, day, evening and night start (default 06:00,10:00,18:00,21:00)
+        #[arg(long, value_delimiter = ',', num_args = 1, value_parser = parse_clock_time, conflicts_with = "latitude")]
+        at: Vec<chrono::NaiveTime>,
+
+        /// Follow sunrise and sunset at this latitude (degrees, south negative)
+        #[arg(long, allow_negative_numbers = true, requires = "longitude", value_parser = clap::value_parser!(f64))]
+        latitude: Option<f64>,
+
+        /// Longitude for --latitude (degrees, west negative)
+        #[arg(long, allow_negative_numbers = true, requires = "latitude", value_parser = clap::value_parser!(f64))]
+        longitude: Option<f64>,
+    },
+}
+
+fn prompt_for_url() -> Result<String> {
     use std::io::{self, Write};
     
     loop {
@@ -74,18 +654,18 @@ fn prompt_for_url() -> Result<String, Box<dyn std::error::Error>> {
             continue;
         }
         
-        if utils::validate_youtube_url(url) {
+        if utils::validate_youtube_url(url) || utils::is_video_id(url) || utils::is_search_query(url) {
             if let Some(id) = utils::extract_video_id(url) {
             logger::success(&format!("Valid YouTube URL detected: {}", id));
         }
             return Ok(url.to_string());
         } else {
-            logger::error("Invalid YouTube URL. Please provide a valid YouTube link.");
+            logger::error("Invalid YouTube URL. Please provide a valid YouTube link, video ID or ytsearch: query.");
         }
     }
 }
 
-fn display_summary(download_path: &PathBuf, video_installed: bool, start_time: std::time::SystemTime) {
+fn display_summary(download_path: &Path, video_installed: bool, start_time: std::time::SystemTime) {
     let total_time = start_time.elapsed().unwrap_or_default();
     let total_seconds = total_time.as_secs_f64();
     
@@ -99,12 +679,12 @@ fn display_summary(download_path: &PathBuf, video_installed: bool, start_time: s
 
     if video_installed {
         logger::success("Live video installed successfully!");
-        println!();
+        logger::blank();
         logger::info("Next Steps:");
         logger::info("   1. Restart your Mac to see the live video in action");
         logger::info("   2. If it becomes static after screen lock, run: cargo run --bin refresh");
         logger::info("   3. For best results, ensure your Mac stays plugged in (power saving can pause animation)");
-        println!();
+        logger::blank();
         logger::warning("Common Issue: Live videos may appear static after unlocking from lock screen");
         logger::info("   This is a known macOS behavior - use the refresh utility to fix it");
     } else {
@@ -112,21 +692,25 @@ fn display_summary(download_path: &PathBuf, video_installed: bool, start_time: s
     }
 }
 
-fn handle_error(error: &Box<dyn std::error::Error>, downloader: &mut downloader::Downloader) {
-    logger::error(&format!("Application error: {}", error));
-    
-    // Provide helpful hints based on error type
-    let error_msg = error.to_string();
-    if error_msg.contains("yt-dlp") {
-        logger::warning("Make sure yt-dlp is installed and accessible");
-        logger::info("Install with: brew install yt-dlp (macOS) or pip install yt-dlp");
-    } else if error_msg.contains("ffmpeg") {
-        logger::warning("Make sure ffmpeg is installed and accessible");
-        logger::info("Install with: brew install ffmpeg (macOS) or apt install ffmpeg (Ubuntu)");
-    } else if error_msg.contains("Video unavailable") {
-        logger::warning("The video might be private, deleted, or region-locked");
-    } else if error_msg.contains("network") || error_msg.contains("connection") {
-        logger::warning("Check your internet connection and try again");
+fn handle_error(error: &DownloaderError, downloader: &mut downloader::Downloader) {
+    // Provide a stable code and hint that `explain` can expand on
+    logger::event("error", serde_json::json!({
+        "code": ErrorCode::for_error(error).map(|code| code.code()),
+        "message": error.to_string(),
+    }));
+    match ErrorCode::for_error(error) {
+        Some(code) => {
+            logger::error(&format!("[{}] Application error: {}", code.code(), error));
+            match error {
+                DownloaderError::YtDlp(YtDlpError::AgeRestricted) if auth::has_run_cookies() => {
+                    logger::warning("The account behind the given cookies can't watch this video; make sure it is signed in and has confirmed its age on youtube.com");
+                }
+                DownloaderError::YtDlp(ytdlp_error) => logger::warning(ytdlp_error.guidance()),
+                _ => logger::warning(code.hint()),
+            }
+            logger::info(&format!("Run 'rust-downloader explain {}' for help fixing this", code.code()));
+        }
+        None => logger::error(&format!("Application error: {}", error)),
     }
     
     // Cancel any ongoing download
@@ -135,13 +719,233 @@ fn handle_error(error: &Box<dyn std::error::Error>, downloader: &mut downloader:
     }
 }
 
-fn setup_signal_handlers() {
-    // In a full implementation, we'd set up proper signal handlers
-    // For now, we'll just note that this would be implemented
+fn run_auth_command(action: AuthCommands, config: &Config) -> Result<()> {
+    match action {
+        AuthCommands::Setup => {
+            auth::assisted_setup()?;
+        }
+        AuthCommands::Show => auth::Auth::load().display(config),
+        AuthCommands::Clear => {
+            auth::Auth::clear()?;
+            logger::success("Stored credentials and profile cooldowns removed");
+        }
+        AuthCommands::List => auth::Auth::load().display_profiles(config),
+    }
+    Ok(())
+}
+
+fn explain_error_code(code: Option<&str>) -> Result<()> {
+    let code = match code {
+        Some(code) => ErrorCode::from_code(code).ok_or_else(|| format!("Unknown error code: {}", code))?,
+        None => {
+            logger::header("Error Codes");
+            for code in ErrorCode::ALL {
+                logger::info(&format!("{}  {:<18} {}", code.code(), code.name(), code.hint()));
+            }
+            return Ok(());
+        }
+    };
+
+    logger::header(&format!("{} {}", code.code(), code.name()));
+    println!("{}", code.explanation());
+    Ok(())
+}
+
+/// On Ctrl-C or SIGTERM, stop the running yt-dlp/ffmpeg children, remove incomplete
+/// outputs and exit with the conventional interrupted status.
+fn setup_signal_handlers(cancel: CancelHandle) {
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminated() => {}
+        }
+
+        logger::blank();
+        logger::warning("Interrupted, shutting down...");
+        cancel.cancel();
+        status::stop();
+        orphans::finish();
+        std::process::exit(130);
+    });
     logger::info("Signal handlers initialized");
 }
 
-#[allow(dead_code)]
+/// Resolves on SIGTERM. Windows has no SIGTERM, so there only Ctrl-C interrupts.
+#[cfg(unix)]
+async fn terminated() {
+    match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            terminate.recv().await;
+        }
+        Err(error) => {
+            logger::warning(&format!("Could not listen for SIGTERM: {}", error));
+            std::future::pending().await
+        }
+    }
+}
+
+#[cfg(windows)]
+async fn terminated() {
+    std::future::pending().await
+}
+
+fn apply_output_dir(config: &mut Config, output_dir: &Path) {
+    config.output_dir = Config::expand_tilde(output_dir.to_str().unwrap_or(""));
+}
+
+fn run_library_command(action: LibraryCommands, config: &Config) -> Result<()> {
+    let library = library::Library::new(config);
+
+    match action {
+        LibraryCommands::List => library.display_entries(),
+        LibraryCommands::ExportPack { ids, output } => {
+            library.export_pack(&ids, &output)?;
+        }
+        LibraryCommands::ImportPack { pack } => {
+            library.import_pack(&pack)?;
+        }
+        LibraryCommands::Attribution { id } => {
+            library.attribution(&id, history::History::load(config).ok().as_ref())?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_wallpaper_command(action: WallpaperCommands, config: &Config) -> Result<()> {
+    match action {
+        WallpaperCommands::Assign { space: Some(space), id, .. } => {
+            spaces::assign_to_space(config, space, &id).await?;
+        }
+        WallpaperCommands::Assign { display, id, .. } => {
+            let index = display.ok_or("Missing --space or --display")? as usize;
+            let entry = library::Library::new(config).find(&id).ok_or_else(|| format!("No library entry with id '{}'", id))?;
+            if displays::install_on_display(config, index, &entry.path, &entry.id).await?.is_none() {
+                return Err("Assignment cancelled by user".into());
+            }
+        }
+        WallpaperCommands::Spaces => spaces::SpaceAssignments::load(config).display(),
+        WallpaperCommands::Displays => displays::DisplayAssignments::load(config).display(),
+        WallpaperCommands::Focus { mode, id, remove } => {
+            let mut automation = daemon::Automation::load(config);
+            match (mode, id) {
+                (Some(mode), _) if remove => {
+                    if automation.remove_focus(&mode).is_none() {
+                        return Err(format!("Focus mode '{}' is not mapped", mode).into());
+                    }
+                    automation.save(config)?;
+                    logger::success(&format!("Removed mapping for Focus {}", mode));
+                }
+                (Some(mode), Some(id)) => {
+                    if library::Library::new(config).find(&id).is_none() {
+                        return Err(format!("No library entry with id '{}'", id).into());
+                    }
+                    automation.remove_focus(&mode);
+                    automation.focus.insert(mode.clone(), id.clone());
+                    automation.save(config)?;
+                    logger::success(&format!("Focus {} will show {}", mode, id));
+                    logger::info("Run 'sudo rust-downloader daemon' to switch automatically");
+                }
+                (Some(_), None) => return Err("Missing library id for the Focus mode".into()),
+                (None, _) => automation.display(),
+            }
+        }
+        WallpaperCommands::PairAppearance { light, dark } => {
+            let library = library::Library::new(config);
+            for id in [&light, &dark] {
+                if library.find(id).is_none() {
+                    return Err(format!("No library entry with id '{}'", id).into());
+                }
+            }
+
+            let mut automation = daemon::Automation::load(config);
+            automation.appearance = Some(daemon::AppearancePair { light: light.clone(), dark: dark.clone() });
+            automation.save(config)?;
+            logger::success(&format!("Light mode shows {}, Dark mode shows {}", light, dark));
+            logger::info("Run 'sudo rust-downloader daemon' to switch automatically");
+        }
+        WallpaperCommands::TimeOfDay { morning, day, evening, night, at, latitude, longitude } => {
+            let library = library::Library::new(config);
+            for id in [&morning, &day, &evening, &night] {
+                if library.find(id).is_none() {
+                    return Err(format!("No library entry with id '{}'", id).into());
+                }
+            }
+            let starts = match at.len() {
+                0 => daemon::TimeOfDaySet::default_starts(),
+                4 if at.windows(2).all(|pair| pair[0] < pair[1]) => [at[0], at[1], at[2], at[3]],
+                4 => return Err("--at times must be in order: morning, day, evening, night".into()),
+                _ => return Err("--at takes four times: when the morning, day, evening and night start".into()),
+            };
+            let location = latitude.zip(longitude);
+            if let Some((latitude, longitude)) = location {
+                if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+                    return Err(format!("{}, {} is not a valid location", latitude, longitude).into());
+                }
+            }
+
+            let mut automation = daemon::Automation::load(config);
+            automation.time_of_day = Some(daemon::TimeOfDaySet { morning, day, evening, night, starts, location });
+            automation.save(config)?;
+            automation.display();
+            logger::info("Run 'sudo rust-downloader daemon' to switch automatically");
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_rotate_command(action: RotateCommands, config: &Config, config_file: Option<&Path>) -> Result<()> {
+    match action {
+        RotateCommands::Set { ids, every, mut at, target } => {
+            let library = library::Library::new(config);
+            if let Some(id) = ids.iter().find(|id| library.find(id).is_none()) {
+                return Err(format!("No library entry with id '{}'", id).into());
+            }
+            at.sort();
+            at.dedup();
+            let schedule = match every {
+                Some(hours) => rotation::Schedule::Every(hours),
+                None => rotation::Schedule::At(at),
+            };
+
+            let rotation = rotation::Rotation { entries: ids, schedule, target_file: target, current: None, last_rotated: None };
+            rotation.save(config)?;
+            logger::success(&format!("Rotating through {} wallpapers {}", rotation.entries.len(), rotation.schedule.describe()));
+            if rotation::agent_path().is_some_and(|path| path.exists()) {
+                rotation::install_agent(&rotation, config, config_file)?;
+                logger::info("Reloaded the launchd agent with the new schedule");
+            } else {
+                logger::info("Run 'rust-downloader rotate install' to rotate in the background");
+            }
+        }
+        RotateCommands::Next => rotation::advance(config).await?,
+        RotateCommands::Show => match rotation::Rotation::load(config) {
+            Some(rotation) => rotation.display(config),
+            None => {
+                logger::info("No rotation set up");
+                logger::info("Create one with: rust-downloader rotate set <id> <id> --every 6");
+            }
+        },
+        RotateCommands::Install => {
+            let rotation = rotation::Rotation::load(config)
+                .ok_or("No rotation set up; create one with 'rust-downloader rotate set <ids>' first")?;
+            let path = rotation::install_agent(&rotation, config, config_file)?;
+            logger::success(&format!("Wallpapers will rotate {}", rotation.schedule.describe()));
+            logger::file(&format!("Agent: {}", path.display()));
+        }
+        RotateCommands::Uninstall => {
+            if rotation::uninstall_agent()? {
+                logger::success("Removed the rotation agent; the current wallpaper stays installed");
+            } else {
+                logger::info("No rotation agent installed");
+            }
+        }
+    }
+
+    Ok(())
+}
+
 fn display_usage() {
     logger::header("Rust YouTube Downloader ");
     logger::info("==========================================================");
@@ -157,6 +961,32 @@ fn display_usage() {
     logger::info("   rust-downloader download URL        (download only)");
     logger::info("   rust-downloader video URL           (download + video)");
     logger::info("   rust-downloader check               (check dependencies)");
+    logger::info("   rust-downloader sync [:ytfav|PLAYLIST] (download what's new in Watch Later)");
+    logger::info("   rust-downloader compare URL --formats 299,303 (compare format samples)");
+    logger::info("   rust-downloader transcript URL --lang en (subtitles as a Markdown transcript)");
+    logger::info("   rust-downloader frames FILE|URL --every 5s (or --count 20) (stills into a folder)");
+    logger::info("   rust-downloader library list        (list downloaded wallpapers)");
+    logger::info("   rust-downloader library export-pack ID... -o pack.tar.zst");
+    logger::info("   rust-downloader library import-pack pack.tar.zst");
+    logger::info("   rust-downloader library attribution ID (credit line for a wallpaper)");
+    logger::info("   rust-downloader wallpaper assign --space 2 ID (per-Space wallpaper)");
+    logger::info("   rust-downloader wallpaper assign --display 2 ID (per-display wallpaper)");
+    logger::info("   rust-downloader wallpaper focus Work ID (Focus-mode wallpaper)");
+    logger::info("   rust-downloader wallpaper pair-appearance LIGHT DARK");
+    logger::info("   rust-downloader wallpaper time-of-day MORNING DAY EVENING NIGHT [--latitude 51.5 --longitude -0.1]");
+    logger::info("   rust-downloader rotate set ID... --every 6 (or --at 08:00,20:00)");
+    logger::info("   rust-downloader rotate install      (rotate in the background via launchd)");
+    logger::info("   rust-downloader history             (recent downloads and totals)");
+    logger::info("   rust-downloader verify all          (re-check downloaded files against their checksums)");
+    logger::info("   rust-downloader stats dashboard     (local usage statistics)");
+    logger::info("   rust-downloader status [--xbar]     (progress of a running download)");
+    logger::info("   rust-downloader ctl cancel JOB      (cancel one running job, e.g. brave-otter)");
+    logger::info("   rust-downloader daemon              (switch wallpapers automatically)");
+    logger::info("   rust-downloader self-update         (install the latest release)");
+    logger::info("   rust-downloader update-deps         (install or update the managed yt-dlp)");
+    logger::info("   rust-downloader report              (diagnostic bundle for bug reports)");
+    logger::info("   rust-downloader explain E0203       (explain an error code)");
+    logger::info("   rust-downloader auth setup          (sign in via browser cookies)");
     logger::info("");
     logger::info("Examples:");
     logger::info("   rust-downloader                     # Start interactive video downloader");
@@ -179,14 +1009,24 @@ fn display_usage() {
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<()> {
     let args = Args::parse();
     let start_time = std::time::SystemTime::now();
     
     // Initialize logger
     logger::init();
+    if args.json {
+        logger::set_sink(logger::Sink::Json);
+    }
 
-    let mut config = Config::default();
+    // Defaults < config file < environment < command line
+    let mut config = match Config::load(args.config.as_deref()) {
+        Ok(config) => config,
+        Err(error) => {
+            logger::error(&error.to_string());
+            std::process::exit(1);
+        }
+    };
     
     // Apply command line arguments
     if args.video {
@@ -196,27 +1036,529 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     if args.download_only {
         config.enable_video = false;
     }
+
+    // Installing asks questions on the terminal, which a wrapping script can't answer
+    if args.json && config.enable_video {
+        logger::warning("Wallpaper installation is skipped with --json; the download is kept");
+        config.enable_video = false;
+    }
+    if args.json && args.command.is_none() && args.urls.is_empty() && args.batch_file.is_none() {
+        logger::error("--json needs a URL; it can't prompt for one");
+        std::process::exit(2);
+    }
+    let single_url = match &args.command {
+        None => args.urls.len() == 1 && args.batch_file.is_none(),
+        Some(command) => matches!(command, Commands::Download { .. } | Commands::Video { .. }),
+    };
+    if !args.mirrors.is_empty() && !single_url {
+        logger::error("--mirror is for downloading a single URL");
+        std::process::exit(2);
+    }
+    if args.honor_timestamp && !single_url {
+        logger::error("--honor-timestamp is for downloading a single URL");
+        std::process::exit(2);
+    }
     
     if let Some(output_dir) = &args.output {
-        config.output_dir = Config::expand_tilde(output_dir.to_str().unwrap_or(""));
+        apply_output_dir(&mut config, output_dir);
+    }
+
+    if let Some(path) = &args.status_file {
+        config.status_file = Some(Config::expand_tilde(&path.to_string_lossy()));
+    }
+
+    // The download/video subcommands' own flags, applied before anything reads the config
+    match &args.command {
+        Some(Commands::Download { output, .. }) => {
+            if let Some(output_dir) = output {
+                apply_output_dir(&mut config, output_dir);
+            }
+            config.enable_video = false;
+        }
+        Some(Commands::Video { output, .. }) => {
+            if let Some(output_dir) = output {
+                apply_output_dir(&mut config, output_dir);
+            }
+            config.enable_video = true;
+        }
+        _ => {}
+    }
+
+    config.conversion_settings.audio_visualizer = args.visualizer;
+    config.conversion_settings.auto_trim |= args.auto_trim;
+    config.conversion_settings.split_scenes = args.split_scenes;
+    config.conversion_settings.split_chapters |= args.split_chapters;
+    if !args.also.is_empty() {
+        config.conversion_settings.extra_resolutions = args.also.clone();
+    }
+    config.conversion_settings.background_encode |= args.background_encode;
+    if args.encode_threads.is_some() {
+        config.conversion_settings.threads = args.encode_threads;
+    }
+    config.video_settings.impact_window = args.measure_impact;
+    config.video_settings.display = args.display.map(|index| index as usize);
+    if let Some(dir) = &args.target_dir {
+        let dir = std::path::absolute(Config::expand_tilde(&dir.to_string_lossy()))?;
+        if let Err(error) = config.video_settings.set_target_dir(&dir) {
+            logger::error(&format!("--target-dir: {}", error));
+            std::process::exit(2);
+        }
+    }
+    if let Some(dir) = &args.sandbox {
+        config.video_settings.use_sandbox(&std::path::absolute(Config::expand_tilde(&dir.to_string_lossy()))?);
+    }
+    config.download_settings.sample_seconds = args.sample;
+    config.download_settings.alternate_urls = args.mirrors.clone();
+    if let Some(count) = args.concurrent_fragments {
+        config.download_settings.concurrent_fragments = count;
+    }
+    config.auto_install_deps |= args.auto_install_deps;
+    if args.auto_install_deps && !cfg!(feature = "managed-ffmpeg") {
+        logger::warning("--auto-install-deps needs a build with --features managed-ffmpeg; install ffmpeg yourself");
+    }
+    config.download_settings.clip = match (args.clip, args.start, args.end) {
+        (Some(clip), _, _) => Some(clip),
+        (None, None, None) => None,
+        (None, start, end) => match ClipRange::new(start.unwrap_or(0.0), end) {
+            Ok(clip) => Some(clip),
+            Err(error) => {
+                logger::error(&error);
+                std::process::exit(2);
+            }
+        },
+    };
+    if args.honor_timestamp {
+        let url = match &args.command {
+            Some(Commands::Download { url, .. } | Commands::Video { url, .. }) => url.as_str(),
+            _ => args.urls[0].as_str(),
+        };
+        if let Err(error) = start_at_link_timestamp(url, &mut config) {
+            logger::error(&error);
+            std::process::exit(2);
+        }
+    }
+    config.conversion_settings.remux.force_reencode |= args.force_reencode;
+    config.video_preferences.explain_selection = args.explain_selection;
+    if args.skip_sponsors {
+        config.download_settings.sponsorblock = Some(SponsorBlockMode::Remove);
+    }
+    if args.sponsorblock.is_some() {
+        config.download_settings.sponsorblock = args.sponsorblock;
+    }
+    if let Some(mode) = args.free_formats {
+        config.use_free_formats(mode);
+    }
+    config.download_settings.save_thumbnail |= args.thumbnail;
+    if args.license.is_some() {
+        config.video_preferences.license = args.license;
+    }
+    if let Some(path) = args.cookies.clone() {
+        config.download_settings.cookies = Some(CookieSource::File(path));
+    }
+    if let Some(browser) = args.cookies_from_browser.clone() {
+        config.download_settings.cookies = Some(CookieSource::Browser(browser));
+    }
+    if args.format.is_some() {
+        config.conversion_settings.loop_format = args.format;
+    }
+    if args.encoder.is_some() {
+        config.conversion_settings.encoder = args.encoder;
+    }
+    if let Some(name) = &args.profile {
+        if config.conversion_settings.find_profile(name).is_none() {
+            let names: Vec<&str> = config.conversion_settings.profiles.iter().map(|p| p.name.as_str()).collect();
+            logger::error(&format!("Unknown profile '{}'; available: {}", name, names.join(", ")));
+            std::process::exit(2);
+        }
+        config.conversion_settings.profile = name.clone();
+    }
+    if args.live_photo {
+        config.conversion_settings.live_photo = true;
+        config.conversion_settings.profile = ConversionProfile::LIVE_PHOTO.to_string();
+    }
+    config.conversion_settings.audio_only |= args.audio_only || config.conversion_settings.profile().music;
+    if args.audio_format.is_some() && !config.conversion_settings.audio_only {
+        logger::error("--audio-format needs --audio-only or a music profile");
+        std::process::exit(2);
+    }
+    if args.audio_format.is_some() {
+        config.conversion_settings.audio_format = args.audio_format;
+    }
+    let audio = &mut config.conversion_settings.audio;
+    audio.normalize |= args.normalize;
+    audio.strip |= args.strip_audio;
+    if args.audio_bitrate.is_some() {
+        audio.bitrate = args.audio_bitrate;
+    }
+    let loop_settings = &mut config.conversion_settings.loop_settings;
+    loop_settings.fps = args.loop_fps.unwrap_or(loop_settings.fps);
+    loop_settings.max_width = args.loop_width.unwrap_or(loop_settings.max_width);
+    loop_settings.loop_count = args.loop_count.unwrap_or(loop_settings.loop_count);
+    config.conversion_settings.still = args.still.map(|format| StillExport {
+        format,
+        set_wallpaper: args.set_still,
+    });
+    config.conversion_settings.text_overlay = args.overlay_text.clone().map(|template| TextOverlay {
+        template,
+        position: args.overlay_position,
+        font: args.overlay_font.clone(),
+        size: args.overlay_size,
+        opacity: args.overlay_opacity,
+    });
+
+    if config.video_preferences.free_formats.is_some() {
+        if config.enable_video && cfg!(target_os = "macos") {
+            logger::error("--free-formats can't be installed as a macOS wallpaper, which only plays HEVC .mov");
+            std::process::exit(2);
+        }
+        let settings = &config.conversion_settings;
+        if settings.text_overlay.is_some() || settings.audio_visualizer.is_some() || !settings.extra_resolutions.is_empty() {
+            logger::warning("Overlays, visualizers and --also only apply to the HEVC conversion and are skipped with --free-formats");
+        }
+    }
+
+    if config.enable_video && config.conversion_settings.audio_only {
+        logger::error("--audio-only keeps no video to install as a wallpaper; use the download command");
+        std::process::exit(2);
+    }
+    let audio = &config.conversion_settings.audio;
+    if audio.strip && config.conversion_settings.audio_only {
+        logger::error("strip_audio leaves nothing for --audio-only to keep");
+        std::process::exit(2);
+    }
+    if audio.strip && audio.reencodes() {
+        logger::warning("The audio is stripped, so normalize and audio_bitrate have no effect");
+    }
+    if config.enable_video && config.conversion_settings.live_photo {
+        logger::error("--live-photo makes an iPhone wallpaper, which can't be installed on the Mac; use the download command");
+        std::process::exit(2);
+    }
+    if config.enable_video && !config.conversion_settings.profile().is_wallpaper() {
+        logger::error(&format!("Profile {} can't be installed as a macOS wallpaper, which only plays HEVC .mov; use the download command", config.conversion_settings.profile));
+        std::process::exit(2);
+    }
+
+    if config.conversion_settings.loop_format.is_some() {
+        if config.enable_video {
+            logger::error("--format makes a clip for sharing, which can't be installed as a wallpaper; use the download command");
+            std::process::exit(2);
+        }
+        if config.download_settings.clip.is_none() && config.download_settings.sample_seconds.is_none() {
+            logger::warning("Looping a whole video makes a very large file; pick a few seconds with --clip START-END");
+        }
+    }
+
+    if let Some(cookies) = &config.download_settings.cookies {
+        if let CookieSource::File(path) = cookies {
+            if !path.is_file() {
+                logger::error(&format!("Cookies file not found: {}", path.display()));
+                std::process::exit(2);
+            }
+        }
+        auth::use_cookies(cookies);
     }
 
     // Ensure output directory exists
     config.ensure_output_dir_exists()?;
 
+    crash::set_config(&config);
+    crash::install_panic_hook();
+
+    // Status output is read by menu bar plugins, so keep update notices out of it
+    if config.check_for_updates && !matches!(args.command, Some(Commands::SelfUpdate { .. } | Commands::UpdateDeps { .. } | Commands::Status { .. } | Commands::Ctl { .. } | Commands::Queue { .. })) {
+        update::notify_if_outdated();
+    }
+
+    // Only downloads report progress; other commands return before the file is finalized
+    let downloads = !args.dry_run && matches!(args.command, None | Some(Commands::Interactive | Commands::Download { .. } | Commands::Video { .. } | Commands::Sync { .. } | Commands::Daemon { .. }));
+    if let (Some(path), true) = (&config.status_file, downloads) {
+        status::start(path.clone());
+    }
+    if downloads {
+        orphans::reap_stale();
+        orphans::start();
+    }
+
     // Handle commands
-    let command_result = if let Some(url) = args.url {
-        // Direct URL provided
-        if config.enable_video{
-            run_with_video(&url, &config, start_time).await
-        } else {
-            run_download_only(&url, &config, start_time).await
+    let command_result = match args.command {
+        Some(Commands::Check) => {
+            let dependency_checker = dependencies::DependencyChecker::new(&config);
+            if let Err(error) = dependency_checker.perform_full_check().await {
+                logger::error(&format!("Environment check failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Compare { url, formats, seconds, stills }) => {
+            if let Err(error) = resolve_url(&url, &config).and_then(|url| compare::compare_formats(&url, &formats, seconds.max(1), &config, stills)) {
+                logger::error(&format!("Comparison failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Transcript { url, lang, txt }) => {
+            if let Err(error) = resolve_url(&url, &config).and_then(|url| transcript::export(&url, &lang, txt, &config)) {
+                logger::error(&format!("Transcript failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Frames { input, every, count }) => {
+            let spacing = match (every, count) {
+                (Some(seconds), _) => frames::Spacing::Every(seconds as f64),
+                (None, count) => frames::Spacing::Count(count.unwrap_or(1)),
+            };
+            if let Err(error) = run_frames(&input, spacing, &config).await {
+                logger::error(&format!("Frame extraction failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Auth { action }) => {
+            if let Err(error) = run_auth_command(action, &config) {
+                logger::error(&format!("Auth command failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Explain { code }) => {
+            if let Err(error) = explain_error_code(code.as_deref()) {
+                logger::error(&error.to_string());
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Report) => {
+            if !crash::has_recorded_failure() {
+                logger::info("No failure recorded yet; bundling environment details only");
+            }
+            match crash::build_bundle() {
+                Ok(path) => logger::success(&format!("Diagnostic bundle written to: {}", path.display())),
+                Err(error) => {
+                    logger::error(&format!("Could not build diagnostic bundle: {}", error));
+                    std::process::exit(1);
+                }
+            }
+            return Ok(());
+        }
+        Some(Commands::SelfUpdate { force }) => {
+            if let Err(error) = update::self_update(force) {
+                logger::error(&format!("Self-update failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::UpdateDeps { force }) => {
+            match dependencies::install_yt_dlp(force) {
+                Ok(path) => logger::info(&format!("Downloads use {}", path.display())),
+                Err(error) => {
+                    logger::error(&format!("Updating yt-dlp failed: {}", error));
+                    std::process::exit(1);
+                }
+            }
+            return Ok(());
+        }
+        Some(Commands::Help) => {
+            display_usage();
+            return Ok(());
+        }
+        Some(Commands::Library { action }) => {
+            if let Err(error) = run_library_command(action, &config) {
+                logger::error(&format!("Library command failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Wallpaper { action }) => {
+            if let Err(error) = run_wallpaper_command(action, &config).await {
+                logger::error(&format!("Wallpaper command failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Rotate { action }) => {
+            if let Err(error) = run_rotate_command(action, &config, args.config.as_deref()).await {
+                logger::error(&format!("Rotate command failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::History { limit }) => {
+            if let Err(error) = history::History::load(&config).and_then(|download_history| download_history.display(limit)) {
+                logger::error(&format!("History command failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Verify { target }) => {
+            if let Err(error) = integrity::verify(&target, &config).await {
+                logger::error(&format!("Verify failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Status { xbar, cancel }) => {
+            let result = match &config.status_file {
+                None => Err("No status file configured; pass --status-file or set status_file in config.toml".into()),
+                Some(path) if xbar => status::print_xbar(path),
+                Some(path) if cancel => status::cancel(path),
+                Some(path) => status::display(path),
+            };
+            if let Err(error) = result {
+                logger::error(&format!("Status command failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Ctl { action: CtlCommands::Cancel { job } }) => {
+            let result = match &config.status_file {
+                None => Err("No status file configured; pass --status-file or set status_file in config.toml".into()),
+                Some(path) => status::cancel_job(path, &job),
+            };
+            if let Err(error) = result {
+                logger::error(&format!("Ctl command failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Queue { action: QueueCommands::List }) => {
+            queue::SavedQueue::load(&config).display();
+            let result = match &config.status_file {
+                Some(path) => status::display_queues(path),
+                None => Ok(()),
+            };
+            if let Err(error) = result {
+                logger::error(&format!("Queue command failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Queue { action: QueueCommands::Add { entries } }) => {
+            let result = queue_for_daemon(entries, args.batch_file.as_deref(), &config);
+            if let Err(error) = result {
+                logger::error(&format!("Queue command failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Stats { action: StatsCommands::Dashboard }) => {
+            if let Err(error) = dashboard::show(&config) {
+                logger::error(&format!("Stats command failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Daemon { interval }) => {
+            let mut wallpaper_daemon = daemon::Daemon::new(&config, Duration::from_secs(interval.max(1)));
+            wallpaper_daemon.set_jobs(args.jobs as usize, args.convert_jobs as usize);
+            let result = wallpaper_daemon.run().await;
+            status::stop();
+            orphans::finish();
+            if let Err(error) = result {
+                logger::error(&format!("Daemon failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Download { url, .. } | Commands::Video { url, .. }) if args.dry_run => {
+            if let Err(error) = run_dry_run(&[url], &config) {
+                logger::error(&format!("Dry run failed: {}", error));
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Sync { sources, retry_unavailable }) => match sync::check(&sources, retry_unavailable, &config) {
+            Ok(plan) if plan.urls().is_empty() => {
+                status::stop();
+                if let Some(Err(error)) = (!args.dry_run).then(|| plan.finish(&config)) {
+                    logger::warning(&format!("Sync state not saved: {}", error));
+                }
+                logger::success("Nothing new to download");
+                return Ok(());
+            }
+            Ok(plan) if args.dry_run => {
+                if let Err(error) = run_dry_run(&plan.urls(), &config) {
+                    logger::error(&format!("Dry run failed: {}", error));
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+            Ok(plan) => {
+                let result = run_queue(&plan.urls(), (args.jobs as usize, args.convert_jobs as usize), &config).await;
+                if let Err(error) = plan.finish(&config) {
+                    logger::warning(&format!("Sync state not saved: {}", error));
+                }
+                result
+            }
+            Err(error) => Err(error),
+        },
+        Some(Commands::Interactive) => interactive_mode(&config, start_time).await,
+        Some(Commands::Download { url, .. }) => run_download_only(&url, &config, start_time).await,
+        Some(Commands::Video { url, .. }) => run_with_video(&url, &config, start_time).await,
+        None => {
+            let mut urls = args.urls.clone();
+            if let Some(batch_file) = &args.batch_file {
+                urls.extend(queue::DownloadQueue::read_batch_file(batch_file)?);
+            }
+
+            // A music profile downloads playlists as albums
+            if config.conversion_settings.profile().music {
+                for url in std::mem::take(&mut urls) {
+                    if !playlist::is_playlist_url(&url) {
+                        urls.push(url);
+                        continue;
+                    }
+                    match playlist::Album::load(&url, &config) {
+                        Ok(album) => {
+                            logger::info(&format!("{}: {} tracks into {}/", url, album.tracks.len(), config.output_dir.join(&album.title).display()));
+                            urls.extend(album.urls());
+                            config.download_settings.albums.push(album);
+                        }
+                        Err(error) => {
+                            logger::error(&format!("Could not read the playlist: {}", error));
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+
+            if args.dry_run && !urls.is_empty() {
+                // Only download entries fetch anything
+                let downloads = urls.iter()
+                    .map(|line| queue::QueueEntry::parse(line).map(|entry| entry.url().map(String::from)))
+                    .filter_map(Result::transpose)
+                    .collect::<Result<Vec<String>>>();
+                if let Err(error) = downloads.and_then(|downloads| run_dry_run(&downloads, &config)) {
+                    logger::error(&format!("Dry run failed: {}", error));
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+
+            // Batch-file entries that name or wait for others need the queue too
+            let expressions = urls.iter().any(|line| !queue::QueueEntry::parse(line).is_ok_and(|entry| entry.is_plain()));
+            let result = match urls.as_slice() {
+                // Direct URL provided
+                [url] if !expressions && config.enable_video => run_with_video(url, &config, start_time).await,
+                [url] if !expressions => run_download_only(url, &config, start_time).await,
+                // Interactive mode
+                [] => interactive_mode(&config, start_time).await,
+                // Several URLs go through the download queue
+                _ => run_queue(&urls, (args.jobs as usize, args.convert_jobs as usize), &config).await,
+            };
+            for album in &config.download_settings.albums {
+                match album.write_m3u(&config.output_dir) {
+                    Ok((path, listed)) => logger::file(&format!("Playlist: {} ({} of {} tracks)", path.display(), listed, album.tracks.len())),
+                    Err(error) => logger::warning(&format!("Could not write the playlist for {}: {}", album.title, error)),
+                }
+            }
+            result
         }
-    } else {
-        // Interactive mode
-        interactive_mode(&config, start_time).await
     };
 
+    status::stop();
+    orphans::finish();
+
     match command_result {
         Ok((download_path, video_installed)) => {
             display_summary(&download_path, video_installed, start_time);
@@ -224,6 +1566,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Err(error) => {
             let mut downloader = downloader::Downloader::new();
             handle_error(&error, &mut downloader);
+            crash::report_failure(&error.to_string());
             std::process::exit(1);
         }
     }
@@ -231,31 +1574,67 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-async fn run_with_video(url: &str, config: &Config, _start_time: std::time::SystemTime) -> Result<(PathBuf, bool), Box<dyn std::error::Error>> {
+async fn run_with_video(url: &str, config: &Config, _start_time: std::time::SystemTime) -> Result<(PathBuf, bool)> {
     logger::header("Rust YouTube Downloader ");
     logger::info("Transform YouTube videos for any purpose");
     logger::info("Intelligent automation with comprehensive error handling");
-    println!();
+    logger::blank();
 
-    // Setup signal handlers
-    setup_signal_handlers();
+    // Setup signal handlers for the downloader's external tools
+    let mut downloader = downloader::Downloader::new();
+    setup_signal_handlers(downloader.cancel_handle());
 
     // Check environment and dependencies
-    let dependency_checker = dependencies::DependencyChecker::new();
+    let dependency_checker = dependencies::DependencyChecker::new(config);
     dependency_checker.perform_full_check().await?;
 
-    // Analyze video
-    let analysis = video_info::analyze(url)?;
+    // Accept bare video ids and ytsearch: shorthand
+    let url = &resolve_url(url, config)?;
 
-    // Perform download and conversion
-    let mut downloader = downloader::Downloader::new();
-    let download_path = downloader.perform_download(url, &analysis, config).await?;
+    // Analyze, download and convert, from a --mirror URL if the video is gone
+    if config.enable_video {
+        downloader.expect_install();
+    }
+    let download_path = download_with_fallback(&mut downloader, url, config).await?;
 
     // Setup video (only if enabled)
     let video_installed = if config.enable_video {
-        logger::info("Starting video installation process...");
-        let video_mgr = video_manager::VideoManager::new();
-        video_mgr.setup_video(&download_path).await?
+        let impact_window = config.video_settings.impact_window.map(Duration::from_secs);
+        let cpu_before = match impact_window {
+            Some(window) => {
+                logger::info(&format!("Measuring idle wallpaper CPU usage for {}s before install...", window.as_secs()));
+                impact::sample_wallpaper_cpu(window).await
+            }
+            None => None,
+        };
+
+        // Batches are reviewed first so only the chosen files go through the sudo step
+        let to_install = if downloader.batch_outputs().len() > 1 && std::io::stdin().is_terminal() {
+            review::review_batch(downloader.batch_outputs())?
+        } else {
+            vec![download_path.clone()]
+        };
+
+        let backend = wallpaper::detect(config)?;
+        logger::info(&format!("Starting video installation process ({})...", backend.name()));
+        let mut installed = false;
+        for (index, path) in to_install.iter().enumerate() {
+            let percent = index as f64 * 100.0 / to_install.len() as f64;
+            status::update(downloader.job_label(), status::Stage::Installing, percent, &format!("{} of {}", index + 1, to_install.len()));
+            installed |= wallpaper::install_on(backend.as_ref(), path, config).await?;
+        }
+        status::finish(downloader.job_label());
+
+        if let (true, Some(window), Some(before)) = (installed, impact_window, cpu_before) {
+            // Give the wallpaper agent time to pick up the new video
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            logger::info(&format!("Measuring idle wallpaper CPU usage for {}s after install...", window.as_secs()));
+            if let Some(after) = impact::sample_wallpaper_cpu(window).await {
+                impact::ImpactReport { before, after }.display(&download_path);
+            }
+        }
+
+        installed
     } else {
         logger::info("Video installation disabled; running in download-only mode.");
         false
@@ -264,31 +1643,210 @@ async fn run_with_video(url: &str, config: &Config, _start_time: std::time::Syst
     Ok((download_path, video_installed))
 }
 
-async fn run_download_only(url: &str, config: &Config, _start_time: std::time::SystemTime) -> Result<(PathBuf, bool), Box<dyn std::error::Error>> {
+/// Check `entries` and the lines of `batch_file` as a queue and save them for the daemon.
+fn queue_for_daemon(entries: Vec<String>, batch_file: Option<&Path>, config: &Config) -> Result<()> {
+    let mut lines = entries;
+    if let Some(batch_file) = batch_file {
+        lines.extend(queue::DownloadQueue::read_batch_file(batch_file)?);
+    }
+    if lines.is_empty() {
+        return Err("Nothing to queue; pass URLs or --batch-file".into());
+    }
+
+    let mut download_queue = queue::DownloadQueue::new(1, 1);
+    for line in &lines {
+        download_queue.push(line)?;
+    }
+    if download_queue.asks() {
+        return Err("The daemon has no terminal to ask with; use `install best of ...` in queued entries".into());
+    }
+    let id = download_queue.save(config)?;
+    logger::success(&format!("Saved {} entries as queue {} for the daemon", download_queue.len(), id));
+    logger::info(&format!("Start it with `rust-downloader daemon` if it isn't running; progress is in {}", queue::SavedQueue::path(config).display()));
+    Ok(())
+}
+
+/// Download and convert several URLs concurrently, then offer the results for installation.
+/// `jobs` is how many download and how many convert at once.
+async fn run_queue(urls: &[String], jobs: (usize, usize), config: &Config) -> Result<(PathBuf, bool)> {
+    let mut download_queue = queue::DownloadQueue::new(jobs.0, jobs.1);
+    setup_signal_handlers(download_queue.cancel_handle());
+
+    let dependency_checker = dependencies::DependencyChecker::new(config);
+    dependency_checker.perform_full_check().await?;
+
+    for url in urls {
+        download_queue.push(url)?;
+    }
+    let installs = download_queue.installs();
+
+    let started = std::time::Instant::now();
+    let results = download_queue.run(config).await;
+    let outputs = queue::display_results(&results);
+
+    if let Some(smtp) = &config.smtp {
+        if let Err(error) = notify::send_queue_report(smtp, &results, started.elapsed()) {
+            logger::warning(&format!("Report not mailed: {}", error));
+        }
+    }
+    let failed_urls: Vec<&str> = results.iter().filter(|r| r.outcome.is_err()).map(|r| r.url.as_str()).collect();
+    mqtt::publish(config, "queue", &serde_json::json!({
+        "total": results.len(),
+        "succeeded": results.len() - failed_urls.len(),
+        "failed": failed_urls.len(),
+        "failed_urls": failed_urls,
+        "seconds": started.elapsed().as_secs_f64(),
+    }), false).await;
+    let first = outputs.first().cloned().ok_or_else(|| format!("All {} downloads failed", results.len()))?;
+
+    // Install entries in the batch file already chose what to install
+    let mut installed = results.iter().any(|r| r.installed && r.outcome.as_ref().is_ok_and(|paths| !paths.is_empty()));
+    if config.enable_video && !installs && std::io::stdin().is_terminal() {
+        let to_install = review::review_batch(&outputs)?;
+        let backend = wallpaper::detect(config)?;
+        for path in &to_install {
+            installed |= wallpaper::install_on(backend.as_ref(), path, config).await?;
+        }
+    }
+
+    Ok((first, installed))
+}
+
+/// Extract stills from a local file, or from a URL downloaded as it is for the purpose.
+async fn run_frames(input: &str, spacing: frames::Spacing, config: &Config) -> Result<PathBuf> {
+    let file = Path::new(input);
+    if file.is_file() {
+        let title = file.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        return frames::extract(file, &title, spacing, config);
+    }
+
+    let url = resolve_url(input, config)?;
+    let mut downloader = downloader::Downloader::new();
+    let analysis = analyze_with_auth_retry(&downloader, &url, config)?;
+    let mut download_config = config.clone();
+    download_config.download_settings.convert_to_mov = false;
+
+    setup_signal_handlers(downloader.cancel_handle());
+    let video = download_with_auth_retry(&mut downloader, &url, &url, &analysis, &download_config).await?;
+    frames::extract(&video, &analysis.info.title, spacing, config)
+}
+
+/// Analyze each URL and show what downloading it would do, one after the other.
+fn run_dry_run(urls: &[String], config: &Config) -> Result<()> {
+    for url in urls {
+        let url = resolve_url(url, config)?;
+        let mut downloader = downloader::Downloader::new();
+        let analysis = analyze_with_auth_retry(&downloader, &url, config)?;
+        downloader.dry_run(&url, &analysis, config)?;
+    }
+    logger::success("Dry run complete; nothing was downloaded or converted");
+    Ok(())
+}
+
+fn resolve_url(input: &str, config: &Config) -> Result<String> {
+    if utils::is_search_query(input) {
+        logger::search(&format!("Searching YouTube: {}", input.split_once(':').map(|(_, q)| q).unwrap_or(input)));
+    }
+    let url = utils::resolve_video_input(input, config.video_preferences.license)?;
+    if url != input.trim() {
+        logger::info(&format!("Using: {}", url));
+    }
+    if let Some(start) = utils::normalize_url(input).start_seconds {
+        match config.download_settings.clip {
+            Some(clip) if clip.start == start as f64 => logger::info(&format!("Starting at {}, where the link points", utils::format_time(start as f64))),
+            Some(_) => {}
+            None => logger::info(&format!("The link points to {}; the whole video will be downloaded (--honor-timestamp starts there)", utils::format_time(start as f64))),
+        }
+    }
+    Ok(url)
+}
+
+/// Start the clip at the link's `t=` timestamp and run to the end of the video.
+/// `--end` still applies; a link without a timestamp leaves the config alone.
+fn start_at_link_timestamp(input: &str, config: &mut Config) -> std::result::Result<(), String> {
+    if let Some(start) = utils::normalize_url(input).start_seconds {
+        let end = config.download_settings.clip.and_then(|clip| clip.end);
+        config.download_settings.clip = Some(ClipRange::new(start as f64, end)?);
+    }
+    Ok(())
+}
+
+/// Analyze, retrying once with the next cookie profile or after the browser sign-in flow
+/// when YouTube throttles or asks for a bot check.
+fn analyze_with_auth_retry(downloader: &downloader::Downloader, url: &str, config: &Config) -> Result<video_info::SelectedFormats> {
+    let session = downloader.auth_session();
+    match video_info::analyze(url, session, config) {
+        Err(error) if session.rotate_after(&error, config) || auth::recover_from_bot_check(&error) => video_info::analyze(url, session, config),
+        result => result,
+    }
+}
+
+/// Analyze and download `url`. When the video is removed, private or blocked there, the
+/// `--mirror` URLs are tried in order; the history records the one that worked.
+async fn download_with_fallback(downloader: &mut downloader::Downloader, url: &str, config: &Config) -> Result<PathBuf> {
+    let mut alternates = config.download_settings.alternate_urls.iter();
+    let mut source = url.to_string();
+    loop {
+        let result = match analyze_with_auth_retry(downloader, &source, config) {
+            Ok(analysis) => download_with_auth_retry(downloader, url, &source, &analysis, config).await,
+            Err(error) => Err(error),
+        };
+        match (result, alternates.next()) {
+            (Err(error), Some(alternate)) if error.is_permanent() => {
+                logger::warning(&format!("{}; trying the mirror {}", error, alternate));
+                source = alternate.clone();
+            }
+            (result, _) => return result,
+        }
+    }
+}
+
+/// Download `source`, recording the outcome in the history under `url`.
+async fn download_with_auth_retry(downloader: &mut downloader::Downloader, url: &str, source: &str, analysis: &video_info::SelectedFormats, config: &Config) -> Result<PathBuf> {
+    let started = std::time::Instant::now();
+    let result = match downloader.perform_download(source, analysis, config).await {
+        Err(error) if downloader.rotate_profile_after(&error, config) || auth::recover_from_bot_check(&error) => {
+            downloader.perform_download(source, analysis, config).await
+        }
+        result => result,
+    };
+
+    if let Ok(download_history) = history::History::load(config) {
+        let outputs = match &result {
+            Ok(_) if !downloader.batch_outputs().is_empty() => Ok(downloader.batch_outputs()),
+            Ok(path) => Ok(std::slice::from_ref(path)),
+            Err(error) => Err(error),
+        };
+        history::record_outcome(&download_history, url, Some(&analysis.info), &downloader.conversion(), outputs, started.elapsed());
+    }
+    result
+}
+
+async fn run_download_only(url: &str, config: &Config, _start_time: std::time::SystemTime) -> Result<(PathBuf, bool)> {
     logger::header("Rust YouTube Downloader");
     logger::info("Download and convert YouTube videos for any purpose");
-    println!();
+    logger::blank();
 
-    // Setup signal handlers
-    setup_signal_handlers();
+    // Setup signal handlers for the downloader's external tools
+    let mut downloader = downloader::Downloader::new();
+    setup_signal_handlers(downloader.cancel_handle());
 
     // Check dependencies only (no sudo needed for download only)
-    let dependency_checker = dependencies::DependencyChecker::new();
     let mut check_config = config.clone();
     check_config.enable_video = false; // Override to skip sudo check
+    let dependency_checker = dependencies::DependencyChecker::new(&check_config);
     let _ = dependency_checker.perform_full_check().await;
 
-    // Analyze video
-    let analysis = video_info::analyze(url)?;
+    // Accept bare video ids and ytsearch: shorthand
+    let url = &resolve_url(url, config)?;
 
-    // Perform download and conversion
-    let mut downloader = downloader::Downloader::new();
-    let download_path = downloader.perform_download(url, &analysis, config).await?;
+    // Analyze, download and convert, from a --mirror URL if the video is gone
+    let download_path = download_with_fallback(&mut downloader, url, config).await?;
 
     Ok((download_path, false))
 }
 
-async fn interactive_mode(config: &Config, start_time: std::time::SystemTime) -> Result<(PathBuf, bool), Box<dyn std::error::Error>> {
+async fn interactive_mode(config: &Config, start_time: std::time::SystemTime) -> Result<(PathBuf, bool)> {
     // Display header
     logger::header("Rust YouTube Downloader ");
     logger::info("Transform YouTube videos into your local machine");
@@ -300,6 +1858,18 @@ async fn interactive_mode(config: &Config, start_time: std::time::SystemTime) ->
 
     // Ask user about video installation
     let mut final_config = config.clone();
+
+    // Offer to start where a `t=` link points, unless a clip was already chosen
+    if let (Some(start), None) = (utils::normalize_url(&url).start_seconds, config.download_settings.clip) {
+        print!("The link starts at {}. Download from there? (y/N): ", utils::format_time(start as f64));
+        std::io::stdout().flush().ok();
+
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        if matches!(input.trim().to_lowercase().as_str(), "y" | "yes") {
+            start_at_link_timestamp(&url, &mut final_config)?;
+        }
+    }
     if !config.enable_video {
         println!();
         logger::info("Supported URL formats:");
@@ -307,6 +1877,8 @@ async fn interactive_mode(config: &Config, start_time: std::time::SystemTime) ->
         logger::info("   • https://youtu.be/VIDEO_ID");
         logger::info("   • https://www.youtube.com/embed/VIDEO_ID");
         logger::info("   • https://www.youtube.com/v/VIDEO_ID");
+        logger::info("   • https://www.youtube.com/shorts/VIDEO_ID, /live/VIDEO_ID, music.youtube.com");
+        logger::info("   • VIDEO_ID or ytsearch:QUERY");
         println!();
 
         print!("Do you want to install this as a live video? (y/N): ");
@@ -0,0 +1,128 @@
+use std::env;
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::dependencies;
+use crate::error::{DownloaderError, Result};
+use crate::http;
+use crate::logger;
+use crate::update;
+
+const TOOLS: [&str; 2] = ["ffmpeg", "ffprobe"];
+
+/// Static builds for Linux, with a `checksums.sha256` asset
+#[cfg(not(target_os = "macos"))]
+const LINUX_BUILDS_URL: &str = "https://api.github.com/repos/BtbN/FFmpeg-Builds/releases/tags/latest";
+
+/// Static builds for macOS, one zip per tool with a `.sha256` next to it
+#[cfg(target_os = "macos")]
+const MACOS_BUILDS_URL: &str = "https://ffmpeg.martin-riedl.de/redirect/latest/macos";
+
+/// Download static ffmpeg and ffprobe builds into `dependencies::managed_dir()`, where
+/// every command finds them before the ones on `PATH`. Each archive is checked against its
+/// published SHA-256 first.
+pub fn install() -> Result<PathBuf> {
+    let dir = dependencies::managed_dir().ok_or("Could not determine data directory")?;
+    fs::create_dir_all(&dir)?;
+    // Unpacked next to the targets so moving them into place is a rename
+    let work = tempfile::Builder::new().prefix(".ffmpeg-install").tempdir_in(&dir)?;
+
+    for (tool, binary) in TOOLS.into_iter().zip(fetch(work.path())?) {
+        #[cfg(unix)]
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755))?;
+        fs::rename(&binary, dir.join(tool))?;
+    }
+
+    let ffmpeg = dir.join("ffmpeg");
+    let output = Command::new(&ffmpeg).arg("-version").output()?;
+    if !output.status.success() {
+        return Err(DownloaderError::MissingDependency(format!("The installed ffmpeg doesn't run: {}", String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    let version = String::from_utf8_lossy(&output.stdout).split_whitespace().nth(2).unwrap_or("unknown").to_string();
+    logger::success(&format!("Installed ffmpeg {} in {}", version, dir.display()));
+    Ok(ffmpeg)
+}
+
+/// ffmpeg and ffprobe, in the order of `TOOLS`, unpacked somewhere in `work`.
+#[cfg(not(target_os = "macos"))]
+fn fetch(work: &Path) -> Result<Vec<PathBuf>> {
+    let platform = match (env::consts::OS, env::consts::ARCH) {
+        ("linux", "x86_64") => "linux64",
+        ("linux", "aarch64") => "linuxarm64",
+        (os, arch) => return Err(DownloaderError::MissingDependency(format!("No static ffmpeg build for this platform ({} {})", os, arch))),
+    };
+    let name = format!("ffmpeg-master-latest-{}-gpl", platform);
+    let archive_name = format!("{}.tar.xz", name);
+
+    logger::search("Looking up the latest static ffmpeg build...");
+    let release = update::fetch_release(LINUX_BUILDS_URL, 15)?;
+    let archive = release.asset(&archive_name).ok_or_else(|| format!("The ffmpeg builds have no {}", archive_name))?;
+    let checksums = release.asset("checksums.sha256").ok_or("The ffmpeg builds have no checksums; refusing to install")?;
+
+    let archive_path = work.join(&archive_name);
+    let checksums_path = work.join("checksums.sha256");
+    logger::download(&format!("Downloading {}...", archive_name));
+    http::download_to(&archive.browser_download_url, &archive_path)?;
+    http::download_to(&checksums.browser_download_url, &checksums_path)?;
+    verify(&archive_path, &fs::read_to_string(&checksums_path)?, &archive_name)?;
+
+    unpack(Command::new("tar").arg("-xJf").arg(&archive_path).arg("-C").arg(work))?;
+    Ok(TOOLS.iter().map(|tool| work.join(&name).join("bin").join(tool)).collect())
+}
+
+/// ffmpeg and ffprobe, in the order of `TOOLS`, unpacked somewhere in `work`.
+#[cfg(target_os = "macos")]
+fn fetch(work: &Path) -> Result<Vec<PathBuf>> {
+    let arch = match env::consts::ARCH {
+        "aarch64" => "arm64",
+        "x86_64" => "amd64",
+        arch => return Err(DownloaderError::MissingDependency(format!("No static ffmpeg build for this platform (macOS {})", arch))),
+    };
+
+    let mut binaries = Vec::new();
+    for tool in TOOLS {
+        let url = format!("{}/{}/release/{}.zip", MACOS_BUILDS_URL, arch, tool);
+        let archive_path = work.join(format!("{}.zip", tool));
+        let checksum_path = work.join(format!("{}.zip.sha256", tool));
+        logger::download(&format!("Downloading static {} for {}...", tool, arch));
+        http::download_to(&url, &archive_path)?;
+        http::download_to(&format!("{}.sha256", url), &checksum_path)?;
+        verify(&archive_path, &fs::read_to_string(&checksum_path)?, &format!("{}.zip", tool))?;
+
+        let unpacked = work.join(tool).with_extension("d");
+        unpack(Command::new("unzip").args(["-o", "-q"]).arg(&archive_path).arg("-d").arg(&unpacked))?;
+        binaries.push(unpacked.join(tool));
+    }
+    Ok(binaries)
+}
+
+/// Compare `path` with its entry in `checksums`: "<sha256>  <name>" lines, or a lone hash.
+fn verify(path: &Path, checksums: &str, name: &str) -> Result<()> {
+    let expected = checksums.lines()
+        .find_map(|line| {
+            let mut fields = line.split_whitespace();
+            let hash = fields.next()?;
+            match fields.next() {
+                Some(file) if file.trim_start_matches('*') != name => None,
+                _ => Some(hash.to_lowercase()),
+            }
+        })
+        .ok_or_else(|| format!("No checksum published for {}", name))?;
+
+    let actual = update::sha256_file(path)?;
+    if actual != expected {
+        return Err(format!("Checksum mismatch for {} (expected {}, got {})", name, expected, actual).into());
+    }
+    logger::success(&format!("Checksum verified for {}", name));
+    Ok(())
+}
+
+fn unpack(command: &mut Command) -> Result<()> {
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(DownloaderError::Other(format!("Could not unpack ffmpeg: {}", String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(())
+}
@@ -0,0 +1,201 @@
+use serde_json::{json, Value};
+use crate::config::Mirror;
+use crate::error::{DownloaderError, Result};
+use crate::extractor::Extractor;
+use crate::http::Request;
+use crate::utils;
+
+pub fn extractor(mirror: &Mirror) -> Box<dyn Extractor> {
+    match mirror {
+        Mirror::Piped(instance) => Box::new(Piped { instance: instance.clone() }),
+        Mirror::Invidious(instance) => Box::new(Invidious { instance: instance.clone() }),
+    }
+}
+
+/// A Piped API instance. Its stream URLs go through the instance's proxy.
+pub struct Piped {
+    instance: String,
+}
+
+/// An Invidious instance, asked for stream URLs through its proxy (`local=true`), as the
+/// plain ones only work from the instance's own address.
+pub struct Invidious {
+    instance: String,
+}
+
+impl Extractor for Piped {
+    fn name(&self) -> String {
+        format!("Piped ({})", host(&self.instance))
+    }
+
+    fn extract(&self, url: &str) -> Result<Value> {
+        let id = utils::extract_video_id(url).ok_or_else(|| DownloaderError::InvalidUrl(url.to_string()))?;
+        from_piped(&get_json(&format!("{}/streams/{}", self.instance, id))?, &id)
+    }
+}
+
+impl Extractor for Invidious {
+    fn name(&self) -> String {
+        format!("Invidious ({})", host(&self.instance))
+    }
+
+    fn extract(&self, url: &str) -> Result<Value> {
+        let id = utils::extract_video_id(url).ok_or_else(|| DownloaderError::InvalidUrl(url.to_string()))?;
+        from_invidious(&get_json(&format!("{}/api/v1/videos/{}?local=true", self.instance, id))?, &id)
+    }
+}
+
+fn host(instance: &str) -> &str {
+    instance.split_once("://").map_or(instance, |(_, rest)| rest)
+}
+
+/// Both APIs answer errors with JSON too, as `{"error": "..."}`.
+fn get_json(url: &str) -> Result<Value> {
+    let response = Request::get(url).send()?;
+    let value: Option<Value> = response.json().ok();
+    if let Some(error) = value.as_ref().and_then(|v| v["error"].as_str()) {
+        return Err(DownloaderError::Network(error.to_string()));
+    }
+    response.error_for_status()?;
+    value.ok_or_else(|| DownloaderError::Network("The instance answered with something other than JSON".to_string()))
+}
+
+/// "mp4" for "video/mp4", "m4a" for "audio/mp4", as yt-dlp names them.
+fn extension(mime: &str) -> Option<&'static str> {
+    match mime.split(';').next()?.trim() {
+        "video/mp4" => Some("mp4"),
+        "audio/mp4" => Some("m4a"),
+        "video/webm" | "audio/webm" => Some("webm"),
+        _ => None,
+    }
+}
+
+/// A format as `yt-dlp --dump-json` lists it; `codec` is the video codec of video streams
+/// and the audio codec of audio streams, as both APIs only list separate streams here.
+#[allow(clippy::too_many_arguments)]
+fn format(id: String, url: &str, ext: &str, video: bool, codec: &str, size: Option<(u64, u64)>, fps: Option<f64>, bitrate: Option<f64>, filesize: Option<u64>) -> Value {
+    let kbps = bitrate.map(|bits| bits / 1000.0);
+    json!({
+        "format_id": id,
+        "url": url,
+        "ext": ext,
+        "protocol": "https",
+        "vcodec": if video { codec } else { "none" },
+        "acodec": if video { "none" } else { codec },
+        "width": size.map(|(width, _)| width),
+        "height": size.map(|(_, height)| height),
+        "fps": fps,
+        "tbr": kbps,
+        // Whole kbps, which is how the audio formats are read
+        "abr": if video { None } else { kbps.map(|kbps| kbps.round() as u64) },
+        "filesize": filesize,
+    })
+}
+
+/// The parts of a yt-dlp info dict the pipeline and `--load-info-json` need.
+fn info(id: &str, fields: Value, formats: Vec<Value>) -> Value {
+    let mut info = json!({
+        "_type": "video",
+        "id": id,
+        "extractor": "youtube",
+        "extractor_key": "Youtube",
+        "webpage_url": format!("https://www.youtube.com/watch?v={}", id),
+        "formats": formats,
+    });
+    if let (Some(info), Value::Object(fields)) = (info.as_object_mut(), fields) {
+        info.extend(fields.into_iter().filter(|(_, value)| !value.is_null()));
+    }
+    info
+}
+
+/// Chapters that only have start times end where the next one starts.
+fn chapters(starts: Vec<(String, f64)>, duration: Option<f64>) -> Vec<Value> {
+    let ends: Vec<Option<f64>> = starts.iter().skip(1).map(|(_, start)| Some(*start)).chain([duration]).collect();
+    starts.into_iter().zip(ends)
+        .filter_map(|((title, start), end)| Some(json!({ "title": title, "start_time": start, "end_time": end? })))
+        .collect()
+}
+
+/// Piped's `/streams/<id>` as yt-dlp info.
+pub fn from_piped(response: &Value, id: &str) -> Result<Value> {
+    let streams = |key: &str| response[key].as_array().cloned().unwrap_or_default();
+    let mut formats = Vec::new();
+    for stream in streams("videoStreams").iter().filter(|s| s["videoOnly"].as_bool() == Some(true)) {
+        let (Some(url), Some(ext)) = (stream["url"].as_str(), stream["mimeType"].as_str().and_then(extension)) else {
+            continue;
+        };
+        let size = stream["width"].as_u64().zip(stream["height"].as_u64());
+        formats.push(format(
+            stream["itag"].to_string(), url, ext, true, stream["codec"].as_str().unwrap_or("unknown"),
+            size, stream["fps"].as_f64(), stream["bitrate"].as_f64(), stream["contentLength"].as_u64().filter(|n| *n > 0),
+        ));
+    }
+    for stream in streams("audioStreams") {
+        let (Some(url), Some(ext)) = (stream["url"].as_str(), stream["mimeType"].as_str().and_then(extension)) else {
+            continue;
+        };
+        formats.push(format(
+            stream["itag"].to_string(), url, ext, false, stream["codec"].as_str().unwrap_or("unknown"),
+            None, None, stream["bitrate"].as_f64(), stream["contentLength"].as_u64().filter(|n| *n > 0),
+        ));
+    }
+    if formats.is_empty() {
+        return Err(DownloaderError::Network("The instance listed no streams".to_string()));
+    }
+
+    let duration = response["duration"].as_f64();
+    let starts = response["chapters"].as_array().into_iter().flatten()
+        .filter_map(|c| Some((c["title"].as_str()?.to_string(), c["start"].as_f64()?)))
+        .collect();
+    Ok(info(id, json!({
+        "title": response["title"],
+        "uploader": response["uploader"],
+        "duration": response["duration"],
+        "view_count": response["views"],
+        // "2021-09-01" or a full timestamp
+        "upload_date": response["uploadDate"].as_str().and_then(|date| date.get(..10)).map(|date| date.replace('-', "")),
+        "description": response["description"],
+        "chapters": chapters(starts, duration),
+    }), formats))
+}
+
+/// Invidious's `/api/v1/videos/<id>` as yt-dlp info.
+pub fn from_invidious(response: &Value, id: &str) -> Result<Value> {
+    let number = |value: &Value| value.as_str().and_then(|s| s.parse::<f64>().ok()).or(value.as_f64());
+    let mut formats = Vec::new();
+    for stream in response["adaptiveFormats"].as_array().into_iter().flatten() {
+        // video/mp4; codecs="avc1.640028"
+        let Some((mime, codecs)) = stream["type"].as_str().map(|t| t.split_once(';').unwrap_or((t, ""))) else {
+            continue;
+        };
+        let (Some(url), Some(ext)) = (stream["url"].as_str(), extension(mime)) else {
+            continue;
+        };
+        let codec = codecs.trim().trim_start_matches("codecs=").trim_matches('"');
+        let video = mime.starts_with("video/");
+        let size = stream["size"].as_str()
+            .and_then(|size| size.split_once('x'))
+            .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+        formats.push(format(
+            stream["itag"].as_str().map(str::to_string).unwrap_or_else(|| stream["itag"].to_string()),
+            url, ext, video, if codec.is_empty() { "unknown" } else { codec },
+            size, stream["fps"].as_f64().filter(|_| video), number(&stream["bitrate"]), number(&stream["clen"]).map(|n| n as u64),
+        ));
+    }
+    if formats.is_empty() {
+        return Err(DownloaderError::Network("The instance listed no streams".to_string()));
+    }
+
+    let upload_date = response["published"].as_i64()
+        .and_then(|seconds| chrono::DateTime::from_timestamp(seconds, 0))
+        .map(|date| date.format("%Y%m%d").to_string());
+    Ok(info(id, json!({
+        "title": response["title"],
+        "uploader": response["author"],
+        "duration": response["lengthSeconds"],
+        "view_count": response["viewCount"],
+        "upload_date": upload_date,
+        "description": response["description"],
+        "license": response["license"].as_str().filter(|license| !license.is_empty()),
+    }), formats))
+}
@@ -0,0 +1,115 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use crate::error::Result;
+use crate::config::{Config, MqttSettings};
+use crate::logger;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const KEEP_ALIVE_SECONDS: u16 = 30;
+
+/// Numbers each connection, so overlapping publishes from one process get distinct client ids
+/// and the broker doesn't drop one as a duplicate session.
+static CONNECTIONS: AtomicU32 = AtomicU32::new(0);
+
+/// Publish `payload` as JSON to `<topic>/<subtopic>` when `[notifications.mqtt]` is set.
+///
+/// Each message uses its own short connection (MQTT 3.1.1, QoS 0), so a broker that is down
+/// only costs a warning, never the download or daemon tick that reported it.
+pub async fn publish(config: &Config, subtopic: &str, payload: &impl Serialize, retain: bool) {
+    let Some(settings) = &config.mqtt else {
+        return;
+    };
+    let topic = format!("{}/{}", settings.topic.trim_end_matches('/'), subtopic);
+
+    let result = match serde_json::to_vec(payload) {
+        Ok(payload) => tokio::time::timeout(TIMEOUT, send(settings, &topic, &payload, retain))
+            .await
+            .unwrap_or_else(|_| Err("timed out".into())),
+        Err(error) => Err(error.into()),
+    };
+    if let Err(error) = result {
+        logger::warning(&format!("MQTT publish to {} failed: {}", topic, error));
+    }
+}
+
+/// `started`, `completed` or `failed` for one download, on `<topic>/job`.
+pub async fn publish_job(config: &Config, job: &str, title: &str, state: &str, error: Option<String>) {
+    publish(config, "job", &json!({
+        "job": job,
+        "title": title,
+        "state": state,
+        "error": error,
+        "at": Utc::now(),
+    }), false).await;
+}
+
+async fn send(settings: &MqttSettings, topic: &str, payload: &[u8], retain: bool) -> Result<()> {
+    let mut stream = TcpStream::connect((settings.host.as_str(), settings.port)).await?;
+
+    stream.write_all(&connect_packet(settings)).await?;
+
+    let mut connack = [0u8; 4];
+    stream.read_exact(&mut connack).await?;
+    match connack {
+        [0x20, 0x02, _, 0] => {}
+        [0x20, 0x02, _, 4 | 5] => return Err("the broker rejected the username or password".into()),
+        [0x20, 0x02, _, code] => return Err(format!("the broker refused the connection (code {})", code).into()),
+        _ => return Err("unexpected reply from the broker".into()),
+    }
+
+    let mut body = encode_string(topic);
+    body.extend_from_slice(payload);
+    stream.write_all(&packet(0x30 | retain as u8, &body)).await?;
+    stream.write_all(&[0xE0, 0x00]).await?;
+    Ok(())
+}
+
+fn connect_packet(settings: &MqttSettings) -> Vec<u8> {
+    let mut flags = 0x02; // clean session
+    let connection = CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+    let mut payload = encode_string(&format!("rust-downloader-{}-{}", std::process::id(), connection));
+    if let Some(username) = &settings.username {
+        flags |= 0x80;
+        payload.extend(encode_string(username));
+        if let Some(password) = &settings.password {
+            flags |= 0x40;
+            payload.extend(encode_string(password));
+        }
+    }
+
+    let mut body = encode_string("MQTT");
+    body.extend([4, flags]);
+    body.extend(KEEP_ALIVE_SECONDS.to_be_bytes());
+    body.extend(payload);
+    packet(0x10, &body)
+}
+
+/// Fixed header (type and flags, variable-length remaining length) followed by `body`.
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    let mut length = body.len();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    packet
+}
+
+fn encode_string(value: &str) -> Vec<u8> {
+    let mut encoded = (value.len() as u16).to_be_bytes().to_vec();
+    encoded.extend_from_slice(value.as_bytes());
+    encoded
+}
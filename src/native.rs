@@ -0,0 +1,124 @@
+use serde_json::{json, Value};
+use crate::error::{DownloaderError, Result};
+use crate::extractor::Extractor;
+use crate::http::Request;
+use crate::utils;
+use crate::ytdlp_error::YtDlpError;
+
+const PLAYER_URL: &str = "https://www.youtube.com/youtubei/v1/player?prettyPrint=false";
+/// The Android app's client, whose formats come with plain URLs rather than signatures
+/// that only yt-dlp knows how to decipher
+const CLIENT_NAME: &str = "ANDROID";
+const CLIENT_VERSION: &str = "19.09.37";
+
+fn user_agent() -> String {
+    format!("com.google.android.youtube/{} (Linux; U; Android 11) gzip", CLIENT_VERSION)
+}
+
+/// YouTube's own player API, asked directly when yt-dlp isn't installed.
+///
+/// Only progressive formats (video and audio in one file, at most 720p) are listed, so the
+/// download needs neither yt-dlp nor a merge.
+pub struct Native;
+
+impl Extractor for Native {
+    fn name(&self) -> String {
+        "YouTube player API".to_string()
+    }
+
+    fn extract(&self, url: &str) -> Result<Value> {
+        let id = utils::extract_video_id(url).ok_or_else(|| DownloaderError::InvalidUrl(url.to_string()))?;
+        from_player(&post_player(&id)?, &id)
+    }
+
+    fn direct(&self) -> bool {
+        true
+    }
+}
+
+fn post_player(id: &str) -> Result<Value> {
+    let body = json!({
+        "videoId": id,
+        "context": { "client": {
+            "clientName": CLIENT_NAME,
+            "clientVersion": CLIENT_VERSION,
+            "androidSdkVersion": 30,
+            "hl": "en",
+            "gl": "US",
+        }},
+        "contentCheckOk": true,
+        "racyCheckOk": true,
+    });
+    Request::post_json(PLAYER_URL, &body)
+        .header("User-Agent", &user_agent())
+        .send()?
+        .error_for_status()?
+        .json()
+}
+
+/// The player response as yt-dlp info, with its progressive formats.
+pub fn from_player(response: &Value, id: &str) -> Result<Value> {
+    let playability = &response["playabilityStatus"];
+    if playability["status"].as_str() != Some("OK") {
+        // "Video unavailable", "This video is private", "Sign in to confirm your age" and the
+        // like read the same as yt-dlp's messages
+        let reason = playability["reason"].as_str().unwrap_or("YouTube would not play the video");
+        return Err(YtDlpError::from_stderr(reason).into());
+    }
+
+    let formats: Vec<Value> = response["streamingData"]["formats"].as_array().into_iter().flatten()
+        .filter_map(format)
+        .collect();
+    if formats.is_empty() {
+        return Err(DownloaderError::Network("YouTube listed no progressive formats with plain URLs; install yt-dlp for this video".to_string()));
+    }
+
+    let details = &response["videoDetails"];
+    let number = |value: &Value| value.as_str().and_then(|s| s.parse::<u64>().ok());
+    let mut info = json!({
+        "_type": "video",
+        "id": id,
+        "extractor": "youtube",
+        "extractor_key": "Youtube",
+        "webpage_url": format!("https://www.youtube.com/watch?v={}", id),
+        "title": details["title"],
+        "uploader": details["author"],
+        "duration": number(&details["lengthSeconds"]),
+        "view_count": number(&details["viewCount"]),
+        "description": details["shortDescription"],
+        "formats": formats,
+    });
+    if let Some(info) = info.as_object_mut() {
+        info.retain(|_, value| !value.is_null());
+    }
+    Ok(info)
+}
+
+/// A progressive format as `yt-dlp --dump-json` lists it, or `None` when it has no plain URL.
+fn format(stream: &Value) -> Option<Value> {
+    let url = stream["url"].as_str()?;
+    // video/mp4; codecs="avc1.42001E, mp4a.40.2"
+    let (mime, codecs) = stream["mimeType"].as_str()?.split_once(';')?;
+    let ext = match mime.trim() {
+        "video/mp4" => "mp4",
+        "video/webm" => "webm",
+        "video/3gpp" => "3gp",
+        _ => return None,
+    };
+    let codecs = codecs.trim().trim_start_matches("codecs=").trim_matches('"');
+    let (vcodec, acodec) = codecs.split_once(',').map(|(v, a)| (v.trim(), a.trim()))?;
+    Some(json!({
+        "format_id": stream["itag"].to_string(),
+        "url": url,
+        "ext": ext,
+        "protocol": "https",
+        "vcodec": vcodec,
+        "acodec": acodec,
+        "width": stream["width"].as_u64(),
+        "height": stream["height"].as_u64(),
+        "fps": stream["fps"].as_f64(),
+        "tbr": stream["bitrate"].as_f64().map(|bits| bits / 1000.0),
+        "filesize": stream["contentLength"].as_str().and_then(|n| n.parse::<u64>().ok()),
+        "http_headers": { "User-Agent": user_agent() },
+    }))
+}
@@ -0,0 +1,122 @@
+use std::io::Write;
+use std::process::Stdio;
+use std::time::Duration;
+use chrono::Local;
+use crate::error::Result;
+use crate::config::SmtpSettings;
+use crate::http;
+use crate::logger;
+use crate::queue::QueueResult;
+use crate::utils;
+
+const BOUNDARY: &str = "rust-downloader-report";
+
+/// Mail the queue summary, with the failed URLs attached as a file that can be passed
+/// straight back to `--batch-file`.
+pub fn send_queue_report(smtp: &SmtpSettings, results: &[QueueResult], elapsed: Duration) -> Result<()> {
+    let failed: Vec<&QueueResult> = results.iter().filter(|r| r.outcome.is_err()).collect();
+    if smtp.only_on_failure && failed.is_empty() {
+        return Ok(());
+    }
+
+    let unavailable = failed.iter().filter(|r| r.unavailable).count();
+    let subject = match failed.len() {
+        0 => format!("rust-downloader: all {} downloads finished", results.len()),
+        _ => format!("rust-downloader: {} of {} downloads failed", failed.len(), results.len()),
+    };
+
+    let mut body = vec![
+        format!(
+            "{} succeeded, {} unavailable, {} failed, in {}",
+            results.len() - failed.len(), unavailable, failed.len() - unavailable, utils::format_time(elapsed.as_secs_f64())
+        ),
+        String::new(),
+    ];
+    for result in results {
+        body.push(match &result.outcome {
+            Ok(paths) => format!("OK      {}  {}", result.label, paths.iter()
+                .map(|p| p.file_name().unwrap_or_default().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")),
+            Err(error) if result.unavailable => format!("SKIPPED {}  {} ({})", result.label, result.url, error),
+            Err(error) => format!("FAILED  {}  {} ({})", result.label, result.url, error),
+        });
+    }
+
+    let mut message = vec![
+        format!("From: {}", smtp.from),
+        format!("To: {}", smtp.to.join(", ")),
+        format!("Subject: {}", subject),
+        format!("Date: {}", Local::now().to_rfc2822()),
+        "MIME-Version: 1.0".to_string(),
+        format!("Content-Type: multipart/mixed; boundary=\"{}\"", BOUNDARY),
+        String::new(),
+        format!("--{}", BOUNDARY),
+        "Content-Type: text/plain; charset=utf-8".to_string(),
+        "Content-Transfer-Encoding: 8bit".to_string(),
+        String::new(),
+    ];
+    message.extend(body);
+
+    if !failed.is_empty() {
+        message.extend([
+            format!("--{}", BOUNDARY),
+            "Content-Type: text/plain; charset=utf-8; name=\"failed-urls.txt\"".to_string(),
+            "Content-Disposition: attachment; filename=\"failed-urls.txt\"".to_string(),
+            String::new(),
+        ]);
+        message.extend(failed.iter().map(|r| r.url.clone()));
+    }
+    message.push(format!("--{}--", BOUNDARY));
+
+    send(smtp, &(message.join("\r\n") + "\r\n"))?;
+    logger::success(&format!("Report mailed to {}", smtp.to.join(", ")));
+    Ok(())
+}
+
+fn send(smtp: &SmtpSettings, message: &str) -> Result<()> {
+    if smtp.to.is_empty() {
+        return Err("[notifications.smtp] has no recipients in 'to'".into());
+    }
+
+    let url = match smtp.port {
+        465 => format!("smtps://{}:{}", smtp.server, smtp.port),
+        port => format!("smtp://{}:{}", smtp.server, port),
+    };
+
+    let mut command = http::curl();
+    command.args(["--url", &url, "--mail-from", &smtp.from]);
+    if smtp.port != 465 {
+        command.arg("--ssl-reqd");
+    }
+    for recipient in &smtp.to {
+        command.args(["--mail-rcpt", recipient]);
+    }
+
+    // Credentials go through a curl config file so they don't show up in the process list
+    let credentials = match &smtp.username {
+        Some(username) => {
+            let mut file = tempfile::NamedTempFile::new()?;
+            let user = format!("{}:{}", username, smtp.password.as_deref().unwrap_or_default());
+            writeln!(file, "user = \"{}\"", user.replace('\\', "\\\\").replace('"', "\\\""))?;
+            command.arg("--config").arg(file.path());
+            Some(file)
+        }
+        None => None,
+    };
+
+    let mut child = command
+        .args(["--upload-file", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().ok_or("curl has no stdin")?.write_all(message.as_bytes())?;
+    let output = child.wait_with_output()?;
+    drop(credentials);
+
+    if !output.status.success() {
+        return Err(format!("Could not send mail through {}: {}", url, String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use crate::cancel::{self, Signal};
+use crate::error::Result;
+use crate::logger;
+
+/// What this run has in flight; `None` until `start`
+static STATE: Mutex<Option<(PathBuf, RunState)>> = Mutex::new(None);
+
+/// A yt-dlp or ffmpeg process started by a run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Child {
+    pub pid: u32,
+    /// e.g. "yt-dlp"; checked against the command line before stopping a leftover process,
+    /// in case its PID was reused
+    pub program: String,
+}
+
+/// The children and incomplete outputs of a run, in `runs/<pid>.json` under the cache
+/// directory. The file exists while the run does, so one whose owner is gone means the run
+/// crashed or was killed and may have left its children behind.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunState {
+    pub pid: u32,
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub children: Vec<Child>,
+    #[serde(default)]
+    pub partial_files: Vec<PathBuf>,
+}
+
+fn runs_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("rust-downloader").join("runs"))
+}
+
+/// Full command line of `pid`, from `ps`, which works the same on macOS and Linux.
+fn command_line(pid: u32) -> Option<String> {
+    let output = Command::new("ps").args(["-o", "args=", "-p", &pid.to_string()]).output().ok()?;
+    let args = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !args.is_empty()).then_some(args)
+}
+
+/// Start recording this run's children and incomplete outputs.
+pub fn start() {
+    let Some(dir) = runs_dir() else {
+        return;
+    };
+    let pid = std::process::id();
+    let state = RunState { pid, started_at: Some(Utc::now()), ..Default::default() };
+    if let Err(error) = fs::create_dir_all(&dir).map_err(Into::into).and_then(|_| save(&dir.join(format!("{}.json", pid)), &state)) {
+        logger::warning(&format!("Could not write the run state file: {}", error));
+        return;
+    }
+    *STATE.lock().unwrap_or_else(|e| e.into_inner()) = Some((dir.join(format!("{}.json", pid)), state));
+}
+
+/// Remove the state file once the run has ended normally.
+pub fn finish() {
+    if let Some((path, _)) = STATE.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        fs::remove_file(path).ok();
+    }
+}
+
+fn save(path: &Path, state: &RunState) -> Result<()> {
+    fs::write(path, serde_json::to_string_pretty(state)?)?;
+    Ok(())
+}
+
+fn update(change: impl FnOnce(&mut RunState)) {
+    let mut guard = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((path, state)) = guard.as_mut() {
+        change(state);
+        save(path, state).ok();
+    }
+}
+
+pub fn track_child(pid: u32, program: &str) {
+    update(|state| state.children.push(Child { pid, program: program.to_string() }));
+}
+
+pub fn untrack_child(pid: u32) {
+    update(|state| state.children.retain(|child| child.pid != pid));
+}
+
+pub fn track_file(path: &Path) {
+    update(|state| state.partial_files.push(path.to_path_buf()));
+}
+
+pub fn untrack_file(path: &Path) {
+    update(|state| state.partial_files.retain(|p| p != path));
+}
+
+/// State files of runs that ended without `finish`, with the path of each.
+pub fn stale_runs() -> Vec<(PathBuf, RunState)> {
+    let Some(entries) = runs_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let own = std::process::id();
+    entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            // An unreadable file is as stale as its owner
+            let state: RunState = fs::read_to_string(&path).ok()
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or_default();
+            (state.pid != own && (state.pid == 0 || !cancel::is_running(state.pid))).then_some((path, state))
+        })
+        .collect()
+}
+
+impl RunState {
+    /// Children that are still running the program they were started as.
+    pub fn orphans(&self) -> Vec<&Child> {
+        self.children.iter()
+            .filter(|child| command_line(child.pid).is_some_and(|args| args.contains(&child.program)))
+            .collect()
+    }
+
+    pub fn existing_partial_files(&self) -> Vec<&PathBuf> {
+        self.partial_files.iter().filter(|path| path.exists()).collect()
+    }
+}
+
+/// Look for runs that crashed, and offer to stop the processes and delete the incomplete
+/// outputs they left behind. Without a terminal to ask on, only report them.
+pub fn reap_stale() {
+    let stale = stale_runs();
+    let leftovers: Vec<(&PathBuf, &RunState, Vec<&Child>, Vec<&PathBuf>)> = stale.iter()
+        .map(|(path, state)| (path, state, state.orphans(), state.existing_partial_files()))
+        .collect();
+    let processes: usize = leftovers.iter().map(|(_, _, orphans, _)| orphans.len()).sum();
+    let files: usize = leftovers.iter().map(|(_, _, _, files)| files.len()).sum();
+
+    if processes + files == 0 {
+        // Nothing left behind; the state files are only stale locks
+        for (path, _) in &stale {
+            fs::remove_file(path).ok();
+        }
+        if !stale.is_empty() {
+            logger::info(&format!("Cleared {} stale run state file(s) left by earlier runs", stale.len()));
+        }
+        return;
+    }
+
+    logger::warning("An earlier run ended without cleaning up:");
+    for (_, state, orphans, partial) in leftovers.iter().filter(|(_, _, orphans, partial)| !orphans.is_empty() || !partial.is_empty()) {
+        let started = state.started_at
+            .map(|at| at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "unknown time".to_string());
+        logger::info(&format!("   Run {} (started {})", state.pid, started));
+        for child in orphans {
+            logger::info(&format!("     • {} still running (PID {})", child.program, child.pid));
+        }
+        for path in partial {
+            logger::file(&format!("     • incomplete file {}", path.display()));
+        }
+    }
+
+    if !std::io::stdin().is_terminal() || logger::sink() != logger::Sink::Text {
+        logger::info("Run again from a terminal to clean these up");
+        return;
+    }
+    print!("Stop {} process(es) and remove {} incomplete file(s)? (Y/n): ", processes, files);
+    std::io::stdout().flush().ok();
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input).ok();
+    if input.trim().eq_ignore_ascii_case("n") || input.trim().eq_ignore_ascii_case("no") {
+        logger::info("Left them alone; you'll be asked again next time");
+        return;
+    }
+
+    let mut stopped = 0;
+    let mut removed = 0;
+    let mut failed = false;
+    for (path, _, orphans, partial) in &leftovers {
+        let mut done = true;
+        for child in orphans {
+            if cancel::kill_group(child.pid, Signal::Terminate) {
+                stopped += 1;
+            } else {
+                done = false;
+                logger::warning(&format!("Could not stop {} (PID {}): {}; was it started with sudo?", child.program, child.pid, std::io::Error::last_os_error()));
+            }
+        }
+        for file in partial {
+            match fs::remove_file(file) {
+                Ok(()) => removed += 1,
+                Err(error) => {
+                    done = false;
+                    logger::warning(&format!("Could not remove {}: {}", file.display(), error));
+                }
+            }
+        }
+        if done {
+            fs::remove_file(path).ok();
+        }
+        failed |= !done;
+    }
+
+    logger::success("Cleaned up after the earlier run");
+    logger::info(&format!("   • Stopped {} orphaned process(es)", stopped));
+    logger::info(&format!("   • Removed {} incomplete file(s)", removed));
+    if failed {
+        logger::info("   • Some leftovers remain; run with sudo to clean up after a sudo run");
+    }
+}
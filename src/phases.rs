@@ -0,0 +1,88 @@
+use crate::config::Config;
+use crate::history::{DownloadStatus, History, HistoryEntry};
+use crate::status::Stage;
+
+/// The phases of a run in the order they happen; `Starting` is the analysis.
+const ORDER: [Stage; 5] = [Stage::Starting, Stage::Downloading, Stage::Extending, Stage::Converting, Stage::Installing];
+
+/// Shares of a run for the phases the history can't time: analysis, looping short videos
+/// and installing
+const ANALYSIS_SHARE: f64 = 0.05;
+const EXTEND_SHARE: f64 = 0.05;
+const INSTALL_SHARE: f64 = 0.05;
+/// Part of the download and conversion time spent converting, until the history has some
+const DEFAULT_CONVERT_SHARE: f64 = 0.5;
+/// Recent downloads the conversion share is estimated from
+const HISTORY_SAMPLE: usize = 50;
+
+/// How much of a run each phase takes, for one overall progress figure across them.
+///
+/// Only the phases the run goes through get a share; the shares add up to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhaseWeights {
+    weights: [f64; 5],
+}
+
+impl PhaseWeights {
+    /// Weights for a download with `config`, with the split between downloading and
+    /// converting taken from recent history. `install` adds the wallpaper install at the end.
+    pub fn for_run(config: &Config, install: bool) -> Self {
+        let convert = config.download_settings.convert_to_mov;
+        let entries = History::load(config).and_then(|history| history.recent(HISTORY_SAMPLE)).unwrap_or_default();
+        let convert_share = convert_share(&entries).unwrap_or(DEFAULT_CONVERT_SHARE);
+        let wallpaper = convert && config.conversion_settings.profile().is_wallpaper();
+
+        let fixed = ANALYSIS_SHARE + if wallpaper { EXTEND_SHARE } else { 0.0 } + if install { INSTALL_SHARE } else { 0.0 };
+        let (download, converting) = match convert {
+            true => ((1.0 - fixed) * (1.0 - convert_share), (1.0 - fixed) * convert_share),
+            false => (1.0 - fixed, 0.0),
+        };
+        Self::new([
+            ANALYSIS_SHARE,
+            download,
+            if wallpaper { EXTEND_SHARE } else { 0.0 },
+            converting,
+            if install { INSTALL_SHARE } else { 0.0 },
+        ])
+    }
+
+    fn new(weights: [f64; 5]) -> Self {
+        let total: f64 = weights.iter().sum();
+        Self { weights: weights.map(|weight| weight / total) }
+    }
+
+    /// Percent of the whole run done when `stage` is `percent` done. A stage this run
+    /// wasn't expected to go through counts as the start of the next one.
+    pub fn overall(&self, stage: Stage, percent: f64) -> f64 {
+        // Waiting for a slot counts as the start of the stage waited for
+        let (stage, percent) = match stage {
+            Stage::Queued => (Stage::Starting, 0.0),
+            Stage::WaitingToConvert => (Stage::Converting, 0.0),
+            stage => (stage, percent),
+        };
+        let index = ORDER.iter().position(|s| *s == stage).unwrap_or(0);
+        let before: f64 = self.weights[..index].iter().sum();
+        (before + self.weights[index] * percent.clamp(0.0, 100.0) / 100.0) * 100.0
+    }
+}
+
+/// Median part of a completed download's time spent converting, over the downloads that
+/// were converted.
+fn convert_share(entries: &[HistoryEntry]) -> Option<f64> {
+    let mut shares: Vec<f64> = entries.iter()
+        .filter(|entry| entry.status == DownloadStatus::Completed && entry.seconds > 0.0)
+        .filter_map(|entry| Some((entry.conversion_seconds? / entry.seconds).clamp(0.0, 1.0)))
+        .collect();
+    if shares.is_empty() {
+        return None;
+    }
+    shares.sort_by(f64::total_cmp);
+    Some(shares[shares.len() / 2])
+}
+
+/// Seconds left, extrapolated from `elapsed` seconds having taken the run from
+/// `from_percent` to `overall_percent`; `None` until there is enough progress to tell.
+pub fn remaining_seconds(elapsed: f64, from_percent: f64, overall_percent: f64) -> Option<f64> {
+    let done = overall_percent - from_percent;
+    (done >= 1.0 && overall_percent < 100.0).then(|| elapsed * (100.0 - overall_percent) / done)
+}
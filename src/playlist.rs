@@ -0,0 +1,195 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use chrono::NaiveDate;
+use regex::Regex;
+use crate::auth;
+use crate::config::Config;
+use crate::dependencies;
+use crate::error::Result;
+use crate::logger;
+use crate::ytdlp_error::YtDlpError;
+
+/// Feeds of the signed-in account that yt-dlp understands: Watch Later, Liked videos,
+/// subscriptions and watch history.
+pub const FEEDS: [&str; 4] = [":ytwatchlater", ":ytfav", ":ytsubs", ":ythis"];
+
+/// Watched by `sync` when no source is given.
+pub const DEFAULT_SOURCE: &str = ":ytwatchlater";
+
+/// What `--audio-only` leaves behind, so the .m3u can find each track whatever its format
+const AUDIO_EXTENSIONS: [&str; 5] = ["m4a", "opus", "mp3", "flac", "webm"];
+
+/// Album folder and track names are cut to this many characters
+const MAX_NAME_CHARS: usize = 120;
+
+pub fn is_feed(source: &str) -> bool {
+    FEEDS.contains(&source.trim())
+}
+
+/// `youtube.com/playlist?list=...` links, including the WL (Watch Later) and LL (Liked) lists.
+pub fn is_playlist_url(source: &str) -> bool {
+    Regex::new(r"^https?://((www|m|music)\.)?youtube\.com/playlist\?(.*&)?list=[\w-]+").unwrap().is_match(source.trim())
+}
+
+/// Channel pages: `youtube.com/@handle`, `/channel/UC...`, `/c/name` or `/user/name`,
+/// optionally with the `videos`, `shorts` or `streams` tab.
+pub fn is_channel_url(source: &str) -> bool {
+    Regex::new(r"^https?://((www|m)\.)?youtube\.com/(@[\w.-]+|channel/UC[\w-]+|c/[\w.-]+|user/[\w.-]+)(/(videos|shorts|streams))?/?$").unwrap().is_match(source.trim())
+}
+
+/// Watch URLs of every video in a feed or playlist, in playlist order, or of a channel's
+/// uploads, newest first.
+///
+/// `uploaded_after` only applies to channels: yt-dlp then reads each upload's date and stops
+/// at the first older one, which is slower per video but skips the rest of the channel.
+///
+/// Feeds and private playlists only exist for a signed-in account, so they need cookies from
+/// `auth` or `--cookies`/`--cookies-from-browser`.
+pub fn video_urls(source: &str, uploaded_after: Option<NaiveDate>, config: &Config) -> Result<Vec<String>> {
+    let source = source.trim();
+    if !is_feed(source) && !is_playlist_url(source) && !is_channel_url(source) {
+        return Err(format!("Not a feed, playlist or channel: {} (expected one of {}, a youtube.com/playlist?list= link or a channel URL)", source, FEEDS.join(", ")).into());
+    }
+
+    if is_feed(source) && !auth::is_signed_in(config) {
+        return Err(format!("{} belongs to your account; pass --cookies-from-browser BROWSER or run 'rust-downloader auth setup' first", source).into());
+    }
+
+    // The bare channel URL lists its tabs rather than videos
+    let target = match is_channel_url(source) && !Regex::new(r"/(videos|shorts|streams)/?$").unwrap().is_match(source) {
+        true => format!("{}/videos", source.trim_end_matches('/')),
+        false => source.to_string(),
+    };
+
+    logger::search(&format!("Reading {}...", source));
+    let mut command = Command::new(dependencies::yt_dlp());
+    command.args(["--print", "id", "--no-warnings"]);
+    match uploaded_after.filter(|_| is_channel_url(source)) {
+        Some(date) => {
+            let date = date.format("%Y%m%d").to_string();
+            command.args(["--dateafter", &date, "--break-match-filters", &format!("upload_date>={}", date)]);
+        }
+        None => {
+            command.arg("--flat-playlist");
+        }
+    }
+    let output = command
+        .args(auth::yt_dlp_args(config))
+        .arg(&target)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(YtDlpError::from_stderr(&String::from_utf8_lossy(&output.stderr)).into());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| format!("https://www.youtube.com/watch?v={}", id))
+        .collect())
+}
+
+/// A playlist downloaded by a music profile, which files its tracks as
+/// `{playlist}/{index} - {title}.{ext}` and lists them in `{playlist}.m3u`.
+#[derive(Debug, Clone)]
+pub struct Album {
+    pub title: String,
+    pub tracks: Vec<Track>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Track {
+    pub id: String,
+    pub title: String,
+    pub duration: Option<f64>,
+}
+
+impl Album {
+    /// Read the playlist's title and tracks without downloading anything.
+    pub fn load(source: &str, config: &Config) -> Result<Self> {
+        logger::search(&format!("Reading {}...", source.trim()));
+        let output = Command::new(dependencies::yt_dlp())
+            .args(["--flat-playlist", "--dump-single-json", "--no-warnings"])
+            .args(auth::yt_dlp_args(config))
+            .arg(source.trim())
+            .output()?;
+        if !output.status.success() {
+            return Err(YtDlpError::from_stderr(&String::from_utf8_lossy(&output.stderr)).into());
+        }
+
+        let info: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+        let tracks: Vec<Track> = info["entries"].as_array()
+            .map(|entries| entries.iter()
+                .filter_map(|entry| Some(Track {
+                    id: entry["id"].as_str()?.to_string(),
+                    title: entry["title"].as_str().unwrap_or_default().to_string(),
+                    duration: entry["duration"].as_f64(),
+                }))
+                .collect())
+            .unwrap_or_default();
+        if tracks.is_empty() {
+            return Err(format!("{} has no videos", source.trim()).into());
+        }
+
+        let title = info["title"].as_str().map(clean_name).filter(|title| !title.is_empty());
+        Ok(Self { title: title.unwrap_or_else(|| "Playlist".to_string()), tracks })
+    }
+
+    pub fn urls(&self) -> Vec<String> {
+        self.tracks.iter().map(|track| format!("https://www.youtube.com/watch?v={}", track.id)).collect()
+    }
+
+    /// Zero-based position of a video in the playlist.
+    pub fn position(&self, video_id: &str) -> Option<usize> {
+        self.tracks.iter().position(|track| track.id == video_id)
+    }
+
+    /// `07 - Title`, padded to at least two digits so players and file browsers sort it right.
+    pub fn track_stem(&self, position: usize) -> String {
+        let width = self.tracks.len().to_string().len().max(2);
+        format!("{:0width$} - {}", position + 1, clean_name(&self.tracks[position].title), width = width)
+    }
+
+    /// `{playlist}/{index} - {title}.{ext}`, relative to the output directory.
+    pub fn track_path(&self, position: usize, extension: &str) -> PathBuf {
+        Path::new(&self.title).join(format!("{}.{}", self.track_stem(position), extension))
+    }
+
+    /// Write `{playlist}.m3u` into the album folder, listing the tracks found there in
+    /// playlist order. Returns its path and how many tracks it lists.
+    pub fn write_m3u(&self, output_dir: &Path) -> Result<(PathBuf, usize)> {
+        let folder = output_dir.join(&self.title);
+        let mut content = String::from("#EXTM3U\n");
+        let mut listed = 0;
+        for (position, track) in self.tracks.iter().enumerate() {
+            let stem = self.track_stem(position);
+            let Some(file) = AUDIO_EXTENSIONS.iter().map(|ext| format!("{}.{}", stem, ext)).find(|name| folder.join(name).is_file()) else {
+                continue;
+            };
+            let seconds = track.duration.map_or(-1, |duration| duration.round() as i64);
+            content.push_str(&format!("#EXTINF:{},{}\n{}\n", seconds, track.title, file));
+            listed += 1;
+        }
+
+        let path = folder.join(format!("{}.m3u", self.title));
+        fs::write(&path, content)?;
+        Ok((path, listed))
+    }
+}
+
+/// A title made safe as a file or folder name, keeping spaces and non-ASCII letters.
+fn clean_name(title: &str) -> String {
+    let cleaned: String = title.chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control() { ' ' } else { c })
+        .collect();
+    // Trailing dots and spaces are dropped by Windows and confuse Finder
+    let cleaned = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut cleaned = cleaned.trim_matches(|c| c == '.' || c == ' ').to_string();
+    if let Some((index, _)) = cleaned.char_indices().nth(MAX_NAME_CHARS) {
+        cleaned.truncate(index);
+        cleaned = cleaned.trim_end().to_string();
+    }
+    cleaned
+}
@@ -0,0 +1,896 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{oneshot, watch, OwnedSemaphorePermit, Semaphore};
+use crate::error::{DownloaderError, Result};
+use crate::cancel::CancelHandle;
+use crate::config::Config;
+use crate::downloader::{self, blocking, Downloader};
+use crate::events::{ProgressEvent, ProgressObserver};
+use crate::history::{self, Conversion, History};
+use crate::library;
+use crate::logger;
+use crate::phases::PhaseWeights;
+use crate::review;
+use crate::status::{self, QueueLimits, Stage};
+use crate::utils;
+use crate::video_info;
+use crate::wallpaper;
+
+/// Outcome of one queued download; errors are kept as text since they cross task boundaries.
+#[derive(Debug)]
+pub struct QueueResult {
+    /// The URL, or the entry as written for steps that don't download
+    pub url: String,
+    pub label: String,
+    /// The files produced, or for an install step the ones installed
+    pub outcome: Result<Vec<PathBuf>, String>,
+    /// The video is private, deleted or region-blocked rather than failing to download
+    pub unavailable: bool,
+    /// An install step; its outputs are already installed
+    pub installed: bool,
+}
+
+/// What a queue entry does once the entries it waits for are done.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Download a URL, converting it unless `convert` is false
+    Download { url: String, convert: bool },
+    /// Convert what the named download-only entry downloaded
+    Convert { source: String },
+    /// Install outputs of the named entries once they have all finished
+    Install { pick: Pick, from: Vec<String> },
+}
+
+/// How an install step chooses among the outputs it waited for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pick {
+    /// The highest resolution, then the largest file
+    Best,
+    /// Whichever the user picks in the review prompt
+    Choice,
+}
+
+/// One line of a batch file, or a URL given on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueEntry {
+    pub step: Step,
+    /// Set with `as <name>`; other entries refer to this one by it, and its job is labelled with it
+    pub name: Option<String>,
+    /// Entries that must succeed first: those listed after `after`, plus the ones the step reads
+    pub after: Vec<String>,
+}
+
+impl QueueEntry {
+    /// Parse one of:
+    ///
+    /// - `<url> [as <name>] [download-only] [after <name>,...]`
+    /// - `convert <name> [as <name>] [after <name>,...]`
+    /// - `install best|choice of <name>,... [as <name>]`
+    ///
+    /// A plain URL is a download with no dependencies.
+    pub fn parse(line: &str) -> Result<Self> {
+        let mut words = line.split_whitespace().peekable();
+        let first = words.next().ok_or("Empty queue entry")?;
+        let mut step = match first {
+            "convert" => Step::Convert { source: words.next().ok_or("`convert` needs the name of a download-only entry")?.to_string() },
+            "install" => {
+                let pick = match words.next() {
+                    Some("best") => Pick::Best,
+                    Some("choice") => Pick::Choice,
+                    _ => return Err(format!("Expected `install best of ...` or `install choice of ...` in '{}'", line).into()),
+                };
+                if words.next() != Some("of") {
+                    return Err(format!("Expected `of` and entry names after `install {}` in '{}'", first, line).into());
+                }
+                Step::Install { pick, from: names(&mut words) }
+            }
+            // Searches like `ytsearch:ocean waves` run up to the first keyword
+            url => {
+                let mut url = url.to_string();
+                while let Some(word) = words.next_if(|word| !KEYWORDS.contains(word)) {
+                    url.push(' ');
+                    url.push_str(word);
+                }
+                Step::Download { url, convert: true }
+            }
+        };
+
+        let mut name = None;
+        let mut after = Vec::new();
+        while let Some(word) = words.next() {
+            match (word, &mut step) {
+                ("as", _) => name = Some(words.next().ok_or("`as` needs a name")?.to_string()),
+                ("after", _) => after.extend(names(&mut words)),
+                ("download-only", Step::Download { convert, .. }) => *convert = false,
+                _ => return Err(format!("Unexpected '{}' in queue entry '{}'", word, line).into()),
+            }
+        }
+
+        let reads = match &step {
+            Step::Convert { source } => vec![source.clone()],
+            Step::Install { from, .. } if from.is_empty() => return Err(format!("No entries to install from in '{}'", line).into()),
+            Step::Install { from, .. } => from.clone(),
+            Step::Download { .. } => Vec::new(),
+        };
+        for name in reads {
+            if !after.contains(&name) {
+                after.push(name);
+            }
+        }
+        Ok(Self { step, name, after })
+    }
+
+    /// A download of its own: no name, nothing to wait for, converted as usual.
+    pub fn is_plain(&self) -> bool {
+        matches!(self.step, Step::Download { convert: true, .. }) && self.name.is_none() && self.after.is_empty()
+    }
+
+    /// The URL a download entry fetches.
+    pub fn url(&self) -> Option<&str> {
+        match &self.step {
+            Step::Download { url, .. } => Some(url),
+            _ => None,
+        }
+    }
+}
+
+/// Words that end a URL or a list of names in a queue entry
+const KEYWORDS: [&str; 3] = ["as", "after", "download-only"];
+
+/// Entry names up to the next keyword, separated by commas and/or spaces.
+fn names(words: &mut std::iter::Peekable<std::str::SplitWhitespace>) -> Vec<String> {
+    let mut names = Vec::new();
+    while let Some(word) = words.next_if(|word| !KEYWORDS.contains(word)) {
+        names.extend(word.split(',').filter(|name| !name.is_empty()).map(String::from));
+    }
+    names
+}
+
+/// An entry waiting in the queue and the label its job is shown under.
+struct QueuedJob {
+    /// The entry as written
+    entry: String,
+    /// The URL, or the entry for steps that don't download
+    text: String,
+    label: String,
+    step: Step,
+    after: Vec<String>,
+    /// Format ids an interrupted download was fetching, to continue with
+    formats: Vec<String>,
+    /// How the job ended in an earlier run of a saved queue; it isn't run again
+    finished: Finished,
+}
+
+/// What a finished job hands the entries waiting for it.
+type Finished = Option<Result<Vec<PathBuf>, String>>;
+
+/// Downloads several URLs with at most `downloads` downloading and `conversions` converting
+/// at once.
+///
+/// Downloads are network-bound and conversions CPU/GPU-bound, so a job gives its download
+/// slot back once its file is downloaded and waits for a conversion slot; the next download
+/// runs meanwhile. Entries can wait for earlier ones (see `QueueEntry`). Each job is its own
+/// tokio task; the downloader runs yt-dlp and ffmpeg as async child processes, and progress
+/// is shown on one row per job via `logger::job_progress`.
+pub struct DownloadQueue {
+    jobs: Vec<QueuedJob>,
+    downloads: usize,
+    conversions: usize,
+    cancel: CancelHandle,
+    /// Set by `resume`: the saved run whose jobs' progress is written to `queue.json`
+    saved_run: Option<String>,
+}
+
+impl DownloadQueue {
+    pub fn new(downloads: usize, conversions: usize) -> Self {
+        Self {
+            jobs: Vec::new(),
+            downloads: downloads.max(1),
+            conversions: conversions.max(1),
+            cancel: CancelHandle::new(),
+            saved_run: None,
+        }
+    }
+
+    /// Add an entry (see `QueueEntry::parse`) unless it downloads a video that is already
+    /// queued under a differently shared link. Returns `false` for duplicates.
+    ///
+    /// Entries can only wait for entries queued before them, so there are no cycles.
+    pub fn push(&mut self, line: &str) -> Result<bool> {
+        let line = line.trim();
+        let entry = QueueEntry::parse(line)?;
+
+        if let Some(url) = entry.url() {
+            let canonical = utils::normalize_url(url).url;
+            let queued = self.jobs.iter().filter_map(|job| match &job.step {
+                Step::Download { url, .. } => Some(url),
+                _ => None,
+            });
+            if queued.into_iter().any(|queued| utils::normalize_url(queued).url == canonical) {
+                logger::info(&format!("Skipping duplicate: {}", url));
+                return Ok(false);
+            }
+        }
+        if let Some(missing) = entry.after.iter().find(|name| !self.jobs.iter().any(|job| &job.label == *name)) {
+            return Err(format!("'{}' waits for '{}', which is not named by an entry above it", line, missing).into());
+        }
+        if let Step::Convert { source } = &entry.step {
+            let download_only = self.jobs.iter().any(|job| &job.label == source && matches!(job.step, Step::Download { convert: false, .. }));
+            if !download_only {
+                return Err(format!("'{}' converts '{}', which is not a download-only entry", line, source).into());
+            }
+        }
+
+        let text = entry.url().unwrap_or(line).to_string();
+        let label = match entry.name {
+            Some(name) if self.jobs.iter().any(|job| job.label == name) => return Err(format!("Two queue entries are named '{}'", name).into()),
+            Some(name) => name,
+            None => self.unique_label(&entry.step),
+        };
+        self.jobs.push(QueuedJob { entry: line.to_string(), text, label, step: entry.step, after: entry.after, formats: Vec::new(), finished: None });
+        Ok(true)
+    }
+
+    /// Save the queued entries as a new run in `queue.json` for the daemon to download, and
+    /// return its id.
+    pub fn save(&self, config: &Config) -> Result<String> {
+        let id = format!("{}-{}", Utc::now().format("%Y%m%d%H%M%S%3f"), std::process::id());
+        let jobs = self.jobs.iter()
+            .map(|job| SavedJob {
+                label: job.label.clone(),
+                entry: job.entry.clone(),
+                state: SavedState::Pending,
+                formats: Vec::new(),
+                partial_path: None,
+                bytes_done: 0,
+            })
+            .collect();
+        SavedQueue::update(config, |saved| {
+            saved.runs.insert(id.clone(), SavedRun { heartbeat: None, jobs });
+        })?;
+        Ok(id)
+    }
+
+    /// A queue for a saved run, e.g. one `claim_orphaned` handed to the daemon. Jobs that
+    /// finished in an earlier attempt aren't run again, but entries waiting for them still get
+    /// their outputs; interrupted downloads continue with the formats they were fetching.
+    pub fn resume(id: &str, run: &SavedRun, downloads: usize, conversions: usize) -> Result<Self> {
+        let mut queue = Self::new(downloads, conversions);
+        for job in &run.jobs {
+            // The label was made unique when the run was saved, so keep it
+            if !queue.push(&format!("{} as {}", job.entry, job.label))? {
+                return Err(format!("Saved queue entry '{}' downloads the same video as another", job.entry).into());
+            }
+            let queued = queue.jobs.last_mut().ok_or("Saved queue entry was not queued")?;
+            if !matches!(queued.step, Step::Download { .. }) {
+                queued.text = job.entry.clone();
+            }
+            queued.entry = job.entry.clone();
+            queued.formats = job.formats.clone();
+            queued.finished = match &job.state {
+                SavedState::Pending => None,
+                SavedState::Done { outputs } => Some(Ok(outputs.clone())),
+                SavedState::Failed { error } => Some(Err(error.clone())),
+            };
+        }
+        queue.saved_run = Some(id.to_string());
+        Ok(queue)
+    }
+
+    /// The video's label (from the search text for queries not resolved yet), or the step's
+    /// name for other entries, with a `-2`, `-3`… suffix when another job in this run already
+    /// has it.
+    fn unique_label(&self, step: &Step) -> String {
+        let base = match step {
+            Step::Download { url, .. } => {
+                let id = utils::extract_video_id(url).or_else(|| utils::is_video_id(url).then(|| url.to_string()));
+                utils::job_label(id.as_deref().unwrap_or(url))
+            }
+            Step::Convert { source } => format!("convert-{}", source),
+            Step::Install { .. } => "install".to_string(),
+        };
+        (1..)
+            .map(|n| if n == 1 { base.clone() } else { format!("{}-{}", base, n) })
+            .find(|label| self.jobs.iter().all(|job| &job.label != label))
+            .unwrap()
+    }
+
+    /// Cancelling stops every job in the queue.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Whether an entry installs a wallpaper itself, so the caller shouldn't offer the results.
+    pub fn installs(&self) -> bool {
+        self.jobs.iter().any(|job| matches!(job.step, Step::Install { .. }))
+    }
+
+    /// Whether an entry asks which outputs to install, which needs someone at a terminal.
+    pub fn asks(&self) -> bool {
+        self.jobs.iter().any(|job| matches!(job.step, Step::Install { pick: Pick::Choice, .. }))
+    }
+
+    /// Read entries from a batch file, one per line; blank lines and `#` comments are skipped.
+    pub fn read_batch_file(path: &Path) -> Result<Vec<String>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read batch file {}: {}", path.display(), e))?;
+
+        Ok(content.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+            .collect())
+    }
+
+    pub async fn run(self, config: &Config) -> Vec<QueueResult> {
+        logger::header("Download Queue");
+        logger::info(&format!(
+            "{} entries, up to {} downloading and {} converting at a time",
+            self.jobs.len(), self.downloads, self.conversions,
+        ));
+        for job in &self.jobs {
+            match job.after.as_slice() {
+                [] => logger::info(&format!("{:<16} {}", job.label, job.text)),
+                after => logger::info(&format!("{:<16} {} (after {})", job.label, job.text, after.join(", "))),
+            }
+        }
+
+        let download_history = History::load(config)
+            .map_err(|e| logger::warning(&format!("Download history unavailable: {}", e)))
+            .ok();
+        status::set_queue_limits(QueueLimits { downloads: self.downloads, conversions: self.conversions });
+        let weights = PhaseWeights::for_run(config, false);
+        let shared = Arc::new(Shared {
+            config: config.clone(),
+            download_slots: Arc::new(Semaphore::new(self.downloads)),
+            conversion_slots: Arc::new(Semaphore::new(self.conversions)),
+            download_history,
+            queue_cancel: self.cancel.clone(),
+            saved_run: self.saved_run.clone(),
+        });
+        // Tells `claim_orphaned` the run is still being worked on
+        let heartbeat = self.saved_run.clone().map(|id| {
+            let config = config.clone();
+            tokio::spawn(async move {
+                loop {
+                    if let Err(error) = SavedQueue::update(&config, |saved| saved.beat(&id)) {
+                        logger::warning(&format!("Could not save the queue's progress: {}", error));
+                    }
+                    tokio::time::sleep(HEARTBEAT_INTERVAL).await;
+                }
+            })
+        });
+        let mut finished: HashMap<String, watch::Receiver<Finished>> = HashMap::new();
+        let mut handles = Vec::new();
+        let mut previous_turn = None;
+
+        // Every job starts waiting right away. Tasks can first run in any order, so downloads
+        // that wait for nothing take turns asking for a slot (see `Turn`) to start in queue order
+        for mut job in self.jobs {
+            let (done, receiver) = watch::channel(job.finished.take());
+            let waits_for: Vec<(String, watch::Receiver<Finished>)> = job.after.iter()
+                .map(|name| (name.clone(), finished[name].clone()))
+                .collect();
+            finished.insert(job.label.clone(), receiver);
+            // Done in an earlier run of a saved queue
+            if done.borrow().is_some() {
+                continue;
+            }
+
+            let turn = (matches!(job.step, Step::Download { .. }) && job.after.is_empty()).then(|| {
+                let (next, next_turn) = oneshot::channel();
+                Turn { previous: previous_turn.replace(next_turn), next }
+            });
+
+            // Each job has its own handle, so `ctl cancel <job>` stops it alone, even while queued
+            let cancel = self.cancel.child();
+            status::enqueue(&job.label, &job.text, weights, cancel.clone());
+            let shared = shared.clone();
+            handles.push(tokio::spawn(async move {
+                let label = job.label.clone();
+                let result = run_job(job, waits_for, turn, &shared, cancel).await;
+                let outcome = result.as_ref().map_or_else(|| Err("Cancelled".to_string()), |result| result.outcome.clone());
+                shared.save_outcome(&label, &outcome);
+                done.send_replace(Some(outcome));
+                result
+            }));
+        }
+
+        let mut results = Vec::new();
+        for handle in handles {
+            match handle.await {
+                Ok(Some(result)) => results.push(result),
+                // Cancelled before it started
+                Ok(None) => {}
+                Err(e) => logger::error(&format!("Queue task failed: {}", e)),
+            }
+        }
+        logger::finish_job_progress();
+
+        if let (Some(id), Some(heartbeat)) = (&self.saved_run, heartbeat) {
+            heartbeat.abort();
+            if let Err(error) = SavedQueue::update(config, |saved| saved.release(id)) {
+                logger::warning(&format!("Could not save the queue's progress: {}", error));
+            }
+        }
+        results
+    }
+}
+
+/// What the jobs of one queue run share.
+struct Shared {
+    config: Config,
+    download_slots: Arc<Semaphore>,
+    conversion_slots: Arc<Semaphore>,
+    download_history: Option<History>,
+    /// The queue's own handle; a job it stopped was interrupted rather than cancelled
+    queue_cancel: CancelHandle,
+    saved_run: Option<String>,
+}
+
+impl Shared {
+    /// Whether a saved run is being stopped, e.g. the daemon shutting down, so its jobs are
+    /// left to resume instead of failing.
+    fn interrupted(&self) -> bool {
+        self.saved_run.is_some() && self.queue_cancel.is_cancelled()
+    }
+
+    /// Record how a job of a saved run ended; interrupted jobs stay pending.
+    fn save_outcome(&self, label: &str, outcome: &Result<Vec<PathBuf>, String>) {
+        let Some(id) = &self.saved_run else {
+            return;
+        };
+        if self.interrupted() {
+            return;
+        }
+        let state = match outcome {
+            Ok(outputs) => SavedState::Done { outputs: outputs.clone() },
+            Err(error) => SavedState::Failed { error: error.clone() },
+        };
+        let saved = SavedQueue::update(&self.config, |saved| {
+            if let Some(job) = saved.job_mut(id, label) {
+                job.state = state;
+            }
+        });
+        if let Err(error) = saved {
+            logger::warning(&format!("[{}] Could not save the queue's progress: {}", label, error));
+        }
+    }
+}
+
+/// A download's place in line for a download slot. The semaphore hands slots out in the order
+/// they were asked for, so each download asks once the one queued before it has a slot (or
+/// has ended), then lets the next one ask.
+struct Turn {
+    previous: Option<oneshot::Receiver<()>>,
+    next: oneshot::Sender<()>,
+}
+
+/// Wait for the entries `job` depends on, then run its step. `None` when the queue was
+/// cancelled before the job started.
+async fn run_job(job: QueuedJob, waits_for: Vec<(String, watch::Receiver<Finished>)>, turn: Option<Turn>, shared: &Shared, cancel: CancelHandle) -> Option<QueueResult> {
+    let mut inputs = Vec::new();
+    let mut blocked = None;
+    for (name, mut receiver) in waits_for {
+        let outcome = tokio::select! {
+            outcome = receiver.wait_for(Option::is_some) => outcome.ok().and_then(|outcome| outcome.clone()),
+            _ = cancel.cancelled() => None,
+        };
+        match outcome {
+            Some(Ok(paths)) => inputs.extend(paths),
+            Some(Err(_)) => blocked = blocked.or(Some(name)),
+            None => {
+                status::finish(&job.label);
+                return None;
+            }
+        }
+    }
+
+    let QueuedJob { text, label, step, formats, .. } = job;
+    if let Some(name) = blocked {
+        status::finish(&label);
+        let error = format!("{} did not finish", name);
+        logger::warning(&format!("[{}] Skipped: {}", label, error));
+        return Some(QueueResult { url: text, label, outcome: Err(error), unavailable: false, installed: false });
+    }
+
+    let result = match step {
+        Step::Download { url, convert } => {
+            // Stop starting new downloads once the job or the queue is cancelled
+            let (previous, next) = match turn {
+                Some(Turn { previous, next }) => (previous, Some(next)),
+                None => (None, None),
+            };
+            let permit = tokio::select! {
+                permit = async {
+                    if let Some(previous) = previous {
+                        // An error means the previous download ended without a slot
+                        previous.await.ok();
+                    }
+                    shared.download_slots.clone().acquire_owned().await
+                } => permit.ok(),
+                _ = cancel.cancelled() => None,
+            };
+            // Also when cancelled, so the next download asks all the same
+            if let Some(next) = next {
+                next.send(()).ok();
+            }
+            let Some(permit) = permit else {
+                status::finish(&label);
+                return None;
+            };
+            let mut config = shared.config.clone();
+            config.download_settings.convert_to_mov &= convert;
+            run_download(url, label, &formats, &config, shared, cancel, permit).await
+        }
+        Step::Convert { .. } => run_conversion(&inputs, text, label, &shared.config, cancel, shared.conversion_slots.clone()).await,
+        Step::Install { pick, .. } => run_install(inputs, pick, text, label, &shared.config).await,
+    };
+    Some(result)
+}
+
+/// Download `url` holding `download_slot` until its file is downloaded. `formats` are the
+/// ones an interrupted attempt was fetching.
+async fn run_download(url: String, label: String, formats: &[String], config: &Config, shared: &Shared, cancel: CancelHandle, download_slot: OwnedSemaphorePermit) -> QueueResult {
+    let started = Instant::now();
+    let mut info = None;
+    let mut conversion = Conversion::default();
+    let mut resolved = None;
+
+    let outcome = async {
+        // Searches and analysis run yt-dlp synchronously, so keep them off the runtime's workers
+        let (input, license) = (url.clone(), config.video_preferences.license);
+        let url = blocking(move || utils::resolve_video_input(&input, license)).await?;
+        resolved = Some(url.clone());
+        let mut downloader = Downloader::new();
+        let mut analysis = {
+            let (url, session, config) = (url.clone(), downloader.auth_session().clone(), config.clone());
+            blocking(move || video_info::analyze(&url, &session, &config)).await?
+        };
+        if !formats.is_empty() && !analysis.pin(formats, config) {
+            logger::warning(&format!("[{}] Formats {} are no longer offered; starting over", label, formats.join("+")));
+        }
+        info = Some(analysis.info.clone());
+        downloader.set_job_label(&label);
+        downloader.share_progress_rows();
+        downloader.set_cancel_handle(cancel);
+        downloader.set_download_slot(download_slot);
+        downloader.set_conversion_slots(shared.conversion_slots.clone());
+        if let Some(id) = &shared.saved_run {
+            downloader.set_observer(Checkpoints::new(config, id, &label));
+        }
+        let path = downloader.perform_download(&url, &analysis, config).await?;
+        conversion = downloader.conversion();
+
+        Ok::<_, DownloaderError>(if downloader.batch_outputs().is_empty() {
+            vec![path]
+        } else {
+            downloader.batch_outputs().to_vec()
+        })
+    }.await;
+
+    // Jobs that failed before `perform_download` are still listed as queued
+    status::finish(&label);
+
+    // An interrupted download resumes later rather than counting as failed
+    if let (Some(download_history), false) = (&shared.download_history, shared.interrupted()) {
+        let outputs = outcome.as_ref().map(|paths| paths.as_slice());
+        // Searches and bare ids are recorded under the watch URL they resolved to
+        let url = resolved.as_deref().unwrap_or(&url);
+        history::record_outcome(download_history, url, info.as_ref(), &conversion, outputs, started.elapsed());
+    }
+
+    let unavailable = outcome.as_ref().err().is_some_and(DownloaderError::is_unavailable);
+    finish(QueueResult { url, label, outcome: outcome.map_err(|e| e.to_string()), unavailable, installed: false })
+}
+
+/// Convert the files of a download-only entry, each once a conversion slot is free.
+async fn run_conversion(inputs: &[PathBuf], text: String, label: String, config: &Config, cancel: CancelHandle, conversion_slots: Arc<Semaphore>) -> QueueResult {
+    let mut downloader = Downloader::new();
+    downloader.set_job_label(&label);
+    downloader.share_progress_rows();
+    downloader.set_cancel_handle(cancel);
+    downloader.set_conversion_slots(conversion_slots);
+
+    let mut outputs = Vec::new();
+    let mut outcome = Ok(());
+    for input in inputs {
+        match downloader.convert_downloaded(input, config).await {
+            Ok(path) => outputs.push(path),
+            Err(error) => {
+                outcome = Err(error.to_string());
+                break;
+            }
+        }
+    }
+    finish(QueueResult { url: text, label, outcome: outcome.map(|()| outputs), unavailable: false, installed: false })
+}
+
+/// Install what the entries an install step waited for produced.
+async fn run_install(candidates: Vec<PathBuf>, pick: Pick, text: String, label: String, config: &Config) -> QueueResult {
+    let outcome = async {
+        // `install best` runs unattended (e.g. in the daemon), so it swaps the wallpaper the way
+        // `api::install_wallpaper` does instead of the interactive flow
+        if pick == Pick::Best {
+            let Some(best) = blocking(move || Ok(best_output(&candidates))).await? else {
+                return Ok(Vec::new());
+            };
+            status::update(&label, Stage::Installing, 0.0, "1 of 1");
+            let replaced = wallpaper::detect(config)?.swap(&best, None).await?;
+            logger::success(&format!("[{}] Installed {} (replaces {})", label, best.file_name().unwrap_or_default().to_string_lossy(), replaced));
+            return Ok(vec![best]);
+        }
+
+        if !std::io::stdin().is_terminal() {
+            return Err("`install choice` needs a terminal to ask which to install".into());
+        }
+        let chosen = blocking(move || review::review_batch(&candidates)).await?;
+        let backend = wallpaper::detect(config)?;
+        let mut installed = Vec::new();
+        for (index, path) in chosen.iter().enumerate() {
+            status::update(&label, Stage::Installing, index as f64 * 100.0 / chosen.len() as f64, &format!("{} of {}", index + 1, chosen.len()));
+            if wallpaper::install_on(backend.as_ref(), path, config).await? {
+                installed.push(path.clone());
+            }
+        }
+        Ok::<_, DownloaderError>(installed)
+    }.await;
+    status::finish(&label);
+
+    finish(QueueResult { url: text, label, outcome: outcome.map_err(|e| e.to_string()), unavailable: false, installed: true })
+}
+
+/// The output with the most pixels, then the largest file.
+fn best_output(candidates: &[PathBuf]) -> Option<PathBuf> {
+    candidates.iter()
+        .max_by_key(|path| {
+            let pixels = library::probe_resolution(path).map(|(width, height)| width * height).unwrap_or(0);
+            (pixels, fs::metadata(path).map(|m| m.len()).unwrap_or(0))
+        })
+        .cloned()
+}
+
+/// Log how a job ended.
+fn finish(result: QueueResult) -> QueueResult {
+    match &result.outcome {
+        Ok(_) => logger::success(&format!("[{}] Finished", result.label)),
+        Err(error) if result.unavailable => logger::warning(&format!("[{}] Skipped: {}", result.label, error)),
+        Err(error) => logger::error(&format!("[{}] Failed: {}", result.label, error)),
+    }
+    result
+}
+
+const SAVED_QUEUE_FILE: &str = "queue.json";
+
+/// How often a running saved queue says it is still being worked on
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+/// A saved run without a heartbeat for this long was left behind by a process that died,
+/// e.g. when the machine rebooted
+const ORPHANED_AFTER_SECONDS: i64 = 120;
+/// How often a download's bytes are checkpointed
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Entries handed to the daemon with `queue add`, saved with their progress until they
+/// finish, so the daemon resumes them after it is restarted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SavedQueue {
+    /// By run id; each `queue add` is one run
+    #[serde(default)]
+    pub runs: BTreeMap<String, SavedRun>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedRun {
+    /// Last sign of life from the process working on it; `None` while nobody has claimed it
+    pub heartbeat: Option<DateTime<Utc>>,
+    pub jobs: Vec<SavedJob>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedJob {
+    pub label: String,
+    /// The entry as queued
+    pub entry: String,
+    #[serde(flatten)]
+    pub state: SavedState,
+    /// Format ids being downloaded; a resumed download picks the same ones, so yt-dlp
+    /// continues their `.part` files instead of starting over
+    #[serde(default)]
+    pub formats: Vec<String>,
+    /// The file being downloaded into; its `.part` files sit next to it
+    #[serde(default)]
+    pub partial_path: Option<PathBuf>,
+    /// Size of the `.part` files at the last checkpoint
+    #[serde(default)]
+    pub bytes_done: u64,
+}
+
+/// Where a saved job got to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum SavedState {
+    /// Not started yet, or interrupted; runs when the queue is resumed
+    Pending,
+    /// Kept for entries that wait for it until the whole run is done
+    Done { outputs: Vec<PathBuf> },
+    Failed { error: String },
+}
+
+impl SavedQueue {
+    pub fn path(config: &Config) -> PathBuf {
+        config.output_dir.join(SAVED_QUEUE_FILE)
+    }
+
+    /// The saved runs for showing them; a file that can't be read shows as empty.
+    pub fn load(config: &Config) -> Self {
+        utils::read_json(&Self::path(config)).unwrap_or_else(|error| {
+            logger::warning(&format!("Could not read the saved queue: {}", error));
+            Self::default()
+        })
+    }
+
+    /// Load, change and save the queue while holding a lock, so the daemon, its jobs and
+    /// `queue add` don't overwrite each other. Fails, leaving the file alone, when it doesn't
+    /// parse.
+    pub fn update<T>(config: &Config, change: impl FnOnce(&mut SavedQueue) -> T) -> Result<T> {
+        utils::update_json(&Self::path(config), change)
+    }
+
+    /// Take over the runs nobody is working on: new ones and ones whose process stopped
+    /// sending heartbeats. Returns them with their ids.
+    pub fn claim_orphaned(&mut self) -> Vec<(String, SavedRun)> {
+        let now = Utc::now();
+        self.runs.iter_mut()
+            .filter(|(_, run)| run.heartbeat.is_none_or(|at| (now - at).num_seconds() >= ORPHANED_AFTER_SECONDS))
+            .map(|(id, run)| {
+                run.heartbeat = Some(now);
+                (id.clone(), run.clone())
+            })
+            .collect()
+    }
+
+    fn beat(&mut self, id: &str) {
+        if let Some(run) = self.runs.get_mut(id) {
+            run.heartbeat = Some(Utc::now());
+        }
+    }
+
+    /// The run has stopped: forget it once every job has ended, otherwise leave it for the
+    /// next daemon to claim.
+    fn release(&mut self, id: &str) {
+        let Some(run) = self.runs.get_mut(id) else {
+            return;
+        };
+        if run.jobs.iter().all(|job| job.state != SavedState::Pending) {
+            self.runs.remove(id);
+        } else {
+            run.heartbeat = None;
+        }
+    }
+
+    fn job_mut(&mut self, id: &str, label: &str) -> Option<&mut SavedJob> {
+        self.runs.get_mut(id)?.jobs.iter_mut().find(|job| job.label == label)
+    }
+
+    /// Print the saved runs and how far each job got.
+    pub fn display(&self) {
+        logger::header("Saved for the Daemon");
+        if self.runs.is_empty() {
+            logger::info("Nothing saved; add entries with: rust-downloader queue add <url>...");
+        }
+        for (id, run) in &self.runs {
+            let state = match run.heartbeat {
+                Some(at) if (Utc::now() - at).num_seconds() < ORPHANED_AFTER_SECONDS => "running",
+                Some(_) => "interrupted",
+                None => "waiting for the daemon",
+            };
+            logger::info(&format!("Run {} ({})", id, state));
+            for job in &run.jobs {
+                let progress = match &job.state {
+                    SavedState::Pending if job.bytes_done > 0 => format!("{} downloaded", utils::format_file_size(Some(job.bytes_done))),
+                    SavedState::Pending => "pending".to_string(),
+                    SavedState::Done { .. } => "done".to_string(),
+                    SavedState::Failed { error } => format!("failed: {}", error),
+                };
+                logger::info(&format!("   {:<16} {} ({})", job.label, job.entry, progress));
+            }
+        }
+    }
+}
+
+/// Saves where a download of a saved run got to: its formats and `.part` files, and every
+/// few seconds how much of them has arrived.
+struct Checkpoints {
+    config: Config,
+    run: String,
+    label: String,
+    /// The file being downloaded into, and when its bytes were last saved
+    progress: Mutex<(Option<PathBuf>, Instant)>,
+}
+
+impl Checkpoints {
+    fn new(config: &Config, run: &str, label: &str) -> Self {
+        Self {
+            config: config.clone(),
+            run: run.to_string(),
+            label: label.to_string(),
+            progress: Mutex::new((None, Instant::now())),
+        }
+    }
+
+    fn save(&self, change: impl FnOnce(&mut SavedJob)) {
+        let saved = SavedQueue::update(&self.config, |saved| saved.job_mut(&self.run, &self.label).map(change));
+        if let Err(error) = saved {
+            logger::warning(&format!("[{}] Could not save the download's progress: {}", self.label, error));
+        }
+    }
+}
+
+impl ProgressObserver for Checkpoints {
+    fn on_event(&self, event: &ProgressEvent) {
+        let mut progress = self.progress.lock().unwrap_or_else(|e| e.into_inner());
+        match event {
+            ProgressEvent::DownloadStarted { path, formats, .. } => {
+                *progress = (Some(path.clone()), Instant::now());
+                let bytes_done = downloader::partial_downloads(path).iter().map(|(_, size)| size).sum();
+                self.save(|job| {
+                    job.formats = formats.clone();
+                    job.partial_path = Some(path.clone());
+                    job.bytes_done = bytes_done;
+                });
+            }
+            ProgressEvent::DownloadProgress { .. } if progress.1.elapsed() >= CHECKPOINT_INTERVAL => {
+                progress.1 = Instant::now();
+                if let Some(path) = &progress.0 {
+                    let bytes_done = downloader::partial_downloads(path).iter().map(|(_, size)| size).sum();
+                    self.save(|job| job.bytes_done = bytes_done);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Print one line per job and return the files that were produced but not installed.
+pub fn display_results(results: &[QueueResult]) -> Vec<PathBuf> {
+    logger::header("Queue Summary");
+
+    let mut outputs = Vec::new();
+    for result in results {
+        match &result.outcome {
+            Ok(paths) if result.installed => {
+                for path in paths {
+                    logger::success(&format!("{:<16} installed {}", result.label, path.file_name().unwrap_or_default().to_string_lossy()));
+                }
+            }
+            Ok(paths) => {
+                for path in paths {
+                    logger::success(&format!("{:<16} {}", result.label, path.file_name().unwrap_or_default().to_string_lossy()));
+                }
+                // A later `convert` entry replaces what a download-only entry left
+                outputs.extend(paths.iter().filter(|path| path.exists()).cloned());
+            }
+            Err(error) if result.unavailable => logger::warning(&format!("{:<16} {} (unavailable: {})", result.label, result.url, error)),
+            Err(error) => logger::error(&format!("{:<16} {} ({})", result.label, result.url, error)),
+        }
+    }
+
+    let unavailable = results.iter().filter(|r| r.unavailable).count();
+    let failed = results.iter().filter(|r| r.outcome.is_err()).count() - unavailable;
+    logger::info(&format!("{} succeeded, {} unavailable, {} failed", results.len() - failed - unavailable, unavailable, failed));
+    outputs
+}
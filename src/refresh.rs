@@ -10,10 +10,9 @@ pub struct RefreshUtility {
 }
 
 impl RefreshUtility {
-    pub fn new() -> Self {
-        let config = Config::default();
-        let customer_dir = std::path::PathBuf::from(config.video_settings.customer_dir);
-        let target_dir = customer_dir.join(config.video_settings.target_sub_dir);
+    pub fn new(config: &Config) -> Self {
+        let customer_dir = config.video_settings.customer_dir.clone();
+        let target_dir = config.video_settings.target_dir();
         
         Self {
             customer_dir,
@@ -204,12 +203,12 @@ end tell"#;
         Ok(())
     }
 
-    pub async fn quick_refresh() -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn quick_refresh(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
         logger::header(" Quick Video Refresh");
         logger::info("Performing rapid video refresh...");
 
         // Quick refresh without detailed status
-        let refresh = RefreshUtility::new();
+        let refresh = RefreshUtility::new(config);
         
         // Just touch files and restart dock
         refresh.touch_video_files().await?;
@@ -233,12 +232,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     logger::init();
     
     let args: Vec<String> = std::env::args().collect();
+    let config = Config::load(None)?;
     
     // Check for quick refresh flag
     if args.len() > 1 && args[1] == "--quick" {
-        RefreshUtility::quick_refresh().await?;
+        RefreshUtility::quick_refresh(&config).await?;
     } else {
-        let mut refresh = RefreshUtility::new();
+        let mut refresh = RefreshUtility::new(&config);
         match refresh.run().await {
             Ok(_) => {
                 logger::success(" video refresh completed successfully!");
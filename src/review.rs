@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+use std::process::Command;
+use crate::error::Result;
+use crate::logger;
+use crate::library;
+use crate::utils;
+
+/// Let the user pick which wallpapers of a batch to install, before anything needs sudo.
+///
+/// Each result gets a poster frame next to it (reused by the library as its thumbnail).
+/// Returns the chosen files in the order given; empty when the user installs nothing.
+pub fn review_batch(outputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    logger::header("Review Converted Wallpapers");
+
+    for (index, output) in outputs.iter().enumerate() {
+        let poster = output.with_extension("jpg");
+        if !poster.exists() {
+            library::extract_thumbnail(output, &poster);
+        }
+
+        let duration = library::probe_duration(output)
+            .map(utils::format_time)
+            .unwrap_or_else(|| "unknown length".to_string());
+        let size = utils::format_file_size(std::fs::metadata(output).ok().map(|m| m.len()));
+
+        logger::video(&format!("{:>2}. {} ({} | {})", index + 1, output.file_name().unwrap().to_string_lossy(), duration, size));
+        if poster.exists() {
+            logger::file(&format!("    Poster: {}", poster.display()));
+        }
+    }
+
+    loop {
+        println!();
+        logger::info("Enter numbers to install (e.g. 1,3), 'p N' to preview one, or press Enter to skip");
+        let input = utils::prompt("Selection: ")?;
+
+        if input.is_empty() {
+            logger::info("Nothing selected; wallpapers stay in the library");
+            return Ok(Vec::new());
+        }
+
+        if let Some(number) = input.strip_prefix('p').map(str::trim) {
+            match parse_index(number, outputs.len()) {
+                Some(index) => preview(&outputs[index]),
+                None => logger::warning(&format!("Not a valid entry: {}", number)),
+            }
+            continue;
+        }
+
+        let indices: Option<Vec<usize>> = input.split(',').map(|part| parse_index(part.trim(), outputs.len())).collect();
+        let chosen: Vec<PathBuf> = match indices {
+            Some(indices) => indices.into_iter().map(|i| outputs[i].clone()).collect(),
+            None => {
+                logger::warning(&format!("Invalid selection: {}", input));
+                continue;
+            }
+        };
+
+        for path in &chosen {
+            logger::wallpaper(&format!("  {}", path.file_name().unwrap().to_string_lossy()));
+        }
+        let confirm = utils::prompt(&format!("Install {} wallpaper(s)? This step needs sudo. (y/N): ", chosen.len()))?;
+        if confirm.eq_ignore_ascii_case("y") || confirm.eq_ignore_ascii_case("yes") {
+            return Ok(chosen);
+        }
+    }
+}
+
+fn parse_index(value: &str, len: usize) -> Option<usize> {
+    value.parse::<usize>().ok()
+        .and_then(|n| n.checked_sub(1))
+        .filter(|i| *i < len)
+}
+
+/// Open a Quick Look preview of the video; closes when the user dismisses it.
+fn preview(path: &PathBuf) {
+    let result = Command::new("qlmanage")
+        .args(["-p"])
+        .arg(path)
+        .output();
+
+    if !result.is_ok_and(|output| output.status.success()) {
+        logger::warning("Quick Look preview unavailable; open the poster image instead");
+    }
+}
+
@@ -0,0 +1,230 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use chrono::{DateTime, NaiveTime, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use crate::config::Config;
+use crate::error::Result;
+use crate::library::Library;
+use crate::logger;
+use crate::wallpaper;
+
+const ROTATION_FILE: &str = "rotation.json";
+const LOG_FILE: &str = "rotation.log";
+
+/// launchd label, also the name of the agent's plist in ~/Library/LaunchAgents
+pub const AGENT_LABEL: &str = "com.rust-downloader.rotate";
+
+/// When `rotate next` runs on its own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Schedule {
+    /// Every this many hours, counted from when the agent is loaded
+    Every(u32),
+    /// At these local times each day
+    At(Vec<NaiveTime>),
+}
+
+impl Schedule {
+    pub fn describe(&self) -> String {
+        match self {
+            Schedule::Every(1) => "every hour".to_string(),
+            Schedule::Every(hours) => format!("every {} hours", hours),
+            Schedule::At(times) => {
+                let times: Vec<String> = times.iter().map(|time| time.format("%H:%M").to_string()).collect();
+                format!("daily at {}", times.join(", "))
+            }
+        }
+    }
+}
+
+/// Library entries installed in turn, stored in `rotation.json` next to the downloads.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rotation {
+    pub entries: Vec<String>,
+    pub schedule: Schedule,
+    /// System video to replace; defaults to the most recently installed one
+    #[serde(default)]
+    pub target_file: Option<String>,
+    /// Index in `entries` of the wallpaper installed last
+    #[serde(default)]
+    pub current: Option<usize>,
+    #[serde(default)]
+    pub last_rotated: Option<DateTime<Utc>>,
+}
+
+impl Rotation {
+    pub fn path(config: &Config) -> PathBuf {
+        config.output_dir.join(ROTATION_FILE)
+    }
+
+    pub fn load(config: &Config) -> Option<Self> {
+        fs::read_to_string(Self::path(config))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+    }
+
+    pub fn save(&self, config: &Config) -> Result<()> {
+        fs::write(Self::path(config), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Index of the entry after `current`, wrapping around, skipping ids that are no longer
+    /// in the library so a deleted wallpaper doesn't stop the rotation.
+    fn next_index(&self, library: &Library) -> Option<usize> {
+        let start = self.current.map_or(0, |current| current + 1);
+        (0..self.entries.len())
+            .map(|offset| (start + offset) % self.entries.len())
+            .find(|index| library.find(&self.entries[*index]).is_some())
+    }
+
+    pub fn display(&self, config: &Config) {
+        logger::header("Wallpaper Rotation");
+        let library = Library::new(config);
+        for (index, id) in self.entries.iter().enumerate() {
+            let marker = if self.current == Some(index) { " (current)" } else { "" };
+            let missing = if library.find(id).is_none() { " (not in the library)" } else { "" };
+            logger::wallpaper(&format!("{}. {}{}{}", index + 1, id, marker, missing));
+        }
+        logger::info(&format!("Schedule: {}", self.schedule.describe()));
+        if let Some(target) = &self.target_file {
+            logger::info(&format!("Replacing system video: {}", target));
+        }
+        if let Some(at) = self.last_rotated {
+            logger::info(&format!("Last rotated: {}", at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")));
+        }
+        if agent_path().is_some_and(|path| path.exists()) {
+            logger::info(&format!("Runs in the background through the launchd agent {}", AGENT_LABEL));
+        } else {
+            logger::info("Not scheduled yet; run 'rust-downloader rotate install' to rotate in the background");
+        }
+    }
+}
+
+/// Install the next wallpaper of the rotation.
+pub async fn advance(config: &Config) -> Result<()> {
+    let mut rotation = Rotation::load(config).ok_or("No rotation set up; create one with 'rust-downloader rotate set <ids>'")?;
+    let library = Library::new(config);
+    let index = rotation.next_index(&library).ok_or("None of the rotation's wallpapers are in the library anymore")?;
+    let entry = library.find(&rotation.entries[index]).ok_or("Library entry disappeared")?;
+
+    logger::wallpaper(&format!("Rotating to {} ({} of {})", entry.id, index + 1, rotation.entries.len()));
+    let replaced = wallpaper::detect(config)?
+        .swap(&entry.path, rotation.target_file.as_deref())
+        .await?;
+    logger::success(&format!("{} installed over {}", entry.id, replaced));
+
+    rotation.current = Some(index);
+    rotation.last_rotated = Some(Utc::now());
+    rotation.save(config)
+}
+
+/// `~/Library/LaunchAgents/<label>.plist`
+pub fn agent_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join("Library/LaunchAgents").join(format!("{}.plist", AGENT_LABEL)))
+}
+
+/// Property list for a launchd agent that runs `arguments` on `schedule`, logging to `log`.
+pub fn agent_plist(arguments: &[String], schedule: &Schedule, log: &Path) -> String {
+    let strings = |values: &[String]| values.iter().map(|value| format!("        <string>{}</string>\n", escape(value))).collect::<String>();
+    let timing = match schedule {
+        Schedule::Every(hours) => format!("    <key>StartInterval</key>\n    <integer>{}</integer>\n", u64::from(*hours) * 3600),
+        Schedule::At(times) => {
+            let entries: String = times.iter()
+                .map(|time| format!(
+                    "        <dict>\n            <key>Hour</key>\n            <integer>{}</integer>\n            <key>Minute</key>\n            <integer>{}</integer>\n        </dict>\n",
+                    time.hour(), time.minute()
+                ))
+                .collect();
+            format!("    <key>StartCalendarInterval</key>\n    <array>\n{}    </array>\n", entries)
+        }
+    };
+    let log = escape(&log.to_string_lossy());
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+{timing}    <key>EnvironmentVariables</key>
+    <dict>
+        <key>PATH</key>
+        <string>/opt/homebrew/bin:/usr/local/bin:/usr/bin:/bin:/usr/sbin:/sbin</string>
+    </dict>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = AGENT_LABEL,
+        arguments = strings(arguments),
+        timing = timing,
+        log = log,
+    )
+}
+
+fn escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Write the launchd agent for `rotation` and load it, replacing an older one. The agent runs
+/// this executable with the same config file, so it finds `rotation.json` in `output_dir`.
+pub fn install_agent(rotation: &Rotation, config: &Config, config_file: Option<&Path>) -> Result<PathBuf> {
+    if !cfg!(target_os = "macos") {
+        let executable = std::env::current_exe()?;
+        return Err(format!(
+            "launchd agents only exist on macOS; schedule '{} rotate next' with cron or a systemd timer instead",
+            executable.display()
+        ).into());
+    }
+    let path = agent_path().ok_or("Cannot determine the home directory")?;
+
+    let mut arguments = vec![std::env::current_exe()?.to_string_lossy().to_string()];
+    if let Some(file) = config_file {
+        arguments.extend(["--config".to_string(), fs::canonicalize(file)?.to_string_lossy().to_string()]);
+    }
+    arguments.extend(["rotate".to_string(), "next".to_string()]);
+
+    fs::create_dir_all(path.parent().unwrap())?;
+    if path.exists() {
+        // Not loaded if it was unloaded by hand; the new one replaces it either way
+        launchctl("unload", &path).ok();
+    }
+    fs::write(&path, agent_plist(&arguments, &rotation.schedule, &config.output_dir.join(LOG_FILE)))?;
+    launchctl("load", &path)?;
+
+    // Agents run as the user, without the sudo a manual install has
+    let videos = config.video_settings.target_dir();
+    if tempfile::tempfile_in(&videos).is_err() {
+        logger::warning(&format!("{} is not writable without sudo, so the agent can't replace wallpapers there", videos.display()));
+        logger::info(&format!("Give your user write access to it, or check {} after the first rotation", config.output_dir.join(LOG_FILE).display()));
+    }
+    Ok(path)
+}
+
+/// Unload and delete the launchd agent. Returns false when none was installed.
+pub fn uninstall_agent() -> Result<bool> {
+    let Some(path) = agent_path().filter(|path| path.exists()) else {
+        return Ok(false);
+    };
+    if let Err(error) = launchctl("unload", &path) {
+        logger::warning(&error.to_string());
+    }
+    fs::remove_file(&path)?;
+    Ok(true)
+}
+
+fn launchctl(action: &str, plist: &Path) -> Result<()> {
+    let output = Command::new("launchctl").args([action, "-w"]).arg(plist).output()?;
+    if !output.status.success() {
+        return Err(format!("launchctl {} failed: {}", action, String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    Ok(())
+}
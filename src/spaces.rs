@@ -0,0 +1,138 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::error::Result;
+use crate::logger;
+use crate::config::Config;
+use crate::library::Library;
+use crate::video_manager::VideoManager;
+
+const ASSIGNMENTS_FILE: &str = "space_assignments.json";
+
+/// Maps a Mission Control Space to the library entry installed over the aerial it displays.
+///
+/// macOS only lets each Space choose an aerial, so per-Space wallpapers work by giving every
+/// Space a different aerial in System Settings and replacing the file behind that aerial.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpaceAssignment {
+    pub space: u32,
+    pub library_id: String,
+    pub target_file: String,
+    pub assigned_at: String,
+}
+
+pub struct SpaceAssignments {
+    path: PathBuf,
+    assignments: Vec<SpaceAssignment>,
+}
+
+impl SpaceAssignments {
+    pub fn load(config: &Config) -> Self {
+        let path = config.output_dir.join(ASSIGNMENTS_FILE);
+        let assignments = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self { path, assignments }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(&self.assignments)?)?;
+        Ok(())
+    }
+
+    pub fn assignments(&self) -> &[SpaceAssignment] {
+        &self.assignments
+    }
+
+    pub fn set(&mut self, assignment: SpaceAssignment) {
+        // One aerial file can only show one video, so drop any Space that shared it
+        self.assignments.retain(|a| a.space != assignment.space && a.target_file != assignment.target_file);
+        self.assignments.push(assignment);
+        self.assignments.sort_by_key(|a| a.space);
+    }
+
+    pub fn display(&self) {
+        logger::header("Space Assignments");
+
+        if self.assignments.is_empty() {
+            logger::info("No wallpapers assigned to Spaces yet");
+            logger::info("Assign one with: rust-downloader wallpaper assign --space 2 <id>");
+            return;
+        }
+
+        for a in &self.assignments {
+            logger::wallpaper(&format!("Space {}: {} (replaces {})", a.space, a.library_id, a.target_file));
+        }
+    }
+}
+
+/// Number of Spaces on the main display, read from the Mission Control preferences.
+pub fn count_spaces() -> Option<usize> {
+    let home = std::env::var("HOME").ok()?;
+    let plist = Path::new(&home).join("Library/Preferences/com.apple.spaces.plist");
+
+    let output = Command::new("plutil")
+        .args(["-convert", "json", "-o", "-"])
+        .arg(&plist)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let value: Value = serde_json::from_slice(&output.stdout).ok()?;
+    value.pointer("/SpacesDisplayConfiguration/Management Data/Monitors")?
+        .as_array()?
+        .iter()
+        .filter_map(|m| m.get("Spaces").and_then(|s| s.as_array()))
+        .map(|spaces| spaces.len())
+        .next()
+}
+
+pub async fn assign_to_space(config: &Config, space: u32, id: &str) -> Result<SpaceAssignment> {
+    if space == 0 {
+        return Err("Spaces are numbered from 1".into());
+    }
+
+    match count_spaces() {
+        Some(count) if space as usize > count => {
+            return Err(format!("Space {} does not exist ({} Spaces configured in Mission Control)", space, count).into());
+        }
+        Some(_) => {}
+        None => logger::warning("Could not read Mission Control Spaces; skipping Space number validation"),
+    }
+
+    let library = Library::new(config);
+    let entry = library.find(id).ok_or_else(|| format!("No library entry with id '{}'", id))?;
+
+    let mut assignments = SpaceAssignments::load(config);
+
+    logger::header(&format!("Assign Wallpaper to Space {}", space));
+    logger::info(&format!("Switch to Space {} and check which aerial it uses in System Settings > Wallpaper", space));
+    logger::info("Each Space needs its own aerial; the one you pick next is replaced by this video");
+    for other in assignments.assignments().iter().filter(|a| a.space != space) {
+        logger::info(&format!("   Space {} already uses {}", other.space, other.target_file));
+    }
+    println!();
+
+    let video_mgr = VideoManager::new(config);
+    let target_file = video_mgr.setup_video_target(&entry.path).await?
+        .ok_or("Assignment cancelled by user")?;
+
+    let assignment = SpaceAssignment {
+        space,
+        library_id: entry.id.clone(),
+        target_file,
+        assigned_at: chrono::Local::now().to_rfc3339(),
+    };
+    assignments.set(assignment.clone());
+    assignments.save()?;
+
+    logger::success(&format!("Space {} now shows {}", space, entry.id));
+    Ok(assignment)
+}
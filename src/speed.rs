@@ -0,0 +1,92 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use crate::utils;
+
+/// How far back the sparkline reaches
+const WINDOW: Duration = Duration::from_secs(60);
+/// Time constant of the moving average; larger values jump less but react slower
+const SMOOTHING_SECONDS: f64 = 5.0;
+/// yt-dlp prints several lines a second; rates over shorter gaps are mostly noise
+const MIN_SAMPLE_GAP: Duration = Duration::from_millis(250);
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Download speed smoothed with an exponential moving average, so the ETA doesn't swing
+/// with every burst and stall the way yt-dlp's instantaneous estimate does.
+#[derive(Debug, Default)]
+pub struct SpeedTracker {
+    last: Option<(Instant, u64)>,
+    speed: Option<f64>,
+    history: VecDeque<(Instant, f64)>,
+}
+
+impl SpeedTracker {
+    /// Record that `bytes` have been downloaded so far, across all streams.
+    pub fn record(&mut self, bytes: u64) {
+        let now = Instant::now();
+        let Some((then, previous)) = self.last else {
+            self.last = Some((now, bytes));
+            return;
+        };
+
+        let elapsed = now.duration_since(then);
+        if elapsed < MIN_SAMPLE_GAP {
+            return;
+        }
+
+        // Weighting by the gap keeps the average independent of how often yt-dlp reports
+        let rate = bytes.saturating_sub(previous) as f64 / elapsed.as_secs_f64();
+        let weight = 1.0 - (-elapsed.as_secs_f64() / SMOOTHING_SECONDS).exp();
+        let speed = match self.speed {
+            Some(speed) => speed + weight * (rate - speed),
+            None => rate,
+        };
+
+        self.speed = Some(speed);
+        self.last = Some((now, bytes));
+        self.history.push_back((now, speed));
+        while self.history.front().is_some_and(|(time, _)| now.duration_since(*time) > WINDOW) {
+            self.history.pop_front();
+        }
+    }
+
+    /// Smoothed speed in bytes per second, once two samples are in.
+    pub fn speed(&self) -> Option<f64> {
+        self.speed
+    }
+
+    /// Seconds needed for `remaining` bytes at the smoothed speed.
+    pub fn eta(&self, remaining: u64) -> Option<f64> {
+        self.speed.filter(|speed| *speed > 0.0).map(|speed| remaining as f64 / speed)
+    }
+
+    /// The smoothed speed over the last minute as `width` bars, oldest on the left and scaled
+    /// to the fastest column. Columns without samples yet are blank.
+    pub fn sparkline(&self, width: usize) -> String {
+        let Some(&(now, _)) = self.history.back() else {
+            return String::new();
+        };
+
+        let column_seconds = WINDOW.as_secs_f64() / width as f64;
+        let mut columns: Vec<Option<f64>> = vec![None; width];
+        for &(time, speed) in &self.history {
+            let age = (now.duration_since(time).as_secs_f64() / column_seconds) as usize;
+            let column = columns[width - 1 - age.min(width - 1)].get_or_insert(0.0);
+            *column = column.max(speed);
+        }
+
+        let peak = columns.iter().flatten().fold(0.0, |peak: f64, speed| peak.max(*speed));
+        columns.iter().map(|column| match column {
+            None => ' ',
+            Some(_) if peak <= 0.0 => BARS[0],
+            Some(speed) => BARS[(speed / peak * (BARS.len() - 1) as f64).round() as usize],
+        }).collect()
+    }
+}
+
+/// e.g. "2.4MB/s"
+pub fn format_speed(bytes_per_second: f64) -> String {
+    match bytes_per_second as u64 {
+        0 => "0B/s".to_string(),
+        bytes => format!("{}/s", utils::format_file_size(Some(bytes))),
+    }
+}
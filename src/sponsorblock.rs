@@ -0,0 +1,80 @@
+use serde::Deserialize;
+use crate::error::Result;
+use crate::config::SPONSORBLOCK_CATEGORIES;
+use crate::http::Request;
+
+const API_URL: &str = "https://sponsor.ajay.app/api/skipSegments";
+
+/// Segments shorter than this are left alone; cutting them only adds a stutter
+const MIN_SEGMENT_SECONDS: f64 = 0.5;
+
+/// A community-submitted segment, in seconds from the start of the video.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub start: f64,
+    pub end: f64,
+    pub category: String,
+}
+
+#[derive(Deserialize)]
+struct ApiSegment {
+    category: String,
+    segment: [f64; 2],
+}
+
+/// Skip segments for `video_id` in the categories `--sponsorblock` handles, sorted by start.
+/// A video nobody submitted segments for has none.
+pub fn fetch_segments(video_id: &str) -> Result<Vec<Segment>> {
+    let categories = serde_json::to_string(&SPONSORBLOCK_CATEGORIES)?;
+    let response = Request::get(API_URL)
+        .query("videoID", video_id)
+        .query("categories", &categories)
+        .timeout(10)
+        .send()?;
+    if response.status == 404 {
+        return Ok(Vec::new());
+    }
+
+    let mut segments: Vec<Segment> = response.error_for_status()?.json::<Vec<ApiSegment>>()?
+        .into_iter()
+        .map(|s| Segment { start: s.segment[0], end: s.segment[1], category: s.category })
+        .filter(|s| s.end - s.start >= MIN_SEGMENT_SECONDS)
+        .collect();
+    segments.sort_by(|a, b| a.start.total_cmp(&b.start));
+    Ok(segments)
+}
+
+/// Move segments onto a file that starts `offset` seconds into the video and lasts `duration`,
+/// dropping the ones outside it and merging overlaps.
+pub fn fit_to_file(segments: &[Segment], offset: f64, duration: f64) -> Vec<Segment> {
+    let mut fitted: Vec<Segment> = Vec::new();
+    for segment in segments {
+        let start = (segment.start - offset).max(0.0);
+        let end = (segment.end - offset).min(duration);
+        if end - start < MIN_SEGMENT_SECONDS {
+            continue;
+        }
+
+        match fitted.last_mut() {
+            Some(last) if start <= last.end => last.end = last.end.max(end),
+            _ => fitted.push(Segment { start, end, category: segment.category.clone() }),
+        }
+    }
+    fitted
+}
+
+/// The parts of a `duration`-second file that remain once `segments` are cut out.
+pub fn keep_ranges(segments: &[Segment], duration: f64) -> Vec<(f64, f64)> {
+    let mut ranges = Vec::new();
+    let mut position = 0.0;
+    for segment in segments {
+        if segment.start - position >= MIN_SEGMENT_SECONDS {
+            ranges.push((position, segment.start));
+        }
+        position = segment.end;
+    }
+    if duration - position >= MIN_SEGMENT_SECONDS {
+        ranges.push((position, duration));
+    }
+    ranges
+}
@@ -0,0 +1,453 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::cancel::{self, CancelHandle};
+use crate::error::Result;
+use crate::logger;
+use crate::phases::{self, PhaseWeights};
+use crate::utils;
+
+const WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Where the status is written; unset unless `start` was called.
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+static JOBS: Mutex<BTreeMap<String, Job>> = Mutex::new(BTreeMap::new());
+/// Set by `set_queue_limits` when a queue is running
+static LIMITS: Mutex<Option<QueueLimits>> = Mutex::new(None);
+static STOPPED: AtomicBool = AtomicBool::new(false);
+/// Keeps a periodic write from landing after the final one
+static WRITING: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    /// In a queue, waiting for a download slot
+    Queued,
+    /// Analyzed, about to download
+    Starting,
+    Downloading,
+    /// Looping a short video up to the minimum wallpaper length
+    Extending,
+    /// Downloaded, waiting for a conversion slot
+    #[serde(rename = "waiting_to_convert")]
+    WaitingToConvert,
+    Converting,
+    Installing,
+}
+
+impl Stage {
+    /// The queue a job at this stage is in: its download, its conversion or its install.
+    pub fn queue(self) -> &'static str {
+        match self {
+            Stage::Queued | Stage::Starting | Stage::Downloading => "download",
+            Stage::WaitingToConvert | Stage::Extending | Stage::Converting => "conversion",
+            Stage::Installing => "install",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub job: String,
+    pub title: String,
+    pub stage: Stage,
+    pub percent: f64,
+    /// Human-readable size, speed and ETA, as shown on the progress row
+    pub detail: String,
+    /// Progress of the whole job across its stages, weighted by how long each usually takes
+    #[serde(default)]
+    pub overall: f64,
+    /// Seconds until the whole job is done; `None` until there is enough progress to tell
+    #[serde(default)]
+    pub remaining_seconds: Option<f64>,
+}
+
+impl JobStatus {
+    /// e.g. "63% overall, ~12m 5s left"
+    pub fn overall_summary(&self) -> String {
+        match self.remaining_seconds {
+            Some(seconds) => format!("{:.0}% overall, ~{} left", self.overall, utils::format_time(seconds)),
+            None => format!("{:.0}% overall", self.overall),
+        }
+    }
+}
+
+/// A job as tracked in this process.
+struct Job {
+    status: JobStatus,
+    weights: PhaseWeights,
+    started: Instant,
+    /// `overall` when the job began, i.e. after the analysis
+    started_at_percent: f64,
+    /// Cancelled when `ctl cancel <job>` asks for it
+    cancel: CancelHandle,
+}
+
+/// How many jobs a queue runs through each of its stages at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueLimits {
+    pub downloads: usize,
+    pub conversions: usize,
+}
+
+/// Contents of the status file.
+///
+/// `running` is false once the run has ended; a stale `updated_at` with `running` still
+/// true means the process was killed before it could say so.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Status {
+    pub pid: u32,
+    pub running: bool,
+    pub updated_at: DateTime<Utc>,
+    pub jobs: Vec<JobStatus>,
+    /// Set while a queue is running
+    #[serde(default)]
+    pub queue: Option<QueueLimits>,
+}
+
+impl Status {
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read status file {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// Write the status of every job to `path` once a second until `stop`, for menu bar
+/// plugins and desktop widgets that poll a file instead of talking to the process.
+pub fn start(path: PathBuf) {
+    if PATH.set(path).is_err() {
+        return;
+    }
+
+    std::thread::spawn(|| {
+        while !STOPPED.load(Ordering::SeqCst) {
+            apply_cancel_requests();
+            if let Err(error) = write(true) {
+                logger::warning(&format!("Could not write status file: {}", error));
+                return;
+            }
+            std::thread::sleep(WRITE_INTERVAL);
+        }
+    });
+}
+
+/// Mark the run as finished in the status file and stop updating it.
+pub fn stop() {
+    if STOPPED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    JOBS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    write(false).ok();
+}
+
+/// Start tracking a job that has been analyzed, with `weights` for its stages. `cancel`
+/// stops it when a cancel request names it.
+pub fn begin(job: &str, title: &str, weights: PhaseWeights, cancel: CancelHandle) {
+    let overall = weights.overall(Stage::Downloading, 0.0);
+    JOBS.lock().unwrap_or_else(|e| e.into_inner()).insert(job.to_string(), Job {
+        status: JobStatus {
+            job: job.to_string(),
+            title: title.to_string(),
+            stage: Stage::Starting,
+            percent: 0.0,
+            detail: String::new(),
+            overall,
+            remaining_seconds: None,
+        },
+        weights,
+        started: Instant::now(),
+        started_at_percent: overall,
+        cancel,
+    });
+}
+
+/// Track a job a queue has yet to start, so it shows up (and can be cancelled) while it
+/// waits for a download slot. `begin` takes over once it is analyzed.
+pub fn enqueue(job: &str, url: &str, weights: PhaseWeights, cancel: CancelHandle) {
+    JOBS.lock().unwrap_or_else(|e| e.into_inner()).insert(job.to_string(), Job {
+        status: JobStatus {
+            job: job.to_string(),
+            title: url.to_string(),
+            stage: Stage::Queued,
+            percent: 0.0,
+            detail: String::new(),
+            overall: 0.0,
+            remaining_seconds: None,
+        },
+        weights,
+        started: Instant::now(),
+        started_at_percent: 0.0,
+        cancel,
+    });
+}
+
+/// The concurrency limits of the running queue, for `queue list`.
+pub fn set_queue_limits(limits: QueueLimits) {
+    *LIMITS.lock().unwrap_or_else(|e| e.into_inner()) = Some(limits);
+}
+
+/// Record the progress of a job started with `begin`; other jobs are ignored by the status
+/// file but still reported as `--json` progress events.
+pub fn update(job: &str, stage: Stage, percent: f64, detail: &str) {
+    let mut jobs = JOBS.lock().unwrap_or_else(|e| e.into_inner());
+    let overall = jobs.get_mut(job).map(|tracked| {
+        let status = &mut tracked.status;
+        status.stage = stage;
+        status.percent = percent;
+        status.detail = detail.to_string();
+        // The audio stream restarts the download from 0%, so never step back
+        status.overall = status.overall.max(tracked.weights.overall(stage, percent));
+        status.remaining_seconds = phases::remaining_seconds(tracked.started.elapsed().as_secs_f64(), tracked.started_at_percent, status.overall);
+        (status.overall, status.remaining_seconds)
+    });
+    drop(jobs);
+
+    let (overall, remaining_seconds) = overall.unzip();
+    logger::event("progress", serde_json::json!({
+        "job": job,
+        "stage": stage,
+        "percent": percent,
+        "detail": detail,
+        "overall": overall,
+        "remaining_seconds": remaining_seconds.flatten(),
+    }));
+}
+
+/// The overall progress of a job started with `begin`, e.g. "63% overall, ~12m 5s left".
+pub fn overall_summary(job: &str) -> Option<String> {
+    JOBS.lock().unwrap_or_else(|e| e.into_inner()).get(job).map(|tracked| tracked.status.overall_summary())
+}
+
+pub fn finish(job: &str) {
+    JOBS.lock().unwrap_or_else(|e| e.into_inner()).remove(job);
+}
+
+/// Jobs to cancel are appended, one label per line, to this file next to the status file.
+fn cancel_requests_path(path: &Path) -> PathBuf {
+    path.with_extension("cancel")
+}
+
+/// Cancel the jobs named in the cancel requests file, then remove it.
+fn apply_cancel_requests() {
+    let Some(path) = PATH.get() else {
+        return;
+    };
+    // Take the file over first, so a request appended meanwhile lands in a new one
+    let requests = cancel_requests_path(path);
+    let taken = requests.with_extension("cancel.taken");
+    if fs::rename(&requests, &taken).is_err() {
+        return;
+    }
+    let content = fs::read_to_string(&taken).unwrap_or_default();
+    fs::remove_file(&taken).ok();
+
+    for job in content.lines().map(str::trim).filter(|job| !job.is_empty()) {
+        let cancel = JOBS.lock().unwrap_or_else(|e| e.into_inner()).get(job).map(|tracked| tracked.cancel.clone());
+        if let Some(cancel) = cancel {
+            logger::warning(&format!("[{}] Cancel requested", job));
+            cancel.cancel();
+        }
+    }
+}
+
+/// Replace the file in one step, so readers never see a half-written status.
+fn write(running: bool) -> Result<()> {
+    let Some(path) = PATH.get() else {
+        return Ok(());
+    };
+    let _writing = WRITING.lock().unwrap_or_else(|e| e.into_inner());
+    if running && STOPPED.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let status = Status {
+        pid: std::process::id(),
+        running,
+        updated_at: Utc::now(),
+        jobs: JOBS.lock().unwrap_or_else(|e| e.into_inner()).values().map(|tracked| tracked.status.clone()).collect(),
+        queue: *LIMITS.lock().unwrap_or_else(|e| e.into_inner()),
+    };
+
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    let file = tempfile::NamedTempFile::new_in(dir)?;
+    serde_json::to_writer_pretty(&file, &status)?;
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// A status older than this while `running` means the writer died.
+const STALE_AFTER_SECONDS: i64 = 10;
+
+impl Status {
+    /// Whether the run that wrote this status is still going.
+    pub fn is_live(&self) -> bool {
+        let fresh = (Utc::now() - self.updated_at).num_seconds() < STALE_AFTER_SECONDS;
+        self.running && fresh && cancel::is_running(self.pid)
+    }
+
+    /// Average overall progress over all jobs.
+    fn percent(&self) -> f64 {
+        if self.jobs.is_empty() {
+            return 0.0;
+        }
+        self.jobs.iter().map(|job| job.overall).sum::<f64>() / self.jobs.len() as f64
+    }
+}
+
+impl Stage {
+    fn label(self) -> &'static str {
+        match self {
+            Stage::Queued => "Queued",
+            Stage::Starting => "Starting",
+            Stage::Downloading => "Downloading",
+            Stage::Extending => "Extending",
+            Stage::WaitingToConvert => "Waiting to convert",
+            Stage::Converting => "Converting",
+            Stage::Installing => "Installing",
+        }
+    }
+}
+
+/// Print the status for a terminal.
+pub fn display(path: &Path) -> Result<()> {
+    let status = Status::read(path)?;
+    logger::header("Download Status");
+
+    if !status.is_live() {
+        logger::info("No downloads running");
+        return Ok(());
+    }
+
+    for job in &status.jobs {
+        logger::info(&format!("[{}] {}", job.job, job.title));
+        logger::info(&format!("   {} {:5.1}% {}", job.stage.label(), job.percent, job.detail));
+        logger::info(&format!("   {}", job.overall_summary()));
+    }
+    if status.jobs.is_empty() {
+        logger::info("Preparing...");
+    }
+    Ok(())
+}
+
+/// Print the jobs of a running queue grouped by the queue they are in, with how many of
+/// each run at once.
+pub fn display_queues(path: &Path) -> Result<()> {
+    let status = Status::read(path)?;
+    logger::header("Download Queue");
+
+    if !status.is_live() {
+        logger::info("No downloads running");
+        return Ok(());
+    }
+
+    for (queue, limit) in [("download", status.queue.map(|limits| limits.downloads)), ("conversion", status.queue.map(|limits| limits.conversions)), ("install", None)] {
+        let jobs: Vec<&JobStatus> = status.jobs.iter().filter(|job| job.stage.queue() == queue).collect();
+        if jobs.is_empty() && (queue == "install" || status.queue.is_none()) {
+            continue;
+        }
+        let waiting = jobs.iter().filter(|job| matches!(job.stage, Stage::Queued | Stage::WaitingToConvert)).count();
+        let limit = limit.map(|limit| format!(", up to {} at a time", limit)).unwrap_or_default();
+        logger::info(&format!("{}: {} running, {} waiting{}", queue, jobs.len() - waiting, waiting, limit));
+        for job in jobs {
+            logger::info(&format!("   [{}] {:<18} {:5.1}% {}", job.job, job.stage.label(), job.percent, job.title));
+        }
+    }
+    if status.jobs.is_empty() {
+        logger::info("Preparing...");
+    }
+    Ok(())
+}
+
+/// Print the status in the SwiftBar/xbar plugin format: the menu bar title, then `---` and
+/// the dropdown, with an action per job that cancels it through `ctl cancel` and one that
+/// cancels the whole run through `status --cancel`.
+pub fn print_xbar(path: &Path) -> Result<()> {
+    // A missing file only means nothing has run yet
+    let status = Status::read(path).ok().filter(Status::is_live);
+
+    let Some(status) = status else {
+        println!("⬇︎");
+        println!("---");
+        println!("No downloads running");
+        return Ok(());
+    };
+
+    let symbol = if status.jobs.iter().all(|job| job.stage == Stage::Converting) && !status.jobs.is_empty() { "⚙︎" } else { "⬇︎" };
+    match status.jobs.len() {
+        0 | 1 => println!("{} {:.0}%", symbol, status.percent()),
+        jobs => println!("{} {} jobs {:.0}%", symbol, jobs, status.percent()),
+    }
+
+    let executable = std::env::current_exe()?;
+    println!("---");
+    for job in &status.jobs {
+        // `|` separates SwiftBar parameters, so keep it out of the text
+        println!("{} | length=50", job.title.replace('|', "/"));
+        println!("{} {:.1}% {} | size=11", job.stage.label(), job.percent, job.detail.replace('|', "/"));
+        println!("{} | size=11", job.overall_summary());
+        println!(
+            "Cancel {} | bash=\"{}\" param1=ctl param2=cancel param3=\"{}\" param4=--status-file param5=\"{}\" terminal=false refresh=true",
+            job.job,
+            executable.display(),
+            job.job,
+            path.display()
+        );
+        println!("---");
+    }
+
+    println!(
+        "Cancel all downloads | bash=\"{}\" param1=status param2=--cancel param3=--status-file param4=\"{}\" terminal=false refresh=true",
+        executable.display(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Stop the run that is writing the status, the same way Ctrl-C would.
+pub fn cancel(path: &Path) -> Result<()> {
+    let status = Status::read(path)?;
+    if !status.is_live() {
+        logger::info("No downloads running");
+        return Ok(());
+    }
+
+    #[cfg(unix)]
+    if unsafe { libc::kill(status.pid as libc::pid_t, libc::SIGTERM) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    // Without SIGTERM, every job is asked to cancel as `cancel_job` would
+    #[cfg(windows)]
+    {
+        let mut requests = OpenOptions::new().create(true).append(true).open(cancel_requests_path(path))?;
+        for running in &status.jobs {
+            writeln!(requests, "{}", running.job)?;
+        }
+    }
+    logger::success(&format!("Cancelled {} download(s)", status.jobs.len()));
+    Ok(())
+}
+
+/// Ask the run writing the status to cancel one job, leaving its other jobs running.
+pub fn cancel_job(path: &Path, job: &str) -> Result<()> {
+    let status = Status::read(path)?;
+    if !status.is_live() {
+        logger::info("No downloads running");
+        return Ok(());
+    }
+    if !status.jobs.iter().any(|running| running.job == job) {
+        return Err(format!("No running job '{}'; `rust-downloader status` lists them", job).into());
+    }
+
+    let mut requests = OpenOptions::new().create(true).append(true).open(cancel_requests_path(path))?;
+    writeln!(requests, "{}", job)?;
+    logger::success(&format!("Asked {} to cancel", job));
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::cancel::CancelHandle;
+use crate::error::{DownloaderError, Result};
+use crate::dependencies;
+use crate::logger;
+use crate::config::StillFormat;
+
+const FALLBACK_RESOLUTION: (u32, u32) = (3840, 2160);
+
+/// Extract the most representative frame of a video as a still sized to the main display.
+///
+/// Frames are sampled every two seconds across the whole video and ffmpeg's `thumbnail`
+/// filter picks the one closest to the average, which skips fades, black frames and blur.
+/// Cancelling `cancel` stops ffmpeg.
+pub fn extract_still(video_path: &Path, duration: f64, format: StillFormat, cancel: &CancelHandle) -> Result<PathBuf> {
+    let (width, height) = display_resolution();
+    let samples = ((duration / 2.0).ceil() as u32).clamp(1, 300);
+
+    let png_path = video_path.with_extension("png");
+    let filter = format!(
+        "fps=1/2,thumbnail={},scale={w}:{h}:force_original_aspect_ratio=increase:flags=lanczos,crop={w}:{h}",
+        samples, w = width, h = height
+    );
+
+    logger::search(&format!("Picking the best frame for a {}x{} still...", width, height));
+
+    let output = cancel.output_blocking(Command::new(dependencies::ffmpeg())
+        .args(["-y", "-v", "error", "-i"])
+        .arg(video_path)
+        .args(["-vf", &filter, "-frames:v", "1", "-update", "1"])
+        .arg(&png_path))?;
+
+    if !output.status.success() || !png_path.exists() {
+        return Err(DownloaderError::Ffmpeg(format!("Failed to extract still frame: {}", String::from_utf8_lossy(&output.stderr).trim())));
+    }
+
+    match format {
+        StillFormat::Png => Ok(png_path),
+        StillFormat::Heic => {
+            let heic_path = video_path.with_extension("heic");
+            let output = cancel.output_blocking(Command::new("sips")
+                .args(["-s", "format", "heic"])
+                .arg(&png_path)
+                .arg("--out")
+                .arg(&heic_path))?;
+
+            if !output.status.success() {
+                logger::warning("Could not convert the still to HEIC, keeping the PNG");
+                return Ok(png_path);
+            }
+
+            fs::remove_file(&png_path).ok();
+            Ok(heic_path)
+        }
+    }
+}
+
+/// Set an image as the static desktop picture on every display.
+///
+/// Under sudo the script runs as the invoking user, since System Events only
+/// controls the desktops of the user it runs as.
+pub fn set_desktop_picture(image_path: &Path) -> Result<()> {
+    let path = fs::canonicalize(image_path)?;
+    let script = format!(
+        "tell application \"System Events\" to tell every desktop to set picture to \"{}\"",
+        path.display().to_string().replace('\\', "\\\\").replace('"', "\\\"")
+    );
+
+    let mut command = match env::var("SUDO_USER") {
+        Ok(user) => {
+            let mut cmd = Command::new("sudo");
+            cmd.args(["-u", &user, "osascript"]);
+            cmd
+        }
+        Err(_) => Command::new("osascript"),
+    };
+
+    let output = command.args(["-e", &script]).output()?;
+
+    if !output.status.success() {
+        return Err(format!("Could not set desktop picture: {}", String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+
+    Ok(())
+}
+
+/// Native pixel size of the main display, falling back to 4K when it can't be read.
+fn display_resolution() -> (u32, u32) {
+    let output = match Command::new("system_profiler").arg("SPDisplaysDataType").output() {
+        Ok(output) if output.status.success() => output,
+        _ => return FALLBACK_RESOLUTION,
+    };
+
+    // "Resolution: 3024 x 1964 Retina" - the first display listed is the main one
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| {
+            let value = line.trim().strip_prefix("Resolution:")?;
+            let mut parts = value.split_whitespace();
+            let width = parts.next()?.parse().ok()?;
+            parts.next().filter(|x| *x == "x")?;
+            let height = parts.next()?.parse().ok()?;
+            Some((width, height))
+        })
+        .unwrap_or(FALLBACK_RESOLUTION)
+}
@@ -0,0 +1,35 @@
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Julian day of 2000-01-01 12:00 UTC (J2000)
+const J2000: f64 = 2451545.0;
+/// Julian day of the Unix epoch
+const UNIX_EPOCH_JD: f64 = 2440587.5;
+/// Sun altitude at sunrise and sunset: refraction plus the sun's radius put it just below the horizon
+const HORIZON_DEGREES: f64 = -0.833;
+
+/// Sunrise and sunset on `date` at a place (degrees, north and east positive), from the
+/// sunrise equation; within a minute or two of published tables outside the polar circles.
+/// `None` when the sun doesn't rise or doesn't set that day.
+pub fn sunrise_sunset(date: NaiveDate, latitude: f64, longitude: f64) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let days = (date - NaiveDate::from_ymd_opt(2000, 1, 1)?).num_days() as f64;
+    let mean_solar_noon = days - longitude / 360.0;
+
+    let anomaly = (357.5291 + 0.98560028 * mean_solar_noon).rem_euclid(360.0).to_radians();
+    let center = 1.9148 * anomaly.sin() + 0.02 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+    let ecliptic_longitude = (anomaly.to_degrees() + center + 180.0 + 102.9372).rem_euclid(360.0).to_radians();
+    let transit = J2000 + mean_solar_noon + 0.0053 * anomaly.sin() - 0.0069 * (2.0 * ecliptic_longitude).sin();
+
+    let declination = (ecliptic_longitude.sin() * 23.4397_f64.to_radians().sin()).asin();
+    let latitude = latitude.to_radians();
+    let cos_hour_angle = (HORIZON_DEGREES.to_radians().sin() - latitude.sin() * declination.sin()) / (latitude.cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return None;
+    }
+    let hour_angle = cos_hour_angle.acos().to_degrees() / 360.0;
+
+    Some((from_julian(transit - hour_angle)?, from_julian(transit + hour_angle)?))
+}
+
+fn from_julian(day: f64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(((day - UNIX_EPOCH_JD) * 86400.0).round() as i64, 0)
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use crate::config::Config;
+use crate::error::Result;
+use crate::history::{DownloadStatus, History};
+use crate::logger;
+use crate::playlist;
+use crate::utils;
+
+const STATE_FILE: &str = "sync_state.json";
+
+/// When each source last synced completely, stored next to the downloads.
+pub struct SyncState {
+    path: PathBuf,
+    last_synced: BTreeMap<String, NaiveDate>,
+}
+
+impl SyncState {
+    pub fn load(config: &Config) -> Self {
+        let path = config.output_dir.join(STATE_FILE);
+        let last_synced = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self { path, last_synced }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(&self.last_synced)?)?;
+        Ok(())
+    }
+
+    pub fn last_synced(&self, source: &str) -> Option<NaiveDate> {
+        self.last_synced.get(source.trim()).copied()
+    }
+
+    pub fn mark(&mut self, source: &str, date: NaiveDate) {
+        self.last_synced.insert(source.trim().to_string(), date);
+    }
+}
+
+/// New videos found in each source by `check`.
+pub struct SyncPlan {
+    started: DateTime<Utc>,
+    sources: Vec<(String, Vec<String>)>,
+}
+
+impl SyncPlan {
+    /// Every new video, without duplicates across sources, in source order.
+    pub fn urls(&self) -> Vec<String> {
+        let mut urls: Vec<String> = Vec::new();
+        for url in self.sources.iter().flat_map(|(_, new)| new) {
+            if !urls.contains(url) {
+                urls.push(url.clone());
+            }
+        }
+        urls
+    }
+
+    /// Record the sources whose new videos all made it into the history (downloaded or
+    /// found unavailable), so the next run only lists channel uploads from after this one.
+    /// A source with a failed download keeps its old date and is looked at again in full.
+    pub fn finish(&self, config: &Config) -> Result<()> {
+        let recent = History::load(config)?.since(self.started)?;
+        let done = |url: &String| {
+            let id = utils::extract_video_id(url);
+            recent.iter().any(|entry| entry.video_id == id && entry.status != DownloadStatus::Failed)
+        };
+
+        let mut state = SyncState::load(config);
+        for (source, new) in &self.sources {
+            if new.iter().all(done) {
+                state.mark(source, self.started.date_naive());
+            } else {
+                logger::warning(&format!("{}: some downloads failed; the next sync checks it again from the last complete run", source));
+            }
+        }
+        state.save()
+    }
+}
+
+/// List the videos in `sources` (or the `[sync] sources`) that aren't in the download history.
+///
+/// Channels are read with yt-dlp's `--dateafter`, starting a day before their last complete
+/// sync since upload dates are in the uploader's time zone; feeds and playlists are listed in
+/// full, as videos are added to them long after upload. The history catches what overlaps.
+///
+/// Videos last found private, deleted or region-blocked are skipped too, unless
+/// `retry_unavailable` is set; channels are then listed in full to reach older ones.
+pub fn check(sources: &[String], retry_unavailable: bool, config: &Config) -> Result<SyncPlan> {
+    let sources = if sources.is_empty() { &config.sync_sources } else { sources };
+    let history = History::load(config)?;
+    let downloaded = history.downloaded_ids()?;
+    let unavailable = if retry_unavailable { Default::default() } else { history.unavailable_ids()? };
+    let state = SyncState::load(config);
+    let started = Utc::now();
+
+    let mut plan = SyncPlan { started, sources: Vec::new() };
+    for source in sources {
+        let since = state.last_synced(source)
+            .filter(|_| playlist::is_channel_url(source) && !retry_unavailable)
+            .and_then(|date| date.checked_sub_days(Days::new(1)));
+        let found = playlist::video_urls(source, since, config)?;
+        let mut skipped = 0;
+        let new: Vec<String> = found.iter()
+            .filter(|url| match utils::extract_video_id(url) {
+                Some(id) if unavailable.contains(&id) => {
+                    skipped += 1;
+                    false
+                }
+                Some(id) => !downloaded.contains(&id),
+                None => true,
+            })
+            .cloned()
+            .collect();
+        match since {
+            Some(date) => logger::info(&format!("{}: {} uploads since {}, {} new", source, found.len(), date, new.len())),
+            None => logger::info(&format!("{}: {} videos, {} new", source, found.len(), new.len())),
+        }
+        if skipped > 0 {
+            logger::info(&format!("{}: skipped {} unavailable video(s); --retry-unavailable tries them again", source, skipped));
+        }
+        plan.sources.push((source.clone(), new));
+    }
+    Ok(plan)
+}
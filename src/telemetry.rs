@@ -0,0 +1,125 @@
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use regex::Regex;
+use crate::logger;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Samples GPU utilization from the IOAccelerator statistics in the background.
+///
+/// `ioreg` works without root (unlike `powermetrics`); on other platforms no samples are taken.
+pub struct GpuSampler {
+    running: Arc<AtomicBool>,
+    samples: Arc<Mutex<Vec<u32>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl GpuSampler {
+    pub fn start() -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let samples = Arc::new(Mutex::new(Vec::new()));
+
+        let handle = if cfg!(target_os = "macos") {
+            let running = Arc::clone(&running);
+            let samples = Arc::clone(&samples);
+            Some(thread::spawn(move || {
+                let pattern = Regex::new(r#""Device Utilization %"\s*=\s*(\d+)"#).unwrap();
+                while running.load(Ordering::Relaxed) {
+                    if let Some(value) = sample_utilization(&pattern) {
+                        samples.lock().unwrap().push(value);
+                    }
+                    thread::sleep(SAMPLE_INTERVAL);
+                }
+            }))
+        } else {
+            None
+        };
+
+        Self { running, samples, handle }
+    }
+
+    /// Stop sampling and return the average utilization, if any samples were taken.
+    pub fn stop(mut self) -> Option<f64> {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+
+        let samples = self.samples.lock().unwrap();
+        if samples.is_empty() {
+            None
+        } else {
+            Some(samples.iter().sum::<u32>() as f64 / samples.len() as f64)
+        }
+    }
+}
+
+fn sample_utilization(pattern: &Regex) -> Option<u32> {
+    let output = Command::new("ioreg")
+        .args(["-r", "-d", "1", "-w", "0", "-c", "IOAccelerator"])
+        .output()
+        .ok()?;
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    pattern.captures_iter(&text)
+        .filter_map(|caps| caps[1].parse().ok())
+        .max()
+}
+
+/// Encoder statistics accumulated over all attempts of one conversion.
+#[derive(Debug, Clone, Default)]
+pub struct EncodeReport {
+    pub hardware_seconds: f64,
+    pub software_seconds: f64,
+    /// Frames and wall time of the successful attempt
+    pub frames: u64,
+    pub encode_seconds: f64,
+    pub gpu_utilization: Option<f64>,
+    pub encoder: Option<String>,
+}
+
+impl EncodeReport {
+    pub fn record_attempt(&mut self, hardware: bool, seconds: f64) {
+        if hardware {
+            self.hardware_seconds += seconds;
+        } else {
+            self.software_seconds += seconds;
+        }
+    }
+
+    pub fn average_fps(&self) -> Option<f64> {
+        if self.frames == 0 || self.encode_seconds <= 0.0 {
+            None
+        } else {
+            Some(self.frames as f64 / self.encode_seconds)
+        }
+    }
+
+    /// Share of encoding time spent on the hardware encoder, in percent.
+    pub fn hardware_ratio(&self) -> f64 {
+        let total = self.hardware_seconds + self.software_seconds;
+        if total <= 0.0 {
+            0.0
+        } else {
+            self.hardware_seconds / total * 100.0
+        }
+    }
+
+    pub fn display(&self) {
+        logger::stats("Encoder report:");
+        if let Some(encoder) = &self.encoder {
+            logger::stats(&format!("   • Encoder used: {}", encoder));
+        }
+        if let Some(fps) = self.average_fps() {
+            logger::stats(&format!("   • Average encode speed: {:.1} fps", fps));
+        }
+        logger::stats(&format!("   • Hardware / software time: {:.0}% / {:.0}%", self.hardware_ratio(), 100.0 - self.hardware_ratio()));
+        match self.gpu_utilization {
+            Some(utilization) => logger::stats(&format!("   • Average GPU utilization: {:.0}%", utilization)),
+            None => logger::stats("   • GPU utilization: not available"),
+        }
+    }
+}
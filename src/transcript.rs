@@ -0,0 +1,198 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use regex::Regex;
+use serde_json::Value;
+use crate::auth;
+use crate::dependencies;
+use crate::config::Config;
+use crate::error::Result;
+use crate::logger;
+use crate::utils;
+use crate::ytdlp_error::YtDlpError;
+
+/// Cues are joined into paragraphs of about this length, each with one timestamp
+const PARAGRAPH_SECONDS: f64 = 30.0;
+
+/// One caption with the second it appears at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start: f64,
+    pub text: String,
+}
+
+/// Download the `lang` subtitles of `url` (manual ones when the uploader provided them,
+/// YouTube's automatic captions otherwise) and write them to the output directory as a
+/// timestamped Markdown or, with `plain_text`, TXT transcript.
+pub fn export(url: &str, lang: &str, plain_text: bool, config: &Config) -> Result<PathBuf> {
+    logger::header("Transcript");
+    logger::download(&format!("Fetching {} subtitles...", lang));
+
+    let dir = tempfile::tempdir()?;
+    let output = Command::new(dependencies::yt_dlp())
+        .args(["--skip-download", "--write-subs", "--write-auto-subs", "--write-info-json", "--no-warnings"])
+        .args(["--sub-langs", &format!("{}.*", lang), "--sub-format", "vtt/best", "--convert-subs", "vtt"])
+        .args(["-o", "transcript.%(ext)s", "-P"])
+        .arg(dir.path())
+        .args(auth::yt_dlp_args(config))
+        .args(dependencies::yt_dlp_ffmpeg_args())
+        .arg(url)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(YtDlpError::from_stderr(&String::from_utf8_lossy(&output.stderr)).into());
+    }
+
+    let info: Value = serde_json::from_str(&fs::read_to_string(dir.path().join("transcript.info.json"))?)?;
+    let (subtitle_lang, subtitle_path) = find_subtitles(dir.path(), lang)
+        .ok_or_else(|| format!("'{}' has no {} subtitles or automatic captions", info["title"].as_str().unwrap_or(url), lang))?;
+    let manual = info["subtitles"].get(&subtitle_lang).is_some();
+
+    let cues = parse_vtt(&fs::read_to_string(&subtitle_path)?);
+    if cues.is_empty() {
+        return Err("The subtitles are empty".into());
+    }
+
+    let title = info["title"].as_str().unwrap_or("Untitled");
+    let header = Header {
+        title,
+        channel: info["uploader"].as_str(),
+        url: info["webpage_url"].as_str().unwrap_or(url),
+        source: format!("{} {}", subtitle_lang, if manual { "subtitles" } else { "automatic captions" }),
+    };
+    let paragraphs = paragraphs(&cues, PARAGRAPH_SECONDS);
+    let (content, extension) = match plain_text {
+        true => (render_text(&header, &paragraphs), "txt"),
+        false => (render_markdown(&header, &paragraphs), "md"),
+    };
+
+    let path = config.output_dir.join(format!("{}.transcript.{}", utils::clean_title(title, &config.file_naming), extension));
+    fs::write(&path, content)?;
+    logger::stats(&format!("{} paragraph(s) from {}", paragraphs.len(), header.source));
+    logger::success(&format!("Transcript saved: {}", path.display()));
+    Ok(path)
+}
+
+/// The downloaded subtitle file, preferring the exact language over variants like `en-GB`.
+fn find_subtitles(dir: &Path, lang: &str) -> Option<(String, PathBuf)> {
+    let mut found: Vec<(String, PathBuf)> = fs::read_dir(dir).ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?;
+            let code = name.strip_prefix("transcript.")?.strip_suffix(".vtt")?.to_string();
+            Some((code, path))
+        })
+        .collect();
+    found.sort_by_key(|(code, _)| (code != lang, code.clone()));
+    found.into_iter().next()
+}
+
+/// Captions from a WebVTT file, cleaned of markup and sound annotations like `[Music]`.
+///
+/// YouTube's automatic captions roll: every cue repeats the line before it, with the new
+/// words in word-timing tags. Lines already seen in the previous cue are dropped, so each
+/// spoken line appears once.
+pub fn parse_vtt(content: &str) -> Vec<Cue> {
+    let tags = Regex::new(r"<[^>]*>").unwrap();
+    let annotation = Regex::new(r"^\[[^\]]*\]$").unwrap();
+
+    let mut cues = Vec::new();
+    let mut previous: Vec<String> = Vec::new();
+    for block in content.replace("\r\n", "\n").split("\n\n") {
+        let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+        let Some(start) = lines.next().and_then(|timing| parse_timestamp(timing.split("-->").next()?)) else {
+            continue;
+        };
+
+        let current: Vec<String> = lines
+            .map(|line| decode_entities(&tags.replace_all(line, "")).split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty() && !annotation.is_match(line))
+            .collect();
+
+        let new: Vec<&String> = current.iter().filter(|line| !previous.contains(line)).collect();
+        if !new.is_empty() {
+            cues.push(Cue { start, text: new.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(" ") });
+        }
+        if !current.is_empty() {
+            previous = current;
+        }
+    }
+    cues
+}
+
+/// `00:01:02.500` or `01:02.500` in seconds.
+fn parse_timestamp(value: &str) -> Option<f64> {
+    value.trim().split(':').try_fold(0.0, |total, part| Some(total * 60.0 + part.trim().parse::<f64>().ok()?))
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Join cues into paragraphs of about `seconds` each, breaking early after a sentence ends.
+pub fn paragraphs(cues: &[Cue], seconds: f64) -> Vec<Cue> {
+    let mut paragraphs: Vec<Cue> = Vec::new();
+    for cue in cues {
+        match paragraphs.last_mut() {
+            Some(last) if cue.start - last.start < seconds
+                || (cue.start - last.start < seconds * 2.0 && !last.text.ends_with(['.', '?', '!'])) => {
+                last.text.push(' ');
+                last.text.push_str(&cue.text);
+            }
+            _ => paragraphs.push(cue.clone()),
+        }
+    }
+    paragraphs
+}
+
+struct Header<'a> {
+    title: &'a str,
+    channel: Option<&'a str>,
+    url: &'a str,
+    source: String,
+}
+
+/// `1:02:03` or `2:03`
+fn timestamp(seconds: f64) -> String {
+    let seconds = seconds as u64;
+    match seconds / 3600 {
+        0 => format!("{}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{}:{:02}:{:02}", hours, seconds / 60 % 60, seconds % 60),
+    }
+}
+
+fn render_markdown(header: &Header, paragraphs: &[Cue]) -> String {
+    let mut lines = vec![format!("# {}", header.title), String::new()];
+    let by = header.channel.map(|channel| format!("{} · ", channel)).unwrap_or_default();
+    lines.push(format!("{}<{}> · {}", by, header.url, header.source));
+    for paragraph in paragraphs {
+        // Timestamps link to that moment of the video
+        let separator = if header.url.contains('?') { '&' } else { '?' };
+        lines.push(String::new());
+        lines.push(format!(
+            "[**{}**]({}{}t={}s) {}",
+            timestamp(paragraph.start), header.url, separator, paragraph.start as u64, paragraph.text
+        ));
+    }
+    lines.join("\n") + "\n"
+}
+
+fn render_text(header: &Header, paragraphs: &[Cue]) -> String {
+    let mut lines = vec![header.title.to_string()];
+    if let Some(channel) = header.channel {
+        lines.push(channel.to_string());
+    }
+    lines.push(header.url.to_string());
+    lines.push(header.source.clone());
+    for paragraph in paragraphs {
+        lines.push(String::new());
+        lines.push(format!("[{}] {}", timestamp(paragraph.start), paragraph.text));
+    }
+    lines.join("\n") + "\n"
+}
@@ -0,0 +1,134 @@
+use std::env;
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use crate::error::Result;
+use crate::http;
+use crate::logger;
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/Sarabjeet-singh1/yt-video-downloader/releases/latest";
+const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl Release {
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    pub(crate) fn asset(&self, name: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+/// Fetch the latest published release from GitHub.
+pub fn latest_release(timeout_secs: u32) -> Result<Release> {
+    fetch_release(LATEST_RELEASE_URL, timeout_secs)
+}
+
+/// Fetch a release from a GitHub `releases/...` API URL.
+pub(crate) fn fetch_release(url: &str, timeout_secs: u32) -> Result<Release> {
+    http::get_json(url, timeout_secs)
+}
+
+/// Compare dotted version numbers, ignoring any pre-release suffix.
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> {
+        version.split(['-', '+']).next().unwrap_or("")
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    parse(candidate) > parse(current)
+}
+
+/// Quick, silent-on-failure check run at startup when update checks are enabled.
+pub fn notify_if_outdated() {
+    if let Ok(release) = latest_release(3) {
+        if is_newer(release.version(), CURRENT_VERSION) {
+            logger::info(&format!("rust-downloader {} is available (you have {}). Run: rust-downloader self-update",
+                release.version(), CURRENT_VERSION));
+        }
+    }
+}
+
+/// Name of the release asset built for this platform, e.g. `rust-downloader-aarch64-apple-darwin`.
+fn platform_asset_name() -> String {
+    let os = match env::consts::OS {
+        "macos" => "apple-darwin",
+        "linux" => "unknown-linux-gnu",
+        other => other,
+    };
+    format!("rust-downloader-{}-{}", env::consts::ARCH, os)
+}
+
+/// Download the latest release binary, verify its SHA-256 against the published checksum
+/// and replace the running executable.
+pub fn self_update(force: bool) -> Result<()> {
+    logger::header("Self Update");
+    logger::search("Checking for a newer release...");
+
+    let release = latest_release(15)?;
+    if !force && !is_newer(release.version(), CURRENT_VERSION) {
+        logger::success(&format!("Already up to date ({})", CURRENT_VERSION));
+        return Ok(());
+    }
+
+    let asset_name = platform_asset_name();
+    let binary = release.asset(&asset_name)
+        .ok_or_else(|| format!("Release {} has no build for this platform ({})", release.tag_name, asset_name))?;
+    let checksum = release.asset(&format!("{}.sha256", asset_name))
+        .ok_or_else(|| format!("Release {} has no checksum for {}; refusing to install", release.tag_name, asset_name))?;
+
+    let current_exe = env::current_exe()?;
+    let install_dir = current_exe.parent().ok_or("Cannot determine install directory")?;
+
+    // Stage next to the executable so the final rename stays on one filesystem
+    let staged = tempfile::Builder::new().prefix(".rust-downloader-update").tempfile_in(install_dir)
+        .map_err(|e| format!("Cannot write to {} ({}). Try again with sudo.", install_dir.display(), e))?;
+
+    logger::download(&format!("Downloading {} {}...", asset_name, release.tag_name));
+    http::download_to(&binary.browser_download_url, staged.path())?;
+
+    let checksum_file = tempfile::NamedTempFile::new()?;
+    http::download_to(&checksum.browser_download_url, checksum_file.path())?;
+    let expected = fs::read_to_string(checksum_file.path())?
+        .split_whitespace()
+        .next()
+        .ok_or("Checksum file is empty")?
+        .to_lowercase();
+
+    let actual = sha256_file(staged.path())?;
+    if actual != expected {
+        return Err(format!("Checksum mismatch for {} (expected {}, got {})", asset_name, expected, actual).into());
+    }
+    logger::success("Checksum verified");
+
+    #[cfg(unix)]
+    fs::set_permissions(staged.path(), fs::Permissions::from_mode(0o755))?;
+    staged.persist(&current_exe).map_err(|e| e.error)?;
+
+    logger::success(&format!("Updated {} → {}", CURRENT_VERSION, release.version()));
+    Ok(())
+}
+
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    let mut file = fs::File::open(path)?;
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
@@ -1,8 +1,16 @@
-use std::fs;
+use std::ffi::OsStr;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
+#[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use crate::config::LicenseFilter;
+use crate::dependencies;
+use crate::error::{DownloaderError, Result};
 
 pub fn format_file_size(bytes: Option<u64>) -> String {
     match bytes {
@@ -37,16 +45,14 @@ pub fn format_duration(seconds: Option<u64>) -> String {
 }
 
 pub fn format_time(seconds: f64) -> String {
-    if seconds < 60.0 {
-        format!("{}s", seconds.round() as u64)
-    } else if seconds < 3600.0 {
-        let mins = (seconds / 60.0).floor() as u64;
-        let secs = (seconds % 60.0).round() as u64;
+    // Round once up front, so 119.6 is "2m 0s" rather than "1m 60s"
+    let total = seconds.round() as u64;
+    let (hours, mins, secs) = (total / 3600, (total % 3600) / 60, total % 60);
+    if total < 60 {
+        format!("{}s", secs)
+    } else if total < 3600 {
         format!("{}m {}s", mins, secs)
     } else {
-        let hours = (seconds / 3600.0).floor() as u64;
-        let mins = ((seconds % 3600.0) / 60.0).floor() as u64;
-        let secs = (seconds % 60.0).round() as u64;
         format!("{}h {}m {}s", hours, mins, secs)
     }
 }
@@ -75,7 +81,7 @@ pub fn format_date(date_string: &str) -> String {
     }
 
     // yt-dlp date format is usually YYYYMMDD
-    if date_string.len() == 8 {
+    if date_string.len() == 8 && date_string.bytes().all(|b| b.is_ascii_digit()) {
         let year = &date_string[0..4];
         let month = &date_string[4..6];
         let day = &date_string[6..8];
@@ -85,31 +91,34 @@ pub fn format_date(date_string: &str) -> String {
     date_string.to_string()
 }
 
-pub fn create_safe_filename(title: &str, quality: &str, extension: &str, max_len: usize) -> String {
-    let config = crate::config::Config::default();
-    
-    // Clean title
-    let _invalid_chars = &config.file_naming.invalid_chars;
-    let replacement = config.file_naming.space_replacement;
-    
-    let mut s: String = title
-        .chars()
-        .filter(|c| c.is_ascii_alphanumeric() || *c == ' ' || *c == '-' || *c == '_')
-        .collect();
-    s = s.trim().replace(' ', replacement);
-    
-    if s.len() > max_len {
-        s.truncate(max_len);
-    }
+pub fn create_safe_filename(title: &str, quality: &str, extension: &str, naming: &crate::config::FileNamingConfig) -> String {
+    let s = clean_title(title, naming);
     
     // Use template from config
-    let template = config.file_naming.template;
+    let template = naming.template;
     template
         .replace("{title}", &s)
         .replace("{quality}", quality)
         .replace("{ext}", extension)
 }
 
+/// A title reduced to characters that are safe in file names on every platform, shortened
+/// to `max_title_length`.
+pub fn clean_title(title: &str, naming: &crate::config::FileNamingConfig) -> String {
+    let mut s: String = title
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == ' ' || *c == '-' || *c == '_')
+        .collect();
+    s = s.trim().replace(' ', naming.space_replacement);
+
+    // `space_replacement` may be multi-byte, so cut at the last whole character
+    if s.len() > naming.max_title_length {
+        let end = (0..=naming.max_title_length).rev().find(|&i| s.is_char_boundary(i)).unwrap_or(0);
+        s.truncate(end);
+    }
+    s
+}
+
 pub fn ensure_directory_exists(path: &Path) -> std::io::Result<()> {
     if !path.exists() {
         fs::create_dir_all(path)?;
@@ -122,15 +131,18 @@ pub fn get_unique_filename(base: &Path) -> std::io::Result<std::path::PathBuf> {
         return Ok(base.to_path_buf());
     }
     let dir = base.parent().unwrap_or_else(|| Path::new("."));
-    let ext = base.extension().and_then(|e| e.to_str()).unwrap_or("");
-    let stem = base.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
+    // Kept as OsStr so names that aren't valid UTF-8 keep their stem
+    let ext = base.extension();
+    let stem = base.file_stem().unwrap_or(OsStr::new("file"));
 
     for i in 1.. {
-        let candidate = if ext.is_empty() {
-            dir.join(format!("{}_{}", stem, i))
-        } else {
-            dir.join(format!("{}_{}.{}", stem, i, ext))
-        };
+        let mut name = stem.to_os_string();
+        name.push(format!("_{}", i));
+        if let Some(ext) = ext {
+            name.push(".");
+            name.push(ext);
+        }
+        let candidate = dir.join(name);
         if !candidate.exists() {
             return Ok(candidate);
         }
@@ -139,19 +151,53 @@ pub fn get_unique_filename(base: &Path) -> std::io::Result<std::path::PathBuf> {
     Ok(base.to_path_buf())
 }
 
-pub fn get_file_stats(file_path: &Path) -> Option<fs::Metadata> {
-    match fs::metadata(file_path) {
-        Ok(metadata) => Some(metadata),
-        Err(_) => None,
+/// The JSON stored at `path`, or the default when there is no file yet. A file that doesn't
+/// parse, e.g. one written by a newer version, is an error, so `update_json` never saves over it.
+pub fn read_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("{} doesn't parse: {}", path.display(), e).into()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(error) => Err(error.into()),
     }
 }
 
+/// Load the JSON at `path` (see `read_json`), change it and replace the file in one rename,
+/// while holding `<path>.lock` so concurrent jobs and processes don't overwrite each other.
+pub fn update_json<T, R>(path: &Path, change: impl FnOnce(&mut T) -> R) -> Result<R>
+where
+    T: DeserializeOwned + Serialize + Default,
+{
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path.with_extension("lock"))?;
+    // Released when `lock` is dropped
+    lock.lock()?;
+
+    let mut value = read_json(path)?;
+    let result = change(&mut value);
+    let file = tempfile::NamedTempFile::new_in(dir)?;
+    serde_json::to_writer_pretty(&file, &value)?;
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(result)
+}
+
+pub fn get_file_stats(file_path: &Path) -> Option<fs::Metadata> {
+    fs::metadata(file_path).ok()
+}
+
 pub fn validate_youtube_url(url: &str) -> bool {
     let patterns = [
-        r"^https?://(www\.)?youtube\.com/watch\?v=[\w-]+",
+        r"^https?://((www|m|music)\.)?youtube\.com/watch\?v=[\w-]+",
         r"^https?://(www\.)?youtu\.be/[\w-]+",
         r"^https?://(www\.)?youtube\.com/embed/[\w-]+",
         r"^https?://(www\.)?youtube\.com/v/[\w-]+",
+        r"^https?://((www|m)\.)?youtube\.com/shorts/[\w-]+",
+        r"^https?://((www|m)\.)?youtube\.com/live/[\w-]+",
     ];
 
     for p in patterns.iter() {
@@ -165,7 +211,7 @@ pub fn validate_youtube_url(url: &str) -> bool {
 }
 
 pub fn extract_video_id(url: &str) -> Option<String> {
-    let re = Regex::new(r"(?:youtube\.com/watch\?v=|youtu\.be/|youtube\.com/embed/|youtube\.com/v/)([^&\n?#]+)").unwrap();
+    let re = Regex::new(r"(?:youtube\.com/watch\?v=|youtu\.be/|youtube\.com/(?:embed|v|shorts|live)/)([\w-]+)").unwrap();
     if let Some(caps) = re.captures(url) {
         if let Some(m) = caps.get(1) {
             return Some(m.as_str().to_string());
@@ -174,36 +220,238 @@ pub fn extract_video_id(url: &str) -> Option<String> {
     None
 }
 
+/// A shared link reduced to its canonical watch URL, keeping the timestamp it pointed at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedUrl {
+    pub url: String,
+    pub start_seconds: Option<u64>,
+}
+
+/// Canonical `watch?v=` URL for any supported link form, dropping tracking (`si=`, `feature=`),
+/// playlist context (`list=`, `index=`) and the timestamp, which is returned separately.
+///
+/// Links that don't carry a video id are returned unchanged.
+pub fn normalize_url(url: &str) -> NormalizedUrl {
+    let url = url.trim();
+    let start_seconds = Regex::new(r"[?&#](?:t|start)=([0-9hms]+)").unwrap()
+        .captures(url)
+        .and_then(|caps| parse_timestamp(&caps[1]));
+
+    match extract_video_id(url) {
+        Some(id) => NormalizedUrl {
+            url: format!("https://www.youtube.com/watch?v={}", id),
+            start_seconds,
+        },
+        None => NormalizedUrl {
+            url: url.to_string(),
+            start_seconds: None,
+        },
+    }
+}
+
+/// Parse a YouTube timestamp: `90`, `90s`, `1m30s` or `1h2m3s`.
+fn parse_timestamp(value: &str) -> Option<u64> {
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(seconds);
+    }
+
+    let caps = Regex::new(r"^(?:(\d+)h)?(?:(\d+)m)?(?:(\d+)s)?$").unwrap().captures(value)?;
+    let part = |i: usize| caps.get(i).and_then(|m| m.as_str().parse::<u64>().ok()).unwrap_or(0);
+    let seconds = part(1) * 3600 + part(2) * 60 + part(3);
+    (seconds > 0).then_some(seconds)
+}
+
+/// Whether the input is a bare 11-character YouTube video id.
+pub fn is_video_id(input: &str) -> bool {
+    Regex::new(r"^[\w-]{11}$").unwrap().is_match(input)
+}
+
+/// Whether the input is yt-dlp search shorthand such as `ytsearch:lofi rain`.
+pub fn is_search_query(input: &str) -> bool {
+    Regex::new(r"^ytsearch\d*:.+").unwrap().is_match(input)
+}
+
+/// Turn a URL, bare video id or `ytsearch:` query into a watch URL.
+///
+/// Searches are resolved to their first result with yt-dlp, so the rest of the pipeline
+/// always works with a single video URL. With a `license` filter, the search goes through
+/// YouTube's own license filter instead of `ytsearch`.
+pub fn resolve_video_input(input: &str, license: Option<LicenseFilter>) -> Result<String> {
+    let input = input.trim();
+
+    if is_video_id(input) {
+        return Ok(format!("https://www.youtube.com/watch?v={}", input));
+    }
+
+    if is_search_query(input) {
+        let query = input.split_once(':').map(|(_, q)| q.trim()).unwrap_or_default();
+        let search = match license {
+            Some(license) => format!(
+                "https://www.youtube.com/results?search_query={}&sp={}",
+                url_encode(query),
+                license.search_filter()
+            ),
+            None => format!("ytsearch1:{}", query),
+        };
+        let output = std::process::Command::new(dependencies::yt_dlp())
+            .args(["--get-id", "--no-warnings", "--flat-playlist", "--playlist-items", "1", &search])
+            .output()?;
+
+        let id = String::from_utf8_lossy(&output.stdout).lines().next().unwrap_or("").trim().to_string();
+        if !output.status.success() || id.is_empty() {
+            return Err(format!("No YouTube results for search: {}", query).into());
+        }
+        return Ok(format!("https://www.youtube.com/watch?v={}", id));
+    }
+
+    if validate_youtube_url(input) {
+        return Ok(normalize_url(input).url);
+    }
+
+    Err(DownloaderError::InvalidUrl(input.to_string()))
+}
+
+/// Percent-encode `value` for a URL query.
+fn url_encode(value: &str) -> String {
+    value.bytes().map(|byte| match byte {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+        b' ' => "+".to_string(),
+        _ => format!("%{:02X}", byte),
+    }).collect()
+}
+
+const LABEL_ADJECTIVES: [&str; 32] = [
+    "amber", "bold", "brave", "calm", "clever", "crisp", "dusky", "eager",
+    "fancy", "gentle", "glad", "golden", "hazy", "jolly", "keen", "lively",
+    "lucky", "mellow", "misty", "noble", "polar", "quiet", "rapid", "rosy",
+    "silent", "snowy", "sunny", "swift", "tidy", "vivid", "wild", "witty",
+];
+
+const LABEL_NOUNS: [&str; 32] = [
+    "badger", "bison", "crane", "dingo", "eagle", "falcon", "ferret", "finch",
+    "gecko", "heron", "ibis", "koala", "lemur", "lynx", "marmot", "moose",
+    "newt", "ocelot", "otter", "panda", "puffin", "quail", "raven", "seal",
+    "stoat", "swan", "tapir", "tern", "toucan", "walrus", "wombat", "yak",
+];
+
+/// Short, memorable id for a job (e.g. `brave-otter`), derived from the video id so the same
+/// video always gets the same label across logs and runs.
+pub fn job_label(video_id: &str) -> String {
+    // FNV-1a; stable across platforms and Rust versions, unlike the std hasher
+    let hash = video_id.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!(
+        "{}-{}",
+        LABEL_ADJECTIVES[(hash % 32) as usize],
+        LABEL_NOUNS[((hash >> 8) % 32) as usize]
+    )
+}
+
 pub fn create_progress_bar(percentage: f64, width: usize) -> String {
-    let filled = ((percentage / 100.0) * width as f64).round() as usize;
+    // yt-dlp can overshoot 100% when its size estimate was low
+    let fraction = if percentage.is_nan() { 0.0 } else { (percentage / 100.0).clamp(0.0, 1.0) };
+    let filled = ((fraction * width as f64).round() as usize).min(width);
     let empty = width - filled;
     
     let bar = "█".repeat(filled) + &"░".repeat(empty);
     format!("[{}] {:.1}%", bar, percentage)
 }
 
-pub fn parse_progress(line: &str) -> Option<(f64, String, String, String)> {
+/// One yt-dlp progress line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadProgress {
+    pub percentage: f64,
+    /// As printed, e.g. `123.45MiB`
+    pub total_size: String,
+    /// The size is yt-dlp's estimate (`~`), as for DASH and HLS downloads
+    pub estimated: bool,
+    /// As printed, e.g. `1.23MiB/s`, or `Unknown`
+    pub speed: String,
+    /// `MM:SS` or `HH:MM:SS`, or `Unknown`
+    pub eta: String,
+    /// Fragments done and in total, for fragmented (DASH/HLS) downloads
+    pub fragments: Option<(u32, u32)>,
+}
+
+pub fn parse_progress(line: &str) -> Option<DownloadProgress> {
     // Match yt-dlp progress format: [download]  45.2% of 123.45MiB at 1.23MiB/s ETA 00:30
-    let re = Regex::new(r"\[download\]\s+(\d+\.?\d*)%\s+of\s+([\d.]+\w+)\s+at\s+([\d.]+\w+\/s)(?:\s+ETA\s+(\d+:\d+))?").unwrap();
-    
-    if let Some(caps) = re.captures(line) {
-        let percentage = caps.get(1)?.as_str().parse::<f64>().ok()?;
-        let total_size = caps.get(2)?.as_str().to_string();
-        let speed = caps.get(3)?.as_str().to_string();
-        let eta = caps.get(4).map_or("Unknown".to_string(), |m| m.as_str().to_string());
-        Some((percentage, total_size, speed, eta))
-    } else {
-        None
-    }
+    // The size is prefixed with ~ while it's an estimate; speed and ETA can be "Unknown"
+    // ("Unknown speed", "Unknown B/s", "ETA Unknown"), fragmented downloads end with
+    // "(frag 3/120)" and the final line has "in 00:00:12" before the speed
+    let re = Regex::new(concat!(
+        r"\[download\]\s+(\d+(?:\.\d+)?)%\s+of\s+(~)?\s*([\d.]+\w+)",
+        r"(?:\s+in\s+[\d:]+)?\s+at\s+(Unknown(?: speed| B/s)?|\S+/s)",
+        r"(?:\s+ETA\s+(\d+:\d+(?::\d+)?|Unknown))?",
+        r"(?:\s+\(frag\s+(\d+)/(\d+)\))?",
+    )).unwrap();
+
+    let caps = re.captures(line)?;
+    let speed = caps.get(4)?.as_str();
+    let fragments = caps.get(6).zip(caps.get(7))
+        .and_then(|(done, total)| Some((done.as_str().parse().ok()?, total.as_str().parse().ok()?)));
+    Some(DownloadProgress {
+        percentage: caps.get(1)?.as_str().parse::<f64>().ok()?,
+        total_size: caps.get(3)?.as_str().to_string(),
+        estimated: caps.get(2).is_some(),
+        speed: if speed.starts_with("Unknown") { "Unknown".to_string() } else { speed.to_string() },
+        eta: caps.get(5).map_or("Unknown", |m| m.as_str()).to_string(),
+        fragments,
+    })
+}
+
+/// Parse a size as printed by yt-dlp (`123.45MiB`, `1.2GB`, `512KiB`) into bytes.
+pub fn parse_size(size: &str) -> Option<u64> {
+    let split = size.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    let (number, unit) = size.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier = match unit {
+        "B" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        _ => return None,
+    };
+    Some((number * multiplier) as u64)
+}
+
+/// `command` as it could be pasted into a shell, quoting arguments where needed.
+pub fn command_line(command: &tokio::process::Command) -> String {
+    let command = command.as_std();
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| {
+            let arg = arg.to_string_lossy();
+            let plain = !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=+,@%".contains(c));
+            if plain {
+                arg.to_string()
+            } else {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Print `message` and read one trimmed line from the terminal.
+pub fn prompt(message: &str) -> Result<String> {
+    print!("{}", message);
+    std::io::stdout().flush().ok();
+
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
 }
 
 pub fn sanitize_input(input: &str) -> String {
     // Remove potentially dangerous characters
-    input.replace(|c: char| matches!(c, ';' | '&' | '|' | '`' | '$' | '(' | ')' | '{' | '}' | '[' | ']'), "")
+    input.replace([';', '&', '|', '`', '$', '(', ')', '{', '}', '[', ']'], "")
 }
 
-pub fn get_output_path(filename: &str) -> PathBuf {
-    let config = crate::config::Config::default();
+pub fn get_output_path(config: &crate::config::Config, filename: &str) -> PathBuf {
     let output_dir = &config.output_dir;
     ensure_directory_exists(output_dir).ok();
     output_dir.join(filename)
@@ -219,24 +467,22 @@ pub fn has_permission_issues(file_path: &Path) -> bool {
     match fs::metadata(file_path) {
         Ok(_metadata) => {
             // Check if we can write to the file
-            match fs::OpenOptions::new().write(true).open(file_path) {
-                Ok(_) => false, // Can write, no permission issues
-                Err(_) => true, // Cannot write, permission issues
-            }
+            // Cannot write means permission issues
+            fs::OpenOptions::new().write(true).open(file_path).is_err()
         }
         Err(_) => true, // Cannot access metadata
     }
 }
 
-pub fn fix_file_permissions(file_path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+pub fn fix_file_permissions(file_path: &Path) -> Result<bool> {
 
 
     // Basic implementation - would need more sophisticated permission handling
     match fs::metadata(file_path) {
         Ok(_metadata) => {
             // Set readable/writable permissions for user and group (0o664)
-            let perms = fs::Permissions::from_mode(0o664);
-            fs::set_permissions(file_path, perms)?;
+            #[cfg(unix)]
+            fs::set_permissions(file_path, fs::Permissions::from_mode(0o664))?;
             Ok(true)
         }
         Err(_) => Ok(false),
@@ -1,7 +1,9 @@
 use serde_json::Value;
-use std::process::Command;
+use crate::error::{DownloaderError, Result};
 use crate::logger;
-use crate::config::Config;
+use crate::config::{AudioOutputFormat, Config};
+use crate::auth;
+use crate::extractor::{self, Extractor, Mirrored};
 use crate::utils;
 
 #[derive(Debug, Clone)]
@@ -29,13 +31,44 @@ pub struct AudioFormat {
 
 #[derive(Debug, Clone)]
 pub struct VideoInfo {
+    /// The URL that was analyzed
+    pub url: String,
+    /// YouTube video id
+    pub id: Option<String>,
     pub title: String,
     pub uploader: Option<String>,
     pub duration: Option<u64>,
     pub view_count: Option<u64>,
     pub upload_date: Option<String>,
     pub description: Option<String>,
+    /// e.g. "Creative Commons Attribution license (reuse allowed)"; `None` for the standard license
+    pub license: Option<String>,
     pub formats: Vec<Value>,
+    /// Chapters from the video description, in order; empty when the video has none
+    pub chapters: Vec<Chapter>,
+    /// Set when the info came from somewhere other than yt-dlp, e.g. a Piped or Invidious instance
+    pub mirror: Option<Mirrored>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    pub title: String,
+    pub start_time: f64,
+    pub end_time: f64,
+}
+
+fn parse_chapters(info: &Value) -> Vec<Chapter> {
+    info.get("chapters")
+        .and_then(|v| v.as_array())
+        .map(|chapters| chapters.iter()
+            .filter_map(|chapter| Some(Chapter {
+                title: chapter.get("title").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                start_time: chapter.get("start_time")?.as_f64()?,
+                end_time: chapter.get("end_time")?.as_f64()?,
+            }))
+            .filter(|chapter| chapter.end_time > chapter.start_time)
+            .collect())
+        .unwrap_or_default()
 }
 
 #[derive(Debug, Clone)]
@@ -45,16 +78,26 @@ pub struct SelectedFormats {
     pub audio_format: Option<AudioFormat>,
 }
 
-fn run_yt_dlp_dump(url: &str) -> Result<String, Box<dyn std::error::Error>> {
-    let output = Command::new("yt-dlp")
-        .args(["--dump-json", "--no-warnings", url])
-        .output()?;
-
-    if !output.status.success() {
-        return Err(format!("yt-dlp failed: {}", String::from_utf8_lossy(&output.stderr)).into());
+impl SelectedFormats {
+    /// Switch to the formats with `ids` (video, then audio), e.g. the ones an interrupted
+    /// download was fetching, so yt-dlp continues their `.part` files. Returns false and keeps
+    /// the selection when the video no longer offers them.
+    pub fn pin(&mut self, ids: &[String], config: &Config) -> bool {
+        let (video_formats, audio_formats, combined_formats) = analyze_formats(&self.info.formats, config);
+        let Some(video) = video_formats.into_iter().chain(combined_formats).find(|format| Some(&format.format_id) == ids.first()) else {
+            return false;
+        };
+        let audio = match ids.get(1) {
+            Some(id) => match audio_formats.into_iter().find(|format| &format.format_id == id) {
+                Some(audio) => Some(audio),
+                None => return false,
+            },
+            None => None,
+        };
+        self.video_format = video;
+        self.audio_format = audio;
+        true
     }
-
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
 pub fn display_video_info(info: &VideoInfo) {
@@ -65,6 +108,12 @@ pub fn display_video_info(info: &VideoInfo) {
         logger::video(&format!("Uploader: {}", uploader));
     }
     logger::video(&format!("Duration: {}", utils::format_duration(info.duration)));
+    if !info.chapters.is_empty() {
+        logger::video(&format!("Chapters: {}", info.chapters.len()));
+    }
+    if let Some(mirror) = &info.mirror {
+        logger::video(&format!("Source: {}", mirror.name));
+    }
     if let Some(views) = info.view_count {
         logger::video(&format!("Views: {}", utils::format_number(Some(views))));
     }
@@ -82,8 +131,7 @@ pub fn display_video_info(info: &VideoInfo) {
     }
 }
 
-fn analyze_formats(formats: &[Value]) -> (Vec<VideoFormat>, Vec<AudioFormat>, Vec<VideoFormat>) {
-    let config = Config::default();
+fn analyze_formats(formats: &[Value], config: &Config) -> (Vec<VideoFormat>, Vec<AudioFormat>, Vec<VideoFormat>) {
     
     // Filter video formats
     let video_formats: Vec<VideoFormat> = formats
@@ -186,12 +234,42 @@ fn analyze_formats(formats: &[Value]) -> (Vec<VideoFormat>, Vec<AudioFormat>, Ve
     (video_formats, audio_formats, combined_formats)
 }
 
-fn find_best_video_format(video_formats: &[VideoFormat]) -> Result<VideoFormat, Box<dyn std::error::Error>> {
+/// Codecs without patent licensing, as they start in yt-dlp's `vcodec`/`acodec`
+const FREE_VIDEO_CODECS: [&str; 3] = ["av01", "vp09", "vp9"];
+const FREE_AUDIO_CODECS: [&str; 2] = ["opus", "vorbis"];
+
+/// Drop formats with other codecs for `--free-formats`, unless that would leave none.
+fn keep_free_formats(video_formats: &mut Vec<VideoFormat>, audio_formats: &mut Vec<AudioFormat>) {
+    let is_free = |codec: &Option<String>, free: &[&str]| codec.as_deref().is_some_and(|c| free.iter().any(|f| c.starts_with(f)));
+
+    let before = video_formats.len();
+    if video_formats.iter().any(|f| is_free(&f.vcodec, &FREE_VIDEO_CODECS)) {
+        video_formats.retain(|f| is_free(&f.vcodec, &FREE_VIDEO_CODECS));
+        logger::stats(&format!("Free formats: {} of {} video formats are VP9 or AV1", video_formats.len(), before));
+    } else {
+        logger::warning("No VP9 or AV1 video offered; falling back to the usual codecs");
+    }
+
+    if audio_formats.iter().any(|f| is_free(&f.acodec, &FREE_AUDIO_CODECS)) {
+        audio_formats.retain(|f| is_free(&f.acodec, &FREE_AUDIO_CODECS));
+    } else if !audio_formats.is_empty() {
+        logger::warning("No Opus or Vorbis audio offered; falling back to the usual codecs");
+    }
+}
+
+/// Prefer audio that `--audio-format` can copy as it is, when the video offers any.
+fn keep_audio_for(format: AudioOutputFormat, audio_formats: &mut Vec<AudioFormat>) {
+    let fits = |f: &AudioFormat| f.acodec.as_deref().and_then(AudioOutputFormat::for_codec) == Some(format);
+    if audio_formats.iter().any(fits) {
+        audio_formats.retain(fits);
+    }
+}
+
+fn find_best_video_format(video_formats: &[VideoFormat], config: &Config) -> Result<VideoFormat> {
     if video_formats.is_empty() {
-        return Err("No suitable video formats found".into());
+        return Err(DownloaderError::NoFormat("No suitable video formats found".to_string()));
     }
     
-    let config = Config::default();
     
     // Group by resolution
     let mut resolutions: Vec<u32> = video_formats
@@ -200,7 +278,7 @@ fn find_best_video_format(video_formats: &[VideoFormat]) -> Result<VideoFormat,
         .collect();
     resolutions.sort_by(|a, b| b.cmp(a));
     
-    let max_resolution = resolutions.get(0).cloned()
+    let max_resolution = resolutions.first().cloned()
         .map(|res| std::cmp::min(res, config.video_preferences.max_resolution))
         .unwrap_or(0);
     
@@ -212,6 +290,7 @@ fn find_best_video_format(video_formats: &[VideoFormat]) -> Result<VideoFormat,
         .iter()
         .filter(|f| f.height == Some(max_resolution))
         .collect();
+    let exact_match = !candidate_formats.is_empty();
     
     if candidate_formats.is_empty() {
         // Fallback to highest available resolution
@@ -222,81 +301,176 @@ fn find_best_video_format(video_formats: &[VideoFormat]) -> Result<VideoFormat,
     }
     
     // Sort by preferences
-    candidate_formats.sort_by(|a, b| {
-        // Prefer specific formats
-        let a_format_score = config.video_preferences.preferred_formats
-            .iter().position(|&f| f == a.ext).unwrap_or(usize::MAX);
-        let b_format_score = config.video_preferences.preferred_formats
-            .iter().position(|&f| f == b.ext).unwrap_or(usize::MAX);
-        
-        if a_format_score != b_format_score {
-            return a_format_score.cmp(&b_format_score);
+    candidate_formats.sort_by(|a, b| compare_video_formats(a, b, config).0);
+
+    if config.video_preferences.explain_selection {
+        logger::header("Video Format Ranking");
+        if exact_match {
+            logger::stats(&format!(
+                "Resolution filter: {}p (best available, capped at max_resolution {}p) keeps {} of {} formats",
+                max_resolution, config.video_preferences.max_resolution, candidate_formats.len(), video_formats.len()
+            ));
+        } else {
+            logger::stats(&format!(
+                "Resolution filter: nothing at {}p, so all {} formats with a known height compete",
+                max_resolution, candidate_formats.len()
+            ));
         }
-        
-        // Prefer higher fps if enabled
-        if config.video_preferences.prefer_high_fps {
-            let a_fps = a.fps.unwrap_or(30.0);
-            let b_fps = b.fps.unwrap_or(30.0);
-            if (a_fps - b_fps).abs() > 0.1 {
-                return b_fps.partial_cmp(&a_fps).unwrap_or(std::cmp::Ordering::Equal);
+        logger::stats(&format!(
+            "Order: extension ({}), then {}codec ({}), then file size",
+            config.video_preferences.preferred_formats.join(" > "),
+            if config.video_preferences.prefer_high_fps { "higher fps, then " } else { "" },
+            config.video_preferences.preferred_codecs.join(" > ")
+        ));
+        for (rank, format) in candidate_formats.iter().enumerate() {
+            logger::info(&format!(
+                "#{} {} {}p {}fps {} ({}) {}",
+                rank + 1, format.format_id, format.height.unwrap_or(0), format.fps.unwrap_or(30.0) as u32,
+                format.ext, format.vcodec.as_deref().unwrap_or("unknown"), utils::format_file_size(format.filesize)
+            ));
+            if let Some(next) = candidate_formats.get(rank + 1) {
+                let (_, rule) = compare_video_formats(format, next, config);
+                logger::info(&format!("   ahead of #{} on {}", rank + 2, rule.describe_video(format, next)));
             }
         }
-        
-        // Prefer better codecs
-        let a_codec_score = config.video_preferences.preferred_codecs
-            .iter().position(|&f| a.vcodec.as_ref().map_or(false, |c| c.contains(&f))).unwrap_or(usize::MAX);
-        let b_codec_score = config.video_preferences.preferred_codecs
-            .iter().position(|&f| b.vcodec.as_ref().map_or(false, |c| c.contains(&f))).unwrap_or(usize::MAX);
-        
-        if a_codec_score != b_codec_score {
-            return a_codec_score.cmp(&b_codec_score);
-        }
-        
-        // Prefer larger file size (usually better quality)
-        b.filesize.cmp(&a.filesize)
-    });
+    }
     
     Ok(candidate_formats[0].clone())
 }
 
-fn find_best_audio_format(audio_formats: &[AudioFormat]) -> Result<AudioFormat, Box<dyn std::error::Error>> {
+/// The preference that decided the order of two candidate formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rule {
+    Extension,
+    Fps,
+    Bitrate,
+    Codec,
+    Size,
+    Tie,
+}
+
+impl Rule {
+    fn describe_video(self, a: &VideoFormat, b: &VideoFormat) -> String {
+        match self {
+            Rule::Extension => format!("extension preference ({} over {})", a.ext, b.ext),
+            Rule::Fps => format!("frame rate ({}fps over {}fps)", a.fps.unwrap_or(30.0), b.fps.unwrap_or(30.0)),
+            Rule::Codec => format!(
+                "codec preference ({} over {})",
+                a.vcodec.as_deref().unwrap_or("unknown"), b.vcodec.as_deref().unwrap_or("unknown")
+            ),
+            Rule::Size => format!("file size ({} over {})", utils::format_file_size(a.filesize), utils::format_file_size(b.filesize)),
+            Rule::Bitrate | Rule::Tie => "nothing; yt-dlp's order was kept".to_string(),
+        }
+    }
+
+    fn describe_audio(self, a: &AudioFormat, b: &AudioFormat) -> String {
+        match self {
+            Rule::Extension => format!("extension preference ({} over {})", a.ext, b.ext),
+            Rule::Bitrate => format!("bitrate ({}kbps over {}kbps)", a.abr.unwrap_or(0), b.abr.unwrap_or(0)),
+            Rule::Codec => format!(
+                "codec preference ({} over {})",
+                a.acodec.as_deref().unwrap_or("unknown"), b.acodec.as_deref().unwrap_or("unknown")
+            ),
+            Rule::Fps | Rule::Size | Rule::Tie => "nothing; yt-dlp's order was kept".to_string(),
+        }
+    }
+}
+
+/// Index of the first preference `matches` accepts; unlisted values rank last.
+fn preference_rank(preferences: &[&str], matches: impl Fn(&str) -> bool) -> usize {
+    preferences.iter().position(|&p| matches(p)).unwrap_or(usize::MAX)
+}
+
+/// Order two video formats by preference, best first, along with the rule that decided.
+fn compare_video_formats(a: &VideoFormat, b: &VideoFormat, config: &Config) -> (std::cmp::Ordering, Rule) {
+    let preferences = &config.video_preferences;
+
+    // Prefer specific formats
+    let a_format_score = preference_rank(&preferences.preferred_formats, |f| f == a.ext);
+    let b_format_score = preference_rank(&preferences.preferred_formats, |f| f == b.ext);
+    if a_format_score != b_format_score {
+        return (a_format_score.cmp(&b_format_score), Rule::Extension);
+    }
+
+    // Prefer higher fps if enabled
+    if preferences.prefer_high_fps {
+        let a_fps = a.fps.unwrap_or(30.0);
+        let b_fps = b.fps.unwrap_or(30.0);
+        if (a_fps - b_fps).abs() > 0.1 {
+            return (b_fps.partial_cmp(&a_fps).unwrap_or(std::cmp::Ordering::Equal), Rule::Fps);
+        }
+    }
+
+    // Prefer better codecs
+    let a_codec_score = preference_rank(&preferences.preferred_codecs, |f| a.vcodec.as_ref().is_some_and(|c| c.contains(f)));
+    let b_codec_score = preference_rank(&preferences.preferred_codecs, |f| b.vcodec.as_ref().is_some_and(|c| c.contains(f)));
+    if a_codec_score != b_codec_score {
+        return (a_codec_score.cmp(&b_codec_score), Rule::Codec);
+    }
+
+    // Prefer larger file size (usually better quality)
+    match b.filesize.cmp(&a.filesize) {
+        std::cmp::Ordering::Equal => (std::cmp::Ordering::Equal, Rule::Tie),
+        order => (order, Rule::Size),
+    }
+}
+
+/// Order two audio formats by preference, best first, along with the rule that decided.
+fn compare_audio_formats(a: &AudioFormat, b: &AudioFormat, config: &Config) -> (std::cmp::Ordering, Rule) {
+    let preferences = &config.audio_preferences;
+
+    // Prefer specific formats
+    let a_format_score = preference_rank(&preferences.preferred_formats, |f| f == a.ext);
+    let b_format_score = preference_rank(&preferences.preferred_formats, |f| f == b.ext);
+    if a_format_score != b_format_score {
+        return (a_format_score.cmp(&b_format_score), Rule::Extension);
+    }
+
+    // Prefer higher bitrate
+    let a_bitrate = a.abr.unwrap_or(0);
+    let b_bitrate = b.abr.unwrap_or(0);
+    if a_bitrate != b_bitrate {
+        return (b_bitrate.cmp(&a_bitrate), Rule::Bitrate);
+    }
+
+    // Prefer better codecs
+    let a_codec_score = preference_rank(&preferences.preferred_codecs, |f| a.acodec.as_ref().is_some_and(|c| c.contains(f)));
+    let b_codec_score = preference_rank(&preferences.preferred_codecs, |f| b.acodec.as_ref().is_some_and(|c| c.contains(f)));
+    if a_codec_score != b_codec_score {
+        return (a_codec_score.cmp(&b_codec_score), Rule::Codec);
+    }
+
+    (std::cmp::Ordering::Equal, Rule::Tie)
+}
+
+fn find_best_audio_format(audio_formats: &[AudioFormat], config: &Config) -> Result<AudioFormat> {
     if audio_formats.is_empty() {
-        return Err("No suitable audio formats found".into());
+        return Err(DownloaderError::NoFormat("No suitable audio formats found".to_string()));
     }
     
-    let config = Config::default();
     
     let mut sorted_formats = audio_formats.to_vec();
-    sorted_formats.sort_by(|a, b| {
-        // Prefer specific formats
-        let a_format_score = config.audio_preferences.preferred_formats
-            .iter().position(|&f| f == a.ext).unwrap_or(usize::MAX);
-        let b_format_score = config.audio_preferences.preferred_formats
-            .iter().position(|&f| f == b.ext).unwrap_or(usize::MAX);
-        
-        if a_format_score != b_format_score {
-            return a_format_score.cmp(&b_format_score);
-        }
-        
-        // Prefer higher bitrate
-        let a_bitrate = a.abr.unwrap_or(0);
-        let b_bitrate = b.abr.unwrap_or(0);
-        if a_bitrate != b_bitrate {
-            return b_bitrate.cmp(&a_bitrate);
-        }
-        
-        // Prefer better codecs
-        let a_codec_score = config.audio_preferences.preferred_codecs
-            .iter().position(|&f| a.acodec.as_ref().map_or(false, |c| c.contains(&f))).unwrap_or(usize::MAX);
-        let b_codec_score = config.audio_preferences.preferred_codecs
-            .iter().position(|&f| b.acodec.as_ref().map_or(false, |c| c.contains(&f))).unwrap_or(usize::MAX);
-        
-        if a_codec_score != b_codec_score {
-            return a_codec_score.cmp(&b_codec_score);
+    sorted_formats.sort_by(|a, b| compare_audio_formats(a, b, config).0);
+
+    if config.video_preferences.explain_selection {
+        logger::header("Audio Format Ranking");
+        logger::stats(&format!(
+            "Order: extension ({}), then bitrate, then codec ({})",
+            config.audio_preferences.preferred_formats.join(" > "),
+            config.audio_preferences.preferred_codecs.join(" > ")
+        ));
+        for (rank, format) in sorted_formats.iter().enumerate() {
+            logger::info(&format!(
+                "#{} {} {}kbps {} ({}) {}",
+                rank + 1, format.format_id, format.abr.unwrap_or(0), format.ext,
+                format.acodec.as_deref().unwrap_or("unknown"), utils::format_file_size(format.filesize)
+            ));
+            if let Some(next) = sorted_formats.get(rank + 1) {
+                let (_, rule) = compare_audio_formats(format, next, config);
+                logger::info(&format!("   ahead of #{} on {}", rank + 2, rule.describe_audio(format, next)));
+            }
         }
-        
-        std::cmp::Ordering::Equal
-    });
+    }
     
     Ok(sorted_formats[0].clone())
 }
@@ -304,7 +478,7 @@ fn find_best_audio_format(audio_formats: &[AudioFormat]) -> Result<AudioFormat,
 pub fn display_selected_formats(video_format: &VideoFormat, audio_format: &Option<AudioFormat>) {
     logger::header("Selected Formats");
     
-    let video_info = vec![
+    let video_info = [
         format!("{}p", video_format.height.unwrap_or(0)),
         format!("{}fps", video_format.fps.unwrap_or(30.0) as u32),
         video_format.ext.clone(),
@@ -314,7 +488,7 @@ pub fn display_selected_formats(video_format: &VideoFormat, audio_format: &Optio
     logger::video(&format!("Video: {}", video_info));
 
     if let Some(audio) = audio_format {
-        let audio_info = vec![
+        let audio_info = [
             format!("{}kbps", audio.abr.unwrap_or(0)),
             audio.ext.clone(),
             format!("({})", audio.acodec.as_ref().unwrap_or(&"unknown".to_string())),
@@ -323,7 +497,7 @@ pub fn display_selected_formats(video_format: &VideoFormat, audio_format: &Optio
         logger::audio(&format!("Audio: {}", audio_info));
     } else {
         // No separate audio format; check if video has embedded audio
-        if video_format.acodec.as_ref().map_or(false, |ac| ac != "none") {
+        if video_format.acodec.as_ref().is_some_and(|ac| ac != "none") {
             logger::audio("Audio: embedded in video format");
         } else {
             logger::audio("Audio: no separate audio stream found (video may be silent)");
@@ -331,37 +505,69 @@ pub fn display_selected_formats(video_format: &VideoFormat, audio_format: &Optio
     }
 }
 
-pub fn analyze(url: &str) -> Result<SelectedFormats, Box<dyn std::error::Error>> {
+/// Look `url` up, signed in as the job behind `session`, and pick its formats.
+pub fn analyze(url: &str, session: &auth::Session, config: &Config) -> Result<SelectedFormats> {
     logger::search("Retrieving video information...");
-    let dumped = run_yt_dlp_dump(url)?;
-    let info_value: Value = serde_json::from_str(&dumped)?;
+    let (info_value, mirror) = extractor::extract(url, session, config)?;
+    select_formats(url, info_value, mirror, config)
+}
+
+/// Analyze with a given extractor instead of yt-dlp and its fallbacks, e.g. a test fixture.
+pub fn analyze_with(url: &str, extractor: &dyn Extractor, config: &Config) -> Result<SelectedFormats> {
+    logger::search(&format!("Retrieving video information from {}...", extractor.name()));
+    let info = extractor.extract(url)?;
+    let mirror = Mirrored { name: extractor.name(), info: info.clone(), direct: extractor.direct() };
+    select_formats(url, info, Some(mirror), config)
+}
 
+fn select_formats(url: &str, info_value: Value, mirror: Option<Mirrored>, config: &Config) -> Result<SelectedFormats> {
     logger::success("Video information retrieved successfully");
 
     // Parse video info
     let video_info = VideoInfo {
+        url: url.to_string(),
+        id: info_value.get("id").and_then(|v| v.as_str()).map(|s| s.to_string()),
         title: info_value.get("title").and_then(|v| v.as_str()).unwrap_or("Unknown").to_string(),
         uploader: info_value.get("uploader").and_then(|v| v.as_str()).map(|s| s.to_string()),
         duration: info_value.get("duration").and_then(|v| v.as_u64()),
         view_count: info_value.get("view_count").and_then(|v| v.as_u64()),
         upload_date: info_value.get("upload_date").and_then(|v| v.as_str()).map(|s| s.to_string()),
         description: info_value.get("description").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        license: info_value.get("license").and_then(|v| v.as_str()).map(|s| s.to_string()),
         formats: info_value.get("formats").and_then(|v| v.as_array()).cloned().unwrap_or_default(),
+        chapters: parse_chapters(&info_value),
+        mirror,
     };
 
     // Display basic info
     display_video_info(&video_info);
+
+    if let Some(license) = config.video_preferences.license {
+        if !license.allows(video_info.license.as_deref()) {
+            return Err(format!(
+                "'{}' is not Creative Commons licensed ({}); --license creativecommons only allows reusable videos",
+                video_info.title,
+                video_info.license.as_deref().unwrap_or("Standard YouTube License")
+            ).into());
+        }
+    }
     
     // Analyze formats
-    let (video_formats, audio_formats, combined_formats) = analyze_formats(&video_info.formats);
+    let (mut video_formats, mut audio_formats, combined_formats) = analyze_formats(&video_info.formats, config);
+    if config.video_preferences.free_formats.is_some() {
+        keep_free_formats(&mut video_formats, &mut audio_formats);
+    }
+    if let (true, Some(format)) = (config.conversion_settings.audio_only, config.conversion_settings.audio_format) {
+        keep_audio_for(format, &mut audio_formats);
+    }
 
     // Find best video format
-    let best_video = find_best_video_format(&video_formats)?;
+    let best_video = find_best_video_format(&video_formats, config)?;
     let mut best_audio: Option<AudioFormat> = None;
 
     // Pick audio if available; otherwise try to use a combined format
     if !audio_formats.is_empty() {
-        best_audio = Some(find_best_audio_format(&audio_formats)?);
+        best_audio = Some(find_best_audio_format(&audio_formats, config)?);
     } else {
         // Try to find a combined format matching the chosen resolution
         let combined_candidate = combined_formats
@@ -408,7 +614,7 @@ pub fn analyze(url: &str) -> Result<SelectedFormats, Box<dyn std::error::Error>>
                 best_audio = Some(fallback);
             } else {
                 // As a last resort, allow using the video format as-is
-                if best_video.acodec.as_ref().map_or(false, |ac| ac != "none") {
+                if best_video.acodec.as_ref().is_some_and(|ac| ac != "none") {
                     logger::info("Using selected video format which includes embedded audio");
                 } else {
                     logger::warning("No separate audio formats found; proceeding with video-only download (no audio)");
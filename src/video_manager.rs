@@ -1,8 +1,9 @@
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use tokio::process::Command;
 use std::time::{Duration, SystemTime};
 use std::io::Write;
+use crate::error::{DownloaderError, Result};
 use crate::logger;
 use crate::Config;
 use crate::utils;
@@ -13,25 +14,32 @@ pub struct VideoManager {
     backup_dir: PathBuf,
     retry_attempts: u32,
     retry_interval: Duration,
+    /// `--sandbox`: leave the system alone, including daemons and System Settings
+    sandbox: bool,
 }
 
 impl VideoManager {
-    pub fn new() -> Self {
-        let config = Config::default();
-        let customer_dir = PathBuf::from(config.video_settings.customer_dir);
-        let target_dir = customer_dir.join(config.video_settings.target_sub_dir);
-        let backup_dir = config.output_dir.join(config.video_settings.backup_dir);
-        
+    pub fn new(config: &Config) -> Self {
+        let customer_dir = config.video_settings.customer_dir.clone();
+        let target_dir = config.video_settings.target_dir();
+        let sandbox = config.video_settings.sandbox.as_deref();
+        let backup_dir = sandbox.unwrap_or(&config.output_dir).join(config.video_settings.backup_dir);
+
         Self {
             customer_dir,
             target_dir,
             backup_dir,
             retry_attempts: config.video_settings.max_retry_attempts,
             retry_interval: Duration::from_millis(config.video_settings.retry_interval),
+            sandbox: sandbox.is_some(),
         }
     }
 
-    async fn check_customer_directory(&self) -> Result<bool, Box<dyn std::error::Error>> {
+    async fn check_customer_directory(&self) -> Result<bool> {
+        if self.sandbox {
+            fs::create_dir_all(&self.customer_dir)?;
+            logger::info(&format!(" Sandbox: installing into {}", self.target_dir.display()));
+        }
         if !self.customer_dir.exists() {
             logger::error(" Customer directory not found");
             logger::info("This usually means macOS video system is not initialized");
@@ -39,7 +47,7 @@ impl VideoManager {
         }
 
         if !self.target_dir.exists() {
-            logger::warning("  4KSDR240FPS directory not found, creating...");
+            logger::warning(&format!("  {} directory not found, creating...", self.target_dir.file_name().unwrap_or_default().to_string_lossy()));
             fs::create_dir_all(&self.target_dir)?;
         }
 
@@ -87,7 +95,7 @@ impl VideoManager {
         }
 
         // Sort by most recently modified
-        videos.sort_by(|a, b| b.modified.cmp(&a.modified));
+        videos.sort_by_key(|v| std::cmp::Reverse(v.modified));
         videos
     }
 
@@ -95,7 +103,14 @@ impl VideoManager {
         self.get_existing_videos().is_empty()
     }
 
-    async fn open_video_settings(&self) -> Result<bool, Box<dyn std::error::Error>> {
+    async fn open_video_settings(&self) -> Result<bool> {
+        if self.sandbox {
+            // Stands in for an aerial downloaded in System Settings
+            let placeholder = self.target_dir.join("sandbox-aerial.mov");
+            fs::write(&placeholder, b"")?;
+            logger::info(&format!(" Sandbox: created {} instead of opening System Settings", placeholder.display()));
+            return Ok(false);
+        }
         logger::info("🔧 Opening System Preferences > video...");
 
         // Use AppleScript to open video settings
@@ -106,7 +121,7 @@ end tell"#;
 
         let output = Command::new("osascript")
             .args(["-e", script])
-            .output()?;
+            .output().await?;
 
         if output.status.success() {
             logger::success(" System Preferences opened");
@@ -118,12 +133,16 @@ end tell"#;
         }
     }
 
-    async fn open_finder_at_video_dir(&self) -> Result<bool, Box<dyn std::error::Error>> {
+    async fn open_finder_at_video_dir(&self) -> Result<bool> {
+        if self.sandbox {
+            logger::info(&format!(" Sandbox: videos are in {}", self.target_dir.display()));
+            return Ok(false);
+        }
         logger::info(" Opening Finder at video directory...");
 
         let output = Command::new("open")
             .arg(&self.target_dir)
-            .output()?;
+            .output().await?;
 
         if output.status.success() {
             logger::success(" Finder opened at video directory");
@@ -135,7 +154,7 @@ end tell"#;
         }
     }
 
-    async fn wait_for_video_setup(&self) -> Result<VideoFile, Box<dyn std::error::Error>> {
+    async fn wait_for_video_setup(&self) -> Result<VideoFile> {
         logger::info(" Waiting for you to download a landscape video...");
         logger::info(" Steps:");
         logger::info("   1. In System Preferences > Wallpaper");
@@ -161,10 +180,10 @@ end tell"#;
         }
         
         println!(); // New line after dots
-        Err("Timeout waiting for video setup. Please download a landscape video and try again.".into())
+        Err(DownloaderError::NoWallpaper("Timeout waiting for video setup. Please download a landscape video and try again.".to_string()))
     }
 
-    async fn create_backup(&self, video_file: &VideoFile) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    async fn create_backup(&self, video_file: &VideoFile) -> Result<Option<PathBuf>> {
         utils::ensure_directory_exists(&self.backup_dir)?;
 
         let timestamp = chrono::Utc::now().format("%Y-%m-%d_%H-%M-%S").to_string();
@@ -189,7 +208,7 @@ end tell"#;
         Ok(Some(backup_path))
     }
 
-    async fn install_video(&self, video_path: &Path, target_video_name: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    async fn install_video(&self, video_path: &Path, target_video_name: &str) -> Result<bool> {
         let target_path = self.target_dir.join(target_video_name);
 
         logger::info(&format!(" Installing video: {}", target_video_name));
@@ -212,7 +231,11 @@ end tell"#;
         Err("Installation verification failed".into())
     }
 
-    async fn refresh_video_system(&self) -> Result<(), Box<dyn std::error::Error>> {
+    async fn refresh_video_system(&self) -> Result<()> {
+        if self.sandbox {
+            logger::info(" Sandbox: skipping the video daemon restart and desktop refresh");
+            return Ok(());
+        }
         logger::info("Refreshing video system to ensure animation works...");
 
         // Method 1: Restart the video daemon
@@ -227,18 +250,19 @@ end tell"#;
         Ok(())
     }
 
-    async fn restart_video_daemon(&self) -> Result<(), Box<dyn std::error::Error>> {
+    async fn restart_video_daemon(&self) -> Result<()> {
         logger::info(" Restarting video daemon...");
 
+        // -n: fail instead of asking for a password when not already running with sudo
         let commands = [
-            vec!["sudo", "launchctl", "unload", "/System/Library/LaunchDaemons/com.apple.idleassetsd.plist"],
-            vec!["sudo", "launchctl", "load", "/System/Library/LaunchDaemons/com.apple.idleassetsd.plist"],
+            vec!["sudo", "-n", "launchctl", "unload", "/System/Library/LaunchDaemons/com.apple.idleassetsd.plist"],
+            vec!["sudo", "-n", "launchctl", "load", "/System/Library/LaunchDaemons/com.apple.idleassetsd.plist"],
         ];
 
         for command in &commands {
             let output = Command::new(command[0])
                 .args(&command[1..])
-                .output()?;
+                .output().await?;
             
             if !output.status.success() {
                 logger::warning("  Could not restart daemon (this is normal on some macOS versions)");
@@ -250,7 +274,7 @@ end tell"#;
         Ok(())
     }
 
-    async fn force_video_refresh(&self) -> Result<(), Box<dyn std::error::Error>> {
+    async fn force_video_refresh(&self) -> Result<()> {
         logger::info(" Forcing video refresh...");
 
         // Method 1: Desktop refresh via AppleScript
@@ -266,7 +290,7 @@ end tell"#;
 
         let output = Command::new("osascript")
             .args(["-e", script])
-            .output()?;
+            .output().await?;
 
         if !output.status.success() {
             // Method 2: Touch video files as fallback
@@ -275,7 +299,7 @@ end tell"#;
             let _ = Command::new("sh")
                 .arg("-c")
                 .arg(&touch_command)
-                .output()?;
+                .output().await?;
             
             logger::warning("  Could not force video refresh");
         } else {
@@ -285,7 +309,7 @@ end tell"#;
         Ok(())
     }
 
-    async fn select_video_from_list(&self, videos: &[VideoFile]) -> Result<Option<VideoFile>, Box<dyn std::error::Error>> {
+    async fn select_video_from_list(&self, videos: &[VideoFile]) -> Result<Option<VideoFile>> {
         logger::video("  Multiple videos found in directory");
         logger::info(" Opening Finder to help you identify the current video...");
 
@@ -337,7 +361,7 @@ end tell"#;
         }
     }
 
-    async fn get_user_confirmation(&self, selected_video: &VideoFile, new_video_path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    async fn get_user_confirmation(&self, selected_video: &VideoFile, new_video_path: &Path) -> Result<bool> {
         logger::warning(&format!("  About to replace: {}", selected_video.name));
         logger::info(&format!(" Current size: {}", utils::format_file_size(Some(selected_video.size))));
 
@@ -354,13 +378,34 @@ end tell"#;
         Ok(input.trim().to_lowercase() == "y" || input.trim().to_lowercase() == "yes")
     }
 
-    pub async fn setup_video(&self, video_path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    /// Non-interactive install used by background switching: replaces `target_video_name`,
+    /// or the most recently installed system video when no target is given.
+    pub async fn swap_video(&self, video_path: &Path, target_video_name: Option<&str>) -> Result<String> {
+        let target = match target_video_name {
+            Some(name) => name.to_string(),
+            None => self.get_existing_videos()
+                .first()
+                .map(|v| v.name.clone())
+                .ok_or_else(|| DownloaderError::NoWallpaper("No system video found to replace. Install a wallpaper once interactively first.".to_string()))?,
+        };
+
+        self.install_video(video_path, &target).await?;
+        Ok(target)
+    }
+
+    pub async fn setup_video(&self, video_path: &Path) -> Result<bool> {
+        Ok(self.setup_video_target(video_path).await?.is_some())
+    }
+
+    /// Run the installation flow and return the name of the system video that was replaced,
+    /// or `None` when the user cancelled.
+    pub async fn setup_video_target(&self, video_path: &Path) -> Result<Option<String>> {
         logger::header("  Video Installation");
         
         // Check directory access
         let has_access = self.check_customer_directory().await?;
         if !has_access {
-            return Err("Cannot access video directory. Please check permissions.".into());
+            return Err(DownloaderError::Permission("Cannot access video directory. Please check permissions.".to_string()));
         }
         
         // Check if directory is empty
@@ -378,8 +423,8 @@ end tell"#;
             self.create_backup(&video_file).await?;
             
             // Install new video
-            let success = self.install_video(video_path, &video_file.name).await?;
-            return Ok(success);
+            let installed = self.install_video(video_path, &video_file.name).await?;
+            Ok(installed.then_some(video_file.name))
         } else {
             // Directory has existing videos
             let existing_videos = self.get_existing_videos();
@@ -398,8 +443,8 @@ end tell"#;
                 self.create_backup(&video_file).await?;
 
                 // Install new video
-                let success = self.install_video(video_path, &video_file.name).await?;
-                return Ok(success);
+                let installed = self.install_video(video_path, &video_file.name).await?;
+                Ok(installed.then_some(video_file.name))
             } else if existing_videos.len() == 1 {
                 // Single video found - use existing logic
                 let target_video = &existing_videos[0];
@@ -408,15 +453,15 @@ end tell"#;
                 let confirmed = self.get_user_confirmation(target_video, video_path).await?;
                 if !confirmed {
                     logger::info(" Video installation cancelled by user");
-                    return Ok(false);
+                    return Ok(None);
                 }
 
                 // Create backup
                 self.create_backup(target_video).await?;
 
                 // Install new video
-                let success = self.install_video(video_path, &target_video.name).await?;
-                return Ok(success);
+                let installed = self.install_video(video_path, &target_video.name).await?;
+                Ok(installed.then(|| target_video.name.clone()))
             } else {
                 // Multiple videos found - let user choose
                 logger::info(&format!(" Found {} videos in directory", existing_videos.len()));
@@ -427,18 +472,18 @@ end tell"#;
                     let confirmed = self.get_user_confirmation(&video, video_path).await?;
                     if !confirmed {
                         logger::info(" Video installation cancelled by user");
-                        return Ok(false);
+                        return Ok(None);
                     }
 
                     // Create backup
                     self.create_backup(&video).await?;
 
                     // Install new video
-                    let success = self.install_video(video_path, &video.name).await?;
-                    return Ok(success);
+                    let installed = self.install_video(video_path, &video.name).await?;
+                    Ok(installed.then_some(video.name))
                 } else {
                     logger::info(" Video installation cancelled by user");
-                    return Ok(false);
+                    Ok(None)
                 }
             }
         }
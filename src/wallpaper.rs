@@ -0,0 +1,253 @@
+use std::env;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::process::Command;
+use crate::cancel::CancelHandle;
+use crate::error::{DownloaderError, Result};
+use crate::config::{Config, StillFormat};
+use crate::dependencies;
+use crate::displays;
+use crate::logger;
+use crate::still;
+use crate::video_manager::VideoManager;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A desktop environment that a downloaded video can be installed into.
+///
+/// Only the macOS backend plays the video itself; the others set the video's best frame
+/// as a static picture unless a video wallpaper engine is available.
+pub trait WallpaperBackend: Send + Sync {
+    /// Shown in logs, e.g. "GNOME"
+    fn name(&self) -> &'static str;
+
+    /// Interactive installation. Returns what was replaced, or `None` when the user cancelled.
+    fn install<'a>(&'a self, video: &'a Path) -> BoxFuture<'a, Result<Option<String>>>;
+
+    /// Non-interactive switch used by background automation.
+    /// `target` only means something to backends that replace a system video.
+    fn swap<'a>(&'a self, video: &'a Path, target: Option<&'a str>) -> BoxFuture<'a, Result<String>>;
+}
+
+/// Pick the backend for the running OS and desktop environment. A `--sandbox` run uses the
+/// macOS flow on any OS, as it only touches the sandbox.
+pub fn detect(config: &Config) -> Result<Box<dyn WallpaperBackend>> {
+    if cfg!(target_os = "macos") || config.video_settings.sandbox.is_some() {
+        return Ok(Box::new(VideoManager::new(config)));
+    }
+    if cfg!(windows) {
+        return Ok(Box::new(Windows));
+    }
+
+    // e.g. "ubuntu:GNOME" or "KDE"
+    let desktop = env::var("XDG_CURRENT_DESKTOP").unwrap_or_default().to_uppercase();
+    if desktop.split(':').any(|name| name == "KDE") {
+        Ok(Box::new(Kde))
+    } else if desktop.split(':').any(|name| matches!(name, "GNOME" | "UNITY" | "BUDGIE")) {
+        Ok(Box::new(Gnome))
+    } else {
+        Err(format!(
+            "Unsupported desktop '{}'; wallpapers can be installed on macOS, GNOME, KDE Plasma and Windows",
+            if desktop.is_empty() { "unknown" } else { &desktop }
+        ).into())
+    }
+}
+
+/// Install `path` through `backend`, or on the chosen `--display` only. Returns false when the
+/// user cancelled.
+pub async fn install_on(backend: &dyn WallpaperBackend, path: &Path, config: &Config) -> Result<bool> {
+    match config.video_settings.display {
+        Some(index) if cfg!(target_os = "macos") => {
+            let label = path.file_stem().unwrap_or_default().to_string_lossy();
+            Ok(displays::install_on_display(config, index, path, &label).await?.is_some())
+        }
+        Some(_) => Err(format!("--display is only supported on macOS; {} sets the same wallpaper on every display", backend.name()).into()),
+        None => Ok(backend.install(path).await?.is_some()),
+    }
+}
+
+impl WallpaperBackend for VideoManager {
+    fn name(&self) -> &'static str {
+        "macOS"
+    }
+
+    fn install<'a>(&'a self, video: &'a Path) -> BoxFuture<'a, Result<Option<String>>> {
+        Box::pin(self.setup_video_target(video))
+    }
+
+    fn swap<'a>(&'a self, video: &'a Path, target: Option<&'a str>) -> BoxFuture<'a, Result<String>> {
+        Box::pin(self.swap_video(video, target))
+    }
+}
+
+/// GNOME (and Unity/Budgie), via `gsettings`. Sets the picture for both light and dark styles.
+pub struct Gnome;
+
+impl Gnome {
+    fn set_picture(&self, video: &Path) -> Result<String> {
+        let picture = still_for(video)?;
+        let uri = format!("file://{}", picture.display());
+
+        for key in ["picture-uri", "picture-uri-dark"] {
+            let output = Command::new("gsettings")
+                .args(["set", "org.gnome.desktop.background", key, &uri])
+                .output()?;
+
+            // picture-uri-dark only exists since GNOME 42
+            if !output.status.success() && key == "picture-uri" {
+                return Err(format!("gsettings failed: {}", String::from_utf8_lossy(&output.stderr).trim()).into());
+            }
+        }
+
+        Ok(file_name(&picture))
+    }
+}
+
+impl WallpaperBackend for Gnome {
+    fn name(&self) -> &'static str {
+        "GNOME"
+    }
+
+    fn install<'a>(&'a self, video: &'a Path) -> BoxFuture<'a, Result<Option<String>>> {
+        Box::pin(async move {
+            logger::header("  Wallpaper Installation (GNOME)");
+            logger::info("GNOME has no video wallpapers; setting the best frame as the desktop picture");
+            let name = self.set_picture(video)?;
+            logger::success(&format!("Desktop picture set to {}", name));
+            Ok(Some(name))
+        })
+    }
+
+    fn swap<'a>(&'a self, video: &'a Path, _target: Option<&'a str>) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move { self.set_picture(video) })
+    }
+}
+
+/// KDE Plasma, via `plasma-apply-wallpaperimage` (Plasma 5.24+).
+pub struct Kde;
+
+impl Kde {
+    fn set_picture(&self, video: &Path) -> Result<String> {
+        let picture = still_for(video)?;
+        let output = Command::new("plasma-apply-wallpaperimage")
+            .arg(&picture)
+            .output()?;
+
+        if !output.status.success() {
+            return Err(format!("plasma-apply-wallpaperimage failed: {}", String::from_utf8_lossy(&output.stderr).trim()).into());
+        }
+        Ok(file_name(&picture))
+    }
+}
+
+impl WallpaperBackend for Kde {
+    fn name(&self) -> &'static str {
+        "KDE Plasma"
+    }
+
+    fn install<'a>(&'a self, video: &'a Path) -> BoxFuture<'a, Result<Option<String>>> {
+        Box::pin(async move {
+            logger::header("  Wallpaper Installation (KDE Plasma)");
+            logger::info("Setting the best frame as the desktop picture on every screen");
+            let name = self.set_picture(video)?;
+            logger::success(&format!("Desktop picture set to {}", name));
+            Ok(Some(name))
+        })
+    }
+
+    fn swap<'a>(&'a self, video: &'a Path, _target: Option<&'a str>) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move { self.set_picture(video) })
+    }
+}
+
+/// Windows. Plays the video through Lively Wallpaper's command utility when it is installed,
+/// otherwise sets the best frame with `SystemParametersInfo`.
+pub struct Windows;
+
+impl Windows {
+    fn set_wallpaper(&self, video: &Path) -> Result<String> {
+        let video = fs::canonicalize(video)?;
+        match Command::new("Livelycu").arg("setwp").arg("--file").arg(&video).output() {
+            Ok(output) if output.status.success() => {
+                logger::info("Playing the video with Lively Wallpaper");
+                return Ok(file_name(&video));
+            }
+            Ok(output) => logger::warning(&format!("Lively Wallpaper failed ({}); setting a still instead", String::from_utf8_lossy(&output.stderr).trim())),
+            Err(_) => logger::info("Lively Wallpaper not found; setting the best frame as a still"),
+        }
+
+        let picture = still_for(&video)?;
+        set_desktop_picture(&picture)?;
+        Ok(file_name(&picture))
+    }
+}
+
+impl WallpaperBackend for Windows {
+    fn name(&self) -> &'static str {
+        "Windows"
+    }
+
+    fn install<'a>(&'a self, video: &'a Path) -> BoxFuture<'a, Result<Option<String>>> {
+        Box::pin(async move {
+            logger::header("  Wallpaper Installation (Windows)");
+            let name = self.set_wallpaper(video)?;
+            logger::success(&format!("Wallpaper set to {}", name));
+            Ok(Some(name))
+        })
+    }
+
+    fn swap<'a>(&'a self, video: &'a Path, _target: Option<&'a str>) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move { self.set_wallpaper(video) })
+    }
+}
+
+#[cfg(windows)]
+fn set_desktop_picture(picture: &Path) -> Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+
+    const SPI_SETDESKWALLPAPER: u32 = 0x0014;
+    const SPIF_UPDATEINIFILE: u32 = 0x01;
+    const SPIF_SENDCHANGE: u32 = 0x02;
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn SystemParametersInfoW(action: u32, param: u32, value: *mut std::ffi::c_void, flags: u32) -> i32;
+    }
+
+    let mut path: Vec<u16> = picture.as_os_str().encode_wide().chain(Some(0)).collect();
+    let ok = unsafe {
+        SystemParametersInfoW(SPI_SETDESKWALLPAPER, 0, path.as_mut_ptr().cast(), SPIF_UPDATEINIFILE | SPIF_SENDCHANGE)
+    };
+    if ok == 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn set_desktop_picture(_picture: &Path) -> Result<()> {
+    Err("SystemParametersInfo is only available on Windows".into())
+}
+
+/// The video's best frame as a PNG next to it, reused when it was already exported.
+fn still_for(video: &Path) -> Result<PathBuf> {
+    let existing = video.with_extension("png");
+    if existing.exists() {
+        return Ok(fs::canonicalize(existing)?);
+    }
+
+    let output = Command::new(dependencies::ffprobe())
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "csv=p=0"])
+        .arg(video)
+        .output()?;
+    let duration = String::from_utf8_lossy(&output.stdout).trim().parse::<f64>()
+        .map_err(|_| DownloaderError::Ffmpeg(format!("Could not read the duration of {}", video.display())))?;
+
+    Ok(fs::canonicalize(still::extract_still(video, duration, StillFormat::Png, &CancelHandle::new())?)?)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().to_string()
+}
@@ -0,0 +1,74 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use crate::cancel::{self, Signal};
+
+/// How often the watchdog checks for a stall
+const POLL: Duration = Duration::from_secs(1);
+
+/// Kills a child's process group once it goes `limit` without progress, so a download on
+/// flaky Wi-Fi or a hung hardware encoder fails and is retried instead of hanging forever.
+///
+/// The child counts as stalled when `feed` isn't called, including when it prints nothing at
+/// all; whoever reads its output calls `feed` when the work has actually moved on.
+pub struct Watchdog {
+    feed: Option<Sender<()>>,
+    stalled: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    /// Watch the group led by `pid`; with no `limit` this does nothing.
+    pub fn start(pid: u32, limit: Option<Duration>) -> Self {
+        let stalled = Arc::new(AtomicBool::new(false));
+        let Some(limit) = limit else {
+            return Self { feed: None, stalled, thread: None };
+        };
+
+        let (feed, fed) = mpsc::channel();
+        let flag = Arc::clone(&stalled);
+        let thread = std::thread::spawn(move || {
+            let mut last = Instant::now();
+            loop {
+                match fed.recv_timeout(POLL) {
+                    Ok(()) => last = Instant::now(),
+                    Err(RecvTimeoutError::Timeout) if last.elapsed() >= limit => {
+                        flag.store(true, Ordering::SeqCst);
+                        // A hung encoder may not react to SIGTERM
+                        cancel::kill_group(pid, Signal::Kill);
+                        return;
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        });
+        Self { feed: Some(feed), stalled, thread: Some(thread) }
+    }
+
+    /// Record progress.
+    pub fn feed(&self) {
+        if let Some(feed) = &self.feed {
+            feed.send(()).ok();
+        }
+    }
+
+    /// Stop watching, once the child has exited. Returns whether it was killed for stalling.
+    pub fn stop(mut self) -> bool {
+        self.feed.take();
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+        self.stalled.load(Ordering::SeqCst)
+    }
+}
+
+/// "5 minutes" for messages.
+pub fn describe(limit: Duration) -> String {
+    match limit.as_secs() / 60 {
+        1 => "1 minute".to_string(),
+        minutes => format!("{} minutes", minutes),
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+use crate::error_codes::ErrorCode;
+
+/// A yt-dlp failure recognized from its stderr, so users get specific guidance
+/// instead of a wall of raw extractor output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum YtDlpError {
+    /// "Sign in to confirm you're not a bot"
+    BotCheck,
+    /// "Sign in to confirm your age"
+    AgeRestricted,
+    /// "Private video" / members-only content
+    Private,
+    /// "Video unavailable", removed or terminated
+    Unavailable,
+    /// Not available in the user's country
+    Geoblocked,
+    /// "Requested format is not available"
+    FormatUnavailable,
+    /// HTTP Error 429: Too Many Requests
+    RateLimited,
+    /// Anything else; keeps the most relevant `ERROR:` line
+    Other(String),
+}
+
+impl YtDlpError {
+    /// Recognize the failure from yt-dlp's stderr.
+    pub fn from_stderr(stderr: &str) -> Self {
+        let lower = stderr.to_lowercase();
+
+        if lower.contains("confirm you're not a bot") || lower.contains("confirm you’re not a bot") {
+            YtDlpError::BotCheck
+        } else if lower.contains("confirm your age") || lower.contains("age-restricted") {
+            YtDlpError::AgeRestricted
+        } else if lower.contains("private video") || lower.contains("members-only") || lower.contains("join this channel") {
+            YtDlpError::Private
+        } else if lower.contains("not available in your country") || lower.contains("geo restrict") || lower.contains("geo-restrict") {
+            YtDlpError::Geoblocked
+        } else if lower.contains("requested format is not available") {
+            YtDlpError::FormatUnavailable
+        } else if lower.contains("http error 429") || lower.contains("too many requests") {
+            YtDlpError::RateLimited
+        } else if lower.contains("video unavailable") || lower.contains("has been removed") || lower.contains("account associated with this video has been terminated") {
+            YtDlpError::Unavailable
+        } else {
+            let line = stderr.lines()
+                .rev()
+                .find(|line| line.starts_with("ERROR:"))
+                .or_else(|| stderr.lines().rev().find(|line| !line.trim().is_empty()))
+                .unwrap_or("unknown error");
+            YtDlpError::Other(line.trim_start_matches("ERROR:").trim().to_string())
+        }
+    }
+
+    /// What the user can do about it.
+    pub fn guidance(&self) -> &'static str {
+        match self {
+            YtDlpError::BotCheck => "YouTube wants a signed-in session. Run 'rust-downloader auth setup' to use cookies from a browser where you are logged in.",
+            YtDlpError::AgeRestricted => "Age-restricted videos need a signed-in, age-verified account. Pass --cookies-from-browser BROWSER (or --cookies FILE), or run 'rust-downloader auth setup'.",
+            YtDlpError::Private => "The video is private or members-only; only the owner or members can download it, with --cookies-from-browser BROWSER signed in as them.",
+            YtDlpError::Unavailable => "The video was removed, made private, or its channel was terminated.",
+            YtDlpError::Geoblocked => "The uploader blocked your country. A VPN to an allowed region is the only workaround.",
+            YtDlpError::FormatUnavailable => "The chosen format disappeared between analysis and download. Retry, or update yt-dlp.",
+            YtDlpError::RateLimited => "YouTube is rate-limiting this IP. Wait a while before retrying, or use cookies from a signed-in browser.",
+            YtDlpError::Other(_) => "Update yt-dlp (rust-downloader update-deps); most extractor failures are fixed upstream within days.",
+        }
+    }
+
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            YtDlpError::BotCheck => ErrorCode::BotCheck,
+            YtDlpError::AgeRestricted => ErrorCode::AgeRestricted,
+            YtDlpError::Private | YtDlpError::Unavailable | YtDlpError::Geoblocked => ErrorCode::VideoUnavailable,
+            YtDlpError::FormatUnavailable => ErrorCode::FormatNotFound,
+            YtDlpError::RateLimited => ErrorCode::RateLimited,
+            YtDlpError::Other(_) => ErrorCode::DownloadFailed,
+        }
+    }
+
+    /// Whether retrying the same request immediately can help.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, YtDlpError::FormatUnavailable | YtDlpError::Other(_))
+    }
+}
+
+impl fmt::Display for YtDlpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            YtDlpError::BotCheck => write!(f, "yt-dlp: YouTube asked to confirm you're not a bot"),
+            YtDlpError::AgeRestricted => write!(f, "yt-dlp: video is age-restricted and requires sign-in"),
+            YtDlpError::Private => write!(f, "yt-dlp: video is private or members-only"),
+            YtDlpError::Unavailable => write!(f, "yt-dlp: video unavailable"),
+            YtDlpError::Geoblocked => write!(f, "yt-dlp: video is not available in your country"),
+            YtDlpError::FormatUnavailable => write!(f, "yt-dlp: requested format is not available"),
+            YtDlpError::RateLimited => write!(f, "yt-dlp: rate-limited by YouTube (HTTP 429)"),
+            YtDlpError::Other(message) => write!(f, "yt-dlp failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for YtDlpError {}
@@ -0,0 +1,54 @@
+use rust_downloader::cancel::CancelHandle;
+
+#[test]
+fn cancelling_a_job_leaves_the_run_going() {
+    let run = CancelHandle::new();
+    let first = run.child();
+    let second = run.child();
+
+    first.cancel();
+
+    assert!(first.is_cancelled());
+    assert!(!second.is_cancelled());
+    assert!(!run.is_cancelled());
+}
+
+#[test]
+fn cancelling_the_run_cancels_every_job() {
+    let run = CancelHandle::new();
+    let job = run.child();
+
+    run.cancel();
+
+    assert!(job.is_cancelled());
+    // A job queued after the cancel starts out cancelled
+    assert!(run.child().is_cancelled());
+}
+
+#[tokio::test]
+async fn cancelled_job_wakes_its_waiters() {
+    let run = CancelHandle::new();
+    let job = run.child();
+    let waiter = tokio::spawn({
+        let job = job.clone();
+        async move { job.cancelled().await }
+    });
+
+    run.cancel();
+    tokio::time::timeout(std::time::Duration::from_secs(5), waiter).await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn cancelling_stops_a_tracked_command() {
+    let run = CancelHandle::new();
+    let job = run.child();
+    let sleeper = tokio::spawn({
+        let job = job.clone();
+        async move { job.output(tokio::process::Command::new("sleep").arg("30")).await }
+    });
+
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    run.cancel();
+    let output = tokio::time::timeout(std::time::Duration::from_secs(5), sleeper).await.unwrap().unwrap().unwrap();
+    assert!(!output.status.success());
+}
@@ -0,0 +1,35 @@
+use std::fs;
+use rust_downloader::config::{Config, CookieSource};
+
+fn load(content: &str) -> rust_downloader::error::Result<Config> {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, content).unwrap();
+    Config::load(Some(&path))
+}
+
+#[test]
+fn auth_profiles_come_from_the_config_file() {
+    let config = load(r#"
+version = 1
+
+[auth]
+cooldown_minutes = 15
+
+[auth.profiles.main]
+browser = "chrome:Profile 2"
+
+[auth.profiles.archive]
+cookies = "/tmp/archive-cookies.txt"
+"#).unwrap();
+
+    assert_eq!(config.auth.cooldown_minutes, 15);
+    assert_eq!(config.auth.profiles["main"], CookieSource::Browser("chrome:Profile 2".to_string()));
+    assert_eq!(config.auth.profiles["archive"], CookieSource::File("/tmp/archive-cookies.txt".into()));
+}
+
+#[test]
+fn auth_profile_needs_exactly_one_cookie_source() {
+    assert!(load("[auth.profiles.both]\nbrowser = \"safari\"\ncookies = \"/tmp/c.txt\"\n").is_err());
+    assert!(load("[auth.profiles.none]\n").is_err());
+}
@@ -0,0 +1,90 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::thread;
+
+/// Serves one file over HTTP on 127.0.0.1, with the `Range` requests curl sends to resume
+/// and to fetch segments.
+pub struct FixtureServer {
+    pub url: String,
+}
+
+impl FixtureServer {
+    pub fn serve(file: &Path) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/{}", listener.local_addr().unwrap(), file.file_name().unwrap().to_string_lossy());
+        let body: Arc<[u8]> = std::fs::read(file).unwrap().into();
+
+        // Lives until the test process exits; segments are requested at the same time
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let body = Arc::clone(&body);
+                thread::spawn(move || respond(stream, &body));
+            }
+        });
+        Self { url }
+    }
+}
+
+fn respond(mut stream: TcpStream, body: &[u8]) {
+    let mut range = None;
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut line = String::new();
+    while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+        // Range: bytes=1234- or bytes=0-4095
+        if let Some((start, end)) = line.to_lowercase().strip_prefix("range: bytes=").and_then(|r| r.trim().split_once('-')) {
+            let start: usize = start.parse().unwrap_or(0);
+            let end: usize = end.parse().unwrap_or(body.len() - 1);
+            range = Some((start.min(body.len()), end.min(body.len() - 1)));
+        }
+        line.clear();
+    }
+
+    let (head, part) = match range {
+        None => (format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n", body.len()), body),
+        Some((start, end)) => (
+            format!(
+                "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\n",
+                end + 1 - start, start, end, body.len()
+            ),
+            &body[start..=end],
+        ),
+    };
+    let _ = stream.write_all(format!("{}Content-Type: video/mp4\r\nConnection: close\r\n\r\n", head).as_bytes());
+    let _ = stream.write_all(part);
+}
+
+/// Whether ffmpeg can make the H.264 sample and encode the HEVC wallpaper, and curl can fetch it.
+pub fn tools_available() -> bool {
+    let encoders = Command::new("ffmpeg").args(["-hide_banner", "-encoders"]).output();
+    let encoders = encoders.map(|output| String::from_utf8_lossy(&output.stdout).to_string()).unwrap_or_default();
+    let runs = |program: &str| Command::new(program).arg("-version").output().is_ok_and(|output| output.status.success());
+    encoders.contains("libx264") && encoders.contains("libx265") && runs("ffprobe") && runs("curl")
+}
+
+/// Whether a test that needs `tools` can run. Missing tools skip the test locally, but fail it
+/// when `CI` is set, so a runner without them can't pass without running anything.
+pub fn require(available: bool, tools: &str) -> bool {
+    if !available {
+        assert!(std::env::var_os("CI").is_none(), "CI is set but the end-to-end tests need {}", tools);
+        eprintln!("skipping: needs {}", tools);
+    }
+    available
+}
+
+/// A 3-second 320x180 test pattern with a tone, as YouTube's progressive H.264/AAC formats are.
+pub fn sample_video(dir: &Path) -> PathBuf {
+    let path = dir.join("sample.mp4");
+    let status = Command::new("ffmpeg")
+        .args(["-y", "-v", "error"])
+        .args(["-f", "lavfi", "-i", "testsrc=size=320x180:rate=30:duration=3"])
+        .args(["-f", "lavfi", "-i", "sine=frequency=440:duration=3"])
+        .args(["-c:v", "libx264", "-pix_fmt", "yuv420p", "-c:a", "aac", "-shortest"])
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(status.success(), "ffmpeg could not make the sample video");
+    path
+}
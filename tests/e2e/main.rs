@@ -0,0 +1,149 @@
+//! The whole pipeline without YouTube: a fixture extractor lists a sample video served from
+//! 127.0.0.1, curl downloads it, ffmpeg converts it and it is installed into a `--sandbox`.
+//!
+//! Skipped, with a note, when ffmpeg (with libx264 and libx265), ffprobe or curl is missing.
+//! Segmented downloads only need curl.
+
+mod fixture;
+
+use std::path::Path;
+use std::process::Command;
+use serde_json::{json, Value};
+use rust_downloader::downloader::Downloader;
+use rust_downloader::error::Result;
+use rust_downloader::extractor::Extractor;
+use rust_downloader::video_manager::VideoManager;
+use rust_downloader::{video_info, Config};
+use fixture::FixtureServer;
+
+const VIDEO_URL: &str = "https://www.youtube.com/watch?v=e2eSample01";
+
+/// Lists the served sample as the only format, a progressive one like YouTube's 18.
+struct FixtureExtractor {
+    format_url: String,
+    filesize: u64,
+}
+
+impl Extractor for FixtureExtractor {
+    fn name(&self) -> String {
+        "fixture".to_string()
+    }
+
+    fn extract(&self, _url: &str) -> Result<Value> {
+        Ok(json!({
+            "id": "e2eSample01",
+            "title": "E2E Sample",
+            "uploader": "Fixture",
+            "duration": 3,
+            "formats": [{
+                "format_id": "18",
+                "url": self.format_url,
+                "ext": "mp4",
+                "vcodec": "avc1.42001E",
+                "acodec": "mp4a.40.2",
+                "width": 320,
+                "height": 180,
+                "fps": 30.0,
+                "filesize": self.filesize,
+            }],
+        }))
+    }
+
+    fn direct(&self) -> bool {
+        true
+    }
+}
+
+/// A config that converts quickly: the wallpaper profile at the sample's own size.
+fn test_config(dir: &Path) -> Config {
+    let mut config = Config { output_dir: dir.join("output"), enable_video: true, ..Config::default() };
+    std::fs::create_dir_all(&config.output_dir).unwrap();
+    config.video_settings.use_sandbox(&dir.join("sandbox"));
+    config.video_settings.min_recommended_duration = 1;
+
+    let profile = &mut config.conversion_settings.profiles[0];
+    profile.resolution = Some((320, 180));
+    profile.frame_rate = Some(30);
+    profile.bitrate = Some(1);
+    profile.pixel_format = "yuv420p".to_string();
+    config
+}
+
+/// The extractor's format, served from `file`.
+fn serve(file: &Path) -> (FixtureServer, FixtureExtractor) {
+    let server = FixtureServer::serve(file);
+    let extractor = FixtureExtractor {
+        format_url: server.url.clone(),
+        filesize: std::fs::metadata(file).unwrap().len(),
+    };
+    (server, extractor)
+}
+
+fn video_codec(path: &Path) -> String {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0", "-show_entries", "stream=codec_name", "-of", "csv=p=0"])
+        .arg(path)
+        .output()
+        .unwrap();
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+#[tokio::test]
+async fn download_convert_and_install_into_sandbox() {
+    if !fixture::require(fixture::tools_available(), "ffmpeg with libx264 and libx265, ffprobe and curl") {
+        return;
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let sample = fixture::sample_video(dir.path());
+    let (_server, extractor) = serve(&sample);
+    let config = test_config(dir.path());
+
+    let analysis = video_info::analyze_with(VIDEO_URL, &extractor, &config).unwrap();
+    assert_eq!(analysis.video_format.format_id, "18");
+    assert!(analysis.audio_format.is_none(), "the progressive format carries the audio");
+
+    let mut downloader = Downloader::new();
+    let wallpaper = downloader.perform_download(VIDEO_URL, &analysis, &config).await.unwrap();
+    assert_eq!(wallpaper.extension().unwrap(), "mov");
+    assert!(wallpaper.starts_with(&config.output_dir));
+    assert_eq!(video_codec(&wallpaper), "hevc");
+
+    // The system video a real install would replace
+    let target_dir = config.video_settings.target_dir();
+    std::fs::create_dir_all(&target_dir).unwrap();
+    std::fs::write(target_dir.join("aerial.mov"), b"").unwrap();
+
+    let replaced = VideoManager::new(&config).swap_video(&wallpaper, None).await.unwrap();
+    assert_eq!(replaced, "aerial.mov");
+    let installed = target_dir.join("aerial.mov");
+    assert_eq!(std::fs::metadata(&installed).unwrap().len(), std::fs::metadata(&wallpaper).unwrap().len());
+    assert_eq!(video_codec(&installed), "hevc");
+}
+
+#[tokio::test]
+async fn segmented_download_is_reassembled() {
+    if !fixture::require(Command::new("curl").arg("--version").output().is_ok(), "curl") {
+        return;
+    }
+
+    // 10 MiB splits into two 5 MiB ranges even when four are asked for
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("large.mp4");
+    let body: Vec<u8> = (0..10 * 1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+    std::fs::write(&file, &body).unwrap();
+    let (_server, extractor) = serve(&file);
+
+    let mut config = test_config(dir.path());
+    config.download_settings.convert_to_mov = false;
+    config.download_settings.concurrent_fragments = 4;
+
+    let analysis = video_info::analyze_with(VIDEO_URL, &extractor, &config).unwrap();
+    let downloaded = Downloader::new().perform_download(VIDEO_URL, &analysis, &config).await.unwrap();
+    assert!(std::fs::read(&downloaded).unwrap() == body, "segments were joined out of order");
+
+    let leftovers: Vec<_> = std::fs::read_dir(&config.output_dir).unwrap().flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().contains(".part"))
+        .collect();
+    assert!(leftovers.is_empty(), "segment files were left behind");
+}
@@ -0,0 +1,26 @@
+use rust_downloader::error::DownloaderError;
+use rust_downloader::error_codes::ErrorCode;
+
+#[test]
+fn codes_come_from_the_variant_not_the_message() {
+    let cases = [
+        (DownloaderError::Permission("Administrator privileges required".into()), ErrorCode::PermissionDenied),
+        (DownloaderError::SudoRequired("Customer directory not accessible".into()), ErrorCode::SudoRequired),
+        (DownloaderError::PrivacyDenied("Cannot read Focus state".into()), ErrorCode::TccDenied),
+        (DownloaderError::NoWallpaper("Nothing to replace".into()), ErrorCode::WallpaperNotFound),
+        (DownloaderError::MissingDependency("ffmpeg".into()), ErrorCode::DependencyMissing),
+        (DownloaderError::NoFormat("Only 360p".into()), ErrorCode::FormatNotFound),
+        (DownloaderError::Download("network hiccup".into()), ErrorCode::DownloadFailed),
+        (DownloaderError::Ffmpeg("Operation not permitted".into()), ErrorCode::ConversionFailed),
+        (DownloaderError::Network("ffmpeg".into()), ErrorCode::NetworkError),
+    ];
+    for (error, code) in cases {
+        assert_eq!(ErrorCode::for_error(&error), Some(code), "{:?}", error);
+    }
+}
+
+#[test]
+fn unclassified_errors_have_no_code() {
+    let error = DownloaderError::Other("Download failed: yt-dlp network ffmpeg permission denied".into());
+    assert_eq!(ErrorCode::for_error(&error), None);
+}
@@ -0,0 +1,246 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Barrier};
+use std::thread;
+use chrono::Utc;
+use rust_downloader::history::{DownloadStatus, History, HistoryEntry, JsonStore};
+#[cfg(feature = "sqlite")]
+use rust_downloader::history::SqliteStore;
+
+const WRITERS: usize = 8;
+
+fn entry(writer: usize, index: usize) -> HistoryEntry {
+    HistoryEntry {
+        video_id: Some(format!("vid{:02}{:04}", writer, index)),
+        url: format!("https://www.youtube.com/watch?v=vid{:02}{:04}", writer, index),
+        title: Some(format!("Writer {} entry {}", writer, index)),
+        uploader: Some(format!("Writer {}", writer)),
+        license: None,
+        path: Some(PathBuf::from(format!("/tmp/writer{}_{}.mov", writer, index))),
+        status: if index.is_multiple_of(10) { DownloadStatus::Failed } else { DownloadStatus::Completed },
+        error: None,
+        bytes: Some(1_000),
+        seconds: 1.0,
+        conversion_seconds: None,
+        conversion_path: None,
+        loudness: None,
+        true_peak: None,
+        source: None,
+        sha256: Some(format!("{:064x}", writer * 10_000 + index)),
+        finished_at: Utc::now(),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn open_sqlite(path: &Path) -> History {
+    History::new(SqliteStore::open(path).unwrap())
+}
+
+fn open_json(path: &Path) -> History {
+    History::new(JsonStore::new(path))
+}
+
+/// Each writer opens its own store, like separate CLI, queue and daemon processes would,
+/// while a reader polls the totals.
+fn simultaneous_writers(open: fn(&Path) -> History, file: &str, per_writer: usize) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(file);
+    open(&path);
+
+    let barrier = Arc::new(Barrier::new(WRITERS + 1));
+    let mut handles = Vec::new();
+    for writer in 0..WRITERS {
+        let path = path.clone();
+        let barrier = Arc::clone(&barrier);
+        handles.push(thread::spawn(move || {
+            let history = open(&path);
+            barrier.wait();
+            for index in 0..per_writer {
+                history.record(&entry(writer, index)).unwrap();
+            }
+        }));
+    }
+
+    let reader = {
+        let path = path.clone();
+        let barrier = Arc::clone(&barrier);
+        thread::spawn(move || {
+            let history = open(&path);
+            barrier.wait();
+            let mut last = 0;
+            for _ in 0..100 {
+                let stats = history.stats().unwrap();
+                let seen = stats.completed + stats.failed;
+                assert!(seen >= last, "row count went backwards");
+                last = seen;
+            }
+        })
+    };
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    reader.join().unwrap();
+
+    let stats = open(&path).stats().unwrap();
+    let total = (WRITERS * per_writer) as u64;
+    assert_eq!(stats.completed + stats.failed, total);
+    assert_eq!(stats.failed, (WRITERS * per_writer.div_ceil(10)) as u64);
+    assert_eq!(stats.total_bytes, stats.completed * 1_000);
+}
+
+fn recent_returns_newest_first(open: fn(&Path) -> History, file: &str) {
+    let dir = tempfile::tempdir().unwrap();
+    let history = open(&dir.path().join(file));
+
+    for index in 0..3 {
+        let mut entry = entry(0, index + 1);
+        entry.conversion_path = Some("remux".to_string());
+        history.record(&entry).unwrap();
+    }
+
+    let recent = history.recent(2).unwrap();
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[0].video_id.as_deref(), Some("vid000003"));
+    assert_eq!(recent[1].video_id.as_deref(), Some("vid000002"));
+    assert_eq!(recent[0].status, DownloadStatus::Completed);
+    assert_eq!(recent[0].conversion_path.as_deref(), Some("remux"));
+}
+
+/// `sync` skips these, so failed downloads must not count.
+fn downloaded_ids_skip_failures(open: fn(&Path) -> History, file: &str) {
+    let dir = tempfile::tempdir().unwrap();
+    let history = open(&dir.path().join(file));
+
+    for index in 0..12 {
+        history.record(&entry(0, index)).unwrap();
+    }
+    // Index 10 failed, then a retry succeeded
+    let mut retry = entry(0, 10);
+    retry.status = DownloadStatus::Completed;
+    history.record(&retry).unwrap();
+
+    let ids = history.downloaded_ids().unwrap();
+    assert_eq!(ids.len(), 11);
+    assert!(ids.contains("vid000001"));
+    assert!(ids.contains("vid000010"));
+    assert!(!ids.contains("vid000000"));
+}
+
+/// `sync` skips these unless `--retry-unavailable`; a later successful retry clears them.
+fn unavailable_ids_follow_latest_attempt(open: fn(&Path) -> History, file: &str) {
+    let dir = tempfile::tempdir().unwrap();
+    let history = open(&dir.path().join(file));
+
+    for index in 1..4 {
+        let mut gone = entry(0, index);
+        gone.status = DownloadStatus::Unavailable;
+        history.record(&gone).unwrap();
+    }
+    // Index 2 came back, index 3 failed for another reason on the retry
+    history.record(&entry(0, 2)).unwrap();
+    let mut failed = entry(0, 3);
+    failed.status = DownloadStatus::Failed;
+    history.record(&failed).unwrap();
+
+    let ids = history.unavailable_ids().unwrap();
+    assert_eq!(ids.len(), 1);
+    assert!(ids.contains("vid000001"));
+}
+
+/// `verify` compares files against the checksum of their latest completed download.
+fn for_path_keeps_latest_checksum(open: fn(&Path) -> History, file: &str) {
+    let dir = tempfile::tempdir().unwrap();
+    let history = open(&dir.path().join(file));
+
+    let first = entry(0, 1);
+    let mut redownload = entry(0, 2);
+    redownload.path = first.path.clone();
+    let mut failed = entry(0, 3);
+    failed.path = first.path.clone();
+    failed.status = DownloadStatus::Failed;
+    for entry in [&first, &redownload, &failed] {
+        history.record(entry).unwrap();
+    }
+
+    let found = history.for_path(first.path.as_deref().unwrap()).unwrap().unwrap();
+    assert_eq!(found.sha256, redownload.sha256);
+    assert!(history.for_path(Path::new("/tmp/never_downloaded.mov")).unwrap().is_none());
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_simultaneous_writers() {
+    simultaneous_writers(open_sqlite, "history.db", 50);
+}
+
+/// Queued jobs share one connection pool across threads.
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_writers_sharing_a_pool() {
+    let dir = tempfile::tempdir().unwrap();
+    let history = open_sqlite(&dir.path().join("history.db"));
+
+    let handles: Vec<_> = (0..WRITERS).map(|writer| {
+        let history = history.clone();
+        thread::spawn(move || {
+            for index in 0..50 {
+                history.record(&entry(writer, index)).unwrap();
+            }
+        })
+    }).collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(history.recent(WRITERS * 50 + 1).unwrap().len(), WRITERS * 50);
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_recent_returns_newest_first() {
+    recent_returns_newest_first(open_sqlite, "history.db");
+}
+
+#[test]
+fn json_simultaneous_writers() {
+    simultaneous_writers(open_json, "history.json", 10);
+}
+
+#[test]
+fn json_recent_returns_newest_first() {
+    recent_returns_newest_first(open_json, "history.json");
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_downloaded_ids_skip_failures() {
+    downloaded_ids_skip_failures(open_sqlite, "history.db");
+}
+
+#[test]
+fn json_downloaded_ids_skip_failures() {
+    downloaded_ids_skip_failures(open_json, "history.json");
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_unavailable_ids_follow_latest_attempt() {
+    unavailable_ids_follow_latest_attempt(open_sqlite, "history.db");
+}
+
+#[test]
+fn json_unavailable_ids_follow_latest_attempt() {
+    unavailable_ids_follow_latest_attempt(open_json, "history.json");
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_for_path_keeps_latest_checksum() {
+    for_path_keeps_latest_checksum(open_sqlite, "history.db");
+}
+
+#[test]
+fn json_for_path_keeps_latest_checksum() {
+    for_path_keeps_latest_checksum(open_json, "history.json");
+}
@@ -0,0 +1,56 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+use rust_downloader::http::{self, Request};
+
+/// Answer one request on a local port with `status` and `body`, returning the base URL.
+fn serve_once(status: &'static str, body: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        // Read up to the blank line that ends the headers
+        let mut reader = BufReader::new(&stream);
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap_or(0) > 2 {
+            line.clear();
+        }
+        write!(stream, "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body).unwrap();
+    });
+    format!("http://{}", address)
+}
+
+#[test]
+fn query_is_percent_encoded() {
+    let request = Request::get("https://sponsor.ajay.app/api/skipSegments")
+        .query("videoID", "dQw4w9WgXcQ")
+        .query("categories", r#"["sponsor","intro"]"#);
+    assert_eq!(
+        request.full_url(),
+        "https://sponsor.ajay.app/api/skipSegments?videoID=dQw4w9WgXcQ&categories=%5B%22sponsor%22%2C%22intro%22%5D",
+    );
+    assert_eq!(Request::get("https://example.com/?a=1").query("b", "x y").full_url(), "https://example.com/?a=1&b=x%20y");
+}
+
+#[test]
+fn status_comes_back_with_the_body() {
+    let url = serve_once("404 Not Found", "{\"error\":\"nope\"}");
+    let response = Request::get(&url).timeout(5).send().unwrap();
+    assert_eq!(response.status, 404);
+    assert_eq!(response.json::<serde_json::Value>().unwrap()["error"], "nope");
+    assert!(response.error_for_status().unwrap_err().to_string().contains("HTTP 404"));
+}
+
+#[test]
+fn get_json_parses_a_success() {
+    let url = serve_once("200 OK", "{\"tag_name\":\"v1.2.3\"}");
+    let value: serde_json::Value = http::get_json(&url, 5).unwrap();
+    assert_eq!(value["tag_name"], "v1.2.3");
+}
+
+#[test]
+fn download_to_fails_on_http_errors() {
+    let url = serve_once("404 Not Found", "");
+    let dir = tempfile::tempdir().unwrap();
+    assert!(http::download_to(&url, &dir.path().join("file")).is_err());
+}
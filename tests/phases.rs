@@ -0,0 +1,108 @@
+use std::path::PathBuf;
+use chrono::Utc;
+use rust_downloader::history::{DownloadStatus, History, HistoryEntry};
+use rust_downloader::phases::{remaining_seconds, PhaseWeights};
+use rust_downloader::status::Stage;
+use rust_downloader::Config;
+
+const ORDER: [Stage; 5] = [Stage::Starting, Stage::Downloading, Stage::Extending, Stage::Converting, Stage::Installing];
+
+fn config(dir: &std::path::Path) -> Config {
+    Config { output_dir: dir.to_path_buf(), ..Config::default() }
+}
+
+fn converted(seconds: f64, conversion_seconds: f64) -> HistoryEntry {
+    HistoryEntry {
+        video_id: None,
+        url: "https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string(),
+        title: None,
+        uploader: None,
+        license: None,
+        path: Some(PathBuf::from("/tmp/converted.mov")),
+        status: DownloadStatus::Completed,
+        error: None,
+        bytes: None,
+        seconds,
+        conversion_seconds: Some(conversion_seconds),
+        conversion_path: None,
+        loudness: None,
+        true_peak: None,
+        source: None,
+        sha256: None,
+        finished_at: Utc::now(),
+    }
+}
+
+#[test]
+fn overall_runs_from_zero_to_one_hundred_in_stage_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = config(dir.path());
+    config.download_settings.convert_to_mov = true;
+
+    for install in [false, true] {
+        let weights = PhaseWeights::for_run(&config, install);
+        let mut last = weights.overall(Stage::Starting, 0.0);
+        assert_eq!(last, 0.0);
+        for stage in ORDER {
+            for percent in [0.0, 50.0, 100.0] {
+                let overall = weights.overall(stage, percent);
+                assert!(overall >= last, "{:?} {}% went back to {}", stage, percent, overall);
+                last = overall;
+            }
+        }
+        assert!((last - 100.0).abs() < 1e-9);
+
+        let converted = weights.overall(Stage::Converting, 100.0);
+        assert_eq!(converted < 100.0, install);
+    }
+}
+
+#[test]
+fn stages_that_do_not_run_take_no_share() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = config(dir.path());
+    config.download_settings.convert_to_mov = false;
+
+    let weights = PhaseWeights::for_run(&config, false);
+    assert!((weights.overall(Stage::Downloading, 100.0) - 100.0).abs() < 1e-9);
+    assert_eq!(weights.overall(Stage::Converting, 0.0), weights.overall(Stage::Converting, 100.0));
+}
+
+#[test]
+fn history_sets_the_split_between_downloading_and_converting() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = config(dir.path());
+    config.download_settings.convert_to_mov = true;
+    let before = PhaseWeights::for_run(&config, false);
+
+    // Conversions have taken 90% of each run
+    let history = History::load(&config).unwrap();
+    for _ in 0..5 {
+        history.record(&converted(100.0, 90.0)).unwrap();
+    }
+    let after = PhaseWeights::for_run(&config, false);
+
+    let downloaded = |weights: &PhaseWeights| weights.overall(Stage::Downloading, 100.0) - weights.overall(Stage::Downloading, 0.0);
+    assert!(downloaded(&after) < downloaded(&before));
+    assert!(downloaded(&after) < 15.0);
+}
+
+#[test]
+fn remaining_time_is_extrapolated_from_progress_since_the_start() {
+    assert_eq!(remaining_seconds(10.0, 5.0, 5.5), None);
+    assert_eq!(remaining_seconds(60.0, 100.0, 100.0), None);
+    // 20 points in 60 seconds leaves 60 points, 180 seconds
+    assert_eq!(remaining_seconds(60.0, 20.0, 40.0), Some(180.0));
+}
+
+#[test]
+fn waiting_for_a_slot_counts_as_the_start_of_its_stage() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = config(dir.path());
+    config.download_settings.convert_to_mov = true;
+    let weights = PhaseWeights::for_run(&config, false);
+
+    assert_eq!(weights.overall(Stage::Queued, 50.0), 0.0);
+    assert_eq!(weights.overall(Stage::WaitingToConvert, 50.0), weights.overall(Stage::Converting, 0.0));
+    assert!(weights.overall(Stage::WaitingToConvert, 0.0) > weights.overall(Stage::Downloading, 99.0));
+}
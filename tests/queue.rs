@@ -0,0 +1,94 @@
+use std::path::PathBuf;
+use rust_downloader::queue::{DownloadQueue, Pick, QueueEntry, SavedQueue, SavedState, Step};
+use rust_downloader::Config;
+
+#[test]
+fn plain_urls_and_searches_are_downloads() {
+    let entry = QueueEntry::parse("https://youtu.be/dQw4w9WgXcQ").unwrap();
+    assert!(entry.is_plain());
+    assert_eq!(entry.url(), Some("https://youtu.be/dQw4w9WgXcQ"));
+
+    let search = QueueEntry::parse("ytsearch:ocean waves 4k as ocean").unwrap();
+    assert_eq!(search.url(), Some("ytsearch:ocean waves 4k"));
+    assert_eq!(search.name.as_deref(), Some("ocean"));
+}
+
+#[test]
+fn steps_wait_for_the_entries_they_read() {
+    let convert = QueueEntry::parse("convert raw after ocean").unwrap();
+    assert_eq!(convert.step, Step::Convert { source: "raw".to_string() });
+    assert_eq!(convert.after, ["ocean", "raw"]);
+
+    let install = QueueEntry::parse("install best of ocean, forest,city as pick").unwrap();
+    assert_eq!(install.step, Step::Install { pick: Pick::Best, from: vec!["ocean".into(), "forest".into(), "city".into()] });
+    assert_eq!(install.after, ["ocean", "forest", "city"]);
+    assert_eq!(install.name.as_deref(), Some("pick"));
+
+    let download = QueueEntry::parse("https://youtu.be/dQw4w9WgXcQ download-only as raw").unwrap();
+    assert_eq!(download.step, Step::Download { url: "https://youtu.be/dQw4w9WgXcQ".into(), convert: false });
+    assert!(!download.is_plain());
+}
+
+#[test]
+fn malformed_entries_are_rejected() {
+    assert!(QueueEntry::parse("install best ocean").is_err());
+    assert!(QueueEntry::parse("install choice of").is_err());
+    assert!(QueueEntry::parse("convert raw download-only").is_err());
+    assert!(QueueEntry::parse("https://youtu.be/dQw4w9WgXcQ as").is_err());
+}
+
+#[test]
+fn entries_only_wait_for_named_entries_above_them() {
+    let mut queue = DownloadQueue::new(2, 1);
+    assert!(queue.push("install choice of ocean").is_err());
+
+    assert!(queue.push("https://youtu.be/dQw4w9WgXcQ as ocean").unwrap());
+    assert!(queue.push("https://youtu.be/9bZkp7q19f0 as ocean").is_err());
+    // Only what a download-only entry left can be converted
+    assert!(queue.push("convert ocean").is_err());
+
+    assert!(queue.push("https://youtu.be/9bZkp7q19f0 download-only as raw").unwrap());
+    assert!(queue.push("convert raw").unwrap());
+    assert!(queue.push("install best of ocean, convert-raw").unwrap());
+    assert!(queue.installs());
+    // `install best` needs nobody to answer
+    assert!(!queue.asks());
+    assert!(queue.push("install choice of ocean").unwrap());
+    assert!(queue.asks());
+    assert_eq!(queue.len(), 5);
+}
+
+#[test]
+fn saved_runs_are_claimed_once_and_resume_where_they_stopped() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config { output_dir: dir.path().to_path_buf(), ..Config::default() };
+
+    let mut queue = DownloadQueue::new(2, 1);
+    queue.push("https://youtu.be/dQw4w9WgXcQ download-only as raw").unwrap();
+    queue.push("convert raw").unwrap();
+    let id = queue.save(&config).unwrap();
+
+    let claimed = SavedQueue::update(&config, SavedQueue::claim_orphaned).unwrap();
+    assert_eq!(claimed.len(), 1);
+    assert_eq!(claimed[0].0, id);
+    assert_eq!(claimed[0].1.jobs[1].label, "convert-raw");
+    // Its heartbeat is fresh, so another daemon leaves it alone
+    assert!(SavedQueue::update(&config, SavedQueue::claim_orphaned).unwrap().is_empty());
+
+    let mut run = claimed[0].1.clone();
+    run.jobs[0].state = SavedState::Done { outputs: vec![PathBuf::from("/tmp/raw.mp4")] };
+    let resumed = DownloadQueue::resume(&id, &run, 2, 1).unwrap();
+    assert_eq!(resumed.len(), 2);
+}
+
+#[test]
+fn an_unreadable_saved_queue_is_not_overwritten() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config { output_dir: dir.path().to_path_buf(), ..Config::default() };
+    let newer = r#"{"runs":{"1":{"heartbeat":null,"jobs":[{"label":"a","entry":"a","state":"paused"}]}}}"#;
+    std::fs::write(SavedQueue::path(&config), newer).unwrap();
+
+    assert!(SavedQueue::update(&config, SavedQueue::claim_orphaned).is_err());
+    assert_eq!(std::fs::read_to_string(SavedQueue::path(&config)).unwrap(), newer);
+    assert!(SavedQueue::load(&config).runs.is_empty());
+}
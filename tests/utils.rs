@@ -0,0 +1,172 @@
+use std::fs;
+use proptest::prelude::*;
+use rust_downloader::config::FileNamingConfig;
+use rust_downloader::utils::{
+    clean_title, create_progress_bar, create_safe_filename, extract_video_id, format_date, format_duration,
+    format_file_size, format_number, format_time, get_unique_filename, parse_progress, parse_size, update_json,
+};
+
+fn naming(max_title_length: usize, space_replacement: &'static str) -> FileNamingConfig {
+    FileNamingConfig {
+        max_title_length,
+        invalid_chars: "[^\\w\\s-]",
+        space_replacement,
+        template: "{title}_{quality}.{ext}",
+    }
+}
+
+proptest! {
+    #[test]
+    fn parse_progress_never_panics(line in ".*") {
+        parse_progress(&line);
+    }
+
+    #[test]
+    fn parse_progress_reads_what_yt_dlp_prints(
+        percentage in 0.0f64..=100.0,
+        size in 0.01f64..10_000.0,
+        unit in prop::sample::select(vec!["KiB", "MiB", "GiB"]),
+        estimated in any::<bool>(),
+        speed in prop::sample::select(vec!["1.23MiB/s", "512.00KiB/s", "Unknown speed", "Unknown B/s"]),
+        eta in prop::sample::select(vec!["00:30", "01:02:03", "Unknown"]),
+        fragments in prop::option::of((0u32..1000, 1u32..1000)),
+    ) {
+        let total_size = format!("{:.2}{}", size, unit);
+        let mut line = format!(
+            "[download] {:5.1}% of {}{} at {} ETA {}",
+            percentage, if estimated { "~ " } else { "" }, total_size, speed, eta
+        );
+        if let Some((done, total)) = fragments {
+            line.push_str(&format!(" (frag {}/{})", done, total));
+        }
+
+        let progress = parse_progress(&line).unwrap();
+        prop_assert!((progress.percentage - percentage).abs() <= 0.05);
+        prop_assert_eq!(&progress.total_size, &total_size);
+        prop_assert_eq!(progress.estimated, estimated);
+        prop_assert_eq!(progress.speed, if speed.starts_with("Unknown") { "Unknown" } else { speed });
+        prop_assert_eq!(progress.eta, eta);
+        prop_assert_eq!(progress.fragments, fragments);
+        prop_assert!(parse_size(&progress.total_size).is_some());
+    }
+
+    #[test]
+    fn parse_size_never_panics(size in ".*") {
+        parse_size(&size);
+    }
+
+    #[test]
+    fn progress_bar_is_always_full_width(percentage in any::<f64>(), width in 0usize..80) {
+        let bar = create_progress_bar(percentage, width);
+        prop_assert_eq!(bar.chars().filter(|c| *c == '█' || *c == '░').count(), width);
+    }
+
+    #[test]
+    fn format_number_groups_thousands(n in any::<u64>()) {
+        let formatted = format_number(Some(n));
+        prop_assert_eq!(formatted.replace(',', "").parse::<u64>().unwrap(), n);
+        let groups: Vec<&str> = formatted.split(',').collect();
+        prop_assert!(groups[0].len() <= 3 && !groups[0].is_empty());
+        prop_assert!(groups[1..].iter().all(|group| group.len() == 3));
+    }
+
+    #[test]
+    fn format_duration_round_trips(seconds in any::<u64>()) {
+        let formatted = format_duration(Some(seconds));
+        let total = formatted.split(':').fold(0u128, |total, part| total * 60 + part.parse::<u128>().unwrap());
+        prop_assert_eq!(total, seconds as u128);
+    }
+
+    #[test]
+    fn format_time_keeps_units_in_range(seconds in 0.0f64..1e7) {
+        let formatted = format_time(seconds);
+        for part in formatted.split(' ').skip_while(|part| part.ends_with('h')) {
+            let value: u64 = part.trim_end_matches(['m', 's']).parse().unwrap();
+            prop_assert!(value < 60, "{} in {}", part, formatted);
+        }
+    }
+
+    #[test]
+    fn format_file_size_never_panics(bytes in any::<Option<u64>>()) {
+        prop_assert!(!format_file_size(bytes).is_empty());
+    }
+
+    #[test]
+    fn format_date_never_panics(date in ".*") {
+        format_date(&date);
+    }
+
+    #[test]
+    fn safe_filenames_stay_within_limits(
+        title in ".*",
+        max_title_length in 0usize..80,
+        space_replacement in prop::sample::select(vec!["_", "-", "·", "—", ""]),
+    ) {
+        let naming = naming(max_title_length, space_replacement);
+        let cleaned = clean_title(&title, &naming);
+        prop_assert!(cleaned.len() <= max_title_length);
+
+        let filename = create_safe_filename(&title, "4K", "mov", &naming);
+        prop_assert!(!filename.contains('/'));
+        prop_assert!(filename.ends_with("_4K.mov"));
+    }
+
+    #[test]
+    fn extract_video_id_finds_every_link_form(
+        id in "[A-Za-z0-9_-]{11}",
+        form in prop::sample::select(vec![
+            "https://www.youtube.com/watch?v={}",
+            "https://m.youtube.com/watch?v={}&list=PL123",
+            "https://youtu.be/{}?si=abc",
+            "https://www.youtube.com/embed/{}",
+            "https://www.youtube.com/shorts/{}",
+            "https://www.youtube.com/live/{}?t=90",
+        ]),
+    ) {
+        prop_assert_eq!(extract_video_id(&form.replace("{}", &id)), Some(id));
+    }
+
+    #[test]
+    fn extract_video_id_never_panics(url in ".*") {
+        extract_video_id(&url);
+    }
+
+    #[test]
+    fn unique_filenames_never_collide(
+        stem in "[a-zA-Z0-9 ._-]{1,20}",
+        ext in prop::option::of("[a-z0-9]{1,4}"),
+        taken in 0usize..5,
+    ) {
+        let dir = tempfile::tempdir().unwrap();
+        let name = match &ext {
+            Some(ext) => format!("{}.{}", stem, ext),
+            None => stem.clone(),
+        };
+        let base = dir.path().join(&name);
+        prop_assume!(base.parent() == Some(dir.path()));
+
+        let mut seen = Vec::new();
+        for _ in 0..=taken {
+            let unique = get_unique_filename(&base).unwrap();
+            prop_assert!(!unique.exists());
+            prop_assert_eq!(unique.parent(), Some(dir.path()));
+            prop_assert!(!seen.contains(&unique));
+            fs::write(&unique, b"").unwrap();
+            seen.push(unique);
+        }
+    }
+}
+
+#[test]
+fn update_json_keeps_a_file_it_cannot_parse() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+
+    update_json(&path, |names: &mut Vec<String>| names.push("first".to_string())).unwrap();
+    update_json(&path, |names: &mut Vec<String>| names.push("second".to_string())).unwrap();
+    assert_eq!(serde_json::from_str::<Vec<String>>(&fs::read_to_string(&path).unwrap()).unwrap(), ["first", "second"]);
+
+    fs::write(&path, "{\"written by\": \"a newer version\"}").unwrap();
+    assert!(update_json(&path, |names: &mut Vec<String>| names.clear()).is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{\"written by\": \"a newer version\"}");
+}